@@ -1,5 +1,12 @@
 $stage4 = ".\.cache\gg\gg-VERVER\stage4.exe"
 
+# Bundled stage4 (gg pack)
+$bundled = Get-ChildItem ".\.cache\gg\gg-VERVER\stage4-*windows*.exe" -ErrorAction SilentlyContinue | Select-Object -First 1
+if (!(Test-Path $stage4) -and $bundled)
+{
+    Copy-Item $bundled.FullName $stage4
+}
+
 if (Test-Path $stage4)
 {
     $proc = Start-Process $stage4 -WorkingDirectory "$( Get-Location )" -PassThru -NoNewWindow -ErrorAction SilentlyContinue -ArgumentList $args
@@ -15,6 +22,11 @@ if ($arch -Eq "AMD64")
 
 $hashes = (Get-Content .cache/gg/gg-VERVER/hashes).Split("`n")
 $hash = ($hashes | Where-Object { $_ -match "$arch.*windows" })
+# Windows on ARM runs the x86_64 build emulated, the system file keeps the real arch
+if (!$hash -and $arch -Eq "ARM64")
+{
+    $hash = ($hashes | Where-Object { $_ -match "x86_64.*windows" })
+}
 if ($hash)
 {
     "$arch-windows" | Out-File .cache\gg\gg-VERVER\system -Encoding ascii
@@ -4,6 +4,18 @@ if [ "$OSTYPE" = "cygwin" ] || [ "$OSTYPE" = "msys" ]; then
   exit $?
 fi
 
+# Bundled stage4 (gg pack), use the first one that runs on this system
+if [ ! -f .cache/gg/gg-VERVER/stage4 ]; then
+  for stage4 in .cache/gg/gg-VERVER/stage4-*; do
+    [ -f "$stage4" ] || continue
+    chmod +x "$stage4"
+    if "$stage4" -V >/dev/null 2>&1; then
+      cp "$stage4" .cache/gg/gg-VERVER/stage4
+      break
+    fi
+  done
+fi
+
 if [ ! -f .cache/gg/gg-VERVER/stage4 ]; then
   cd .cache/gg/gg-VERVER || exit
   for stage3 in stage3*; do
@@ -1,11 +1,68 @@
 use std::fmt::Write;
-use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::time::Duration;
 
-pub fn create_barus() -> ProgressBar {
-    let pb = ProgressBar::new(1);
-    pb.set_style(ProgressStyle::with_template("{prefix:.bold} {spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+use indicatif::{HumanBytes, ProgressBar, ProgressState, ProgressStyle};
+
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::with_template("{prefix:.bold} {spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} ({eta})")
         .unwrap()
         .with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
-        .progress_chars("#>-"));
+        .progress_chars("#>-")
+}
+
+pub fn create_barus() -> ProgressBar {
+    let pb = ProgressBar::new(1);
+    pb.set_style(bar_style());
     pb
 }
+
+/// For steps without a size, like extraction
+pub fn spinner(pb: &ProgressBar, message: &'static str) {
+    pb.set_style(ProgressStyle::with_template("{prefix:.bold} {spinner:.green} {msg} [{elapsed_precise}]").unwrap());
+    pb.set_message(message);
+    pb.enable_steady_tick(Duration::from_millis(100));
+}
+
+/// Back to bytes and speed, e.g. for the next download with the same bar
+pub fn bar(pb: &ProgressBar) {
+    pb.disable_steady_tick();
+    pb.set_style(bar_style());
+}
+
+/// Nothing is drawn without a terminal (CI, output to a file), so progress is printed as plain lines instead.
+/// To stderr, as stdout belongs to the tool
+pub fn plain(pb: &ProgressBar, message: impl AsRef<str>) {
+    if pb.is_hidden() {
+        let prefix = pb.prefix();
+        if prefix.is_empty() {
+            eprintln!("{}", message.as_ref());
+        } else {
+            eprintln!("{prefix}: {}", message.as_ref());
+        }
+    }
+}
+
+/// Every quarter of the download gets a plain line
+pub fn get_quarter(downloaded: u64, total: u64) -> u64 {
+    (downloaded * 4).checked_div(total).unwrap_or(4)
+}
+
+pub fn get_speed(bytes: u64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64().max(0.001);
+    format!("{}/s", HumanBytes((bytes as f64 / secs) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_quarter() {
+        assert_eq!(0, get_quarter(10, 200));
+        assert_eq!(1, get_quarter(50, 200));
+        assert_eq!(3, get_quarter(199, 200));
+        assert_eq!(4, get_quarter(200, 200));
+        assert_eq!(4, get_quarter(0, 0));
+        assert_eq!("1.00 MiB/s", get_speed(2 * 1024 * 1024, Duration::from_secs(2)));
+    }
+}
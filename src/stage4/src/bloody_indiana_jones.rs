@@ -1,19 +1,226 @@
 use std::cmp::min;
-use std::fs::{create_dir_all, File, read_dir, remove_dir, rename};
-use std::io::Write;
+use std::fs;
+use std::fs::{create_dir_all, File, OpenOptions, read_dir, remove_dir, rename};
+use std::io;
+use std::io::{Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
 
 use futures_util::StreamExt;
-use indicatif::ProgressBar;
-use log::{debug, info};
+use indicatif::{HumanBytes, ProgressBar};
+use log::{debug, info, warn};
+use reqwest::header::{ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use tar::EntryType;
+
+use crate::barus;
+use crate::diagnostics::Diagnostic;
+use crate::http;
+use tokio::io::AsyncRead;
 use tokio::task;
+use tokio_util::io::SyncIoBridge;
 
+/// The last part of the path, or a hash of the URL when there is none to name the file after
 fn get_file_name(url: &str) -> String {
-    reqwest::Url::parse(url).unwrap().path_segments().unwrap().last().unwrap().to_string()
+    reqwest::Url::parse(url).ok()
+        .and_then(|url| url.path_segments()?.last().map(|s| s.to_string()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| sha256::digest(url))
 }
 
 const DOWNLOADS_DIR: &str = ".cache/gg/downloads";
 
+/// Written into an install last, before it is moved in place. Installs without it, and without gg-meta.json from
+/// before there was a marker, are half extracted
+pub const COMPLETE_MARKER: &str = "gg-complete";
+
+/// Tries in one download, as long as each try gets further
+const RESUME_ATTEMPTS: u32 = 5;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "SHA-1",
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Sha512 => "SHA-512",
+        }
+    }
+}
+
+/// A published hash of a download and what it is a hash of
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct Checksum {
+    pub algorithm: HashAlgorithm,
+    pub hash: String,
+}
+
+impl Checksum {
+    pub fn sha256(hash: &str) -> Self {
+        Checksum { algorithm: HashAlgorithm::Sha256, hash: hash.to_string() }
+    }
+}
+
+pub fn checksum_of(path: &Path, algorithm: HashAlgorithm) -> io::Result<String> {
+    match algorithm {
+        HashAlgorithm::Sha1 => digest_of::<Sha1>(path),
+        HashAlgorithm::Sha256 => sha256::try_digest(path),
+        HashAlgorithm::Sha512 => digest_of::<Sha512>(path),
+    }
+}
+
+fn digest_of<D: Digest + Write>(path: &Path) -> io::Result<String> {
+    let mut hasher = D::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn get_size(path: &str) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// The strong ETag, or else Last-Modified, of the response a .part was started from. As If-Range on resume the server
+/// sends the whole file again when it changed, instead of the rest of another file
+fn get_validator(res: &reqwest::Response) -> Option<String> {
+    let header = |name| res.headers().get(name).and_then(|v| v.to_str().ok());
+    header(ETAG).filter(|etag| !etag.starts_with("W/")).or_else(|| header(LAST_MODIFIED)).map(|v| v.to_string())
+}
+
+/// Creating symlinks on Windows requires admin (or developer mode), so there symlinks in archives are
+/// replaced by junctions for folders and copies for files
+fn unpack_tar<R: Read>(mut archive: tar::Archive<R>, dest: &Path, symlinks: bool) -> io::Result<()> {
+    create_dir_all(dest)?;
+    let mut links = vec!();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !symlinks && entry.header().entry_type() == EntryType::Symlink {
+            if let Some(link_name) = entry.link_name()? {
+                links.push((entry.path()?.to_path_buf(), link_name.to_path_buf()));
+            }
+            continue;
+        }
+        entry.unpack_in(dest)?;
+    }
+    resolve_links(dest, links);
+    Ok(())
+}
+
+/// Symlinks in zips have unix mode 0o120000 and the target as content. Written as links, or as in [unpack_tar]
+/// without symlinks
+fn unpack_zip<R: Read + Seek>(reader: R, dest: &Path, symlinks: bool) -> io::Result<()> {
+    create_dir_all(dest)?;
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut links = vec!();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(path) = file.enclosed_name().map(|p| p.to_path_buf()) else {
+            warn!("Skipping {} outside of the archive", file.name());
+            continue;
+        };
+        let out = dest.join(&path);
+        if file.is_dir() {
+            create_dir_all(&out)?;
+            continue;
+        }
+        if let Some(parent) = out.parent() {
+            create_dir_all(parent)?;
+        }
+        let mode = file.unix_mode();
+        if mode.map(|m| m & 0o170000 == 0o120000).unwrap_or(false) {
+            let mut link_name = String::new();
+            file.read_to_string(&mut link_name)?;
+            links.push((path, PathBuf::from(link_name)));
+            continue;
+        }
+        io::copy(&mut file, &mut File::create(&out)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&out, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+    if symlinks {
+        for (path, link_name) in links {
+            symlink(&link_name, &dest.join(path))?;
+        }
+    } else {
+        resolve_links(dest, links);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// Links can point to other links, so resolve until nothing more can be resolved
+fn resolve_links(dest: &Path, mut links: Vec<(PathBuf, PathBuf)>) {
+    while !links.is_empty() {
+        let before = links.len();
+        links.retain(|(path, link_name)| {
+            let link = dest.join(path);
+            let target = link.parent().unwrap_or(dest).join(link_name);
+            let inside = target.canonicalize().map(|t| t.starts_with(dest.canonicalize().unwrap_or_default())).unwrap_or(false);
+            if !inside {
+                return true;
+            }
+            let res = if target.is_dir() { link_dir(&target, &link) } else { fs::copy(&target, &link).map(|_| ()) };
+            if let Err(e) = res {
+                warn!("Unable to create {}: {e}", link.display());
+            }
+            false
+        });
+        if links.len() == before {
+            for (path, link_name) in &links {
+                warn!("Skipping link {} -> {}", path.display(), link_name.display());
+            }
+            break;
+        }
+    }
+}
+
+/// Junctions don't require admin on Windows
+fn link_dir(target: &Path, link: &Path) -> io::Result<()> {
+    if cfg!(windows) {
+        let status = Command::new("cmd").arg("/C").arg("mklink").arg("/J").arg(link).arg(target).output()?.status;
+        if status.success() {
+            return Ok(());
+        }
+    }
+    copy_dir(target, link)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    create_dir_all(to)?;
+    for entry in read_dir(from)? {
+        let entry = entry?;
+        let to = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir(&entry.path(), &to)?;
+        } else {
+            fs::copy(entry.path(), to)?;
+        }
+    }
+    Ok(())
+}
+
 pub struct BloodyIndianaJones {
     url: String,
     path: String,
@@ -35,138 +242,406 @@ impl BloodyIndianaJones {
         Self { url, path, file_name, file_path, pb }
     }
 
-    pub async fn download(&self) {
+    /// Next to the file, named by the URL too, so a .part is only resumed from the same URL
+    fn get_part_path(&self) -> String {
+        format!("{}.{}.part", self.file_path, &sha256::digest(&self.url)[..16])
+    }
+
+    /// Into `<file>.<hash of url>.part` first. What is there from an interrupted download is resumed with a Range
+    /// request when the server supports it, also when the connection breaks during this download
+    pub async fn download(&self) -> Result<(), String> {
         info!("Downloading {}", &self.url);
+        barus::bar(&self.pb);
         self.pb.reset();
         self.pb.set_message("Preparing");
 
-        create_dir_all(DOWNLOADS_DIR).expect("Unable to create download dir");
+        create_dir_all(DOWNLOADS_DIR).map_err(|e| Diagnostic::new("Unable to create download dir").note("cause", e).to_string())?;
 
         self.pb.set_message("Downloading");
-        let client = reqwest::Client::new();
-        let res = client.get(&self.url)
-            .send()
-            .await
-            .expect(format!("Failed to get {}", &self.url).as_str());
+        let part_path = self.get_part_path();
+        let start = Instant::now();
+        let mut attempt = 1;
+        let downloaded = loop {
+            let before = get_size(&part_path);
+            match self.download_part(&part_path).await {
+                Ok(downloaded) => break downloaded,
+                Err(e) if attempt < RESUME_ATTEMPTS && get_size(&part_path) > before => {
+                    warn!("Download of {} interrupted, resuming: {e}", self.file_name);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        rename(&part_path, &self.file_path)
+            .map_err(|e| Diagnostic::new(format!("Unable to move '{part_path}' to '{}'", &self.file_path)).note("cause", e).to_string())?;
+        let _ = fs::remove_file(format!("{part_path}.validator"));
+        barus::plain(&self.pb, format!("Downloaded {} in {:.1}s ({})", self.file_name, start.elapsed().as_secs_f64(), barus::get_speed(downloaded, start.elapsed())));
+
+        info!("Downloaded {} to {}", &self.url, &self.file_path);
+        Ok(())
+    }
+
+    /// The rest from `offset`, only if the file is still the one the validator is of
+    fn request(&self, offset: u64, validator: Option<&str>) -> reqwest::RequestBuilder {
+        let request = http::client().get(&self.url);
+        match validator {
+            Some(validator) if offset > 0 => request.header(RANGE, format!("bytes={offset}-")).header(IF_RANGE, validator),
+            _ => request,
+        }
+    }
+
+    /// The rest of the file after what is in `part_path`, returns the size of the whole file. Without a validator from
+    /// when the .part was started, or when the server sends all of it or can't serve the rest, it starts over
+    async fn download_part(&self, part_path: &str) -> Result<u64, String> {
+        let download_error = |e: &reqwest::Error| Diagnostic::new(format!("Unable to download {}", self.file_name)).url(&self.url).request_error(e).to_string();
+        let validator_path = format!("{part_path}.validator");
+        let validator = fs::read_to_string(&validator_path).ok();
+        let offset = if validator.is_some() { get_size(part_path) } else { 0 };
+        let mut res = http::send(self.request(offset, validator.as_deref())).await.map_err(|e| download_error(&e))?;
+        if offset > 0 && res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            debug!("{part_path} is {offset} bytes, more than the server has, starting over");
+            res = http::send(self.request(0, None)).await.map_err(|e| download_error(&e))?;
+        }
+        let res = res.error_for_status().map_err(|e| download_error(&e))?;
+        let resumed = offset > 0 && res.status() == StatusCode::PARTIAL_CONTENT;
+        if offset > 0 && !resumed {
+            debug!("{} changed or does not support resuming, starting over", self.url);
+        }
+        if !resumed {
+            let _ = match get_validator(&res) {
+                Some(validator) => fs::write(&validator_path, validator),
+                None => fs::remove_file(&validator_path),
+            };
+        }
+        let offset = if resumed { offset } else { 0 };
         let total_size = res
             .content_length()
-            .expect(format!("Failed to get content length from {}", &self.url).as_str());
+            .ok_or(Diagnostic::new("Missing content length").url(&self.url).to_string())? + offset;
 
-        debug!("Total size {:?}", total_size);
+        debug!("Total size {:?}, resuming from {offset}", total_size);
 
         self.pb.set_length(total_size);
+        self.pb.set_position(offset);
+        if resumed {
+            barus::plain(&self.pb, format!("Resuming {} at {} of {}", self.file_name, HumanBytes(offset), HumanBytes(total_size)));
+        } else {
+            barus::plain(&self.pb, format!("Downloading {} ({})", self.file_name, HumanBytes(total_size)));
+        }
+        let mut quarter = barus::get_quarter(offset, total_size);
 
-        let file_name = get_file_name(&self.url);
-        debug!("File name {:?}", file_name);
-
-        debug!("{:?}", &self.file_path);
-
-        let mut file = File::create(&self.file_path)
-            .expect(format!("Failed to create file '{}'", &self.file_path).as_str());
-        let mut downloaded: u64 = 0;
+        let mut file = OpenOptions::new().create(true).write(true).append(resumed).truncate(!resumed).open(part_path)
+            .map_err(|e| Diagnostic::new(format!("Failed to create file '{part_path}'")).note("cause", e).to_string())?;
+        let mut downloaded: u64 = offset;
         let mut stream = res.bytes_stream();
 
         while let Some(item) = stream.next().await {
-            let chunk = item.expect(format!("Error while downloading file").as_str());
+            let chunk = item.map_err(|e| download_error(&e))?;
             file.write_all(&chunk)
-                .expect("Error while writing to file");
+                .map_err(|e| Diagnostic::new(format!("Error while writing to '{part_path}'")).note("cause", e).to_string())?;
             let new = min(downloaded + (chunk.len() as u64), total_size);
             downloaded = new;
             self.pb.set_position(new);
+            let new_quarter = barus::get_quarter(downloaded, total_size);
+            if new_quarter > quarter && new_quarter < 4 {
+                barus::plain(&self.pb, format!("Downloading {} {}%", self.file_name, new_quarter * 25));
+            }
+            quarter = new_quarter;
         }
+        Ok(downloaded)
+    }
 
-        info!("Downloaded {} to {}", &self.url, &self.file_path);
+    /// An archive downloaded before, instead of downloading it again
+    pub fn copy_from(&self, cached: &Path) -> Result<(), String> {
+        create_dir_all(DOWNLOADS_DIR).map_err(|e| Diagnostic::new("Unable to create download dir").note("cause", e).to_string())?;
+        fs::copy(cached, &self.file_path)
+            .map_err(|e| Diagnostic::new(format!("Unable to copy {} to '{}'", cached.display(), &self.file_path)).note("cause", e).to_string())?;
+        Ok(())
     }
 
-    pub async fn unpack_and_all_that_stuff(&self) {
+    /// The file is removed on mismatch, so the next try downloads it again
+    pub fn verify(&self, checksum: &Checksum) -> Result<(), String> {
+        self.pb.set_message("Verifying");
+        let expected = checksum.hash.trim();
+        let actual = checksum_of(Path::new(&self.file_path), checksum.algorithm)
+            .map_err(|e| Diagnostic::new(format!("Unable to read '{}'", &self.file_path)).note("cause", e).to_string())?;
+        if actual.eq_ignore_ascii_case(expected) {
+            info!("Checksum of {} ok", self.file_name);
+            return Ok(());
+        }
+        let _ = fs::remove_file(&self.file_path);
+        Err(Diagnostic::new(format!("Checksum mismatch for {}", self.file_name))
+            .url(&self.url)
+            .note("expected", format!("{} {expected}", checksum.algorithm.name()))
+            .note("actual", actual)
+            .help("The download is corrupt or was changed on the way. Try again, or check proxies and mirrors")
+            .to_string())
+    }
+
+    /// Into `<install>.tmp` next to the install, which is renamed to the install when everything is there, `prep`
+    /// included. So a crash or a failing prep leaves no half done install behind
+    pub async fn unpack_and_all_that_stuff(&self, prep: impl FnOnce(&str) -> Result<(), String>) -> Result<(), String> {
+        let staging = format!("{}.tmp", self.path);
+        let _ = fs::remove_dir_all(&staging);
+        self.unpack_into(&staging).await.map_err(|e| format!("Unable to extract {}: {e}", self.file_name))?;
+        if let Err(e) = prep(&staging) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+        move_in_place(Path::new(&staging), Path::new(&self.path)).map_err(|e| format!("Unable to move {} in place: {e}", self.path))?;
+        self.pb.finish_with_message("Done");
+        Ok(())
+    }
+
+    async fn unpack_into(&self, dest: &str) -> io::Result<()> {
         self.pb.reset();
-        self.pb.set_message("Extracting");
+        barus::spinner(&self.pb, "Extracting");
+        barus::plain(&self.pb, format!("Extracting {}", self.file_name));
 
         info!("Extracting {}", self.file_name);
-        let ext = Path::new(&self.file_name).extension().unwrap().to_str();
-        let file_buf_reader = tokio::io::BufReader::new(tokio::fs::File::open(&self.file_path).await.unwrap());
-        let file_path_decomp = &Path::new(&format!("{DOWNLOADS_DIR}/{}", self.file_name)).with_extension("").to_str().unwrap().to_string();
+        // Binaries without an archive, e.g. kubectl, have no extension
+        let ext = Path::new(&self.file_name).extension().and_then(|e| e.to_str());
+        let file_buf_reader = tokio::io::BufReader::new(tokio::fs::File::open(&self.file_path).await?);
+        let file_path_decomp = Path::new(&format!("{DOWNLOADS_DIR}/{}", self.file_name)).with_extension("");
+        // .tgz, e.g. npm packages, is .tar.gz
+        let compressed_tar = ext == Some("tgz") || file_path_decomp.extension().map(|e| e == "tar").unwrap_or(false);
+        let path = PathBuf::from(dest);
+        let file_path = PathBuf::from(&self.file_path);
 
         match ext {
-            Some("xz") | Some("gz") => {
-                match ext {
-                    Some("xz") => {
-                        info!("Decompressing Xz");
-                        let mut decoder = async_compression::tokio::bufread::XzDecoder::new(file_buf_reader);
-                        let mut file_writer = tokio::io::BufWriter::new(tokio::fs::File::create(file_path_decomp).await.unwrap());
-                        tokio::io::copy(&mut decoder, &mut file_writer).await.unwrap();
-                    }
-                    _ => {
-                        info!("Decompressing Gzip");
-                        self.pb.set_message("Gunzip");
-                        let mut decoder = async_compression::tokio::bufread::GzipDecoder::new(file_buf_reader);
-                        let mut file_writer = tokio::io::BufWriter::new(tokio::fs::File::create(file_path_decomp).await.unwrap());
-                        tokio::io::copy(&mut decoder, &mut file_writer).await.unwrap();
-                    }
+            Some("xz") | Some("gz") | Some("tgz") | Some("zst") => {
+                let decoder: Box<dyn AsyncRead + Unpin + Send> = match ext {
+                    Some("xz") => Box::new(async_compression::tokio::bufread::XzDecoder::new(file_buf_reader)),
+                    Some("zst") => Box::new(async_compression::tokio::bufread::ZstdDecoder::new(file_buf_reader)),
+                    _ => Box::new(async_compression::tokio::bufread::GzipDecoder::new(file_buf_reader)),
                 };
+                if compressed_tar {
+                    // Straight from the decoder into tar, without the uncompressed tar on disk
+                    info!("Untar {}", self.file_name);
+                    self.pb.set_message("Untar");
+                    let reader = SyncIoBridge::new(decoder);
+                    task::spawn_blocking(move || unpack_tar(tar::Archive::new(reader), &path, !cfg!(windows))).await??;
+                } else {
+                    info!("Decompressing {}", self.file_name);
+                    self.pb.set_message("Decompress");
+                    let mut decoder = decoder;
+                    let mut file_writer = tokio::io::BufWriter::new(tokio::fs::File::create(&file_path_decomp).await?);
+                    tokio::io::copy(&mut decoder, &mut file_writer).await?;
+                }
             }
             Some("zip") => {
                 info!("Decompressing Zip");
-                info!("Path is {}", &self.path);
+                info!("Path is {dest}");
                 self.pb.set_message("Unzip");
-                let file_path_string = self.file_path.clone();
-                let path_string = self.path.clone();
-                task::spawn_blocking(move || {
-                    create_dir_all(&path_string).expect("Unable to create download dir");
-                    let target_dir = PathBuf::from(&path_string);
-                    zip_extract::extract(File::open(file_path_string).unwrap(), &target_dir, true).unwrap();
-                }).await.expect("Unable to unzip");
+                task::spawn_blocking(move || unpack_zip(File::open(file_path)?, &path, !cfg!(windows))).await??;
+            }
+            Some("tar") => {
+                info!("Untar {}", self.file_name);
+                self.pb.set_message("Untar");
+                task::spawn_blocking(move || unpack_tar(tar::Archive::new(io::BufReader::new(File::open(file_path)?)), &path, !cfg!(windows))).await??;
             }
-            Some("tar") => (),
             _ => {
                 self.pb.set_message("Move");
-                create_dir_all(&self.path).expect("Unable to create download dir");
-                rename(&self.file_path, self.path.to_string() + "/" + self.file_name.as_str()).unwrap();
-                self.pb.finish_with_message("Done");
-                return;
+                create_dir_all(dest)?;
+                return rename(&self.file_path, Path::new(dest).join(&self.file_name));
             }
         }
 
-        let file_name = Path::new(&format!(".cache/gg/downloads/{}", self.file_name)).with_extension("").to_str().unwrap().to_string();
+        self.pb.set_message("Move");
+        let path = PathBuf::from(dest);
+        task::spawn_blocking(move || move_up_single_dir(&path)).await?
+    }
+}
 
-        if let Some(extension) = Path::new(&file_name).extension() {
-            if extension == "tar" {
-                info!("Untar {file_name}");
-                self.pb.set_message("Untar");
-                let mut archive = tar::Archive::new(std::io::BufReader::new(File::open(file_name).unwrap()));
-                archive.unpack(&self.path).expect("Unable to extract");
-            }
+/// Archives with everything in one folder, e.g. node-v20.11.1-linux-x64/, have what is in it moved up
+fn move_up_single_dir(dest: &Path) -> io::Result<()> {
+    let entries = read_dir(dest)?.collect::<Result<Vec<_>, _>>()?;
+    let [entry] = entries.as_slice() else { return Ok(()) };
+    if !entry.path().is_dir() {
+        return Ok(());
+    }
+    debug!("Extracted files are contained in sub-folder. Moving them up");
+    let parent = entry.path();
+    for entry in read_dir(&parent)? {
+        let path = entry?.path();
+        if let Some(file_name) = path.file_name() {
+            rename(&path, dest.join(file_name))?;
         }
+    }
+    remove_dir(parent).ok();
+    Ok(())
+}
 
-        let path_string = self.path.clone();
-        self.pb.set_message("Move");
-        task::spawn_blocking(move || {
-            let parent_path = Path::new(&path_string);
-            let entries = read_dir(&path_string);
-            if let Ok(entries) = entries {
-                let entries = entries.collect::<Vec<_>>();
-                if entries.len() == 1 {
-                    for entry in entries {
-                        if let Ok(entry) = entry {
-                            if entry.path().is_dir() {
-                                debug!("Extracted files are contained in sub-folder. Moving them up");
-                                let parent = entry.path();
-                                if let Ok(entries) = read_dir(&parent) {
-                                    for entry in entries {
-                                        if let Ok(entry) = entry {
-                                            let path = entry.path();
-                                            let new_path = parent_path.join(path.file_name().unwrap());
-                                            rename(&path, new_path).unwrap();
-                                        }
-                                    }
-                                    remove_dir(parent).ok();
-                                }
-                            }
-                        }
-                    }
-                }
+/// What is left of an install without the marker is from a crash, or from before installs were moved in place
+fn move_in_place(staging: &Path, dest: &Path) -> io::Result<()> {
+    create_dir_all(staging)?;
+    fs::write(staging.join(COMPLETE_MARKER), "")?;
+    if dest.exists() {
+        fs::remove_dir_all(dest)?;
+    }
+    rename(staging, dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a restricted Windows user gets: no symlinks, but links still resolve
+    #[test]
+    fn unpack_tar_without_symlinks() {
+        let dir = std::env::temp_dir().join(format!("gg-unpack-test-{}", std::process::id()));
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o755);
+        builder.append_data(&mut header, "lib/tool", "hello".as_bytes()).unwrap();
+        for (path, target) in [("bin/tool", "../lib/tool"), ("current", "lib")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, path, target).unwrap();
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        builder.append_data(&mut header, "bin/", io::empty()).unwrap();
+        let data = builder.into_inner().unwrap();
+
+        unpack_tar(tar::Archive::new(data.as_slice()), &dir, false).unwrap();
+        assert!(!fs::symlink_metadata(dir.join("bin/tool")).unwrap().file_type().is_symlink());
+        assert_eq!("hello", fs::read_to_string(dir.join("bin/tool")).unwrap());
+        assert_eq!("hello", fs::read_to_string(dir.join("current/tool")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unpack_zip_symlinks() {
+        use zip::write::FileOptions;
+        let dir = std::env::temp_dir().join(format!("gg-unzip-test-{}", std::process::id()));
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        writer.start_file("lib/tool", FileOptions::default().unix_permissions(0o755)).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.add_symlink("bin/tool", "../lib/tool", FileOptions::default()).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        for symlinks in [cfg!(unix), false] {
+            let _ = fs::remove_dir_all(&dir);
+            unpack_zip(io::Cursor::new(&data), &dir, symlinks).unwrap();
+            assert_eq!(symlinks, fs::symlink_metadata(dir.join("bin/tool")).unwrap().file_type().is_symlink());
+            assert_eq!("hello", fs::read_to_string(dir.join("bin/tool")).unwrap());
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                assert_eq!(0o755, fs::metadata(dir.join("lib/tool")).unwrap().permissions().mode() & 0o777);
             }
-        }).await.expect("Unable to move files");
-        self.pb.finish_with_message("Done");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Honours Range when If-Range is the ETag "v1", otherwise sends the whole file
+    async fn serve_with_etag(body: Vec<u8>) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 4096];
+            let n = socket.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).to_lowercase();
+            let offset: usize = request.split("range: bytes=").nth(1)
+                .filter(|_| request.contains("if-range: \"v1\""))
+                .and_then(|r| r.split('-').next()?.parse().ok())
+                .unwrap_or(0);
+            let rest = &body[offset..];
+            let status = if offset > 0 { "206 Partial Content" } else { "200 OK" };
+            let head = format!("HTTP/1.1 {status}\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", rest.len());
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(rest).await.unwrap();
+        });
+        port
+    }
+
+    /// A .part with the first part of the file, started from the same ETag or another
+    #[tokio::test]
+    async fn download_resumes_part() {
+        let body: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let dir = std::env::temp_dir().join(format!("gg-resume-test-{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let file_path = dir.join("tool.bin").to_string_lossy().to_string();
+        for (etag, part) in [("\"v1\"", &body[..4000]), ("\"v0\"", &[0u8; 6000][..])] {
+            let port = serve_with_etag(body.clone()).await;
+            let bloody = BloodyIndianaJones::new_with_file_name(format!("http://127.0.0.1:{port}/tool.bin"), file_path.clone(), ProgressBar::hidden());
+            let part_path = bloody.get_part_path();
+            fs::write(&part_path, part).unwrap();
+            fs::write(format!("{part_path}.validator"), etag).unwrap();
+            bloody.download().await.unwrap();
+            assert_eq!(body, fs::read(&file_path).unwrap());
+            assert!(!Path::new(&part_path).exists() && !Path::new(&format!("{part_path}.validator")).exists());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unpack_tar_zst() {
+        use tokio::io::AsyncWriteExt;
+        let dir = std::env::temp_dir().join(format!("gg-unpack-zst-test-{}", std::process::id()));
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, "tool-1.0/bin/tool", "hello".as_bytes()).unwrap();
+        let mut encoder = async_compression::tokio::write::ZstdEncoder::new(Vec::new());
+        encoder.write_all(&builder.into_inner().unwrap()).await.unwrap();
+        encoder.shutdown().await.unwrap();
+        create_dir_all(&dir).unwrap();
+        let file_path = dir.join("tool-1.0.tar.zst").to_string_lossy().to_string();
+        fs::write(&file_path, encoder.into_inner()).unwrap();
+
+        let path = dir.join("tool").to_string_lossy().to_string();
+        let bloody = BloodyIndianaJones { url: String::new(), path: path.clone(), file_name: "tool-1.0.tar.zst".to_string(), file_path, pb: ProgressBar::hidden() };
+        fs::create_dir_all(Path::new(&path).join("half")).unwrap();
+        bloody.unpack_and_all_that_stuff(|staging| {
+            assert!(Path::new(staging).join("bin/tool").exists());
+            Ok(())
+        }).await.unwrap();
+        assert_eq!("hello", fs::read_to_string(Path::new(&path).join("bin/tool")).unwrap());
+        assert!(Path::new(&path).join(COMPLETE_MARKER).exists());
+        assert!(!Path::new(&path).join("half").exists() && !Path::new(&format!("{path}.tmp")).exists());
+        assert!(bloody.unpack_and_all_that_stuff(|_| Err("prep".to_string())).await.is_err());
+        assert!(!Path::new(&format!("{path}.tmp")).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A broken archive is an error for prep to show, not a panic, and leaves no install behind
+    #[tokio::test]
+    async fn unpack_broken_tar_gz() {
+        let dir = std::env::temp_dir().join(format!("gg-unpack-broken-test-{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let file_path = dir.join("tool-1.0.tar.gz").to_string_lossy().to_string();
+        fs::write(&file_path, "not gzip").unwrap();
+        let path = dir.join("tool").to_string_lossy().to_string();
+        let bloody = BloodyIndianaJones { url: String::new(), path: path.clone(), file_name: "tool-1.0.tar.gz".to_string(), file_path, pb: ProgressBar::hidden() };
+        assert!(bloody.unpack_and_all_that_stuff(|_| Ok(())).await.is_err());
+        assert!(!Path::new(&path).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_file_name() {
+        assert_eq!("node-v20.tar.gz", get_file_name("https://nodejs.org/dist/v20/node-v20.tar.gz?x=1"));
+        assert_eq!(64, get_file_name("https://example.com/").len());
+        assert_eq!(64, get_file_name("not a url").len());
+    }
+
+    #[test]
+    fn test_checksum_of() {
+        let path = std::env::temp_dir().join(format!("gg-checksum-{}", std::process::id()));
+        fs::write(&path, "hello").unwrap();
+        let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let sha512 = "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043";
+        assert_eq!(sha256, checksum_of(&path, HashAlgorithm::Sha256).unwrap());
+        assert_eq!(sha512, checksum_of(&path, HashAlgorithm::Sha512).unwrap());
+        assert_eq!("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d", checksum_of(&path, HashAlgorithm::Sha1).unwrap());
+        let _ = fs::remove_file(path);
     }
 }
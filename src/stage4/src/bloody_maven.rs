@@ -5,6 +5,8 @@ use std::pin::Pin;
 use serde::{Deserialize, Serialize};
 use serde_xml_rs::from_str;
 
+use crate::error::GgError;
+use crate::diagnostics;
 use crate::executor::{Download, GgVersion};
 use crate::target::{Arch, Os, Variant};
 
@@ -47,16 +49,17 @@ pub struct Versions {
     version: Vec<String>,
 }
 
-pub fn get_download_urls_from_maven<'a>(group: &'a str, artifact: &'a str) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
+pub const MAVEN_CENTRAL: &str = "https://repo1.maven.org/maven2";
+
+/// `repository` is Maven Central or a mirror of it
+pub fn get_download_urls_from_maven<'a>(repository: String, group: &'a str, artifact: &'a str) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
     Box::pin(async move {
-        let root_url = format!("https://repo1.maven.org/maven2/org/{group}/{artifact}");
+        let root_url = format!("{repository}/org/{group}/{artifact}");
         let metadata_url = format!("{root_url}/maven-metadata.xml");
-        let body = reqwest::get(metadata_url.clone()).await
-            .expect("Unable to connect to archive.apache.org").text().await
-            .expect("Unable to download maven metadata xml");
-        let root: Metadata = from_str(body.as_str()).expect("XML was not well-formatted");
+        let body = diagnostics::get_text(&metadata_url).await?;
+        let root: Metadata = from_str(body.as_str()).map_err(|e| GgError::parse(&metadata_url, "XML", e))?;
 
-        root.versioning.versions.version.into_iter().map(|ver| {
+        Ok(root.versioning.versions.version.into_iter().map(|ver| {
             let mut tags = HashSet::new();
             if ver.contains("beta") {
                 tags.insert("beta".to_string());
@@ -68,7 +71,10 @@ pub fn get_download_urls_from_maven<'a>(group: &'a str, artifact: &'a str) -> Pi
                 arch: Some(Arch::Any),
                 variant: Some(Variant::Any),
                 tags,
+                sha256: None,
+                checksum: None,
+                date: None,
             }
-        }).collect()
+        }).collect())
     })
 }
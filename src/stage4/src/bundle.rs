@@ -0,0 +1,122 @@
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use log::info;
+use regex::Regex;
+
+use crate::executor::AppInput;
+use crate::target::Os;
+
+/// gg.toml and gg.lock in the bundle, used when the project has none
+pub const BUNDLE_DIR: &str = ".cache/gg/bundle";
+
+/// Stage 1 ends where the gzipped tar starts, the batch part skips that many bytes
+pub fn get_stage1_len(gg_cmd: &[u8]) -> Option<usize> {
+    let head = String::from_utf8_lossy(&gg_cmd[..gg_cmd.len().min(4096)]);
+    Regex::new(r"-Skip (\d+)").unwrap().captures(&head)?.get(1)?.as_str().parse().ok()
+}
+
+/// .cache/gg/gg-<version> as found in the tar of gg.cmd
+pub fn get_gg_dir(archive: &[u8]) -> Option<PathBuf> {
+    let mut archive = tar::Archive::new(archive);
+    let entries = archive.entries().ok()?;
+    entries.filter_map(|e| e.ok()).find_map(|e| {
+        let path = e.path().ok()?.to_path_buf();
+        path.ancestors().find(|p| p.file_name().map(|n| n.to_string_lossy().starts_with("gg-")).unwrap_or(false)).map(|p| p.to_path_buf())
+    })
+}
+
+fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &Path, data: &[u8], mode: u32) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(mode);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data).map_err(|e| e.to_string())
+}
+
+/// gg.cmd with stage 4 for this system and the project's gg.toml and gg.lock included, so nothing but the tools
+/// has to be downloaded. stage2 picks the stage4-* that runs on the system
+fn bundle_to(input: &AppInput, file: &str) -> Result<Vec<String>, String> {
+    let gg_cmd = fs::read("gg.cmd").map_err(|e| format!("gg.cmd: {e}"))?;
+    let stage1_len = get_stage1_len(&gg_cmd).ok_or("Unable to find end of stage 1 in gg.cmd")?;
+    let mut archive = vec!();
+    GzDecoder::new(&gg_cmd[stage1_len..]).read_to_end(&mut archive).map_err(|e| e.to_string())?;
+    let gg_dir = get_gg_dir(&archive).ok_or("Unable to find gg version in gg.cmd")?;
+
+    let mut builder = tar::Builder::new(vec!());
+    let mut added = vec!();
+    let mut old = tar::Archive::new(archive.as_slice());
+    for entry in old.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?.to_path_buf();
+        let mut header = entry.header().clone();
+        let mut data = vec!();
+        entry.read_to_end(&mut data).map_err(|e| e.to_string())?;
+        if path.file_name().map(|n| n.to_string_lossy().starts_with("stage4-")).unwrap_or(false) {
+            added.push(path.file_name().unwrap().to_string_lossy().to_string());
+        }
+        builder.append_data(&mut header, &path, data.as_slice()).map_err(|e| e.to_string())?;
+    }
+
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    let ext = if input.target.os == Os::Windows { ".exe" } else { "" };
+    let stage4_name = format!("stage4-{}{ext}", input.target.key());
+    if !added.contains(&stage4_name) {
+        append_file(&mut builder, &gg_dir.join(&stage4_name), &fs::read(exe).map_err(|e| e.to_string())?, 0o755)?;
+        added.push(stage4_name);
+    }
+    for name in ["gg.toml", "gg.lock"] {
+        if let Ok(data) = fs::read(name) {
+            info!("Adding {name}");
+            append_file(&mut builder, &Path::new(BUNDLE_DIR).join(name), &data, 0o644)?;
+            added.push(name.to_string());
+        }
+    }
+
+    let mut encoder = GzEncoder::new(vec!(), Compression::best());
+    encoder.write_all(&builder.into_inner().map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    let mut out = gg_cmd[..stage1_len].to_vec();
+    out.extend(encoder.finish().map_err(|e| e.to_string())?);
+    fs::write(file, out).map_err(|e| e.to_string())?;
+    Ok(added)
+}
+
+pub fn bundle(input: &AppInput, file: Option<&String>) -> ExitCode {
+    let file = file.map(|f| f.as_str()).unwrap_or("gg-bundle.cmd");
+    match bundle_to(input, file) {
+        Ok(added) => {
+            println!("Created {file} with {}", added.join(", "));
+            ExitCode::from(0)
+        }
+        Err(e) => {
+            println!("Unable to bundle: {e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_stage1_len() {
+        let gg_cmd = b": <<BATCH\n    powershell -c \"sc m2 ([byte[]](gc gg.cmd -Encoding Byte | select -Skip 1234)) -Encoding Byte\"\nBATCH\ntail -c +1235 gg.cmd";
+        assert_eq!(Some(1234), get_stage1_len(gg_cmd));
+        assert_eq!(None, get_stage1_len(b"echo hello"));
+    }
+
+    #[test]
+    fn test_get_gg_dir() {
+        let mut builder = tar::Builder::new(vec!());
+        append_file(&mut builder, Path::new(".cache/gg/gg-1.2.3/stage2.sh"), b"echo", 0o755).unwrap();
+        let archive = builder.into_inner().unwrap();
+        assert_eq!(Some(PathBuf::from(".cache/gg/gg-1.2.3")), get_gg_dir(&archive));
+    }
+}
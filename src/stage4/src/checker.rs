@@ -6,7 +6,7 @@ use crate::executor::{AppInput, GgMeta, prep};
 use crate::Executor;
 
 pub async fn check(input: &AppInput, update: bool) {
-    let entries = walkdir::WalkDir::new("./.cache/gg").into_iter()
+    let entries = walkdir::WalkDir::new(input.config.get_install_dir()).into_iter()
         .filter_map(|x| x.ok())
         .filter(|x| x.file_name().to_string_lossy() == "gg-meta.json");
     for entry in entries {
@@ -15,7 +15,13 @@ pub async fn check(input: &AppInput, update: bool) {
         if let Ok(meta) = meta {
             debug!("Meta: {:?}", &meta);
             if let Some(executor) = <dyn Executor>::new(meta.cmd.clone()) {
-                let urls = executor.get_download_urls(input).await;
+                let urls = match executor.get_download_urls(input).await {
+                    Ok(urls) => urls,
+                    Err(e) => {
+                        eprintln!("{}", e.to_diagnostic());
+                        continue;
+                    }
+                };
                 info!("Got {} urls", urls.len());
                 let urls_matches = executor.get_url_matches(&urls, input);
                 info!("Got {} url matches", urls_matches.len());
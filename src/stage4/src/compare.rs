@@ -0,0 +1,130 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, ExitCode};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::executor::AppInput;
+
+pub struct VariantResult {
+    pub tools: String,
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+}
+
+/// Tools before `--`, the command after
+pub fn split_args(args: &[String]) -> (Vec<String>, Vec<String>) {
+    match args.iter().position(|arg| arg == "--") {
+        Some(i) => (args[..i].to_vec(), args[i + 1..].to_vec()),
+        None => (args.to_vec(), vec!()),
+    }
+}
+
+/// gg itself with e.g. `run:node@18 npm test`, so the command gets the tools in PATH like any other run.
+/// Output is captured and printed when done when running in parallel, so it is not mixed up
+fn run_variant(input: &AppInput, tools: &str, command: &[String], capture: bool) -> VariantResult {
+    let args: Vec<String> = input.no_clap.gg_args.iter().cloned()
+        .chain([format!("run:{tools}")])
+        .chain(command.iter().cloned())
+        .collect();
+    let header = format!("==> {tools}: {}", command.join(" "));
+    if !capture {
+        println!("{header}");
+    }
+    let start = Instant::now();
+    let exit_code = env::current_exe().and_then(|exe| {
+        debug!("Running {:?} {:?}", exe, args);
+        let mut command = Command::new(exe);
+        command.args(&args);
+        if capture {
+            let output = command.output()?;
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            let _ = writeln!(stdout, "{header}");
+            let _ = stdout.write_all(&output.stdout);
+            let _ = stdout.write_all(&output.stderr);
+            Ok(output.status)
+        } else {
+            command.status()
+        }
+    }).ok().and_then(|status| status.code());
+    VariantResult { tools: tools.to_string(), exit_code, duration: start.elapsed() }
+}
+
+/// One after another, or up to `jobs` at the same time
+pub fn run_variants(input: &AppInput, variants: &[String], command: &[String], jobs: usize) -> Vec<VariantResult> {
+    if jobs <= 1 {
+        return variants.iter().map(|tools| run_variant(input, tools, command, false)).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec!());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(variants.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(tools) = variants.get(i) else { break };
+                let result = run_variant(input, tools, command, true);
+                results.lock().unwrap().push((i, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub fn get_summary(results: &[VariantResult]) -> String {
+    let width = results.iter().map(|r| r.tools.len()).max().unwrap_or(0) + 2;
+    results.iter().map(|r| {
+        let status = if r.exit_code == Some(0) { "pass" } else { "FAIL" };
+        let exit_code = r.exit_code.map(|c| c.to_string()).unwrap_or("-".to_string());
+        format!("{:<width$}{status}  exit {exit_code:<4}{:>8.1}s", r.tools, r.duration.as_secs_f64())
+    }).collect::<Vec<_>>().join("\n")
+}
+
+/// Non-zero exit code if any of the variants failed
+pub fn print_summary(results: &[VariantResult]) -> ExitCode {
+    println!();
+    println!("{}", get_summary(results));
+    if results.iter().all(|r| r.exit_code == Some(0)) {
+        ExitCode::from(0)
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+/// `compare node@18 node@20 -- npm test` runs the command with each of the tools
+pub fn compare(input: &AppInput) -> ExitCode {
+    let (variants, command) = split_args(&input.no_clap.app_args);
+    if variants.len() < 2 || command.is_empty() {
+        println!("Usage: compare <tools> <tools> [...] -- <command>, e.g. compare node@18 node@20 -- npm test");
+        return ExitCode::from(1);
+    }
+    print_summary(&run_variants(input, &variants, &command, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_args() {
+        let args = ["node@18", "node@20", "--", "npm", "test"].map(String::from).to_vec();
+        let (variants, command) = split_args(&args);
+        assert_eq!(vec!("node@18", "node@20"), variants);
+        assert_eq!(vec!("npm", "test"), command);
+    }
+
+    #[test]
+    fn test_get_summary() {
+        let results = vec!(
+            VariantResult { tools: "node@18".to_string(), exit_code: Some(0), duration: Duration::from_millis(12300) },
+            VariantResult { tools: "node@20".to_string(), exit_code: Some(1), duration: Duration::from_millis(800) },
+        );
+        assert_eq!("node@18  pass  exit 0       12.3s\nnode@20  FAIL  exit 1        0.8s", get_summary(&results));
+    }
+}
@@ -0,0 +1,162 @@
+use std::process::ExitCode;
+
+use crate::executor::{AppInput, get_cmds};
+use crate::executors::plugin;
+use crate::installed;
+use crate::no_clap::{command_name, COMMANDS, option_name, OPTIONS};
+
+/// Completes options, commands and tools, `tool@` with installed versions, and files after the command.
+/// Works on the segment after the last `:`
+const BASH: &str = r#"_gg() {
+    local line="${COMP_LINE:0:COMP_POINT}"
+    local words=($line)
+    [[ "$line" == *" " ]] && words+=("")
+    local i
+    for ((i = 1; i < ${#words[@]} - 1; i++)); do
+        [[ "${words[i]}" == -* ]] || return 0
+    done
+    local cur="${COMP_WORDS[COMP_CWORD]}" head=""
+    [[ "$cur" == *:* ]] && head="${cur%:*}:" && cur="${cur##*:}"
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "__OPTIONS__" -- "$cur"))
+    elif [[ "$cur" == *@* ]]; then
+        local tool="${cur%%@*}"
+        COMPREPLY=($(compgen -P "$head$tool@" -W "$("${COMP_WORDS[0]}" completions --versions "$tool" 2>/dev/null)" -- "${cur#*@}"))
+    else
+        COMPREPLY=($(compgen -P "$head" -W "__COMMANDS__ __TOOLS__" -- "$cur"))
+    fi
+}
+complete -o default -F _gg gg gg.cmd ./gg.cmd
+"#;
+
+const ZSH: &str = r#"#compdef gg gg.cmd
+_gg() {
+    local i
+    for ((i = 2; i < CURRENT; i++)); do
+        [[ "${words[i]}" == -* ]] || { _files; return }
+    done
+    compset -P '*:'
+    if [[ "$PREFIX" == -* ]]; then
+        compadd -- __OPTIONS__
+    elif compset -P '*@'; then
+        local tool="${IPREFIX%@}"
+        compadd -- $("${words[1]}" completions --versions "${tool##*:}" 2>/dev/null)
+    else
+        compadd -- __COMMANDS__ __TOOLS__
+    fi
+}
+compdef _gg gg gg.cmd ./gg.cmd
+"#;
+
+const FISH: &str = r#"function __gg_at_command
+    for token in (commandline -opc)[2..-1]
+        string match -q -- '-*' $token; or return 1
+    end
+end
+function __gg_complete
+    set -l cur (commandline -ct)
+    set -l head (string match -r -- '^.*:' $cur)
+    set -l word (string replace -r -- '^.*:' '' $cur)
+    if string match -q -- '-*' $word
+        printf '%s\n' __OPTIONS__
+    else if string match -q -- '*@*' $word
+        set -l gg (commandline -opc)[1]
+        set -l tool (string split -m1 @ -- $word)[1]
+        for version in ($gg completions --versions $tool 2>/dev/null)
+            echo $head$tool@$version
+        end
+    else
+        printf "$head%s\n" __COMMANDS__ __TOOLS__
+    end
+end
+complete -c gg -c gg.cmd -n __gg_at_command -f -a '(__gg_complete)'
+"#;
+
+const POWERSHELL: &str = r#"Register-ArgumentCompleter -Native -CommandName gg, gg.cmd, ./gg.cmd, .\gg.cmd -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $before = $commandAst.CommandElements | Select-Object -Skip 1 | Where-Object { $_.Extent.EndOffset -lt $cursorPosition }
+    if ($before | Where-Object { -not $_.ToString().StartsWith('-') }) { return }
+    $head = ''
+    $word = $wordToComplete
+    if ($word.Contains(':')) {
+        $head = $word.Substring(0, $word.LastIndexOf(':') + 1)
+        $word = $word.Substring($head.Length)
+    }
+    if ($word.StartsWith('-')) {
+        $names = @(__OPTIONS__)
+    } elseif ($word.Contains('@')) {
+        $tool = $word.Split('@')[0]
+        $names = & $commandAst.CommandElements[0].ToString() completions --versions $tool 2>$null | ForEach-Object { "$tool@$_" }
+    } else {
+        $names = @(__COMMANDS__, __TOOLS__)
+    }
+    $names | Where-Object { $_ -like "$word*" } | ForEach-Object { [System.Management.Automation.CompletionResult]::new("$head$_") }
+}
+"#;
+
+/// Built in tools and the ones from manifests
+fn get_tools() -> Vec<String> {
+    get_cmds().into_iter().chain(plugin::get_names()).collect()
+}
+
+fn render(template: &str, join: fn(&[String]) -> String) -> String {
+    let options: Vec<String> = OPTIONS.iter().map(|(usage, _)| option_name(usage).to_string()).collect();
+    let commands: Vec<String> = COMMANDS.iter().map(|(usage, _)| command_name(usage).to_string()).collect();
+    template
+        .replace("__OPTIONS__", &join(&options))
+        .replace("__COMMANDS__", &join(&commands))
+        .replace("__TOOLS__", &join(&get_tools()))
+}
+
+fn get_script(shell: &str) -> Option<String> {
+    let words = |names: &[String]| names.join(" ");
+    let quoted = |names: &[String]| names.iter().map(|n| format!("'{n}'")).collect::<Vec<_>>().join(", ");
+    match shell {
+        "bash" => Some(render(BASH, words)),
+        "zsh" => Some(render(ZSH, words)),
+        "fish" => Some(render(FISH, words)),
+        "powershell" | "pwsh" => Some(render(POWERSHELL, quoted)),
+        _ => None,
+    }
+}
+
+/// `completions <shell>` prints the script. The scripts call `completions --versions <tool>` for installed versions
+pub fn completions(input: &AppInput, args: &[String]) -> ExitCode {
+    let script = match args.iter().map(|a| a.as_str()).collect::<Vec<_>>().as_slice() {
+        ["--versions", tool] => {
+            for version in installed::get_versions(&input.config, tool) {
+                println!("{version}");
+            }
+            return ExitCode::from(0);
+        }
+        [shell] => get_script(shell),
+        _ => None,
+    };
+    match script {
+        Some(script) => {
+            print!("{script}");
+            ExitCode::from(0)
+        }
+        None => {
+            println!("Usage: completions <shell>, where shell is bash, zsh, fish or powershell");
+            ExitCode::from(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_script() {
+        let bash = get_script("bash").unwrap();
+        assert!(bash.contains("compgen -W \"-v -vv -vvv"));
+        assert!(bash.contains(" check-update ") && bash.contains(" run "));
+        assert!(!get_script("zsh").unwrap().contains("__"));
+        assert!(get_script("powershell").unwrap().contains("'update', 'help'"));
+        assert!(get_script("tcsh").is_none());
+        assert!(bash.contains(" --log-output --vulns= ") && !bash.contains("--log-output="));
+        assert!(bash.contains(" matrix prep "));
+    }
+}
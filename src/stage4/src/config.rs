@@ -0,0 +1,567 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use serde::Deserialize;
+
+use crate::bundle::BUNDLE_DIR;
+use crate::executors::plugin::ToolManifest;
+use crate::target::Target;
+
+const CONFIG_FILE: &str = "gg.toml";
+const DEFAULT_INSTALL_DIR: &str = ".cache/gg";
+const DEFAULT_PREP_JOBS: usize = 4;
+const GITIGNORE: &str = ".gitignore";
+
+/// Project configuration from gg.toml
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct GgConfig {
+    #[serde(default)]
+    pub scripts: HashMap<String, String>,
+    /// Shell commands run with every tool in [tools] in PATH, e.g. `web = "npm run dev"`
+    #[serde(default)]
+    pub tasks: BTreeMap<String, String>,
+    #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub hermetic: HermeticConfig,
+    #[serde(default)]
+    pub path: PathConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub container: ContainerConfig,
+    #[serde(default)]
+    pub prep: PrepConfig,
+    /// Named handles for a tool and version, e.g. `java8 = "java@8"`
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    /// Names for versions, e.g. `prod-node = "20.11.1"` for `node@prod-node`. Also read from the global config
+    #[serde(default)]
+    pub versions: BTreeMap<String, String>,
+    /// Tools of the project, same as a manifest in ~/.config/gg/tools, e.g. [custom-tools.ourcli]
+    #[serde(default, rename = "custom-tools")]
+    pub custom_tools: BTreeMap<String, ToolManifest>,
+    /// Sections like [node] and [java]
+    #[serde(flatten)]
+    pub executors: HashMap<String, ExecutorConfig>,
+    /// Tools shared by all projects, set for the target when not --local
+    #[serde(skip)]
+    pub shared_install_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct HermeticConfig {
+    /// Environment variables passed through with --hermetic, in addition to the defaults
+    #[serde(default)]
+    pub env: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpConfig {
+    /// Sent instead of gg.cmd/<version>. GG_USER_AGENT takes precedence
+    pub user_agent: Option<String>,
+    /// Extra headers per host, e.g. [http.headers."mirror.corp"], sent only to that host. `${NAME}` in values is read
+    /// from the environment
+    #[serde(default)]
+    pub headers: BTreeMap<String, BTreeMap<String, String>>,
+    /// Used instead of HTTPS_PROXY / HTTP_PROXY, e.g. http://proxy.corp:3128. `${NAME}` is read from the environment
+    pub proxy: Option<String>,
+    /// Hosts to reach without the proxy, comma separated as NO_PROXY, which is used when this is not set
+    pub no_proxy: Option<String>,
+    /// Tries again on connection errors, timeouts and 5xx up to this many times, default 2. Other 4xx fail right away
+    pub retries: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct ContainerConfig {
+    /// Run in a container when there is no native build for this system, same as --container
+    #[serde(default)]
+    pub fallback: bool,
+    /// docker or podman, found in PATH when not set
+    pub engine: Option<String>,
+    /// Image per executor, without tag. The tag is the version, e.g. 18 for node@18
+    #[serde(default)]
+    pub images: HashMap<String, String>,
+}
+
+/// Scripts per executor, e.g. `post_install.node = "corepack enable"`
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct HooksConfig {
+    /// Run before a tool is downloaded
+    #[serde(default)]
+    pub pre_install: HashMap<String, String>,
+    /// Run after a tool is installed, with the tool in PATH
+    #[serde(default)]
+    pub post_install: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct PrepConfig {
+    /// Tools resolved and downloaded at the same time. GG_PREP_JOBS takes precedence
+    pub jobs: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct PathConfig {
+    /// "prepend" (default) lets gg tools shadow system ones, "append" the other way around
+    pub placement: Option<String>,
+    /// Directories removed from the inherited PATH, e.g. /usr/local/bin
+    #[serde(default)]
+    pub strip: Vec<String>,
+}
+
+/// Executor specific options. Not every executor supports every option. Unknown keys are an error, so typos of
+/// sections, e.g. [tool], fail instead of being read as an executor
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExecutorConfig {
+    /// Replaces the upstream base URL, e.g. https://npmmirror.com/mirrors/node for node. GG_<TOOL>_MIRROR wins over this
+    pub mirror: Option<String>,
+    /// JDK vendor for java: zulu (default), temurin, liberica, graalvm or corretto
+    #[cfg_attr(not(feature = "java"), allow(dead_code))]
+    pub vendor: Option<String>,
+    /// Require (true) or avoid (false) JDKs bundled with JavaFX
+    #[cfg_attr(not(feature = "java"), allow(dead_code))]
+    pub javafx: Option<bool>,
+    /// Prefer (true) or avoid (false) musl builds, instead of detecting it
+    pub musl: Option<bool>,
+    /// Only consider LTS versions
+    #[serde(default)]
+    pub lts_only: bool,
+    /// Use a system installed binary when it satisfies the version requirement
+    #[serde(default)]
+    pub system: bool,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct CacheConfig {
+    /// Remote cache checked before downloading from upstream, http(s)://
+    pub remote: Option<String>,
+    /// Upload to the remote cache after downloading from upstream
+    #[serde(default)]
+    pub upload: bool,
+    /// Where tools are installed, e.g. .gg for a folder next to gg.cmd that is easy to archive
+    #[serde(rename = "install-dir")]
+    pub install_dir: Option<String>,
+    /// Downloaded archives shared by all projects, "off" to not keep them
+    #[serde(rename = "download-dir")]
+    pub download_dir: Option<String>,
+    /// Seconds version lists are used without asking upstream, 0 to always revalidate
+    #[serde(rename = "index-ttl")]
+    pub index_ttl: Option<u64>,
+    /// Tools in .cache/gg next to gg.cmd instead of the cache shared by all projects
+    #[serde(default)]
+    pub local: bool,
+}
+
+/// GG_HOME, or gg in XDG_CACHE_HOME, ~/.cache or LOCALAPPDATA
+fn get_cache_home() -> Option<PathBuf> {
+    if let Ok(home) = env::var("GG_HOME") {
+        return Some(PathBuf::from(home));
+    }
+    env::var("XDG_CACHE_HOME").map(PathBuf::from)
+        .or(env::var("HOME").map(|home| Path::new(&home).join(".cache")))
+        .or(env::var("LOCALAPPDATA").map(PathBuf::from))
+        .ok()
+        .map(|dir| dir.join("gg"))
+}
+
+/// gg in XDG_CONFIG_HOME, ~/.config or APPDATA
+pub fn get_config_home() -> Option<PathBuf> {
+    env::var("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or(env::var("HOME").map(|home| Path::new(&home).join(".config")))
+        .or(env::var("APPDATA").map(PathBuf::from))
+        .ok()
+        .map(|dir| dir.join("gg"))
+}
+
+impl GgConfig {
+    /// gg.toml and the global config. A file that does not parse is an error, not the defaults, as every section
+    /// without a known name is read as options of an executor
+    pub fn load() -> Result<Self, String> {
+        let bundled = Path::new(BUNDLE_DIR).join(CONFIG_FILE);
+        let mut config = match fs::read_to_string(CONFIG_FILE).or_else(|_| fs::read_to_string(bundled)) {
+            Ok(text) => {
+                info!("Found {CONFIG_FILE}");
+                GgConfig::parse(&text).map_err(|e| format!("Unable to parse {CONFIG_FILE}: {e}"))?
+            }
+            Err(_) => GgConfig::default(),
+        };
+        if let Some(global) = GlobalConfig::load()? {
+            config.merge_global(global);
+        }
+        Ok(config)
+    }
+
+    /// The project wins over the global config
+    fn merge_global(&mut self, global: GlobalConfig) {
+        for (name, version) in global.versions {
+            self.versions.entry(name).or_insert(version);
+        }
+    }
+
+    /// A version alias from [versions] to the version it stands for, anything else as is
+    pub fn resolve_version(&self, version: &str) -> String {
+        self.versions.get(version).cloned().unwrap_or(version.to_string())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// GG_DOWNLOAD_DIR, [cache] download-dir, or gg/downloads in XDG_CACHE_HOME, ~/.cache or LOCALAPPDATA.
+    /// None when set to off
+    pub fn get_download_dir(&self) -> Option<PathBuf> {
+        let dir = env::var("GG_DOWNLOAD_DIR").ok().or(self.cache.download_dir.clone());
+        match dir {
+            Some(dir) if dir == "off" => None,
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => get_cache_home().map(|dir| dir.join("downloads")),
+        }
+    }
+
+    /// Version lists from upstream, gg/index next to the downloads
+    pub fn get_index_dir(&self) -> Option<PathBuf> {
+        get_cache_home().map(|dir| dir.join("index"))
+    }
+
+    /// Relative to the project. GG_INSTALL_DIR, [cache] install-dir, the shared one, or .cache/gg
+    pub fn get_install_dir(&self) -> PathBuf {
+        env::var("GG_INSTALL_DIR").ok()
+            .or(self.cache.install_dir.clone())
+            .map(PathBuf::from)
+            .or(self.shared_install_dir.clone())
+            .unwrap_or(PathBuf::from(DEFAULT_INSTALL_DIR))
+    }
+
+    /// gg/tools/<target> next to the downloads, e.g. ~/.cache/gg/tools/linux-x86_64. Not with --local or
+    /// [cache] local, then tools are in .cache/gg of the project
+    pub fn set_shared_install_dir(&mut self, target: &Target, local: bool) {
+        if local || self.cache.local {
+            return;
+        }
+        self.shared_install_dir = get_cache_home().map(|dir| dir.join("tools").join(target.key()));
+    }
+
+    pub fn is_shared_install_dir(&self) -> bool {
+        self.shared_install_dir.as_ref() == Some(&self.get_install_dir())
+    }
+
+    /// GG_PREP_JOBS, [prep] jobs, or 4. At least 1
+    pub fn get_prep_jobs(&self) -> usize {
+        env::var("GG_PREP_JOBS").ok().and_then(|jobs| jobs.parse().ok())
+            .or(self.prep.jobs)
+            .unwrap_or(DEFAULT_PREP_JOBS)
+            .max(1)
+    }
+
+    pub fn executor(&self, name: &str) -> ExecutorConfig {
+        self.executors.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Script as arguments to gg, e.g. `build = "gradle@7 assemble"` gives `["gradle@7", "assemble"]`
+    pub fn get_script_args(&self, name: &str) -> Option<Vec<String>> {
+        let script = self.scripts.get(name)?;
+        shell_words::split(script).ok().filter(|args| !args.is_empty())
+    }
+
+    /// Task as arguments to gg: run with the tools in [tools], and the task with extra arguments in the shell
+    pub fn get_task_args(&self, name: &str, args: &[String]) -> Option<Vec<String>> {
+        self.get_task_args_for(name, args, cfg!(windows))
+    }
+
+    /// With cmd /C on Windows and sh -c elsewhere, the extra arguments quoted for that shell
+    fn get_task_args_for(&self, name: &str, args: &[String], windows: bool) -> Option<Vec<String>> {
+        let task = self.tasks.get(name)?;
+        let quote = |a: &String| if windows { quote_cmd(a) } else { shell_words::quote(a).to_string() };
+        let command = vec!(task.clone()).into_iter().chain(args.iter().map(quote)).collect::<Vec<_>>().join(" ");
+        let cmds = vec!("run".to_string()).into_iter().chain(self.tools.keys().cloned()).collect::<Vec<_>>().join(":");
+        let shell = if windows { ["cmd", "/C"] } else { ["sh", "-c"] };
+        Some(vec!(cmds).into_iter().chain(shell.iter().map(|s| s.to_string())).chain([command]).collect())
+    }
+}
+
+/// An argument for cmd /C, in double quotes when it has spaces or characters cmd acts on. Quotes inside are doubled,
+/// which keeps cmd from seeing the rest as unquoted, and backslashes before a quote doubled as programs read them.
+/// cmd still expands %VAR% in quotes
+fn quote_cmd(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "\"&|<>^()%!,;=".contains(c)) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                continue;
+            }
+            '"' => quoted.push_str(&format!("{}\"\"", "\\".repeat(backslashes * 2))),
+            _ => quoted.push_str(&format!("{}{c}", "\\".repeat(backslashes))),
+        }
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Custom install dirs are added to .gitignore in git repositories, .cache is expected to be there already
+pub fn ensure_gitignored(install_dir: &Path) {
+    if install_dir == Path::new(DEFAULT_INSTALL_DIR) || install_dir.is_absolute() || !Path::new(".git").exists() {
+        return;
+    }
+    let text = fs::read_to_string(GITIGNORE).unwrap_or_default();
+    if let Some(text) = add_to_gitignore(&text, install_dir) {
+        info!("Adding {} to {GITIGNORE}", install_dir.display());
+        if let Err(e) = fs::write(GITIGNORE, text) {
+            warn!("Unable to update {GITIGNORE}: {e}");
+        }
+    }
+}
+
+fn add_to_gitignore(text: &str, install_dir: &Path) -> Option<String> {
+    let entry = format!("/{}/", install_dir.to_string_lossy().trim_matches('/'));
+    let present = text.lines().any(|line| line.trim().trim_matches('/') == entry.trim_matches('/'));
+    if present {
+        return None;
+    }
+    let separator = if text.is_empty() || text.ends_with('\n') { "" } else { "\n" };
+    Some(format!("{text}{separator}{entry}\n"))
+}
+
+/// Per user configuration, shared by all projects
+#[derive(Deserialize, Debug, Default)]
+struct GlobalConfig {
+    #[serde(default)]
+    versions: BTreeMap<String, String>,
+}
+
+impl GlobalConfig {
+    /// GG_CONFIG, or gg/config.toml in XDG_CONFIG_HOME, ~/.config or APPDATA
+    fn get_path() -> Option<PathBuf> {
+        if let Ok(path) = env::var("GG_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        get_config_home().map(|dir| dir.join("config.toml"))
+    }
+
+    fn load() -> Result<Option<Self>, String> {
+        let Some(path) = Self::get_path() else { return Ok(None) };
+        let Ok(text) = fs::read_to_string(&path) else { return Ok(None) };
+        info!("Found {}", path.display());
+        toml::from_str(&text).map(Some).map_err(|e| format!("Unable to parse {}: {e}", path.display()))
+    }
+}
+
+/// Set version of a tool in [tools] in gg.toml, keeping the rest of the file as is
+pub fn set_tool_version(name: &str, version: &str) -> Result<(), String> {
+    let text = fs::read_to_string(CONFIG_FILE).unwrap_or_default();
+    let text = set_tool_version_in_text(&text, name, version)?;
+    fs::write(CONFIG_FILE, text).map_err(|e| e.to_string())
+}
+
+fn set_tool_version_in_text(text: &str, name: &str, version: &str) -> Result<String, String> {
+    let mut doc = text.parse::<toml_edit::DocumentMut>().map_err(|e| e.to_string())?;
+    if !doc.contains_table("tools") {
+        doc["tools"] = toml_edit::table();
+    }
+    match doc["tools"][name].as_value_mut() {
+        Some(value) => {
+            let decor = value.decor().clone();
+            *value = version.into();
+            *value.decor_mut() = decor;
+        }
+        None => doc["tools"][name] = toml_edit::value(version),
+    }
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts() {
+        let config = GgConfig::parse(r#"
+[scripts]
+build = "gradle assemble"
+dev = "node server.js --port '8080 8081'"
+"#).unwrap();
+        assert_eq!(Some(vec!["gradle".to_string(), "assemble".to_string()]), config.get_script_args("build"));
+        assert_eq!(Some(["node", "server.js", "--port", "8080 8081"].map(String::from).to_vec()), config.get_script_args("dev"));
+        assert_eq!(None, config.get_script_args("test"));
+    }
+
+    #[test]
+    fn tasks() {
+        let config = GgConfig::parse(r#"
+[tools]
+node = "20"
+java = "17"
+
+[tasks]
+web = "npm ci && npm run dev"
+"#).unwrap();
+        assert_eq!(Some(["run:java:node", "sh", "-c", "npm ci && npm run dev -- --port '8080 8081'"].map(String::from).to_vec()),
+                   config.get_task_args_for("web", &["--".to_string(), "--port".to_string(), "8080 8081".to_string()], false));
+        assert_eq!(Some(["run:java:node", "cmd", "/C", "npm ci && npm run dev -- --port \"8080 8081\""].map(String::from).to_vec()),
+                   config.get_task_args_for("web", &["--".to_string(), "--port".to_string(), "8080 8081".to_string()], true));
+        assert_eq!(None, config.get_task_args("build", &[]));
+    }
+
+    #[test]
+    fn test_quote_cmd() {
+        assert_eq!("--port", quote_cmd("--port"));
+        assert_eq!("C:\\tools\\bin", quote_cmd("C:\\tools\\bin"));
+        assert_eq!("\"\"", quote_cmd(""));
+        assert_eq!("\"a & b\"", quote_cmd("a & b"));
+        assert_eq!("\"say \"\"hi\"\"\"", quote_cmd("say \"hi\""));
+        assert_eq!("\"C:\\my dir\\\\\"", quote_cmd("C:\\my dir\\"));
+        assert_eq!("\"100%\"", quote_cmd("100%"));
+    }
+
+    #[test]
+    fn unknown_keys() {
+        assert!(GgConfig::parse("[node]\nmirror = \"https://npmmirror.com/mirrors/node\"\n").is_ok());
+        assert!(GgConfig::parse("[node]\nmiror = \"https://npmmirror.com/mirrors/node\"\n").is_err());
+        assert!(GgConfig::parse("[tool]\nnode = \"20\"\n").is_err());
+        assert!(GgConfig::parse("jobs = 2\n").is_err());
+    }
+
+    #[test]
+    fn tools() {
+        let config = GgConfig::parse(r#"
+[tools]
+node = "^18"
+java = "17"
+"#).unwrap();
+        assert_eq!(Some(&"^18".to_string()), config.tools.get("node"));
+        assert_eq!(Some(&"17".to_string()), config.tools.get("java"));
+    }
+
+    #[test]
+    fn versions() {
+        let mut config = GgConfig::parse(r#"
+[versions]
+prod-node = "20.11.1"
+"#).unwrap();
+        let global: GlobalConfig = toml::from_str(r#"
+[versions]
+prod-node = "18"
+old-jdk = "8"
+"#).unwrap();
+        config.merge_global(global);
+        assert_eq!("20.11.1", config.resolve_version("prod-node"));
+        assert_eq!("8", config.resolve_version("old-jdk"));
+        assert_eq!("^18", config.resolve_version("^18"));
+    }
+
+    #[test]
+    fn executor_sections() {
+        let config = GgConfig::parse(r#"
+[tools]
+node = "^18"
+
+[node]
+mirror = "https://npmmirror.com/mirrors/node"
+lts-only = true
+
+[java]
+javafx = false
+musl = true
+"#).unwrap();
+        assert_eq!(Some("https://npmmirror.com/mirrors/node".to_string()), config.executor("node").mirror);
+        assert!(config.executor("node").lts_only);
+        assert_eq!(Some(false), config.executor("java").javafx);
+        assert_eq!(Some(true), config.executor("java").musl);
+        assert_eq!(None, config.executor("gradle").mirror);
+    }
+
+    #[test]
+    fn prep_jobs() {
+        assert_eq!(2, GgConfig::parse("[prep]\njobs = 2\n").unwrap().get_prep_jobs());
+        assert_eq!(1, GgConfig::parse("[prep]\njobs = 0\n").unwrap().get_prep_jobs());
+    }
+
+    #[test]
+    fn install_dir() {
+        let config = GgConfig::parse("[cache]\ninstall-dir = \".gg\"\n").unwrap();
+        assert_eq!(Some(".gg".to_string()), config.cache.install_dir);
+        assert_eq!(None, GgConfig::default().cache.install_dir);
+    }
+
+    #[test]
+    fn hooks() {
+        let config = GgConfig::parse(r#"
+[hooks]
+post_install.node = "corepack enable"
+post_install.java = "./scripts/trust-certs.sh"
+"#).unwrap();
+        assert_eq!(Some(&"corepack enable".to_string()), config.hooks.post_install.get("node"));
+        assert_eq!(Some(&"./scripts/trust-certs.sh".to_string()), config.hooks.post_install.get("java"));
+        assert!(config.hooks.pre_install.is_empty());
+        assert!(config.executors.is_empty());
+    }
+
+    #[test]
+    fn custom_tools() {
+        let config = GgConfig::parse(r#"
+[tools]
+ourcli = "2.1"
+
+[custom-tools.ourcli]
+url = "https://github.com/acme/ourcli/releases/download/v{version}/ourcli-{os}-{arch}.{ext}"
+bin = "ourcli"
+versions = "https://api.github.com/repos/acme/ourcli/releases"
+"#).unwrap();
+        assert_eq!("ourcli", config.custom_tools["ourcli"].bin);
+        assert_eq!("", config.custom_tools["ourcli"].name);
+        assert!(config.executors.is_empty());
+    }
+
+    #[test]
+    fn gitignore() {
+        assert_eq!(Some("/.gg/\n".to_string()), add_to_gitignore("", Path::new(".gg")));
+        assert_eq!(Some("target\n/.gg/\n".to_string()), add_to_gitignore("target", Path::new(".gg")));
+        assert_eq!(None, add_to_gitignore("target\n.gg/\n", Path::new(".gg")));
+    }
+
+    #[test]
+    fn set_tool_version_keeps_rest() {
+        let text = "# Project tools\n[tools]\nnode = \"18.1.0\" # pinned\njava = \"17\"\n";
+        let text = set_tool_version_in_text(text, "node", "18.19.0").unwrap();
+        assert_eq!("# Project tools\n[tools]\nnode = \"18.19.0\" # pinned\njava = \"17\"\n", text);
+        let text = set_tool_version_in_text("", "node", "20.0.0").unwrap();
+        assert_eq!("[tools]\nnode = \"20.0.0\"\n", text);
+    }
+
+    #[test]
+    fn shared_install_dir() {
+        let target = Target::parse("x86_64-unknown-linux-gnu");
+        let mut config = GgConfig::parse("[cache]\nlocal = true\n").unwrap();
+        config.set_shared_install_dir(&target, false);
+        assert_eq!(PathBuf::from(DEFAULT_INSTALL_DIR), config.get_install_dir());
+        let mut config = GgConfig::parse("").unwrap();
+        config.set_shared_install_dir(&target, false);
+        assert!(config.shared_install_dir.as_ref().unwrap().ends_with("tools/linux-x86_64"));
+        assert!(config.is_shared_install_dir());
+        config.cache.install_dir = Some(".gg".to_string());
+        assert_eq!(PathBuf::from(".gg"), config.get_install_dir());
+        assert!(!config.is_shared_install_dir());
+    }
+
+    #[test]
+    fn empty() {
+        let config = GgConfig::parse("").unwrap();
+        assert!(config.scripts.is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+use std::env;
+use std::io::IsTerminal;
+use std::process::Command;
+
+use log::info;
+use semver::{Op, VersionReq};
+
+use crate::executor::{AppInput, Executor, resolve_version_req, with_deps};
+use crate::target::Os;
+
+/// Project dir inside the container
+const WORKDIR: &str = "/work";
+
+pub fn is_enabled(input: &AppInput) -> bool {
+    input.no_clap.container || input.config.container.fallback
+}
+
+/// GG_CONTAINER_ENGINE, [container] engine, or docker / podman, whichever is found first
+fn get_engine(input: &AppInput) -> Option<String> {
+    env::var("GG_CONTAINER_ENGINE").ok()
+        .or(input.config.container.engine.clone())
+        .or(["docker", "podman"].iter().find(|e| which::which(e).is_ok()).map(|e| e.to_string()))
+}
+
+/// Image tags are versions like 18 or 3.9, so `^18` gives 18 and `~3.9.1` gives 3.9.1
+fn get_tag(version_req: &Option<VersionReq>) -> String {
+    version_req.as_ref()
+        .and_then(|req| req.comparators.iter().find(|c| matches!(c.op, Op::Exact | Op::Caret | Op::Tilde | Op::Wildcard)))
+        .map(|c| [Some(c.major), c.minor, c.patch].iter().flatten().map(|n| n.to_string()).collect::<Vec<_>>().join("."))
+        .unwrap_or("latest".to_string())
+}
+
+fn get_image(executor: &dyn Executor, input: &AppInput, version_req: &Option<VersionReq>) -> Option<String> {
+    let tag = get_tag(version_req);
+    match input.config.container.images.get(executor.get_name()) {
+        Some(image) => Some(format!("{image}:{tag}")),
+        None => executor.get_container_image(&tag),
+    }
+}
+
+fn get_args(engine: &str, image: &str, cwd: &str, bin: &str, app_args: &[String], tty: bool) -> Vec<String> {
+    let mut args = vec!(engine.to_string(), "run".to_string(), "--rm".to_string(), "-i".to_string());
+    if tty {
+        args.push("-t".to_string());
+    }
+    args.extend(["-v".to_string(), format!("{cwd}:{WORKDIR}"), "-w".to_string(), WORKDIR.to_string()]);
+    args.push(image.to_string());
+    args.push(bin.to_string());
+    args.extend(app_args.iter().cloned());
+    args
+}
+
+/// The tool that is run and what it depends on, as its image has those too, e.g. gradle:8 has a JDK
+fn get_covered(executor: &dyn Executor, input: &AppInput) -> Vec<String> {
+    match <dyn Executor>::new(executor.get_executor_cmd().clone()) {
+        Some(copy) => with_deps(vec!(copy), input).iter().map(|e| e.get_name().to_string()).collect(),
+        None => vec!(executor.get_name().to_string()),
+    }
+}
+
+/// Tools without a native build the image of the tool that is run does not bring
+fn get_uncovered(covered: &[String], without_native: &[String]) -> Vec<String> {
+    without_native.iter().filter(|name| !covered.contains(name)).cloned().collect()
+}
+
+/// For targets without a native build, run the first tool in a Linux container with the project dir mounted. Only
+/// when its image covers every tool in `without_native`, e.g. not for node:deno where deno has no native build
+pub fn run(executor: &dyn Executor, input: &AppInput, without_native: &[String]) -> Result<bool, String> {
+    let name = executor.get_name();
+    let uncovered = get_uncovered(&get_covered(executor, input), without_native);
+    if !uncovered.is_empty() {
+        return Err(format!("No native {} for {}, and the container runs {name} only", uncovered.join(", "), input.target.key()));
+    }
+    let image = get_image(executor, input, &resolve_version_req(executor, input))
+        .ok_or(format!("No container image for {name}. Set one with [container] images.{name} in gg.toml"))?;
+    let engine = get_engine(input).ok_or("Neither docker nor podman found")?;
+    let mut linux_input = input.clone();
+    linux_input.target.os = Os::Linux;
+    let bin = executor.get_bins(&linux_input).into_iter().next().ok_or("Binary not found")?;
+    let cwd = env::current_dir().map_err(|e| e.to_string())?;
+    let args = get_args(&engine, &image, &cwd.to_string_lossy(), &bin, &input.no_clap.app_args, std::io::stdout().is_terminal());
+    println!("No native {name} for {}, running in container {image}", input.target.key());
+    info!("Executing: {}", args.join(" "));
+    let status = Command::new(&args[0]).args(&args[1..]).status()
+        .map_err(|e| format!("Unable to run {engine}: {e}"))?;
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_tag() {
+        assert_eq!("18", get_tag(&VersionReq::parse("^18").ok()));
+        assert_eq!("3.9.1", get_tag(&VersionReq::parse("~3.9.1").ok()));
+        assert_eq!("17", get_tag(&VersionReq::parse(">=11, 17").ok()));
+        assert_eq!("latest", get_tag(&VersionReq::parse(">=11").ok()));
+        assert_eq!("latest", get_tag(&None));
+    }
+
+    #[test]
+    fn test_get_uncovered() {
+        let covered = vec!("gradle".to_string(), "java".to_string());
+        assert!(get_uncovered(&covered, &["java".to_string()]).is_empty());
+        assert_eq!(vec!("deno".to_string()), get_uncovered(&covered, &["java".to_string(), "deno".to_string()]));
+    }
+
+    #[test]
+    fn test_get_args() {
+        assert_eq!(
+            "docker run --rm -i -v /project:/work -w /work node:18 npm install",
+            get_args("docker", "node:18", "/project", "npm", &["install".to_string()], false).join(" ")
+        );
+    }
+}
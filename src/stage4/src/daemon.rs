@@ -0,0 +1,188 @@
+use std::process::ExitCode;
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::process::ExitCode;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use log::{debug, info};
+
+    /// Per project, next to the rest of gg
+    const SOCKET: &str = ".cache/gg/daemon.sock";
+    /// Version indexes and resolved versions are kept this long
+    const TTL_SECS: u64 = 600;
+    /// The daemon exits when it has not been used for an hour
+    const IDLE_SECS: u64 = 3600;
+
+    struct State {
+        values: HashMap<String, (String, Instant)>,
+        last_used: Instant,
+    }
+
+    /// One request per connection: `GET <key>`, `SET <key> <value>`, `CLEAR` or `STOP`. Values are single lines
+    fn handle(state: &Mutex<State>, line: &str) -> Option<String> {
+        let mut state = state.lock().unwrap();
+        state.last_used = Instant::now();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "GET" => Some(state.values.get(rest)
+                .filter(|(_, at)| at.elapsed() < Duration::from_secs(TTL_SECS))
+                .map(|(value, _)| value.clone())
+                .unwrap_or_default()),
+            "SET" => {
+                let (key, value) = rest.split_once(' ')?;
+                state.values.insert(key.to_string(), (value.to_string(), Instant::now()));
+                Some(String::new())
+            }
+            "CLEAR" => {
+                state.values.clear();
+                Some(String::new())
+            }
+            _ => None,
+        }
+    }
+
+    fn request(line: &str) -> Option<String> {
+        let mut stream = UnixStream::connect(SOCKET).ok()?;
+        stream.set_read_timeout(Some(Duration::from_millis(500))).ok()?;
+        stream.set_write_timeout(Some(Duration::from_millis(500))).ok()?;
+        writeln!(stream, "{line}").ok()?;
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).ok()?;
+        Some(response.trim_end_matches('\n').to_string())
+    }
+
+    pub fn get(key: &str) -> Option<String> {
+        if !Path::new(SOCKET).exists() {
+            return None;
+        }
+        request(&format!("GET {key}")).filter(|value| !value.is_empty())
+    }
+
+    pub fn set(key: &str, value: &str) {
+        if Path::new(SOCKET).exists() && !value.contains('\n') {
+            let _ = request(&format!("SET {key} {value}"));
+        }
+    }
+
+    pub fn clear() {
+        if Path::new(SOCKET).exists() {
+            let _ = request("CLEAR");
+        }
+    }
+
+    pub fn stop() -> ExitCode {
+        match request("STOP") {
+            Some(_) => {
+                println!("Daemon stopped");
+                ExitCode::from(0)
+            }
+            None => {
+                println!("No daemon running");
+                ExitCode::from(1)
+            }
+        }
+    }
+
+    pub fn serve() -> ExitCode {
+        if request("GET ping").is_some() {
+            println!("Daemon is already running");
+            return ExitCode::from(1);
+        }
+        let _ = fs::remove_file(SOCKET);
+        let _ = fs::create_dir_all(".cache/gg");
+        let listener = match UnixListener::bind(SOCKET) {
+            Ok(listener) => listener,
+            Err(e) => {
+                println!("Unable to listen on {SOCKET}: {e}");
+                return ExitCode::from(1);
+            }
+        };
+        println!("Daemon listening on {SOCKET}");
+        let state = Arc::new(Mutex::new(State { values: HashMap::new(), last_used: Instant::now() }));
+
+        let idle_state = state.clone();
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(60));
+            if idle_state.lock().unwrap().last_used.elapsed() > Duration::from_secs(IDLE_SECS) {
+                info!("Idle for {IDLE_SECS}s, stopping");
+                let _ = fs::remove_file(SOCKET);
+                std::process::exit(0);
+            }
+        });
+
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(Duration::from_millis(500)));
+            let mut line = String::new();
+            let mut reader = BufReader::new(&stream);
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+            let line = line.trim_end_matches('\n');
+            debug!("Daemon request {line}");
+            if line == "STOP" {
+                let _ = writeln!(&stream);
+                break;
+            }
+            let response = handle(&state, line).unwrap_or_default();
+            let _ = writeln!(&stream, "{response}");
+        }
+        let _ = fs::remove_file(SOCKET);
+        ExitCode::from(0)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_handle() {
+            let state = Mutex::new(State { values: HashMap::new(), last_used: Instant::now() });
+            assert_eq!(Some(String::new()), handle(&state, "GET resolved/node-abc"));
+            assert_eq!(Some(String::new()), handle(&state, "SET resolved/node-abc /project/.cache/gg/node/20.11.1"));
+            assert_eq!(Some("/project/.cache/gg/node/20.11.1".to_string()), handle(&state, "GET resolved/node-abc"));
+            assert_eq!(Some(String::new()), handle(&state, "CLEAR"));
+            assert_eq!(Some(String::new()), handle(&state, "GET resolved/node-abc"));
+            assert_eq!(None, handle(&state, "NOPE"));
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{clear, get, set};
+
+/// Not supported on Windows, gg works the same without it
+#[cfg(not(unix))]
+pub fn get(_key: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn set(_key: &str, _value: &str) {}
+
+#[cfg(not(unix))]
+pub fn clear() {}
+
+/// `daemon` runs in the foreground until `daemon stop`, or until it has been idle for an hour
+pub fn daemon(args: &[String]) -> ExitCode {
+    #[cfg(unix)]
+    {
+        match args.first().map(|s| s.as_str()) {
+            Some("stop") => unix::stop(),
+            _ => unix::serve(),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = args;
+        println!("The daemon is only supported on Linux and macOS");
+        ExitCode::from(1)
+    }
+}
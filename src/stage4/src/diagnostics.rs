@@ -0,0 +1,190 @@
+use std::fmt;
+use std::panic;
+
+use console::style;
+use log::{debug, warn};
+use reqwest::header::{ETAG, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+
+use crate::error::GgError;
+use crate::http;
+use crate::index_cache;
+use crate::target::Target;
+
+/// Error with context and suggestions, printed in the style of cargo:
+///
+/// ```text
+/// error: Unable to download node 18.19.0
+///   --> https://nodejs.org/dist/...
+///    = target: linux-x86_64
+///    = cause: connection refused
+/// help: Check your proxy settings (HTTPS_PROXY)
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostic {
+    message: String,
+    url: Option<String>,
+    notes: Vec<(&'static str, String)>,
+    help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), ..Default::default() }
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    pub fn note(mut self, name: &'static str, value: impl fmt::Display) -> Self {
+        self.notes.push((name, value.to_string()));
+        self
+    }
+
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// Cause and suggestions for a failed request
+    pub fn request_error(self, e: &reqwest::Error) -> Self {
+        let diagnostic = self.note("cause", e);
+        if e.is_connect() || e.is_timeout() {
+            diagnostic
+                .help("Check your network and proxy settings (HTTPS_PROXY / HTTP_PROXY, or proxy in [http] in gg.toml)")
+                .help("Without internet access, use `export` and `import`, or a remote cache")
+        } else if e.status().map(|s| s.as_u16() == 404).unwrap_or(false) {
+            diagnostic.help("The file might have been removed upstream. Try another version")
+        } else {
+            diagnostic
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", style("error").red().bold(), style(&self.message).bold())?;
+        if let Some(url) = &self.url {
+            write!(f, "\n  {} {url}", style("-->").blue().bold())?;
+        }
+        for (name, value) in &self.notes {
+            write!(f, "\n   {} {name}: {value}", style("=").blue().bold())?;
+        }
+        for help in &self.help {
+            write!(f, "\n{}: {help}", style("help").cyan().bold())?;
+        }
+        Ok(())
+    }
+}
+
+/// Fetch a text document (version lists etc.)
+fn get_header(response: &reqwest::Response, name: HeaderName) -> Option<String> {
+    response.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string())
+}
+
+async fn fetch(url: &str, cached: Option<&index_cache::Entry>) -> reqwest::Result<Option<index_cache::Entry>> {
+    let mut request = http::client().get(url);
+    if let Some(etag) = cached.and_then(|c| c.etag.as_ref()) {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = cached.and_then(|c| c.last_modified.as_ref()) {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = http::send(request).await?.error_for_status()?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    Ok(Some(index_cache::Entry {
+        url: url.to_string(),
+        etag: get_header(&response, ETAG),
+        last_modified: get_header(&response, LAST_MODIFIED),
+        body: response.text().await?,
+    }))
+}
+
+/// Version lists are cached on disk. Within [cache] index-ttl upstream is not asked, after that only whether it
+/// changed. When upstream is down the cached list is used, however old
+pub async fn get_text(url: &str) -> Result<String, GgError> {
+    if let Some(body) = index_cache::get_fresh(url) {
+        return Ok(body);
+    }
+    let cached = index_cache::get(url);
+    match (fetch(url, cached.as_ref()).await, cached) {
+        (Ok(Some(entry)), _) => {
+            index_cache::set(&entry);
+            Ok(entry.body)
+        }
+        (Ok(None), Some(cached)) => {
+            debug!("{url} not modified");
+            index_cache::touch(url);
+            Ok(cached.body)
+        }
+        (Ok(None), None) => Err(GgError::Other(format!("{url} answered 304 Not Modified without being asked"))),
+        (Err(e), Some(cached)) => {
+            warn!("Unable to fetch {url}, using the cached list: {e}");
+            Ok(cached.body)
+        }
+        (Err(source), None) => Err(GgError::Request { url: url.to_string(), source }),
+    }
+}
+
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, GgError> {
+    serde_json::from_str(&get_text(url).await?).map_err(|e| {
+        index_cache::remove(url);
+        GgError::parse(url, "JSON", e)
+    })
+}
+
+/// Errors are Strings, already rendered diagnostics are printed as they are
+pub fn render_error(e: &str) -> String {
+    if console::strip_ansi_codes(e).starts_with("error: ") {
+        e.to_string()
+    } else {
+        Diagnostic::new(e).to_string()
+    }
+}
+
+/// Panics (mostly unexpected responses from upstream) as diagnostics instead of a raw backtrace message
+pub fn install_panic_hook(target: &Target) {
+    let target = target.key();
+    panic::set_hook(Box::new(move |info| {
+        if let Some(diagnostic) = info.payload().downcast_ref::<Diagnostic>() {
+            eprintln!("{}", diagnostic.clone().note("target", &target));
+            return;
+        }
+        let message = info.payload().downcast_ref::<String>().cloned()
+            .or(info.payload().downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or("Unknown error".to_string());
+        let mut diagnostic = Diagnostic::new(message).note("target", &target);
+        if let Some(location) = info.location() {
+            diagnostic = diagnostic.note("location", location);
+        }
+        let diagnostic = diagnostic
+            .help("Run again with -vv for more details")
+            .help("If this keeps happening, please report it at https://github.com/eirikb/gg/issues");
+        eprintln!("{diagnostic}");
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        console::set_colors_enabled(false);
+        let diagnostic = Diagnostic::new("Unable to download node")
+            .url("https://nodejs.org/node.tar.gz")
+            .note("target", "linux-x86_64")
+            .help("Try another version");
+        assert_eq!(
+            "error: Unable to download node\n  --> https://nodejs.org/node.tar.gz\n   = target: linux-x86_64\nhelp: Try another version",
+            diagnostic.to_string()
+        );
+        assert_eq!("error: Binary not found", render_error("Binary not found"));
+        assert_eq!(diagnostic.to_string(), render_error(&diagnostic.to_string()));
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use log::{debug, warn};
+
+use crate::bloody_indiana_jones::{Checksum, checksum_of};
+use crate::config::GgConfig;
+
+/// `<download dir>/<sha256 of url and checksum>/<file name>`, so a changed checksum is a new entry
+fn get_path(dir: &Path, url: &str, checksum: &Option<Checksum>) -> Option<PathBuf> {
+    let file_name = url.rsplit('/').next().filter(|f| !f.is_empty())?;
+    let key = sha256::digest(format!("{url} {}", checksum.as_ref().map(|c| c.hash.as_str()).unwrap_or_default()));
+    Some(dir.join(&key[..32]).join(file_name))
+}
+
+/// An archive downloaded before, by this or any other project
+pub fn get(config: &GgConfig, url: &str, checksum: &Option<Checksum>) -> Option<PathBuf> {
+    let path = get_path(&config.get_download_dir()?, url, checksum)?;
+    if !path.is_file() {
+        debug!("{url} not in download cache");
+        return None;
+    }
+    if let Some(expected) = checksum {
+        if !checksum_of(&path, expected.algorithm).map(|actual| actual.eq_ignore_ascii_case(expected.hash.trim())).unwrap_or(false) {
+            warn!("Checksum mismatch for {}, downloading again", path.display());
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+    }
+    Some(path)
+}
+
+/// Copied to a temp file first, so other processes never see half an archive
+pub fn put(config: &GgConfig, url: &str, checksum: &Option<Checksum>, file: &str) {
+    let Some(path) = config.get_download_dir().and_then(|dir| get_path(&dir, url, checksum)) else { return };
+    let tmp = path.with_extension(format!("tmp{}", process::id()));
+    let res = path.parent().map(fs::create_dir_all).unwrap_or(Ok(()))
+        .and_then(|_| fs::copy(file, &tmp))
+        .and_then(|_| fs::rename(&tmp, &path));
+    match res {
+        Ok(_) => debug!("Added {url} to download cache {}", path.display()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            warn!("Unable to add {url} to download cache: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_path() {
+        let dir = Path::new("/cache");
+        let url = "https://nodejs.org/dist/v20.11.1/node-v20.11.1-linux-x64.tar.gz";
+        let path = get_path(dir, url, &None).unwrap();
+        assert!(path.starts_with(dir));
+        assert!(path.ends_with("node-v20.11.1-linux-x64.tar.gz"));
+        assert_eq!(path, get_path(dir, url, &None).unwrap());
+        assert_ne!(path, get_path(dir, url, &Some(Checksum::sha256("abc"))).unwrap());
+        assert_eq!(None, get_path(dir, "https://example.com/", &None));
+    }
+
+    /// A hit that no longer matches the published checksum is dropped, not reused
+    #[test]
+    fn test_get_checks_sha256() {
+        let dir = std::env::temp_dir().join(format!("gg-download-cache-test-{}", process::id()));
+        let mut config = GgConfig::default();
+        config.cache.download_dir = Some(dir.to_string_lossy().to_string());
+        let file = dir.join("hello.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&file, "hello").unwrap();
+        let url = "https://example.com/hello.txt";
+        let sha256 = Some(Checksum::sha256("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"));
+        put(&config, url, &sha256, &file.to_string_lossy());
+        let cached = get(&config, url, &sha256).unwrap();
+        fs::write(&cached, "tampered").unwrap();
+        assert_eq!(None, get(&config, url, &sha256));
+        assert!(!cached.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+
+/// End of life for a major version
+struct Eol {
+    major: u64,
+    eol: &'static str,
+    lts: bool,
+}
+
+const fn eol(major: u64, eol: &'static str, lts: bool) -> Eol {
+    Eol { major, eol, lts }
+}
+
+/// https://github.com/nodejs/release#release-schedule
+const NODE: [Eol; 13] = [
+    eol(10, "2021-04-30", true),
+    eol(12, "2022-04-30", true),
+    eol(14, "2023-04-30", true),
+    eol(16, "2023-09-11", true),
+    eol(17, "2022-06-01", false),
+    eol(18, "2025-04-30", true),
+    eol(19, "2023-06-01", false),
+    eol(20, "2026-04-30", true),
+    eol(21, "2024-06-01", false),
+    eol(22, "2027-04-30", true),
+    eol(23, "2025-06-01", false),
+    eol(24, "2028-04-30", true),
+    eol(25, "2026-06-01", false),
+];
+
+/// Azul Zulu support for LTS, non-LTS ends with the next feature release
+const JAVA: [Eol; 19] = [
+    eol(8, "2030-12-31", true),
+    eol(9, "2018-03-20", false),
+    eol(10, "2018-09-25", false),
+    eol(11, "2032-01-31", true),
+    eol(12, "2019-09-17", false),
+    eol(13, "2020-03-17", false),
+    eol(14, "2020-09-15", false),
+    eol(15, "2021-03-16", false),
+    eol(16, "2021-09-14", false),
+    eol(17, "2029-09-30", true),
+    eol(18, "2022-09-20", false),
+    eol(19, "2023-03-21", false),
+    eol(20, "2023-09-19", false),
+    eol(21, "2031-09-30", true),
+    eol(22, "2024-09-17", false),
+    eol(23, "2025-03-18", false),
+    eol(24, "2025-09-16", false),
+    eol(25, "2033-09-30", true),
+    eol(26, "2026-09-15", false),
+];
+
+fn get_schedule(name: &str) -> Option<&'static [Eol]> {
+    match name {
+        "node" => Some(&NODE),
+        "java" => Some(&JAVA),
+        _ => None,
+    }
+}
+
+/// Days since epoch to yyyy-mm-dd. http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub fn civil_from_days(days: i64) -> String {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    civil_from_days((secs / 86400) as i64)
+}
+
+/// Some(message) when the major version is past end-of-life
+fn get_eol_message(name: &str, major: u64, today: &str) -> Option<String> {
+    let schedule = get_schedule(name)?;
+    let eol_date = match schedule.iter().find(|e| e.major == major) {
+        Some(e) if e.eol > today => return None,
+        Some(e) => e.eol.to_string(),
+        None if major < schedule[0].major => "long ago".to_string(),
+        None => return None,
+    };
+    let mut supported = schedule.iter().filter(|e| e.lts && e.eol > today);
+    let suggestion = supported.clone().find(|e| e.major > major).or(supported.next_back())
+        .map(|e| format!(" Consider {name}@{}", e.major))
+        .unwrap_or_default();
+    Some(format!("{name} {major} reached end-of-life ({eol_date}).{suggestion}"))
+}
+
+/// Warns about end-of-life versions. Fails in strict mode
+pub fn check(name: &str, major: u64, strict: bool) -> Result<(), String> {
+    if let Some(message) = get_eol_message(name, major, &today()) {
+        if strict {
+            return Err(message);
+        }
+        warn!("{message}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!("1970-01-01", civil_from_days(0));
+        assert_eq!("2000-03-01", civil_from_days(11017));
+        assert_eq!("2024-02-29", civil_from_days(19782));
+    }
+
+    #[test]
+    fn test_eol_message() {
+        assert_eq!(None, get_eol_message("node", 20, "2025-01-01"));
+        assert_eq!(
+            Some("node 16 reached end-of-life (2023-09-11). Consider node@18".to_string()),
+            get_eol_message("node", 16, "2024-01-01")
+        );
+        assert_eq!(
+            Some("node 19 reached end-of-life (2023-06-01). Consider node@20".to_string()),
+            get_eol_message("node", 19, "2024-01-01")
+        );
+        assert_eq!(
+            Some("java 6 reached end-of-life (long ago). Consider java@8".to_string()),
+            get_eol_message("java", 6, "2024-01-01")
+        );
+        assert_eq!(None, get_eol_message("java", 99, "2024-01-01"));
+        assert_eq!(None, get_eol_message("gradle", 1, "2024-01-01"));
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::diagnostics::Diagnostic;
+use crate::target::Target;
+
+/// Failures talking to upstream (version lists, GitHub releases). Most of gg has String errors, these turn into the
+/// same rendered diagnostics with `?`
+#[derive(Debug, Error)]
+pub enum GgError {
+    #[error("Unable to fetch {url}: {source}")]
+    Request { url: String, source: reqwest::Error },
+    #[error("Unable to parse {url} as {format}: {message}")]
+    Parse { url: String, format: &'static str, message: String },
+    #[error("No {name} build available for this architecture")]
+    NoBuild { name: String, target: String },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl GgError {
+    pub fn parse(url: &str, format: &'static str, e: impl fmt::Display) -> Self {
+        GgError::Parse { url: url.to_string(), format, message: e.to_string() }
+    }
+
+    pub fn no_build(name: &str, target: &Target) -> Self {
+        GgError::NoBuild { name: name.to_string(), target: target.key() }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            GgError::Request { url, source } => {
+                let diagnostic = Diagnostic::new("Unable to fetch list of versions").url(url);
+                match source.status() {
+                    Some(status) => diagnostic.note("status", status),
+                    None => diagnostic,
+                }.request_error(source)
+            }
+            GgError::Parse { url, format, message } => Diagnostic::new(format!("Unexpected response, not valid {format}"))
+                .url(url)
+                .note("cause", message)
+                .help("The service might be having problems, try again later"),
+            GgError::NoBuild { target, .. } => Diagnostic::new(self.to_string())
+                .note("target", target)
+                .help("Try --container to run it in a Linux container"),
+            GgError::Other(message) => Diagnostic::new(message),
+        }
+    }
+}
+
+impl From<GgError> for String {
+    fn from(e: GgError) -> Self {
+        e.to_diagnostic().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_diagnostic() {
+        console::set_colors_enabled(false);
+        let e = serde_json::from_str::<Vec<String>>("<html>").unwrap_err();
+        let message: String = GgError::parse("https://example.com/index.json", "JSON", e).into();
+        assert!(message.starts_with("error: Unexpected response, not valid JSON\n  --> https://example.com/index.json\n   = cause: expected value"));
+    }
+}
@@ -1,29 +1,94 @@
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs;
 use std::fs::File;
 use std::future::Future;
-use std::io::Write;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 use indicatif::ProgressBar;
-use log::{debug, info};
+use log::{debug, info, warn};
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use which::which_in;
-use crate::bloody_indiana_jones::BloodyIndianaJones;
+use crate::bloody_indiana_jones::{BloodyIndianaJones, Checksum, COMPLETE_MARKER, HashAlgorithm};
+use crate::config;
+use crate::config::GgConfig;
+use crate::daemon;
+use crate::download_cache;
+use crate::diagnostics::Diagnostic;
+use crate::eol;
+use crate::error::GgError;
+use crate::hooks;
+use crate::http;
+use crate::install_lock;
+use crate::installed;
+use crate::vulns;
+use crate::lock;
+use crate::remote_cache::RemoteCache;
+use crate::resolve_cache;
+use crate::signatures::{self, Signature};
+use crate::suggest;
+use crate::tee;
+use crate::timings;
+use crate::tool_versions;
+use crate::lock::{GgLock, LockedDownload};
+#[cfg(feature = "caddy")]
 use crate::executors::caddy::Caddy;
+#[cfg(feature = "python")]
+use crate::executors::python::Python;
 use crate::executors::custom_command::CustomCommand;
+use crate::executors::plugin::{self, Plugin};
+#[cfg(feature = "composer")]
+use crate::executors::composer::Composer;
+#[cfg(feature = "dart")]
+use crate::executors::dart::Dart;
+#[cfg(feature = "deno")]
 use crate::executors::deno::Deno;
+#[cfg(feature = "dotnet")]
+use crate::executors::dotnet::Dotnet;
+#[cfg(feature = "elixir")]
+use crate::executors::elixir::Elixir;
+#[cfg(feature = "erlang")]
+use crate::executors::erlang::Erlang;
+#[cfg(feature = "flutter")]
+use crate::executors::flutter::Flutter;
+#[cfg(feature = "go")]
 use crate::executors::go::Go;
+#[cfg(feature = "gradle")]
 use crate::executors::gradle::Gradle;
+#[cfg(feature = "helm")]
+use crate::executors::helm::Helm;
+#[cfg(feature = "java")]
 use crate::executors::java::Java;
+#[cfg(feature = "kotlin")]
+use crate::executors::kotlin::Kotlin;
+#[cfg(feature = "kubectl")]
+use crate::executors::kubectl::Kubectl;
+#[cfg(feature = "maven")]
 use crate::executors::maven::Maven;
-use crate::executors::node::Node;
+#[cfg(feature = "node")]
+use crate::executors::node::{self, Node};
+#[cfg(feature = "node")]
+use crate::executors::npm_registry::PackageManager;
+#[cfg(feature = "openapi")]
 use crate::executors::openapigenerator::OpenAPIGenerator;
+#[cfg(feature = "php")]
+use crate::executors::php::Php;
+#[cfg(feature = "rat")]
 use crate::executors::rat::Rat;
+#[cfg(feature = "ruby")]
+use crate::executors::ruby::Ruby;
+#[cfg(feature = "rust")]
+use crate::executors::rust::Rust;
+#[cfg(feature = "terraform")]
+use crate::executors::terraform::{OpenTofu, Terraform};
 use crate::no_clap::NoClap;
+use crate::target;
 use crate::target::{Arch, Os, Target, Variant};
 
 #[derive(PartialEq, Debug, Clone)]
@@ -31,9 +96,111 @@ pub struct AppPath {
     pub install_dir: PathBuf,
 }
 
+/// A binary of a tool by the names it is run as, e.g. pip and pip3. The files are relative to the bin dirs, per OS as
+/// Windows has npm.cmd or pip.exe. The first file found is used
+#[derive(PartialEq, Debug, Clone)]
+pub struct NamedBin {
+    pub names: Vec<String>,
+    pub unix: Vec<String>,
+    pub windows: Vec<String>,
+}
+
+impl NamedBin {
+    /// The same file as the name on all OS
+    pub fn new(name: &str) -> NamedBin {
+        NamedBin { names: vec!(name.to_string()), unix: vec!(name.to_string()), windows: vec!(name.to_string()) }
+    }
+
+    #[cfg_attr(not(any(feature = "kotlin", feature = "maven", feature = "rat", feature = "python", feature = "terraform", feature = "erlang", feature = "rust")), allow(dead_code))]
+    pub fn alias(mut self, name: &str) -> NamedBin {
+        self.names.push(name.to_string());
+        self
+    }
+
+    #[cfg_attr(not(any(feature = "kotlin", feature = "maven", feature = "rat", feature = "python", feature = "terraform")), allow(dead_code))]
+    pub fn unix(mut self, files: &[&str]) -> NamedBin {
+        self.unix = files.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    #[cfg_attr(not(any(feature = "kotlin", feature = "maven", feature = "rat", feature = "node", feature = "python", feature = "terraform", feature = "erlang", feature = "rust")), allow(dead_code))]
+    pub fn windows(mut self, files: &[&str]) -> NamedBin {
+        self.windows = files.iter().map(|f| f.to_string()).collect();
+        self
+    }
+}
+
+/// The files of the named bin the command is, the first named bin when none has that name
+pub fn get_named_bin_files(named_bins: &[NamedBin], cmd: &str, os: &Os) -> Vec<String> {
+    let Some(named_bin) = named_bins.iter().find(|b| b.names.iter().any(|n| n == cmd)).or(named_bins.first()) else {
+        return vec!();
+    };
+    match os {
+        Os::Windows => named_bin.windows.clone(),
+        _ => named_bin.unix.clone(),
+    }
+}
+
+/// What a tool runs with besides its binary, env like JAVA_HOME and the dirs put first in PATH
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ExecContext {
+    pub env: HashMap<String, String>,
+    pub path_prepends: Vec<PathBuf>,
+}
+
+impl ExecContext {
+    /// The bin dirs of the install in PATH, without env
+    pub fn new(app_path: &AppPath, bin_dirs: &[String]) -> Self {
+        Self { env: HashMap::new(), path_prepends: bin_dirs.iter().map(|dir| app_path.install_dir.join(dir)).collect() }
+    }
+
+    #[cfg_attr(not(any(feature = "go", feature = "java", feature = "dotnet")), allow(dead_code))]
+    pub fn env(mut self, name: &str, value: &Path) -> Self {
+        self.env.insert(name.to_string(), value.to_string_lossy().to_string());
+        self
+    }
+
+    /// Adds the context of another tool of the same run, e.g. java for gradle. Its PATH dirs come after, so a tool is
+    /// found before what it depends on, and its env wins. Dirs already there, e.g. of the same system install, once
+    pub fn merge(&mut self, other: ExecContext) {
+        for dir in other.path_prepends {
+            if !self.path_prepends.contains(&dir) {
+                self.path_prepends.push(dir);
+            }
+        }
+        self.env.extend(other.env);
+    }
+
+    pub fn path_vars(&self) -> Vec<String> {
+        self.path_prepends.iter().map(|p| p.to_string_lossy().to_string()).collect()
+    }
+
+    /// The binary as it would be found in the PATH dirs
+    #[cfg_attr(not(feature = "node"), allow(dead_code))]
+    pub fn find(&self, bin: &str) -> Option<PathBuf> {
+        which_in(bin, Some(env::join_paths(&self.path_prepends).ok()?), ".").ok()
+    }
+}
+
+#[derive(Clone)]
 pub struct AppInput {
     pub target: Target,
     pub no_clap: NoClap,
+    pub config: GgConfig,
+    pub lock: GgLock,
+}
+
+impl AppInput {
+    /// Input with the target adjusted by the musl preference in the executor's config section
+    pub fn for_executor(&self, name: &str) -> AppInput {
+        let mut input = self.clone();
+        match self.config.executor(name).musl {
+            Some(true) if input.target.os == Os::Linux => input.target.variant = Some(Variant::Musl),
+            Some(false) => input.target.variant = None,
+            _ => {}
+        }
+        input
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -87,17 +254,50 @@ impl GgVersionReq {
     }
 }
 
+/// A version requirement and where it came from, e.g. `.nvmrc`
+#[derive(Debug, Clone)]
+pub struct VersionSource {
+    pub source: String,
+    pub version_req: VersionReq,
+}
+
+impl VersionSource {
+    pub fn new(source: &str, version_req: VersionReq) -> Self {
+        Self { source: source.to_string(), version_req }
+    }
+}
+
+/// The requirement parsed from a project file, e.g. `.ruby-version`, when it is there
+#[cfg_attr(not(any(feature = "java", feature = "kotlin", feature = "ruby", feature = "elixir")), allow(dead_code))]
+pub fn get_file_version(file: &str, parse: fn(&str) -> Option<VersionReq>) -> Option<VersionSource> {
+    let version_req = parse(&fs::read_to_string(file).ok()?)?;
+    info!("Got version {version_req} from {file}");
+    Some(VersionSource::new(file, version_req))
+}
+
+/// All requirements must match, e.g. `>=18` and `^18.2` gives `>=18, ^18.2`
+pub fn intersect_version_reqs(sources: &[VersionSource]) -> Option<VersionReq> {
+    if sources.is_empty() {
+        return None;
+    }
+    let comparators = sources.iter().flat_map(|s| s.version_req.comparators.clone()).collect();
+    Some(VersionReq { comparators })
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GgMeta {
     pub version_req: GgVersionReq,
     pub download: Download,
     pub cmd: ExecutorCmd,
+    /// get_flavor when it was installed, e.g. the JDK vendor
+    #[serde(default)]
+    pub flavor: Option<String>,
 }
 
 #[cfg(test)]
 impl AppInput {
     pub fn dummy() -> Self {
-        Self { target: Target::parse(""), no_clap: NoClap::new() }
+        Self { target: Target::parse(""), no_clap: NoClap::new(), config: GgConfig::default(), lock: GgLock::default() }
     }
 }
 
@@ -109,6 +309,15 @@ pub struct Download {
     pub arch: Option<Arch>,
     pub os: Option<Os>,
     pub variant: Option<Variant>,
+    /// Expected SHA-256 of the file, checked before extracting
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// For tools that publish another hash than SHA-256, e.g. SHA-512 for .NET
+    #[serde(default)]
+    pub checksum: Option<Checksum>,
+    /// Release date, e.g. 2024-02-13
+    #[serde(default)]
+    pub date: Option<String>,
 }
 
 impl Download {
@@ -120,10 +329,40 @@ impl Download {
             arch: Some(Arch::Any),
             variant,
             tags: HashSet::new(),
+            sha256: None,
+            checksum: None,
+            date: None,
         };
     }
 }
 
+/// Lines of `<sha256>  <file>`, as in SHASUMS256.txt. `*<file>` is the same file, read in binary mode
+pub fn get_sha256_from_shasums(text: &str, file: &str) -> Option<String> {
+    text.lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == file)
+        .map(|(sha256, _)| sha256.to_string())
+}
+
+/// A file with only the hash, or lines of `<sha256>  <file>` with one for `file`
+pub fn parse_sha256(text: &str, file: &str) -> Option<String> {
+    let sha256 = match text.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [sha256] => sha256.to_string(),
+        _ => get_sha256_from_shasums(text, file)?,
+    };
+    Some(sha256.to_lowercase()).filter(|s| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The SHA-256 of a download from the checksum file at `url`. Errors instead of skipping the check, so a download is
+/// not used unchecked because the checksum file could not be read
+pub async fn fetch_sha256(url: &str, download: &Download) -> Result<String, String> {
+    let file = download.download_url.rsplit('/').next().unwrap_or_default();
+    let error = |e: &reqwest::Error| Diagnostic::new(format!("Unable to get the checksum of {file}")).url(url).request_error(e).to_string();
+    let res = http::get(url).await.and_then(|res| res.error_for_status()).map_err(|e| error(&e))?;
+    let text = res.text().await.map_err(|e| error(&e))?;
+    parse_sha256(&text, file).ok_or(Diagnostic::new(format!("No SHA-256 of {file}")).url(url).to_string())
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExecutorCmd {
     pub cmd: String,
@@ -139,25 +378,76 @@ impl ExecutorCmd {
     }
 }
 
+type NewExecutor = fn(ExecutorCmd) -> Box<dyn Executor>;
+
+/// Every built in executor, behind cargo features, all enabled by default (full)
+fn get_executors() -> Vec<NewExecutor> {
+    vec!(
+        #[cfg(feature = "node")] |executor_cmd| Box::new(Node { executor_cmd }),
+        #[cfg(feature = "gradle")] |executor_cmd| Box::new(Gradle::new(executor_cmd)),
+        #[cfg(feature = "java")] |executor_cmd| Box::new(Java { executor_cmd }),
+        #[cfg(feature = "maven")] |executor_cmd| Box::new(Maven { executor_cmd }),
+        #[cfg(feature = "openapi")] |executor_cmd| Box::new(OpenAPIGenerator { executor_cmd }),
+        #[cfg(feature = "rat")] |executor_cmd| Box::new(Rat { executor_cmd }),
+        |executor_cmd| Box::new(CustomCommand { executor_cmd }),
+        #[cfg(feature = "deno")] |executor_cmd| Box::new(Deno { executor_cmd }),
+        #[cfg(feature = "go")] |executor_cmd| Box::new(Go { executor_cmd }),
+        #[cfg(feature = "caddy")] |executor_cmd| Box::new(Caddy { executor_cmd }),
+        #[cfg(feature = "python")] |executor_cmd| Box::new(Python { executor_cmd }),
+        #[cfg(feature = "kotlin")] |executor_cmd| Box::new(Kotlin { executor_cmd }),
+        #[cfg(feature = "terraform")] |executor_cmd| Box::new(Terraform { executor_cmd }),
+        #[cfg(feature = "terraform")] |executor_cmd| Box::new(OpenTofu { executor_cmd }),
+        #[cfg(feature = "kubectl")] |executor_cmd| Box::new(Kubectl { executor_cmd }),
+        #[cfg(feature = "helm")] |executor_cmd| Box::new(Helm { executor_cmd }),
+        #[cfg(feature = "rust")] |executor_cmd| Box::new(Rust { executor_cmd }),
+        #[cfg(feature = "dotnet")] |executor_cmd| Box::new(Dotnet { executor_cmd }),
+        #[cfg(feature = "dart")] |executor_cmd| Box::new(Dart { executor_cmd }),
+        #[cfg(feature = "flutter")] |executor_cmd| Box::new(Flutter { executor_cmd }),
+        #[cfg(feature = "ruby")] |executor_cmd| Box::new(Ruby { executor_cmd }),
+        #[cfg(feature = "php")] |executor_cmd| Box::new(Php { executor_cmd }),
+        #[cfg(feature = "composer")] |executor_cmd| Box::new(Composer { executor_cmd }),
+        #[cfg(feature = "erlang")] |executor_cmd| Box::new(Erlang { executor_cmd }),
+        #[cfg(feature = "elixir")] |executor_cmd| Box::new(Elixir { executor_cmd }),
+        #[cfg(feature = "pnpm")] |executor_cmd| Box::new(PackageManager::new("pnpm", executor_cmd)),
+        #[cfg(feature = "yarn")] |executor_cmd| Box::new(PackageManager::new("yarn", executor_cmd)),
+    )
+}
+
+/// The commands an executor is run as, the names of its named bins or else its name
+pub fn get_executor_cmds(executor: &dyn Executor) -> Vec<String> {
+    let names: Vec<String> = executor.get_named_bins().into_iter().flat_map(|b| b.names).collect();
+    if names.is_empty() { vec!(executor.get_name().to_string()) } else { names }
+}
+
+/// Per built in executor the commands it is run as. Made once as some executors read project files when made
+fn get_executor_table() -> &'static Vec<(Vec<String>, NewExecutor)> {
+    static EXECUTOR_TABLE: OnceLock<Vec<(Vec<String>, NewExecutor)>> = OnceLock::new();
+    EXECUTOR_TABLE.get_or_init(|| get_executors().into_iter().map(|new| {
+        let executor = new(ExecutorCmd { cmd: String::new(), version: None, include_tags: HashSet::new(), exclude_tags: HashSet::new() });
+        (get_executor_cmds(executor.as_ref()), new)
+    }).collect())
+}
+
+/// Every command with a built in executor
+pub fn get_cmds() -> Vec<String> {
+    get_executor_table().iter().flat_map(|(cmds, _)| cmds.clone()).collect()
+}
+
 impl dyn Executor {
     pub fn new(executor_cmd: ExecutorCmd) -> Option<Box<Self>> {
-        match executor_cmd.cmd.as_str() {
-            "node" | "npm" | "npx" => Some(Box::new(Node { executor_cmd })),
-            "gradle" => Some(Box::new(Gradle::new(executor_cmd))),
-            "java" => Some(Box::new(Java { executor_cmd })),
-            "maven" | "mvn" => Some(Box::new(Maven { executor_cmd })),
-            "openapi" => Some(Box::new(OpenAPIGenerator { executor_cmd })),
-            "rat" | "ra" => Some(Box::new(Rat { executor_cmd })),
-            "run" => Some(Box::new(CustomCommand { executor_cmd })),
-            "deno" => Some(Box::new(Deno { executor_cmd })),
-            "go" => Some(Box::new(Go { executor_cmd })),
-            "caddy" => Some(Box::new(Caddy { executor_cmd })),
-            _ => None,
+        #[cfg(feature = "node")]
+        if matches!(executor_cmd.cmd.as_str(), "npm" | "npx") && node::is_npm_pinned() {
+            return Some(Box::new(PackageManager::new("npm", executor_cmd)));
+        }
+        match get_executor_table().iter().find(|(cmds, _)| cmds.contains(&executor_cmd.cmd)) {
+            Some((_, new)) => Some(new(executor_cmd)),
+            None => plugin::get(&executor_cmd.cmd).map(|manifest| -> Box<Self> { Box::new(Plugin { executor_cmd, manifest }) }),
         }
     }
 
     pub fn get_url_matches(&self, urls: &Vec<Download>, input: &AppInput) -> Vec<Download> {
-        get_url_matches(urls, input, self)
+        let version_req = self.get_executor_cmd().version.as_ref().map(|v| v.to_version_req());
+        get_url_matches(urls, input, self, &version_req)
     }
 }
 
@@ -166,26 +456,43 @@ pub trait Executor {
     fn get_version_req(&self) -> Option<VersionReq> {
         None
     }
-    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>>;
-    fn get_bins(&self, input: &AppInput) -> Vec<String>;
+    /// Version requirements found in project files. Override when there are several places to look
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        self.get_version_req().map(|v| VersionSource::new("project files", v)).into_iter().collect()
+    }
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>>;
+    /// The binaries of a tool run by more than one name, e.g. node, npm and npx
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!()
+    }
+    /// Of get_named_bins the one the tool is run as
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        get_named_bin_files(&self.get_named_bins(), &self.get_executor_cmd().cmd, &input.target.os)
+    }
     fn get_name(&self) -> &str;
-    fn get_deps(&self) -> Vec<&str> {
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
         vec![]
     }
     fn get_default_include_tags(&self) -> HashSet<String> {
         HashSet::new()
     }
+    /// Tells apart builds of the same version from different sources, e.g. the JDK vendor. Part of the install folder
+    fn get_flavor(&self, _input: &AppInput) -> Option<String> {
+        None
+    }
     fn get_default_exclude_tags(&self) -> HashSet<String> {
         HashSet::new()
     }
-    fn get_env(&self, _app_path: &AppPath) -> HashMap<String, String> {
-        HashMap::new()
-    }
-
     fn get_bin_dirs(&self) -> Vec<String> {
         vec!["bin".to_string(), ".".to_string()]
     }
 
+    /// The bin dirs of the install in PATH. Tools that need env, e.g. JAVA_HOME, add it. `deps` is the context of the
+    /// deps, e.g. of python for node with --node-gyp
+    fn get_exec_context(&self, _input: &AppInput, app_path: &AppPath, _deps: &ExecContext) -> ExecContext {
+        ExecContext::new(app_path, &self.get_bin_dirs())
+    }
+
     fn customize_args(&self, input: &AppInput, _app_path: &AppPath) -> Vec<String> {
         input.no_clap.app_args.clone()
     }
@@ -196,65 +503,364 @@ pub trait Executor {
     fn post_download(&self, _download_file_path: String) -> bool {
         true
     }
-    fn post_prep(&self, _cache_path: &str) {}
+    /// On the unpacked install in `cache_path`, before it is moved to `install_dir`. An error leaves no install
+    fn post_prep(&self, _cache_path: &str, _install_dir: &str) -> Result<(), String> {
+        Ok(())
+    }
+    /// Version of a system installed binary, for reusing it instead of downloading
+    fn get_system_version(&self, _bin_path: &Path) -> Option<GgVersion> {
+        None
+    }
+    /// Expected SHA-256 of a download. Override when checksums are published separately, e.g. SHASUMS256.txt, with
+    /// [fetch_sha256]. An error fails the install, None is for tools that publish no checksum
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move { Ok(download.sha256.clone()) })
+    }
+    /// Linux image for running in a container when there is no native build, e.g. node:18
+    fn get_container_image(&self, _tag: &str) -> Option<String> {
+        None
+    }
+    /// Where the signature of a download is, for --verify-signatures. None when the tool publishes none
+    fn get_signature(&self, _download: &Download) -> Option<Signature> {
+        None
+    }
+    /// Files the version requirement is read from, used to tell when a resolved version can be reused
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec![]
+    }
 }
 
-fn get_executor_app_path(_executor: &dyn Executor, _input: &AppInput, path: &str) -> Option<AppPath> {
+/// The tools asked for, then what they depend on and so on, each tool once. E.g. gradle:maven gets one java
+pub fn with_deps(mut executors: Vec<Box<dyn Executor>>, input: &AppInput) -> Vec<Box<dyn Executor>> {
+    let mut i = 0;
+    while i < executors.len() {
+        let deps: Vec<String> = executors[i].get_deps(input).iter().map(|d| d.to_string()).collect();
+        for dep in deps {
+            if executors.iter().any(|e| e.get_name() == dep) {
+                continue;
+            }
+            if let Some(executor) = <dyn Executor>::new(ExecutorCmd {
+                cmd: dep,
+                version: None,
+                include_tags: Default::default(),
+                exclude_tags: Default::default(),
+            }) {
+                executors.push(executor);
+            }
+        }
+        i += 1;
+    }
+    executors
+}
+
+pub fn read_meta(install_dir: &Path) -> Option<GgMeta> {
+    let json = fs::read_to_string(install_dir.join("gg-meta.json")).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn get_executor_app_path(_executor: &dyn Executor, input: &AppInput, path: &str) -> Option<AppPath> {
     info!( "Trying to find {path}");
-    if let Ok(app_path) = get_app_path(path) {
-        Some(app_path)
+    get_app_path(input, path).ok()
+}
+
+/// Version from command line wins. Otherwise [tools] in gg.toml, .tool-versions and whatever the executor finds in project files
+pub fn resolve_version_sources(executor: &dyn Executor, input: &AppInput) -> Vec<VersionSource> {
+    let executor_cmd = executor.get_executor_cmd();
+    if let Some(ver) = &executor_cmd.version {
+        return vec!(VersionSource::new("command line", ver.to_version_req()));
+    }
+    let mut sources = vec!();
+    if let Some(ver) = input.config.tools.get(executor.get_name()).and_then(|v| GgVersionReq::new(&input.config.resolve_version(v))) {
+        info!("Got version {} from gg.toml", ver.to_string());
+        sources.push(VersionSource::new("gg.toml", ver.to_version_req()));
+    }
+    if let Some(version_req) = tool_versions::get_version_req(executor.get_name()) {
+        sources.push(VersionSource::new(".tool-versions", version_req));
+    }
+    sources.extend(executor.get_version_sources());
+    sources
+}
+
+pub fn resolve_version_req(executor: &dyn Executor, input: &AppInput) -> Option<VersionReq> {
+    intersect_version_reqs(&resolve_version_sources(executor, input))
+}
+
+fn get_conflict_message(name: &str, sources: &[VersionSource]) -> String {
+    let sources = sources.iter().map(|s| format!("{} ({})", s.source, s.version_req)).collect::<Vec<_>>().join(", ");
+    format!("Conflicting version requirements for {name}: {sources}. No version satisfies all of them")
+}
+
+/// What tells installs of the same version apart, the tags, flavor and post_install hook, e.g. _ijre_temurin. A hook
+/// changes the install, so one installed with it is not used by projects without it or with another one
+pub fn get_install_suffix(executor: &dyn Executor, input: &AppInput) -> String {
+    let executor_cmd = executor.get_executor_cmd();
+    let mut include_tags: Vec<&String> = executor_cmd.include_tags.iter().collect();
+    let mut exclude_tags: Vec<&String> = executor_cmd.exclude_tags.iter().collect();
+    include_tags.sort();
+    exclude_tags.sort();
+    include_tags.iter().map(|t| format!("_i{t}"))
+        .chain(exclude_tags.iter().map(|t| format!("_e{t}")))
+        .chain(executor.get_flavor(input).map(|f| format!("_{f}")))
+        .chain(input.config.hooks.post_install.get(executor.get_name()).map(|script| format!("_hook{}", &sha256::digest(script.as_str())[..8])))
+        .collect()
+}
+
+/// Relative to the install dir, one per version, tags and flavor, e.g. node/20.11.1 or java/17.0.10+7_temurin. By the
+/// version requirement for downloads without a version
+pub fn get_install_path(executor: &dyn Executor, input: &AppInput, version: Option<&GgVersion>, version_req: &Option<VersionReq>) -> PathBuf {
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => version_req.as_ref().map(|v| v.to_string()).unwrap_or("*".to_string())
+            .replace("*", "_star_").replace("^", "_hat_").replace(", ", "_"),
+    };
+    Path::new(executor.get_name()).join(version + &get_install_suffix(executor, input))
+}
+
+/// The binary in the bin dirs of an installed tool, as it would be found in PATH
+pub fn find_bin(executor: &dyn Executor, input: &AppInput, app_path: &AppPath) -> Option<PathBuf> {
+    let bin_dirs = executor.get_bin_dirs().iter().map(|dir| app_path.install_dir.join(dir)).collect::<Vec<_>>();
+    let paths = env::join_paths(bin_dirs).ok()?;
+    executor.get_bins(input).iter().find_map(|bin| which_in(bin, Some(&paths), ".").ok())
+}
+
+/// Locked version from gg.lock, as long as it satisfies the version requirement
+pub fn get_locked_version_req(executor: &dyn Executor, input: &AppInput, version_req: &Option<VersionReq>) -> Option<VersionReq> {
+    let locked = input.lock.tools.get(executor.get_name())?;
+    let version = GgVersion::new(&locked.version)?;
+    if let Some(version_req) = version_req {
+        if !version_req.matches(&version.to_version()) {
+            info!("Locked version {} does not match {version_req}. Ignoring gg.lock", locked.version);
+            return None;
+        }
+    }
+    info!("Using locked version {}", locked.version);
+    VersionReq::parse(format!("={}", version.to_string()).as_str()).ok()
+}
+
+/// The download in gg.lock for the target, when the locked version is used, so the versions aren't fetched
+fn get_locked_download(executor: &dyn Executor, input: &AppInput, version_req: &Option<VersionReq>) -> Option<Download> {
+    let locked = input.lock.tools.get(executor.get_name())?;
+    let version = GgVersion::new(&locked.version)?;
+    if !version_req.as_ref()?.matches(&version.to_version()) {
+        return None;
+    }
+    let download = locked.targets.get(&input.target.key())?;
+    Some(Download {
+        version: Some(version),
+        tags: HashSet::new(),
+        download_url: download.url.clone(),
+        arch: Some(input.target.arch),
+        os: Some(input.target.os),
+        variant: input.target.variant,
+        sha256: download.sha256.clone().filter(|sha256| sha256.len() == 64),
+        // gg.lock from before typed checksums has the SHA-512 of .NET as sha256
+        checksum: download.sha256.as_ref().filter(|hash| hash.len() == 128).map(|hash| Checksum { algorithm: HashAlgorithm::Sha512, hash: hash.clone() }),
+        date: None,
+    })
+}
+
+/// System installed binary satisfying the version requirement. Install dir is the parent of the bin dir
+fn find_system_app_path(executor: &dyn Executor, input: &AppInput, version_req: &Option<VersionReq>) -> Option<AppPath> {
+    let bin = executor.get_bins(input).into_iter().next()?;
+    let bin_path = which::which(&bin).ok()?;
+    if target::is_wsl() && target::is_windows_mount(&bin_path.to_string_lossy()) {
+        warn!("Ignoring Windows {} in WSL", bin_path.display());
+        return None;
+    }
+    let bin_path = bin_path.canonicalize().ok()?;
+    if bin_path.starts_with(env::current_dir().ok()?.join(input.config.get_install_dir())) {
+        return None;
+    }
+    let version = executor.get_system_version(&bin_path)?;
+    let matches = version_req.as_ref().map(|r| r.matches(&version.to_version())).unwrap_or(true);
+    info!("Found system {} {} at {}", executor.get_name(), version.to_string(), bin_path.display());
+    if !matches {
+        return None;
+    }
+    let install_dir = bin_path.parent()?.parent()?.to_path_buf();
+    Some(AppPath { install_dir })
+}
+
+/// E.g. `v18.19.0` from node or `openjdk version "17.0.8" 2023-07-18` from java
+#[cfg_attr(not(any(feature = "java", feature = "node", feature = "python")), allow(dead_code))]
+pub fn parse_system_version(output: &str) -> Option<GgVersion> {
+    let re = regex::Regex::new(r"(\d+)(?:\.(\d+))?(?:\.(\d+))?(?:_(\d+))?").unwrap();
+    let captures = re.captures(output)?;
+    let part = |i: usize| captures.get(i).map(|m| m.as_str()).unwrap_or("0");
+    // Java 8 and older are 1.8.0_292
+    let version = if part(1) == "1" && captures.get(4).is_some() {
+        format!("{}.{}.{}", part(2), part(3), part(4))
     } else {
-        None
+        format!("{}.{}.{}", part(1), part(2), part(3))
+    };
+    GgVersion::new(&version)
+}
+
+fn get_no_match_diagnostic(name: &str, input: &AppInput, version_req: &Option<VersionReq>, nearest: &[Download]) -> Diagnostic {
+    let closest = nearest.iter().filter_map(|d| d.version.as_ref().map(|v| v.to_string())).collect::<Vec<_>>();
+    let version_req = version_req.as_ref().map(|v| v.to_string()).unwrap_or("*".to_string());
+    let diagnostic = Diagnostic::new(format!("No {name} version matches {version_req}"))
+        .note("target", input.target.key());
+    if closest.is_empty() {
+        diagnostic.help(format!("{name} might not be available for this target"))
+    } else {
+        diagnostic.help(format!("Pick another version, closest for this target are {}. Or run with --yes to use {}", closest.join(", "), closest[0]))
     }
 }
 
 pub async fn prep(executor: &dyn Executor, input: &AppInput, pb: &ProgressBar) -> Result<AppPath, String> {
+    let start = Instant::now();
+    let res = prep_executor(executor, input, pb).await;
+    timings::record(executor.get_name(), timings::PREP, start);
+    if let Ok(app_path) = &res {
+        installed::mark_used(&app_path.install_dir);
+    }
+    res
+}
+
+/// The version exists, just not for this target. Used for deciding on running in a container instead
+pub async fn only_for_other_targets(executor: &dyn Executor, input: &AppInput) -> Result<bool, GgError> {
+    let input = &input.for_executor(executor.get_name());
+    let version_req = resolve_version_req(executor, input);
+    let urls = get_download_urls(executor, input).await?;
+    let any_target = urls.iter().any(|u| match (&version_req, &u.version) {
+        (Some(version_req), Some(version)) => version_req.matches(&version.to_version()),
+        (Some(_), None) => false,
+        (None, _) => true,
+    });
+    Ok(any_target && get_url_matches(&urls, input, executor, &version_req).is_empty())
+}
+
+/// Timed version of get_download_urls, kept warm by the daemon when it runs
+pub async fn get_download_urls(executor: &dyn Executor, input: &AppInput) -> Result<Vec<Download>, GgError> {
+    let start = Instant::now();
+    let name = executor.get_name();
+    // With the version asked for, as it can change the index, e.g. gradle ignoring the wrapper or the .NET channels
+    let key = format!("index/{}", sha256::digest(format!("{name} {} {:?} {:?} {:?}", input.target.key(), input.config.executor(name), resolve_version_req(executor, input), executor.get_flavor(input))));
+    let cached = daemon::get(&key).and_then(|json| serde_json::from_str::<Vec<Download>>(&json).ok());
+    let urls = match cached {
+        Some(urls) => urls,
+        None => {
+            let urls = executor.get_download_urls(input).await?;
+            if let Ok(json) = serde_json::to_string(&urls) {
+                daemon::set(&key, &json);
+            }
+            urls
+        }
+    };
+    timings::record(name, timings::INDEX, start);
+    Ok(urls)
+}
+
+async fn prep_executor(executor: &dyn Executor, input: &AppInput, pb: &ProgressBar) -> Result<AppPath, String> {
+    let input = &input.for_executor(executor.get_name());
     if let Some(app_path) = executor.custom_prep(input) {
         return Ok(app_path);
     }
 
-    let executor_cmd = &executor.get_executor_cmd();
-    let version_req = if let Some(ver) = &executor_cmd.version {
-        Some(ver.to_version_req())
-    } else if let Some(ver) = executor.get_version_req() {
-        Some(ver)
-    } else {
-        None
-    };
-    let version_req_str = &version_req.as_ref().map(|v| v.to_string()).unwrap_or("*".to_string());
-    let path_path = Path::new(executor.get_name()).join(
-        executor.get_name().to_string() + &version_req_str.as_str().replace("*", "_star_").replace("^", "_hat_")
-            + executor_cmd.include_tags.iter().map(|t| format!("i{t}")).collect::<Vec<String>>().join("_").as_str()
-            + executor_cmd.exclude_tags.iter().map(|t| format!("e{t}")).collect::<Vec<String>>().join("_").as_str()
-    );
-    let path = path_path.to_str().unwrap();
+    let resolve_key = resolve_cache::get_key(executor, input);
+    if input.no_clap.vuln_policy == "off" {
+        if let Some(app_path) = resolve_cache::get(&resolve_key) {
+            if let Some(version) = read_meta(&app_path.install_dir).and_then(|meta| meta.download.version) {
+                eol::check(executor.get_name(), version.to_version().major, input.no_clap.strict)?;
+            }
+            record_lock(executor.get_name(), input, &app_path.install_dir);
+            return Ok(app_path);
+        }
+    }
 
-    let app_path = get_executor_app_path(executor, input, path);
+    let version_sources = resolve_version_sources(executor, input);
+    let version_req = intersect_version_reqs(&version_sources);
+    if input.no_clap.system || input.config.executor(executor.get_name()).system {
+        if let Some(app_path) = find_system_app_path(executor, input, &version_req) {
+            return Ok(app_path);
+        }
+    }
+    let version_req = get_locked_version_req(executor, input, &version_req).or(version_req);
+    let version_req_str = &version_req.as_ref().map(|v| v.to_string()).unwrap_or("*".to_string());
 
     let name = executor.get_name();
 
     pb.set_prefix(String::from(name));
 
+    let installed = installed::find_version(&input.config, name, &get_install_suffix(executor, input), &version_req);
+    let app_path = installed.and_then(|dir| get_executor_app_path(executor, input, &Path::new(name).join(dir).to_string_lossy()));
+
     match app_path {
-        Some(app_path_ok) if app_path_ok.install_dir.exists() => return Ok(app_path_ok),
+        Some(app_path_ok) if app_path_ok.install_dir.exists() => {
+            if let Some(version) = read_meta(&app_path_ok.install_dir).and_then(|meta| meta.download.version) {
+                eol::check(name, version.to_version().major, input.no_clap.strict)?;
+                if input.no_clap.vuln_policy != "off" {
+                    let urls = get_download_urls(executor, input).await?;
+                    vulns::check(name, &version, &urls, &input.no_clap.vuln_policy).await?;
+                }
+            }
+            record_lock(name, input, &app_path_ok.install_dir);
+            resolve_cache::set(&resolve_key, &app_path_ok);
+            return Ok(app_path_ok);
+        }
         _ => {
             info!("{name} not found in cache. Download time");
         }
     }
 
-    pb.set_message(format!("Fetching versions"));
+    let locked_download = match input.no_clap.vuln_policy.as_str() {
+        "off" => get_locked_download(executor, input, &version_req),
+        _ => None,
+    };
+    let (url, urls) = match locked_download {
+        Some(download) => {
+            info!("Using {} from gg.lock", download.download_url);
+            (download, vec!())
+        }
+        None => {
+            pb.set_message("Fetching versions");
 
-    let urls = executor.get_download_urls(input).await;
-    pb.set_message(format!("{} versions", &urls.len()));
-    debug!( "{:?}", urls);
+            let urls = get_download_urls(executor, input).await?;
+            pb.set_message(format!("{} versions", &urls.len()));
+            debug!( "{:?}", urls);
 
-    if urls.is_empty() {
-        panic!("Did not find any download URL!");
-    }
+            if urls.is_empty() {
+                return Err(Diagnostic::new(format!("Did not find any versions of {name}"))
+                    .note("target", input.target.key())
+                    .help("Check your network and proxy settings (HTTPS_PROXY / HTTP_PROXY, or proxy in [http] in gg.toml)")
+                    .to_string());
+            }
+            if !urls.iter().any(|u| u.arch.map(|arch| input.target.can_run(arch)).unwrap_or(false)) {
+                return Err(GgError::no_build(name, &input.target).into());
+            }
 
-    let urls_match = get_url_matches(&urls, input, executor);
+            let urls_match = get_url_matches(&urls, input, executor, &version_req);
+            if urls_match.is_empty() && version_sources.len() > 1 {
+                let any_match = version_sources.iter().all(|s| !get_url_matches(&urls, input, executor, &Some(s.version_req.clone())).is_empty());
+                if any_match {
+                    return Err(get_conflict_message(name, &version_sources));
+                }
+            }
 
-    let url = urls_match.first();
+            let url = match urls_match.first() {
+                Some(url) => url.clone(),
+                None => {
+                    let nearest = suggest::get_nearest(&version_req, &get_url_matches(&urls, input, executor, &None), 5);
+                    let interactive = !input.no_clap.ci && io::stdin().is_terminal();
+                    match pb.suspend(|| suggest::pick(name, &nearest, input.no_clap.yes, interactive)) {
+                        Some(url) => {
+                            eprintln!("Using {name} {} instead", url.version.as_ref().map(|v| v.to_string()).unwrap_or_default());
+                            url
+                        }
+                        None => return Err(get_no_match_diagnostic(name, input, &version_req, &nearest).to_string()),
+                    }
+                }
+            };
+            (url, urls)
+        }
+    };
+    let url = Some(&url);
+
+    if let Some(version) = url.and_then(|u| u.version.clone()) {
+        eol::check(name, version.to_version().major, input.no_clap.strict)?;
+        vulns::check(name, &version, &urls, &input.no_clap.vuln_policy).await?;
+    }
 
     let url_string = if let Some(url) = url {
         pb.set_prefix(format!("{name} {}", url.version.clone().map(|v| v.0).unwrap_or("".to_string())));
@@ -265,35 +871,187 @@ pub async fn prep(executor: &dyn Executor, input: &AppInput, pb: &ProgressBar) -
 
     debug!("{:?}", url_string);
 
-    let cache_path = format!(".cache/gg/{path}");
-    let bloody_indiana_jones = BloodyIndianaJones::new(url_string.to_string(), cache_path.clone(), pb.clone());
-    bloody_indiana_jones.download().await;
+    let path_path = get_install_path(executor, input, url.and_then(|u| u.version.as_ref()), &version_req);
+    let path = path_path.to_str().unwrap();
+    // Another gg might be installing the same one, e.g. CI jobs sharing a cache. Waits, and then uses that install
+    let _install_lock = install_lock::acquire(&input.config.get_install_dir().join(path), pb).await;
+    if let Some(app_path) = get_executor_app_path(executor, input, path) {
+        info!("{url_string} is already installed in {}", app_path.install_dir.display());
+        record_lock(name, input, &app_path.install_dir);
+        resolve_cache::set(&resolve_key, &app_path);
+        return Ok(app_path);
+    }
+
+    let signature = match url {
+        Some(download) if input.no_clap.verify_signatures => Some(executor.get_signature(download).ok_or(signatures::unavailable(name))?),
+        _ => None,
+    };
+
+    let install_dir = input.config.get_install_dir();
+    config::ensure_gitignored(&install_dir);
+    let cache_path = install_dir.join(path).to_string_lossy().to_string();
+    let download_start = Instant::now();
+    // The one from gg.lock or the version list first, else the published one. Before the cache, so hits are checked too
+    let checksum = match url {
+        Some(download) => match (&download.sha256, &download.checksum) {
+            (Some(sha256), _) => Some(Checksum::sha256(sha256)),
+            (None, Some(checksum)) => Some(checksum.clone()),
+            (None, None) => executor.get_checksum(download).await?.map(|sha256| Checksum::sha256(&sha256)),
+        },
+        None => None,
+    };
+    let cached = download_cache::get(&input.config, url_string, &checksum);
+    let remote_cache = RemoteCache::from_config(&input.config);
+    let from_remote_cache = match &remote_cache {
+        Some(remote_cache) if cached.is_none() => remote_cache.exists(url_string).await,
+        _ => false,
+    };
+    let download_url = match &remote_cache {
+        Some(remote_cache) if from_remote_cache => {
+            info!("Found {url_string} in remote cache");
+            remote_cache.get_url(url_string)
+        }
+        _ => url_string.to_string(),
+    };
+    if let Some(script) = input.config.hooks.pre_install.get(name) {
+        hooks::run("pre_install", name, script, None, &HashMap::new())?;
+    }
+    let bloody_indiana_jones = BloodyIndianaJones::new(download_url, cache_path.clone(), pb.clone());
+    match &cached {
+        Some(cached) => {
+            info!("Found {url_string} in {}", cached.display());
+            bloody_indiana_jones.copy_from(cached)?;
+        }
+        None => {
+            bloody_indiana_jones.download().await?;
+            match &checksum {
+                Some(checksum) => bloody_indiana_jones.verify(checksum)?,
+                None => debug!("No checksum for {url_string} (skipping check)"),
+            }
+        }
+    }
+    if let Some(signature) = &signature {
+        pb.set_message("Verifying signature");
+        let file_name = url_string.rsplit('/').next().unwrap_or_default();
+        signatures::verify(signature, Path::new(&bloody_indiana_jones.file_path), file_name).await?;
+    }
+    let locked_sha256 = checksum.as_ref().filter(|c| c.algorithm == HashAlgorithm::Sha256).map(|c| c.hash.clone())
+        .or_else(|| sha256::try_digest(Path::new(&bloody_indiana_jones.file_path)).ok());
     if !executor.post_download(bloody_indiana_jones.file_path.clone()) {
         return Err("Post download failed".to_string());
     }
-    bloody_indiana_jones.unpack_and_all_that_stuff().await;
-
-    if let Some(download) = url {
-        let download = download;
-        let meta = GgMeta {
-            download: download.clone(),
-            version_req: GgVersionReq(version_req_str.to_string()),
-            cmd: executor.get_executor_cmd().clone(),
-        };
-        let meta_path = Path::new(&cache_path).join("gg-meta.json");
-        if let Ok(json) = serde_json::to_string(&meta) {
-            if let Ok(mut file) = File::create(meta_path) {
-                let _ = file.write_all(json.as_bytes());
+    if cached.is_none() {
+        if let Some(remote_cache) = &remote_cache {
+            if !from_remote_cache {
+                remote_cache.upload(url_string, &bloody_indiana_jones.file_path).await;
             }
         }
+        download_cache::put(&input.config, url_string, &checksum, &bloody_indiana_jones.file_path);
+    }
+    timings::record(name, timings::DOWNLOAD, download_start);
+    let extract_start = Instant::now();
+    bloody_indiana_jones.unpack_and_all_that_stuff(|staging| {
+        if let Some(download) = url {
+            // The SHA-256 of the archive, for gg.lock also when the install is used later or by another project
+            let mut download = download.clone();
+            download.sha256 = locked_sha256;
+            let meta = GgMeta {
+                download,
+                version_req: GgVersionReq(version_req_str.to_string()),
+                cmd: executor.get_executor_cmd().clone(),
+                flavor: executor.get_flavor(input),
+            };
+            let meta_path = Path::new(staging).join("gg-meta.json");
+            if let Ok(json) = serde_json::to_string(&meta) {
+                if let Ok(mut file) = File::create(meta_path) {
+                    let _ = file.write_all(json.as_bytes());
+                }
+            }
+        }
+        executor.post_prep(staging, &cache_path)
+    }).await?;
+
+    let app_path = get_executor_app_path(executor, input, path).ok_or("Binary not found".to_string())?;
+    if let Some(script) = input.config.hooks.post_install.get(name) {
+        if let Err(e) = run_post_install(executor, input, &app_path, script).await {
+            // Removed so the hook runs again on the next try
+            let _ = fs::remove_dir_all(&app_path.install_dir);
+            return Err(e);
+        }
+    }
+    record_lock(name, input, &app_path.install_dir);
+    timings::record(name, timings::EXTRACT, extract_start);
+    resolve_cache::set(&resolve_key, &app_path);
+    Ok(app_path)
+}
+
+/// The download and digest of the install in gg.lock, when the tool is locked to its version and they are missing for
+/// the target. Also for installs from before, e.g. by another project sharing the cache
+fn record_lock(name: &str, input: &AppInput, install_dir: &Path) {
+    let Some(download) = read_meta(install_dir).map(|meta| meta.download) else { return };
+    let Some(version) = download.version.map(|v| v.to_string()) else { return };
+    let target_key = input.target.key();
+    let missing = input.lock.tools.get(name)
+        .filter(|locked| locked.version == version)
+        .map(|locked| !locked.targets.contains_key(&target_key) || !locked.digests.contains_key(&target_key))
+        .unwrap_or(false);
+    if missing {
+        lock::record_digest(name, &version, &target_key, install_dir);
+        lock::record_download(name, &version, &target_key, LockedDownload { url: download.download_url, sha256: download.sha256 });
     }
+}
 
-    executor.post_prep(cache_path.as_str());
+/// The dep as given on the command line, e.g. java@17 of java@17:gradle, else as it resolves on its own
+fn get_dep_executor(input: &AppInput, dep: &str) -> Option<Box<dyn Executor>> {
+    let from_cmds = input.no_clap.cmds.iter()
+        .filter_map(|cmd| <dyn Executor>::new(ExecutorCmd {
+            cmd: cmd.cmd.clone(),
+            version: GgVersionReq::new(cmd.version.as_deref().unwrap_or_default()),
+            include_tags: cmd.include_tags.clone(),
+            exclude_tags: cmd.exclude_tags.clone(),
+        }))
+        .find(|executor| executor.get_name() == dep);
+    from_cmds.or_else(|| <dyn Executor>::new(ExecutorCmd { cmd: dep.to_string(), version: None, include_tags: HashSet::new(), exclude_tags: HashSet::new() }))
+}
 
-    get_executor_app_path(executor, input, path).ok_or("Binary not found".to_string())
+/// The context of the deps of a tool and of theirs, e.g. JAVA_HOME for gradle, prepped as for running the tool. Paths
+/// are absolute, as what the tool runs may leave the project folder
+pub async fn get_deps_exec_context(executor: &dyn Executor, input: &AppInput) -> Result<ExecContext, String> {
+    let mut exec_context = ExecContext::default();
+    for dep in executor.get_deps(input).iter().filter_map(|dep| get_dep_executor(input, dep)) {
+        let app_path = Box::pin(prep(&*dep, input, &ProgressBar::hidden())).await?;
+        let app_path = AppPath { install_dir: std::path::absolute(&app_path.install_dir).unwrap_or(app_path.install_dir) };
+        let deps = Box::pin(get_deps_exec_context(&*dep, input)).await?;
+        exec_context.merge(dep.get_exec_context(input, &app_path, &deps));
+        exec_context.merge(deps);
+    }
+    Ok(exec_context)
 }
 
-fn get_url_matches(urls: &Vec<Download>, input: &AppInput, executor: &dyn Executor) -> Vec<Download> {
+/// In the provisioned environment, with the bin dirs of the tool and its deps in PATH and their env, e.g. JAVA_HOME
+async fn run_post_install(executor: &dyn Executor, input: &AppInput, app_path: &AppPath, script: &str) -> Result<(), String> {
+    let deps = get_deps_exec_context(executor, input).await?;
+    let mut exec_context = executor.get_exec_context(input, app_path, &deps);
+    exec_context.merge(deps);
+    let path = get_path(input, exec_context.path_vars(), &env::var("PATH").unwrap_or_default());
+    hooks::run("post_install", executor.get_name(), script, Some(&path), &exec_context.env)
+}
+
+pub fn get_url_matches(urls: &[Download], input: &AppInput, executor: &dyn Executor, version_req: &Option<VersionReq>) -> Vec<Download> {
+    let urls_match = get_url_matches_for_arch(urls, input, executor, version_req, input.target.arch);
+    match input.target.get_emulated_arch() {
+        Some(arch) if urls_match.is_empty() => {
+            debug!("No {:?} build of {}, looking for {arch:?} to run emulated", input.target.arch, executor.get_name());
+            get_url_matches_for_arch(urls, input, executor, version_req, arch)
+        }
+        _ => urls_match,
+    }
+}
+
+fn get_url_matches_for_arch(urls: &[Download], input: &AppInput, executor: &dyn Executor, version_req: &Option<VersionReq>, target_arch: Arch) -> Vec<Download> {
+    // --lts is for all tools, but only those that have LTS releases
+    let lts_only = input.config.executor(executor.get_name()).lts_only
+        || (input.no_clap.lts && urls.iter().any(|u| u.tags.contains("lts")));
     let mut urls_match = urls.iter().filter(|u| {
         if let Some(t_var) = input.target.variant {
             if let Some(u_var) = u.variant {
@@ -319,13 +1077,17 @@ fn get_url_matches(urls: &Vec<Download>, input: &AppInput, executor: &dyn Execut
             return false;
         }
         if let Some(arch) = u.arch {
-            if arch != Arch::Any && arch != input.target.arch {
+            if arch != Arch::Any && arch != target_arch {
                 return false;
             }
         } else {
             return false;
         }
 
+        if lts_only && !u.tags.contains("lts") {
+            return false;
+        }
+
         let cmd = executor.get_executor_cmd();
         for tag in &cmd.include_tags {
             if !u.tags.contains(tag.as_str()) {
@@ -347,9 +1109,9 @@ fn get_url_matches(urls: &Vec<Download>, input: &AppInput, executor: &dyn Execut
                 return false;
             }
         }
-        if let Some(version_req) = &cmd.version {
+        if let Some(version_req) = version_req {
             if let Some(version) = &u.version {
-                if version_req.to_version_req().matches(&version.to_version()) {
+                if version_req.matches(&version.to_version()) {
                     return true;
                 }
             }
@@ -363,40 +1125,135 @@ fn get_url_matches(urls: &Vec<Download>, input: &AppInput, executor: &dyn Execut
     urls_match.into_iter().map(|d| d.clone()).collect()
 }
 
-fn get_app_path(path: &str) -> Result<AppPath, String> {
+/// The marker, or gg-meta.json for installs from before the marker
+pub fn is_complete(install_dir: &Path) -> bool {
+    install_dir.join(COMPLETE_MARKER).exists() || install_dir.join("gg-meta.json").exists()
+}
+
+fn get_app_path(input: &AppInput, path: &str) -> Result<AppPath, String> {
     let path = env::current_dir()
         .map_err(|_| "Current dir not found")?
-        .join(".cache/gg")
+        .join(input.config.get_install_dir())
         .join(path);
 
-    if path.exists() {
+    if is_complete(&path) {
         Ok(AppPath { install_dir: path })
     } else {
         Err("Binary not found".to_string())
     }
 }
 
-pub async fn try_run(input: &AppInput, executor: &dyn Executor, app_path: AppPath, path_vars: Vec<String>, env_vars: HashMap<String, String>) -> Result<bool, String> {
-    let args = executor.customize_args(&input, &app_path);
-    let path_string = &env::var("PATH").unwrap_or("".to_string());
-    let paths = env::join_paths(path_vars).unwrap().to_str().unwrap().to_string();
-    let all_paths = vec!(paths, path_string.to_string()).join(match env::consts::OS {
+/// Passed through with --hermetic, so the tools still work as the user (home, temp, locale, terminal)
+const HERMETIC_ENV: [&str; 17] = [
+    "HOME", "USER", "LOGNAME", "LANG", "LC_ALL", "TERM", "TZ", "TMPDIR", "TEMP", "TMP",
+    "USERPROFILE", "APPDATA", "LOCALAPPDATA", "SYSTEMROOT", "WINDIR", "COMSPEC", "PATHEXT",
+];
+
+/// With --hermetic only the environment variables in HERMETIC_ENV and [hermetic] env in gg.toml
+fn get_hermetic_env(input: &AppInput) -> HashMap<String, String> {
+    env::vars()
+        .filter(|(key, _)| HERMETIC_ENV.iter().any(|k| k.eq_ignore_ascii_case(key)) || input.config.hermetic.env.contains(key))
+        .collect()
+}
+
+/// PATH with the gg bin dirs placed according to [path] in gg.toml. Only gg bin dirs with --hermetic
+pub fn get_path(input: &AppInput, path_vars: Vec<String>, inherited: &str) -> String {
+    let separator = match env::consts::OS {
         "windows" => ";",
         _ => ":",
-    });
+    };
+    if input.no_clap.hermetic {
+        return path_vars.join(separator);
+    }
+    let strip = &input.config.path.strip;
+    let inherited = inherited.split(separator)
+        .filter(|p| !p.is_empty() && !strip.iter().any(|s| s.trim_end_matches(['/', '\\']) == p.trim_end_matches(['/', '\\'])))
+        .map(|p| p.to_string());
+    let all_paths: Vec<String> = match input.config.path.placement.as_deref() {
+        Some("append") => inherited.chain(path_vars).collect(),
+        Some("prepend") | None => path_vars.into_iter().chain(inherited).collect(),
+        Some(placement) => {
+            warn!("Unknown PATH placement {placement}, using prepend");
+            path_vars.into_iter().chain(inherited).collect()
+        }
+    };
+    if target::is_wsl() {
+        return windows_mounts_last(all_paths).join(separator);
+    }
+    all_paths.join(separator)
+}
+
+/// In WSL Windows PATH is appended with /mnt/c/..., which can contain e.g. a Windows npm that would start node.exe
+fn windows_mounts_last(paths: Vec<String>) -> Vec<String> {
+    let (windows, linux): (Vec<String>, Vec<String>) = paths.into_iter().partition(|p| target::is_windows_mount(p));
+    linux.into_iter().chain(windows).collect()
+}
+
+/// The bin gg provides, when another one with the same name comes first in PATH
+fn get_shadowed(bin: &str, bin_path: &Path, gg_paths: &str) -> Option<PathBuf> {
+    let gg_bin_path = which_in(bin, Some(gg_paths), ".").ok()?;
+    let same = match (gg_bin_path.canonicalize(), bin_path.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => gg_bin_path == bin_path,
+    };
+    if same {
+        None
+    } else {
+        Some(gg_bin_path)
+    }
+}
+
+pub async fn try_run(input: &AppInput, executor: &dyn Executor, app_path: AppPath, exec_context: ExecContext) -> Result<bool, String> {
+    let start = Instant::now();
+    let args = executor.customize_args(&input, &app_path);
+    let path_vars = exec_context.path_vars();
+    let gg_paths = env::join_paths(&path_vars).ok().and_then(|p| p.into_string().ok()).unwrap_or_default();
+    let all_paths = get_path(input, path_vars, &env::var("PATH").unwrap_or("".to_string()));
     info!("PATH: {all_paths}");
     let bins = executor.get_bins(&input);
     info!("Trying to find these bins: {}", bins.join(","));
+    // Never execute Windows binaries from WSL
+    let lookup_paths = if target::is_wsl() {
+        all_paths.split(':').filter(|p| !target::is_windows_mount(p)).collect::<Vec<_>>().join(":")
+    } else {
+        all_paths.clone()
+    };
     for bin in bins {
-        let bin_paths = which_in(bin, Some(&all_paths), ".");
+        let bin_paths = which_in(&bin, Some(&lookup_paths), ".");
+        if bin_paths.is_err() {
+            if let Ok(windows_bin_path) = which_in(&bin, Some(&all_paths), ".") {
+                warn!("Only found Windows {} in WSL PATH, not using it. Is PATH contaminated with Windows paths?", windows_bin_path.display());
+            }
+        }
         if let Ok(bin_path) = bin_paths {
+            if let Some(gg_bin_path) = get_shadowed(&bin, &bin_path, &gg_paths) {
+                let message = format!("{} shadows {} provided by gg", bin_path.display(), gg_bin_path.display());
+                if input.no_clap.fail_on_shadow {
+                    return Err(format!("{message}. Failing because of --fail-on-shadow"));
+                }
+                warn!("{message}");
+            }
             info!("Executing: {:?}. With args:{:?}", bin_path, args);
             let mut command = Command::new(&bin_path);
-            let res = command
+            if input.no_clap.hermetic {
+                command.env_clear().envs(get_hermetic_env(input));
+            }
+            command
                 .env("PATH", all_paths)
-                .envs(env_vars)
-                .args(args)
-                .spawn().map_err(|e| e.to_string())?.wait().map_err(|_| "eh")?.success();
+                .envs(&exec_context.env)
+                .args(args);
+            timings::record(executor.get_name(), timings::EXEC, start);
+            timings::report(input).await;
+            // Replace gg with the tool, so signals and process tree are the same as running the tool directly
+            #[cfg(unix)]
+            if input.no_clap.log_output.is_none() {
+                use std::os::unix::process::CommandExt;
+                return Err(format!("Unable to execute {}: {}", bin_path.display(), command.exec()));
+            }
+            let res = match &input.no_clap.log_output {
+                Some(log_dir) => tee::run_tee(&mut command, log_dir, executor.get_name()).map_err(|e| e.to_string())?,
+                None => command.spawn().map_err(|e| e.to_string())?.wait().map_err(|_| "eh")?.success(),
+            };
             if !res {
                 info!("Unable to execute {}", bin_path.display());
             }
@@ -405,3 +1262,200 @@ pub async fn try_run(input: &AppInput, executor: &dyn Executor, app_path: AppPat
     }
     Err("Binary not found".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_install_path() {
+        let mut input = AppInput::dummy();
+        let cmd = ExecutorCmd { cmd: "run".to_string(), include_tags: ["b", "a"].map(String::from).into(), exclude_tags: ["c"].map(String::from).into(), ..ExecutorCmd::dummy() };
+        let executor = CustomCommand { executor_cmd: cmd };
+        let version = GgVersion::new("20.11.1");
+        assert_eq!(Path::new("custom_command/20.11.1_ia_ib_ec"), get_install_path(&executor, &input, version.as_ref(), &None));
+        assert_eq!(Path::new("custom_command/_hat_20_ia_ib_ec"), get_install_path(&executor, &input, None, &VersionReq::parse("^20").ok()));
+        input.config.hooks.post_install.insert("custom_command".to_string(), "corepack enable".to_string());
+        let suffix = get_install_suffix(&executor, &input);
+        assert!(suffix.starts_with("_ia_ib_ec_hook") && suffix.len() == "_ia_ib_ec_hook".len() + 8, "{}", suffix);
+    }
+
+    #[test]
+    fn test_intersect_version_reqs() {
+        let sources = vec!(
+            VersionSource::new("package.json engines", VersionReq::parse(">=18").unwrap()),
+            VersionSource::new(".nvmrc", VersionReq::parse("^18.2").unwrap()),
+        );
+        let version_req = intersect_version_reqs(&sources).unwrap();
+        assert!(version_req.matches(&Version::parse("18.19.0").unwrap()));
+        assert!(!version_req.matches(&Version::parse("18.1.0").unwrap()));
+        assert!(!version_req.matches(&Version::parse("20.0.0").unwrap()));
+        assert!(intersect_version_reqs(&[]).is_none());
+    }
+
+    #[test]
+    fn test_conflict_message() {
+        let sources = vec!(
+            VersionSource::new("package.json engines", VersionReq::parse(">=20").unwrap()),
+            VersionSource::new(".nvmrc", VersionReq::parse("16").unwrap()),
+        );
+        assert_eq!(
+            "Conflicting version requirements for node: package.json engines (>=20), .nvmrc (^16). No version satisfies all of them",
+            get_conflict_message("node", &sources)
+        );
+    }
+
+    #[test]
+    fn test_hermetic_env() {
+        env::set_var("GG_TEST_HERMETIC", "1");
+        env::set_var("GG_TEST_NOT_HERMETIC", "1");
+        let mut input = AppInput::dummy();
+        input.config.hermetic.env = vec!("GG_TEST_HERMETIC".to_string());
+        let env_vars = get_hermetic_env(&input);
+        assert!(env_vars.contains_key("GG_TEST_HERMETIC"));
+        assert!(!env_vars.contains_key("GG_TEST_NOT_HERMETIC"));
+        assert!(!env_vars.contains_key("PATH"));
+    }
+
+    #[test]
+    fn test_get_named_bin_files() {
+        let named_bins = vec!(
+            NamedBin::new("python").alias("python3").unix(&["python3", "python"]).windows(&["python.exe"]),
+            NamedBin::new("pip").alias("pip3").windows(&["pip.exe"]),
+        );
+        assert_eq!(vec!("pip"), get_named_bin_files(&named_bins, "pip3", &Os::Linux));
+        assert_eq!(vec!("pip.exe"), get_named_bin_files(&named_bins, "pip", &Os::Windows));
+        assert_eq!(vec!("python3", "python"), get_named_bin_files(&named_bins, "python", &Os::Mac));
+        assert_eq!(vec!("python.exe"), get_named_bin_files(&named_bins, "other", &Os::Windows));
+        assert!(get_named_bin_files(&[], "pip", &Os::Linux).is_empty());
+    }
+
+    #[test]
+    fn test_exec_context_merge() {
+        let mut exec_context = ExecContext {
+            env: HashMap::from([("GRADLE_USER_HOME".to_string(), "a".to_string())]),
+            path_prepends: vec!(PathBuf::from("gradle")),
+        };
+        exec_context.merge(ExecContext {
+            env: HashMap::from([("JAVA_HOME".to_string(), "b".to_string())]),
+            path_prepends: vec!(PathBuf::from("java"), PathBuf::from("gradle")),
+        });
+        assert_eq!(vec!("gradle", "java"), exec_context.path_vars());
+        assert_eq!(Some(&"b".to_string()), exec_context.env.get("JAVA_HOME"));
+        assert_eq!(2, exec_context.env.len());
+        let app_path = AppPath { install_dir: PathBuf::from("go") };
+        let exec_context = ExecContext::new(&app_path, &["bin".to_string()]).env("GOROOT", &app_path.install_dir);
+        assert_eq!(vec!(PathBuf::from("go").join("bin")), exec_context.path_prepends);
+        assert_eq!(Some(&"go".to_string()), exec_context.env.get("GOROOT"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_get_path() {
+        let mut input = AppInput::dummy();
+        input.no_clap.hermetic = false;
+        let gg_paths = vec!(".cache/gg/node/bin".to_string());
+        assert_eq!(".cache/gg/node/bin:/usr/local/bin:/usr/bin", get_path(&input, gg_paths.clone(), "/usr/local/bin:/usr/bin"));
+        input.config.path.placement = Some("append".to_string());
+        input.config.path.strip = vec!("/usr/local/bin/".to_string());
+        assert_eq!("/usr/bin:.cache/gg/node/bin", get_path(&input, gg_paths.clone(), "/usr/local/bin:/usr/bin"));
+        input.no_clap.hermetic = true;
+        assert_eq!(".cache/gg/node/bin", get_path(&input, gg_paths, "/usr/local/bin:/usr/bin"));
+    }
+
+    #[test]
+    fn test_parse_system_version() {
+        assert_eq!("18.19.0", parse_system_version("v18.19.0\n").unwrap().to_string());
+        assert_eq!("17.0.8", parse_system_version("openjdk version \"17.0.8\" 2023-07-18").unwrap().to_string());
+        assert_eq!("8.0.292", parse_system_version("openjdk version \"1.8.0_292\"").unwrap().to_string());
+        assert_eq!("21.0.0", parse_system_version("openjdk version \"21\" 2023-09-19").unwrap().to_string());
+        assert!(parse_system_version("nope").is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_get_shadowed() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = env::temp_dir().join(format!("gg-shadow-test-{}", std::process::id()));
+        for sub in ["system", "gg"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+            fs::write(dir.join(sub).join("tool"), "").unwrap();
+            fs::set_permissions(dir.join(sub).join("tool"), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let gg_paths = dir.join("gg").to_str().unwrap().to_string();
+        assert_eq!(Some(dir.join("gg/tool")), get_shadowed("tool", &dir.join("system/tool"), &gg_paths));
+        assert_eq!(None, get_shadowed("tool", &dir.join("gg/tool"), &gg_paths));
+        assert_eq!(None, get_shadowed("other", &dir.join("system/other"), &gg_paths));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(feature = "gradle", feature = "maven", feature = "java"))]
+    #[test]
+    fn test_with_deps() {
+        let executors = ["gradle", "maven"].iter()
+            .filter_map(|cmd| <dyn Executor>::new(ExecutorCmd { cmd: cmd.to_string(), ..ExecutorCmd::dummy() }))
+            .collect();
+        let names: Vec<String> = with_deps(executors, &AppInput::dummy()).iter().map(|e| e.get_name().to_string()).collect();
+        assert_eq!(vec!("gradle", "maven", "java"), names);
+    }
+
+    #[test]
+    fn test_get_cmds() {
+        let cmds = get_cmds();
+        for cmd in &cmds {
+            let executor = <dyn Executor>::new(ExecutorCmd { cmd: cmd.to_string(), ..ExecutorCmd::dummy() }).expect(cmd);
+            assert!(get_executor_cmds(executor.as_ref()).contains(cmd), "{}", cmd);
+        }
+        for new in get_executors() {
+            let executor = new(ExecutorCmd::dummy());
+            for cmd in get_executor_cmds(executor.as_ref()) {
+                assert!(cmds.contains(&cmd), "{}", cmd);
+            }
+        }
+        for cmd in ["mvn", "ra", "kotlinc", "tofu", "pip3", "erl", "iex", "run", "bundle"] {
+            assert!(cmds.contains(&cmd.to_string()), "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn test_get_dep_executor() {
+        let mut input = AppInput::dummy();
+        input.no_clap = NoClap::parse(vec!("java@17:gradle".to_string()));
+        let java = get_dep_executor(&input, "java").unwrap();
+        assert_eq!("17", java.get_executor_cmd().version.as_ref().unwrap().to_string());
+        assert!(get_dep_executor(&input, "node").unwrap().get_executor_cmd().version.is_none());
+    }
+
+    /// gg commands are matched before tools, a command with the name of a tool would hide it
+    #[test]
+    fn test_commands_do_not_shadow_tools() {
+        let cmds = get_cmds();
+        for (usage, _) in crate::no_clap::COMMANDS {
+            let command = crate::no_clap::command_name(usage);
+            assert!(!cmds.contains(&command.to_string()), "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_get_sha256_from_shasums() {
+        let text = "0123abcd  node-v20.11.1-darwin-arm64.tar.gz\n4567ef01  node-v20.11.1-linux-x64.tar.gz\n";
+        assert_eq!(Some("4567ef01".to_string()), get_sha256_from_shasums(text, "node-v20.11.1-linux-x64.tar.gz"));
+        assert_eq!(None, get_sha256_from_shasums(text, "node-v20.11.1-linux-x64.tar.xz"));
+        assert_eq!(Some("0123abcd".to_string()), get_sha256_from_shasums("0123abcd *dartsdk.zip\n", "dartsdk.zip"));
+    }
+
+    #[test]
+    fn test_parse_sha256() {
+        let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(Some(sha256.to_string()), parse_sha256(&format!("{}\n", sha256.to_uppercase()), "tool.zip"));
+        assert_eq!(Some(sha256.to_string()), parse_sha256(&format!("{sha256}  tool.zip\n{}  other.zip\n", "0".repeat(64)), "tool.zip"));
+        assert_eq!(None, parse_sha256(&format!("{sha256}  a.zip\n{sha256}  b.zip\n"), "tool.zip"));
+        assert_eq!(None, parse_sha256("<html>Not Found</html>", "tool.zip"));
+    }
+
+    #[test]
+    fn test_windows_mounts_last() {
+        let paths = ["/mnt/c/Program Files/nodejs", "/usr/bin", "/mnt/c/Windows", "/bin"].map(String::from).to_vec();
+        assert_eq!(["/usr/bin", "/bin", "/mnt/c/Program Files/nodejs", "/mnt/c/Windows"].map(String::from).to_vec(), windows_mounts_last(paths));
+    }
+}
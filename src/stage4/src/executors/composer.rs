@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::error::GgError;
+use crate::executor::{AppInput, AppPath, Download, Executor, ExecutorCmd, fetch_sha256};
+use crate::http;
+use crate::mirror::mirror_url;
+use crate::target::{Arch, Os, Variant};
+
+pub const COMPOSER_DL: &str = "https://getcomposer.org/download";
+const RELEASE_PAGES: u32 = 2;
+
+pub struct Composer {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// getcomposer.org/download/2.7.1/composer.phar, 2.7.0-RC1 is tagged rc
+fn to_download(base: &str, tag: &str) -> Option<Download> {
+    let mut download = Download::new(format!("{base}/{tag}/composer.phar"), tag, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(Os::Any);
+    download.arch = Some(Arch::Any);
+    if let Some((_, pre)) = tag.split_once('-') {
+        download.tags.insert(pre.trim_end_matches(|c: char| c.is_ascii_digit()).to_lowercase());
+    }
+    Some(download)
+}
+
+impl Executor for Composer {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    /// Tags of composer/composer, the phar from getcomposer.org
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let base = mirror_url(input, "composer", COMPOSER_DL);
+            Ok(http::get_releases("composer", "composer", RELEASE_PAGES, 100).await?.iter()
+                .filter_map(|release| to_download(&base, &release.tag_name))
+                .collect())
+        })
+    }
+
+    /// <url>.sha256sum, `<hash>  composer.phar`
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256sum", download.download_url), download).await.map(Some)
+        })
+    }
+
+    /// The phar is run with php from the php dependency
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "php.exe",
+            _ => "php"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "composer"
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
+        vec!("php")
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["alpha", "beta", "rc"].into_iter().map(|s| s.to_string()).collect()
+    }
+
+    fn customize_args(&self, input: &AppInput, app_path: &AppPath) -> Vec<String> {
+        let phar = app_path.install_dir.join("composer.phar");
+        vec!(phar.to_string_lossy().to_string()).into_iter().chain(input.no_clap.app_args.iter().cloned()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_download() {
+        let download = to_download(COMPOSER_DL, "2.7.1").unwrap();
+        assert_eq!("https://getcomposer.org/download/2.7.1/composer.phar", download.download_url);
+        assert!(download.tags.is_empty());
+        assert!(to_download(COMPOSER_DL, "2.7.0-RC1").unwrap().tags.contains("rc"));
+    }
+}
@@ -4,8 +4,9 @@ use std::pin::Pin;
 
 use semver::VersionReq;
 
+use crate::error::GgError;
 use crate::Executor;
-use crate::executor::{AppInput, AppPath, Download, ExecutorCmd};
+use crate::executor::{AppInput, AppPath, Download, ExecutorCmd, NamedBin};
 
 pub struct CustomCommand {
     pub executor_cmd: ExecutorCmd,
@@ -20,14 +21,19 @@ impl Executor for CustomCommand {
         None
     }
 
-    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
-        Box::pin(async move { vec!() })
+    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move { Ok(vec!()) })
     }
 
     fn get_bins(&self, input: &AppInput) -> Vec<String> {
         vec![input.no_clap.app_args[0].as_str().to_string()]
     }
 
+    /// Run as run, the binary is the first argument
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(NamedBin::new("run"))
+    }
+
     fn get_name(&self) -> &str {
         "custom_command"
     }
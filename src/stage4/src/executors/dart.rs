@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use log::{info, warn};
+use regex::Regex;
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256, intersect_version_reqs, VersionSource};
+use crate::mirror::with_mirror;
+use crate::target::{Arch, Os, Target, Variant};
+
+pub const DART_ARCHIVE: &str = "https://storage.googleapis.com/dart-archive";
+/// The JSON API of the bucket, for listing the versions
+const DART_ARCHIVE_LIST: &str = "https://storage.googleapis.com/storage/v1/b/dart-archive/o";
+const CHANNELS: [&str; 2] = ["stable", "beta"];
+pub const PUBSPEC_YAML: &str = "pubspec.yaml";
+
+pub struct Dart {
+    pub executor_cmd: ExecutorCmd,
+}
+
+#[derive(Deserialize)]
+struct Objects {
+    #[serde(default)]
+    prefixes: Vec<String>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+/// `sdk` or `flutter` under `environment:`, without YAML parsing as that is all that is needed
+fn get_environment_value(text: &str, key: &str) -> Option<String> {
+    let line = Regex::new(&format!(r#"^\s+{key}:\s*["']?([^"'#]*?)["']?\s*(?:#.*)?$"#)).unwrap();
+    text.lines()
+        .skip_while(|l| l.trim_end() != "environment:")
+        .skip(1)
+        .take_while(|l| l.trim().is_empty() || l.starts_with(char::is_whitespace) || l.starts_with('#'))
+        .find_map(|l| line.captures(l).map(|c| c[1].to_string()))
+}
+
+/// Dart constraints are `^3.2.0`, `>=3.2.0 <4.0.0`, `any`, or a version that is exactly that version
+pub fn to_version_req(constraint: &str) -> Option<VersionReq> {
+    let constraint = constraint.trim();
+    if constraint == "any" {
+        return None;
+    }
+    let req = if constraint.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("={constraint}")
+    } else {
+        constraint.split_whitespace().collect::<Vec<_>>().join(", ")
+    };
+    VersionReq::parse(&req).map_err(|e| warn!("Unable to use {constraint} from {PUBSPEC_YAML}: {e}")).ok()
+}
+
+/// `environment.<key>` of pubspec.yaml, `sdk` is the Dart version
+pub fn get_pubspec_version(key: &str) -> Option<VersionSource> {
+    let text = fs::read_to_string(PUBSPEC_YAML).ok()?;
+    let version_req = to_version_req(&get_environment_value(&text, key)?)?;
+    info!("Got version {version_req} from {PUBSPEC_YAML} environment.{key}");
+    Some(VersionSource::new(PUBSPEC_YAML, version_req))
+}
+
+/// channels/stable/release/3.2.6/sdk/dartsdk-linux-x64-release.zip. Before 2.0 versions were numbers, those are left out
+fn to_download(prefix: &str, target: &Target) -> Option<Download> {
+    let mut parts = prefix.trim_end_matches('/').split('/');
+    let (channel, version) = (parts.nth(1)?, parts.nth(1)?);
+    if !version.contains('.') {
+        return None;
+    }
+    let os = match target.os {
+        Os::Linux => "linux",
+        Os::Mac => "macos",
+        Os::Windows => "windows",
+        Os::Any => return None,
+    };
+    let arch = match target.arch {
+        Arch::X86_64 => "x64",
+        Arch::Arm64 => "arm64",
+        Arch::Armv7 => "arm",
+        Arch::Riscv64 => "riscv64",
+        _ => return None,
+    };
+    let url = format!("{DART_ARCHIVE}/channels/{channel}/release/{version}/sdk/dartsdk-{os}-{arch}-release.zip");
+    let mut download = Download::new(url, version, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(target.os);
+    download.arch = Some(target.arch);
+    download.tags.insert(channel.to_string());
+    Some(download)
+}
+
+impl Executor for Dart {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_pubspec_version("sdk").into_iter().collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(PUBSPEC_YAML))
+    }
+
+    /// The version folders of each channel in the dart-archive bucket
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let mut downloads = vec!();
+            for channel in CHANNELS {
+                let mut page_token: Option<String> = None;
+                loop {
+                    let mut url = format!("{DART_ARCHIVE_LIST}?prefix=channels/{channel}/release/&delimiter=/");
+                    if let Some(token) = &page_token {
+                        url.push_str(&format!("&pageToken={token}"));
+                    }
+                    let objects: Objects = diagnostics::get_json(&url).await?;
+                    downloads.extend(objects.prefixes.iter().filter_map(|prefix| to_download(prefix, &input.target)));
+                    page_token = objects.next_page_token;
+                    if page_token.is_none() {
+                        break;
+                    }
+                }
+            }
+            Ok(with_mirror(input, "dart", DART_ARCHIVE, downloads))
+        })
+    }
+
+    /// <url>.sha256sum, `<hash> *<file>`
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256sum", download.download_url), download).await.map(Some)
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "dart.exe",
+            _ => "dart"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "dart"
+    }
+
+    /// Unless pubspec.yaml asks for a beta
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        if self.get_version_req().map(|req| req.to_string().contains('-')).unwrap_or(false) {
+            return HashSet::new();
+        }
+        vec!["beta".to_string()].into_iter().collect()
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("dart:{tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_environment_value() {
+        let text = "name: app\nenvironment:\n  sdk: '>=3.2.0 <4.0.0' # dart\n\n  flutter: \"3.16.9\"\ndependencies:\n  sdk: nope\n";
+        assert_eq!(Some(">=3.2.0 <4.0.0".to_string()), get_environment_value(text, "sdk"));
+        assert_eq!(Some("3.16.9".to_string()), get_environment_value(text, "flutter"));
+        assert_eq!(None, get_environment_value("dependencies:\n  sdk: ^3.0.0\n", "sdk"));
+    }
+
+    #[test]
+    fn test_to_version_req() {
+        assert_eq!(">=3.2.0, <4.0.0", to_version_req(">=3.2.0 <4.0.0").unwrap().to_string());
+        assert_eq!("^3.2.0", to_version_req("^3.2.0").unwrap().to_string());
+        assert_eq!("=3.16.9", to_version_req("3.16.9").unwrap().to_string());
+        assert!(to_version_req("any").is_none());
+    }
+
+    #[test]
+    fn test_to_download() {
+        let target = Target { os: Os::Mac, arch: Arch::Arm64, variant: None };
+        let download = to_download("channels/beta/release/3.3.0-174.2.beta/", &target).unwrap();
+        assert_eq!("https://storage.googleapis.com/dart-archive/channels/beta/release/3.3.0-174.2.beta/sdk/dartsdk-macos-arm64-release.zip", download.download_url);
+        assert!(download.tags.contains("beta"));
+        assert!(to_download("channels/stable/release/latest/", &target).is_none());
+        assert!(to_download("channels/stable/release/29803/", &target).is_none());
+    }
+}
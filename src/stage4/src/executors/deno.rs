@@ -2,10 +2,15 @@ use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
 
+use crate::error::GgError;
 use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion};
+use crate::http;
+use crate::mirror::with_mirror;
 use crate::target::{Arch, Os, Variant};
 use crate::target::Os::Windows;
 
+const DENO_RELEASES: &str = "https://github.com/denoland/deno/releases/download";
+
 pub struct Deno {
     pub executor_cmd: ExecutorCmd,
 }
@@ -15,15 +20,10 @@ impl Executor for Deno {
         &self.executor_cmd
     }
 
-    fn get_download_urls<'a>(&self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
         Box::pin(async move {
             let mut downloads: Vec<Download> = vec!();
-            let octocrab = octocrab::Octocrab::builder().base_uri("https://ghapi.ggcmd.io/").unwrap().build().unwrap();
-            let mut page: u32 = 1;
-            loop {
-                let releases = octocrab.repos("denoland", "deno")
-                    .releases().list().page(page).per_page(100).send().await.unwrap();
-                for release in releases.items {
+            for release in http::get_releases("denoland", "deno", u32::MAX, 100).await? {
                     for asset in release.assets {
                         let os = if asset.name.contains("windows") {
                             Some(Windows)
@@ -47,16 +47,14 @@ impl Executor for Deno {
                                 arch,
                                 tags: HashSet::new(),
                                 variant: Some(Variant::Any),
+                                sha256: None,
+                                checksum: None,
+                                date: None,
                             });
                         }
                     }
                 }
-                if releases.next == None {
-                    break;
-                }
-                page += 1;
-            }
-            downloads
+            Ok(with_mirror(input, "deno", DENO_RELEASES, downloads))
         })
     }
 
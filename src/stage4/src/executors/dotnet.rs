@@ -0,0 +1,392 @@
+use std::collections::HashSet;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use futures_util::future::join_all;
+use log::{info, warn};
+use semver::{Comparator, Version, VersionReq};
+use serde::Deserialize;
+
+use crate::bloody_indiana_jones::{Checksum, HashAlgorithm};
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, AppPath, Download, ExecContext, Executor, ExecutorCmd, intersect_version_reqs, resolve_version_req, VersionSource};
+use crate::mirror::{mirror_url, with_mirror};
+use crate::target::{Arch, Os, Variant};
+
+pub const DOTNET_DL: &str = "https://dotnetcli.blob.core.windows.net/dotnet";
+const GLOBAL_JSON: &str = "global.json";
+
+pub struct Dotnet {
+    pub executor_cmd: ExecutorCmd,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    #[serde(rename = "releases-index")]
+    releases_index: Vec<Channel>,
+}
+
+#[derive(Deserialize)]
+struct Channel {
+    #[serde(rename = "channel-version", default)]
+    channel_version: String,
+    #[serde(rename = "release-type", default)]
+    release_type: String,
+    #[serde(rename = "support-phase", default)]
+    support_phase: String,
+    #[serde(rename = "releases.json")]
+    releases_json: String,
+}
+
+#[derive(Deserialize)]
+struct Releases {
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    #[serde(rename = "release-date", default)]
+    release_date: Option<String>,
+    #[serde(default)]
+    sdk: Option<Sdk>,
+    #[serde(default)]
+    sdks: Vec<Sdk>,
+}
+
+#[derive(Deserialize)]
+struct Sdk {
+    version: String,
+    #[serde(default)]
+    files: Vec<File>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    rid: String,
+    name: String,
+    url: String,
+    #[serde(default)]
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct GlobalJson {
+    sdk: Option<GlobalJsonSdk>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalJsonSdk {
+    version: Option<String>,
+    roll_forward: Option<String>,
+    allow_prerelease: Option<bool>,
+}
+
+/// Runtime identifiers with SDK archives gg can run
+fn get_target(rid: &str) -> Option<(Os, Arch, Option<Variant>)> {
+    Some(match rid {
+        "linux-x64" => (Os::Linux, Arch::X86_64, None),
+        "linux-arm64" => (Os::Linux, Arch::Arm64, None),
+        "linux-arm" => (Os::Linux, Arch::Armv7, None),
+        "linux-musl-x64" => (Os::Linux, Arch::X86_64, Some(Variant::Musl)),
+        "linux-musl-arm64" => (Os::Linux, Arch::Arm64, Some(Variant::Musl)),
+        "linux-musl-arm" => (Os::Linux, Arch::Armv7, Some(Variant::Musl)),
+        "osx-x64" => (Os::Mac, Arch::X86_64, Some(Variant::Any)),
+        "osx-arm64" => (Os::Mac, Arch::Arm64, Some(Variant::Any)),
+        "win-x64" => (Os::Windows, Arch::X86_64, Some(Variant::Any)),
+        "win-arm64" => (Os::Windows, Arch::Arm64, Some(Variant::Any)),
+        _ => return None,
+    })
+}
+
+/// Archives of every SDK in releases.json, installers are left out. 9.0.100-rc.1.24452.12 is tagged rc, previews preview
+fn get_downloads(releases: &Releases, lts: bool) -> Vec<Download> {
+    let mut seen = HashSet::new();
+    releases.releases.iter()
+        .flat_map(|release| release.sdks.iter().chain(release.sdk.iter()).map(move |sdk| (release, sdk)))
+        .filter(|(_, sdk)| seen.insert(sdk.version.clone()))
+        .flat_map(|(release, sdk)| sdk.files.iter().filter_map(move |file| {
+            if !file.name.ends_with(".tar.gz") && !file.name.ends_with(".zip") {
+                return None;
+            }
+            let (os, arch, variant) = get_target(&file.rid)?;
+            let mut download = Download::new(file.url.clone(), &sdk.version, variant);
+            download.version.as_ref()?;
+            download.os = Some(os);
+            download.arch = Some(arch);
+            download.checksum = Some(file.hash.clone()).filter(|hash| !hash.is_empty())
+                .map(|hash| Checksum { algorithm: HashAlgorithm::Sha512, hash });
+            download.date = release.release_date.clone();
+            if lts {
+                download.tags.insert("lts".to_string());
+            }
+            if let Some((_, pre)) = sdk.version.split_once('-') {
+                download.tags.insert(pre.split('.').next().unwrap_or_default().to_lowercase());
+            }
+            Some(download)
+        }))
+        .collect()
+}
+
+/// Whether a channel, e.g. 8.0, can have an SDK matching the requirement. Without one only those still supported, as
+/// the newest SDK is never in an eol channel
+fn is_channel_needed(channel: &Channel, version_req: &Option<VersionReq>) -> bool {
+    let Some(version_req) = version_req else { return channel.support_phase != "eol" };
+    let Some((major, minor)) = channel.channel_version.split_once('.') else { return true };
+    let (Ok(major), Ok(minor)) = (major.parse::<u64>(), minor.parse::<u64>()) else { return true };
+    let same_channel = |c: &&Comparator| c.major == major && c.minor == Some(minor);
+    if version_req.comparators.iter().filter(same_channel).any(|c| !c.pre.is_empty()) {
+        return true;
+    }
+    let mut patches: Vec<u64> = (1..=9).flat_map(|band| [band * 100, band * 100 + 99]).collect();
+    for patch in version_req.comparators.iter().filter(same_channel).filter_map(|c| c.patch) {
+        patches.extend([patch.saturating_sub(1), patch, patch + 1]);
+    }
+    patches.into_iter().any(|patch| version_req.matches(&Version::new(major, minor, patch)))
+}
+
+/// rollForward of global.json as a range. SDK versions are major.minor.feature band and patch, 8.0.100 is band 1, patch 0.
+/// Without rollForward it is latestPatch
+fn to_version_req(version: &str, roll_forward: Option<&str>) -> Result<VersionReq, String> {
+    let v = Version::parse(version).map_err(|e| format!("{version}: {e}"))?;
+    let band = v.patch / 100 * 100;
+    let range = match roll_forward.unwrap_or("latestPatch") {
+        "disable" => format!("={version}"),
+        "patch" | "latestPatch" => format!(">={version}, <{}.{}.{}", v.major, v.minor, band + 100),
+        "feature" | "latestFeature" => format!(">={version}, <{}.{}.0", v.major, v.minor + 1),
+        "minor" | "latestMinor" => format!(">={version}, <{}.0.0", v.major + 1),
+        "major" | "latestMajor" => format!(">={version}"),
+        other => return Err(format!("rollForward {other} is not one of disable, patch, feature, minor, major, latestPatch, latestFeature, latestMinor or latestMajor")),
+    };
+    VersionReq::parse(&range).map_err(|e| format!("{range}: {e}"))
+}
+
+/// patch, feature, minor and major take the nearest SDK that is there, not the newest. patch the version itself, the
+/// others the lowest feature band with a match, then minor and major, of which the newest patch. Others are left as is
+fn roll_forward(downloads: Vec<Download>, version: &str, policy: &str) -> Vec<Download> {
+    let (Ok(v), Ok(version_req)) = (Version::parse(version), to_version_req(version, Some(policy))) else { return downloads };
+    let band = |v: &Version| (v.major, v.minor, v.patch / 100);
+    let matching: Vec<Version> = downloads.iter()
+        .filter_map(|d| d.version.as_ref().map(|v| v.to_version()))
+        .filter(|d| version_req.matches(d))
+        .collect();
+    let keep: Box<dyn Fn(&Version) -> bool> = match policy {
+        "patch" if matching.contains(&v) => Box::new(|d| *d == v),
+        "feature" | "minor" | "major" => match matching.iter().map(band).min() {
+            Some(nearest) => Box::new(move |d| band(d) == nearest),
+            None => return downloads,
+        },
+        _ => return downloads,
+    };
+    downloads.into_iter().filter(|d| {
+        let Some(version) = d.version.as_ref().map(|v| v.to_version()) else { return true };
+        !version_req.matches(&version) || keep(&version)
+    }).collect()
+}
+
+/// The narrowing rollForward of global.json, when it is where the version comes from
+fn get_roll_forward(executor_cmd: &ExecutorCmd) -> Option<(String, String)> {
+    if executor_cmd.version.is_some() {
+        return None;
+    }
+    let sdk = get_global_json()?;
+    let policy = sdk.roll_forward.filter(|p| matches!(p.as_str(), "patch" | "feature" | "minor" | "major"))?;
+    Some((sdk.version?, policy))
+}
+
+fn parse_global_json(text: &str) -> Result<Option<GlobalJsonSdk>, String> {
+    let global_json: GlobalJson = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(global_json.sdk)
+}
+
+fn get_sdk_version_req(sdk: &GlobalJsonSdk) -> Result<Option<VersionReq>, String> {
+    match &sdk.version {
+        Some(version) => to_version_req(version, sdk.roll_forward.as_deref()).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn get_global_json() -> Option<GlobalJsonSdk> {
+    let text = fs::read_to_string(GLOBAL_JSON).ok()?;
+    parse_global_json(&text).map_err(|e| warn!("Unable to use {GLOBAL_JSON}: {e}")).ok()?
+}
+
+fn get_global_json_version() -> Option<VersionSource> {
+    match get_sdk_version_req(&get_global_json()?) {
+        Ok(version_req) => {
+            let version_req = version_req?;
+            info!("Got version {version_req} from {GLOBAL_JSON}");
+            Some(VersionSource::new(GLOBAL_JSON, version_req))
+        }
+        Err(e) => {
+            warn!("Unable to use {GLOBAL_JSON}: {e}");
+            None
+        }
+    }
+}
+
+impl Executor for Dotnet {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_global_json_version().into_iter().collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(GLOBAL_JSON))
+    }
+
+    /// releases-index.json lists the channels, e.g. 8.0, each with a releases.json of all its SDKs. Only those of
+    /// channels that can match are fetched
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let base = mirror_url(input, "dotnet", DOTNET_DL);
+            let index: Index = diagnostics::get_json(&format!("{base}/release-metadata/releases-index.json")).await?;
+            let version_req = resolve_version_req(self, input);
+            let needed = index.releases_index.iter().filter(|channel| is_channel_needed(channel, &version_req));
+            let channels = needed.map(|channel| {
+                let url = channel.releases_json.replacen(DOTNET_DL, &base, 1);
+                async move {
+                    let releases: Releases = diagnostics::get_json(&url).await?;
+                    Ok::<_, GgError>(get_downloads(&releases, channel.release_type == "lts"))
+                }
+            });
+            let mut downloads = vec!();
+            for channel in join_all(channels).await {
+                downloads.extend(channel?);
+            }
+            if let Some((version, policy)) = get_roll_forward(&self.executor_cmd) {
+                downloads = roll_forward(downloads, &version, &policy);
+            }
+            Ok(with_mirror(input, "dotnet", DOTNET_DL, downloads))
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "dotnet.exe",
+            _ => "dotnet"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "dotnet"
+    }
+
+    /// The SDK picked by a narrowing rollForward is another than the newest of the same range
+    fn get_flavor(&self, _input: &AppInput) -> Option<String> {
+        get_roll_forward(&self.executor_cmd).map(|(_, policy)| policy)
+    }
+
+    /// Without +preview or +rc only a prerelease in global.json or allowPrerelease gives one
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        let sdk = get_global_json();
+        let prerelease = sdk.as_ref().and_then(|sdk| sdk.version.as_ref()).map(|v| v.contains('-')).unwrap_or(false);
+        if prerelease || sdk.and_then(|sdk| sdk.allow_prerelease).unwrap_or(false) {
+            return HashSet::new();
+        }
+        vec!["preview", "rc"].into_iter().map(|s| s.to_string()).collect()
+    }
+
+    /// So the dotnet on PATH finds its own runtimes and workloads, not those of a system install
+    fn get_exec_context(&self, _input: &AppInput, app_path: &AppPath, _deps: &ExecContext) -> ExecContext {
+        ExecContext::new(app_path, &self.get_bin_dirs()).env("DOTNET_ROOT", &app_path.install_dir)
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("mcr.microsoft.com/dotnet/sdk:{tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::executor::GgVersion;
+
+    use super::*;
+
+    #[test]
+    fn test_get_downloads() {
+        let releases: Releases = serde_json::from_str(r#"{"releases": [
+            {"release-date": "2024-01-09", "sdk": {"version": "8.0.101", "files": []}, "sdks": [{"version": "8.0.101", "files": [
+                {"name": "dotnet-sdk-linux-musl-x64.tar.gz", "rid": "linux-musl-x64", "url": "https://example.com/a.tar.gz", "hash": "abc"},
+                {"name": "dotnet-sdk-win-x64.exe", "rid": "win-x64", "url": "https://example.com/a.exe", "hash": "def"}
+            ]}]},
+            {"release-date": "2023-10-10", "sdks": [{"version": "8.0.100-rc.2.23502.2", "files": [
+                {"name": "dotnet-sdk-win-x64.zip", "rid": "win-x64", "url": "https://example.com/b.zip", "hash": "ghi"}
+            ]}]}
+        ]}"#).unwrap();
+        let downloads = get_downloads(&releases, true);
+        assert_eq!(2, downloads.len());
+        assert_eq!(Some(Variant::Musl), downloads[0].variant);
+        assert_eq!(Some(Checksum { algorithm: HashAlgorithm::Sha512, hash: "abc".to_string() }), downloads[0].checksum);
+        assert_eq!(Some("2024-01-09".to_string()), downloads[0].date);
+        assert!(downloads[0].tags.contains("lts"));
+        assert_eq!(Some(GgVersion::new("8.0.100-rc.2.23502.2").unwrap()), downloads[1].version);
+        assert!(downloads[1].tags.contains("rc"));
+    }
+
+    #[test]
+    fn test_to_version_req() {
+        let version = |v: &str| Version::parse(v).unwrap();
+        let req = to_version_req("8.0.100", None).unwrap();
+        assert!(req.matches(&version("8.0.104")) && !req.matches(&version("8.0.200")));
+        assert_eq!("=8.0.100", to_version_req("8.0.100", Some("disable")).unwrap().to_string());
+        let req = to_version_req("8.0.302", Some("latestFeature")).unwrap();
+        assert!(req.matches(&version("8.0.404")) && !req.matches(&version("8.0.301")) && !req.matches(&version("9.0.100")));
+        assert!(to_version_req("6.0.100", Some("latestMajor")).unwrap().matches(&version("9.0.100")));
+        assert!(to_version_req("8.0.100", Some("newest")).is_err());
+        let req = to_version_req("8.0.302", Some("feature")).unwrap();
+        assert!(req.matches(&version("8.0.404")) && !req.matches(&version("8.1.100")));
+    }
+
+    #[test]
+    fn test_roll_forward() {
+        let downloads: Vec<Download> = ["8.0.100", "8.0.104", "8.0.303", "8.0.405", "9.0.100"].iter()
+            .map(|v| Download::new(format!("https://example.com/{v}.tar.gz"), v, None))
+            .collect();
+        let versions = |policy: &str, version: &str| roll_forward(downloads.clone(), version, policy).into_iter()
+            .map(|d| d.version.unwrap().to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!("8.0.100", "8.0.303", "8.0.405", "9.0.100"), versions("patch", "8.0.100"));
+        assert_eq!(5, versions("patch", "8.0.101").len());
+        assert_eq!(vec!("8.0.100", "8.0.104", "8.0.303", "9.0.100"), versions("feature", "8.0.201"));
+        assert_eq!(vec!("8.0.100", "8.0.104", "8.0.303", "8.0.405"), versions("major", "8.0.350"));
+        assert_eq!(5, versions("latestPatch", "8.0.100").len());
+    }
+
+    #[test]
+    fn test_is_channel_needed() {
+        let channel = |version: &str, phase: &str| Channel {
+            channel_version: version.to_string(),
+            release_type: "sts".to_string(),
+            support_phase: phase.to_string(),
+            releases_json: String::new(),
+        };
+        let req = |r: &str| Some(VersionReq::parse(r).unwrap());
+        assert!(is_channel_needed(&channel("8.0", "active"), &None));
+        assert!(!is_channel_needed(&channel("7.0", "eol"), &None));
+        assert!(is_channel_needed(&channel("7.0", "eol"), &req("=7.0.410")));
+        assert!(!is_channel_needed(&channel("9.0", "active"), &req(">=8.0.100, <8.0.200")));
+        assert!(is_channel_needed(&channel("9.0", "preview"), &req("=9.0.100-rc.2.24474.11")));
+        assert!(is_channel_needed(&channel("9.0", "active"), &req(">=8.0.100")));
+        assert!(!is_channel_needed(&channel("6.0", "eol"), &req(">=8")));
+    }
+
+    #[test]
+    fn test_parse_global_json() {
+        let sdk = parse_global_json(r#"{"sdk": {"version": "8.0.204", "rollForward": "latestMinor", "allowPrerelease": true}, "msbuild-sdks": {}}"#).unwrap().unwrap();
+        assert_eq!(">=8.0.204, <9.0.0", get_sdk_version_req(&sdk).unwrap().unwrap().to_string());
+        assert_eq!(Some(true), sdk.allow_prerelease);
+        assert!(parse_global_json(r#"{"msbuild-sdks": {"Microsoft.Build.Traversal": "3.0.3"}}"#).unwrap().is_none());
+    }
+}
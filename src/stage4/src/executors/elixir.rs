@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use log::{info, warn};
+use regex::Regex;
+use semver::{Op, VersionReq};
+
+use crate::error::GgError;
+use crate::executor::{self, AppInput, Download, Executor, ExecutorCmd, fetch_sha256, get_file_version, get_locked_version_req, GgVersionReq, intersect_version_reqs, NamedBin, resolve_version_req, VersionSource};
+use crate::executors::erlang::Erlang;
+use crate::http;
+use crate::mirror::with_mirror;
+use crate::target::{Arch, Os, Variant};
+use crate::tool_versions;
+
+const ELIXIR_RELEASES: &str = "https://github.com/elixir-lang/elixir/releases/download";
+const RELEASE_PAGES: u32 = 2;
+const MIX_EXS: &str = "mix.exs";
+
+pub struct Elixir {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// elixir-otp-26.zip, compiled with OTP 26. The OTP major is a tag, e.g. otp-26
+fn asset_to_download(tag: &str, name: &str, url: &str) -> Option<(u64, Download)> {
+    let otp = name.strip_prefix("elixir-otp-")?.strip_suffix(".zip")?.parse().ok()?;
+    let mut download = Download::new(url.to_string(), tag, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(Os::Any);
+    download.arch = Some(Arch::Any);
+    download.tags.insert(format!("otp-{otp}"));
+    if let Some((_, pre)) = tag.split_once('-') {
+        download.tags.insert(pre.split('.').next().unwrap_or_default().to_lowercase());
+    }
+    Some((otp, download))
+}
+
+/// Per Elixir version the build for the newest OTP up to the one erlang runs, as .beam files run on newer OTP but not
+/// older. Versions without such a build are left out
+fn pair_with_otp(builds: Vec<(u64, Download)>, otp: Option<u64>) -> Vec<Download> {
+    let mut best: BTreeMap<String, (u64, Download)> = BTreeMap::new();
+    for (build_otp, download) in builds {
+        if otp.map(|otp| build_otp > otp).unwrap_or(false) {
+            continue;
+        }
+        let Some(version) = download.version.as_ref().map(|v| v.to_string()) else { continue };
+        if best.get(&version).map(|(o, _)| *o < build_otp).unwrap_or(true) {
+            best.insert(version, (build_otp, download));
+        }
+    }
+    best.into_values().map(|(_, download)| download).collect()
+}
+
+/// The erlang of the same command line, e.g. erlang@26:elixir, with its own version sources
+fn get_erlang(input: &AppInput) -> Erlang {
+    let mut erlang = Erlang {
+        executor_cmd: ExecutorCmd { cmd: "erlang".to_string(), version: None, include_tags: HashSet::new(), exclude_tags: HashSet::new() },
+    };
+    let cmds = executor::get_executor_cmds(&erlang);
+    if let Some(cmd) = input.no_clap.cmds.iter().find(|cmd| cmds.contains(&cmd.cmd)) {
+        erlang.executor_cmd.version = GgVersionReq::new(cmd.version.as_deref().unwrap_or_default());
+        erlang.executor_cmd.include_tags = cmd.include_tags.clone();
+        erlang.executor_cmd.exclude_tags = cmd.exclude_tags.clone();
+    }
+    erlang
+}
+
+/// The major every version of the requirement has, e.g. 26 of =26.2.5, ^26 or ~26.2. None of >=26
+fn get_major(version_req: &VersionReq) -> Option<u64> {
+    version_req.comparators.iter()
+        .find(|c| matches!(c.op, Op::Exact | Op::Tilde | Op::Caret | Op::Wildcard))
+        .map(|c| c.major)
+}
+
+/// `-otp-26` of `elixir 1.16.0-otp-26` in .tool-versions
+fn parse_tool_versions_otp(version: &str) -> Option<u64> {
+    version.split_once("-otp-")?.1.parse().ok()
+}
+
+/// The OTP major of the version of erlang in gg.lock, else of its requirement, else of elixir in .tool-versions. Both
+/// the build and the flavor are from it, without one the build for the newest OTP is used
+fn get_otp_major(input: &AppInput) -> Option<u64> {
+    let erlang = get_erlang(input);
+    let erlang_input = &input.for_executor("erlang");
+    let version_req = resolve_version_req(&erlang, erlang_input);
+    let version_req = get_locked_version_req(&erlang, erlang_input, &version_req).or(version_req);
+    version_req.as_ref().and_then(get_major)
+        .or_else(|| parse_tool_versions_otp(&tool_versions::get_version("elixir")?))
+}
+
+/// An Elixir requirement, `~> 1.15` is >= 1.15 and < 2, `~> 1.15.2` >= 1.15.2 and < 1.16. Of `or` the first is used
+fn to_version_req(requirement: &str) -> Option<VersionReq> {
+    let requirement = requirement.split(" or ").next()?;
+    let comparators: Vec<String> = requirement.split(" and ").map(|c| {
+        let c = c.trim();
+        if let Some(v) = c.strip_prefix("~>") {
+            let v = v.trim();
+            if v.split('.').count() >= 3 { format!("~{v}") } else { format!("^{v}") }
+        } else if let Some(v) = c.strip_prefix("==") {
+            format!("={}", v.trim())
+        } else {
+            c.to_string()
+        }
+    }).collect();
+    VersionReq::parse(&comparators.join(", ")).map_err(|e| warn!("Unable to use elixir {requirement} from {MIX_EXS}: {e}")).ok()
+}
+
+/// `elixir: "~> 1.15"` in the project of mix.exs
+fn parse_mix_exs(text: &str) -> Option<VersionReq> {
+    let re = Regex::new(r#"elixir:\s*"([^"]+)""#).unwrap();
+    to_version_req(&re.captures(text)?[1])
+}
+
+impl Executor for Elixir {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_file_version(MIX_EXS, parse_mix_exs).into_iter().collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(MIX_EXS))
+    }
+
+    /// Builds of GitHub releases of elixir-lang/elixir, paired with the OTP of erlang
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let releases = http::get_releases("elixir-lang", "elixir", RELEASE_PAGES, 100).await?;
+            let builds = releases.iter()
+                .flat_map(|release| release.assets.iter().map(move |asset| (release, asset)))
+                .filter_map(|(release, asset)| asset_to_download(&release.tag_name, &asset.name, asset.browser_download_url.as_str()))
+                .collect();
+            let otp = get_otp_major(input);
+            info!("Elixir builds for OTP {}", otp.map(|o| o.to_string()).unwrap_or("any".to_string()));
+            Ok(with_mirror(input, "elixir", ELIXIR_RELEASES, pair_with_otp(builds, otp)))
+        })
+    }
+
+    /// Each zip has a .sha256sum next to it
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256sum", download.download_url), download).await.map(Some)
+        })
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(
+            NamedBin::new("elixir").windows(&["elixir.bat"]),
+            NamedBin::new("mix").windows(&["mix.bat"]),
+            NamedBin::new("iex").windows(&["iex.bat"]),
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        "elixir"
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
+        vec!("erlang")
+    }
+
+    /// Separate installs per OTP, as the same Elixir version is another build for another OTP
+    fn get_flavor(&self, input: &AppInput) -> Option<String> {
+        get_otp_major(input).map(|major| format!("otp-{major}"))
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["rc".to_string()].into_iter().collect()
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("elixir:{tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_with_otp() {
+        let builds = vec!(
+            asset_to_download("v1.16.0", "elixir-otp-24.zip", "a").unwrap(),
+            asset_to_download("v1.16.0", "elixir-otp-26.zip", "b").unwrap(),
+            asset_to_download("v1.16.0", "elixir-otp-25.zip", "c").unwrap(),
+            asset_to_download("v1.18.0", "elixir-otp-27.zip", "d").unwrap(),
+        );
+        assert!(asset_to_download("v1.16.0", "Docs.zip", "e").is_none());
+        let downloads = pair_with_otp(builds.clone(), Some(25));
+        assert_eq!(1, downloads.len());
+        assert_eq!("c", downloads[0].download_url);
+        assert!(downloads[0].tags.contains("otp-25"));
+        let urls: Vec<String> = pair_with_otp(builds, None).into_iter().map(|d| d.download_url).collect();
+        assert_eq!(vec!("b", "d"), urls);
+    }
+
+    #[test]
+    fn test_get_otp_major() {
+        assert_eq!(Some(26), get_major(&VersionReq::parse("=26.2.5").unwrap()));
+        assert_eq!(Some(26), get_major(&VersionReq::parse(">=26.1, ~26.2").unwrap()));
+        assert_eq!(None, get_major(&VersionReq::parse(">=26").unwrap()));
+        assert_eq!(Some(26), parse_tool_versions_otp("1.16.0-otp-26"));
+        assert_eq!(None, parse_tool_versions_otp("1.16.0"));
+    }
+
+    #[test]
+    fn test_parse_mix_exs() {
+        let text = "def project do\n  [\n    app: :app,\n    version: \"0.1.0\",\n    elixir: \"~> 1.15\",\n    deps: deps()\n  ]\nend\n";
+        assert_eq!("^1.15", parse_mix_exs(text).unwrap().to_string());
+        assert_eq!("~1.15.2", to_version_req("~> 1.15.2").unwrap().to_string());
+        assert_eq!(">=1.14.0, <1.17.0", to_version_req(">= 1.14.0 and < 1.17.0").unwrap().to_string());
+        assert_eq!("^1.14", to_version_req("~> 1.14 or ~> 2.0").unwrap().to_string());
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Command;
+
+use log::debug;
+use semver::Version;
+
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion, NamedBin};
+use crate::http;
+use crate::mirror::{mirror_url, with_mirror};
+use crate::target::{Arch, Os, Variant};
+use crate::tool_versions;
+
+/// Prebuilt OTP of Hex, as setup-beam uses, built on Ubuntu 22.04
+pub const HEX_BUILDS: &str = "https://builds.hex.pm/builds/otp";
+const OTP_RELEASES: &str = "https://github.com/erlang/otp/releases/download";
+const UBUNTU: &str = "ubuntu-22.04";
+const RELEASE_PAGES: u32 = 3;
+const OTP_VERSION_EVAL: &str = r#"{ok, V} = file:read_file(filename:join([code:root_dir(), "releases", erlang:system_info(otp_release), "OTP_VERSION"])), io:put_chars(V), halt()."#;
+
+pub struct Erlang {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// OTP versions can have four parts, 26.2.5.4 is 26.2.5+4 so it sorts after 26.2.5 and =26.2.5 matches it.
+/// 27.0-rc1 is 27.0.0-rc1
+pub fn to_semver(version: &str) -> String {
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+    let mut parts: Vec<&str> = core.split('.').collect();
+    let build = if parts.len() > 3 { Some(parts.split_off(3).join(".")) } else { None };
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    let mut semver = parts.join(".");
+    if let Some(pre) = pre {
+        semver.push_str(&format!("-{pre}"));
+    }
+    if let Some(build) = build {
+        semver.push_str(&format!("+{build}"));
+    }
+    semver
+}
+
+fn to_download(url: String, version: &str, os: Os, arch: Arch, variant: Option<Variant>) -> Option<Download> {
+    let mut download = Download::new(url, &to_semver(version), variant);
+    download.version.as_ref()?;
+    download.os = Some(os);
+    download.arch = Some(arch);
+    if let Some((_, pre)) = version.split_once('-') {
+        download.tags.insert(pre.trim_end_matches(|c: char| c.is_ascii_digit()).to_lowercase());
+    }
+    Some(download)
+}
+
+/// builds.txt has lines of `OTP-26.2.1 <commit> <date> <sha256>`, the maint and master builds are left out
+fn get_hex_downloads(base: &str, builds: &str, arch: Arch) -> Vec<Download> {
+    builds.lines().filter_map(|line| {
+        let mut columns = line.split_whitespace();
+        let name = columns.next()?;
+        let version = name.strip_prefix("OTP-")?;
+        let date = columns.nth(1);
+        let sha256 = columns.next().filter(|s| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit()));
+        let mut download = to_download(format!("{base}/{name}.tar.gz"), version, Os::Linux, arch, None)?;
+        download.date = date.map(|d| d.chars().take(10).collect());
+        download.sha256 = sha256.map(|s| s.to_lowercase());
+        Some(download)
+    }).collect()
+}
+
+/// The requirement of 26.2.5.4 in .tool-versions, or of 26.2.5+4 in gg.lock, is =26.2.5, which all builds of 26.2.5
+/// match. The other builds of it are left out
+fn keep_build(downloads: Vec<Download>, pinned: Option<&str>) -> Vec<Download> {
+    let Some(pinned) = pinned.and_then(|v| Version::parse(&to_semver(v)).ok()).filter(|v| !v.build.is_empty()) else { return downloads };
+    downloads.into_iter().filter(|download| {
+        let Some(version) = download.version.as_ref().map(|v| v.to_version()) else { return true };
+        (version.major, version.minor, version.patch) != (pinned.major, pinned.minor, pinned.patch) || version == pinned
+    }).collect()
+}
+
+fn get_pinned_version(input: &AppInput) -> Option<String> {
+    input.lock.tools.get("erlang").map(|locked| locked.version.clone()).or_else(|| tool_versions::get_version("erlang"))
+}
+
+/// otp_win64_26.2.1.zip, the zip is there from OTP 26, before only the installer
+fn asset_to_download(name: &str, url: &str) -> Option<Download> {
+    let version = name.strip_prefix("otp_win64_")?.strip_suffix(".zip")?;
+    to_download(url.to_string(), version, Os::Windows, Arch::X86_64, Some(Variant::Any))
+}
+
+impl Executor for Erlang {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    /// Hex builds for Linux, the zips of erlang/otp for Windows. None for macOS
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            match (input.target.os, input.target.variant) {
+                (Os::Linux, None) => {
+                    let arch = match input.target.arch {
+                        Arch::X86_64 => "amd64",
+                        Arch::Arm64 => "arm64",
+                        _ => return Ok(vec!()),
+                    };
+                    let base = format!("{}/{arch}/{UBUNTU}", mirror_url(input, "erlang", HEX_BUILDS));
+                    let builds = diagnostics::get_text(&format!("{base}/builds.txt")).await?;
+                    Ok(keep_build(get_hex_downloads(&base, &builds, input.target.arch), get_pinned_version(input).as_deref()))
+                }
+                (Os::Windows, _) => {
+                    let releases = http::get_releases("erlang", "otp", RELEASE_PAGES, 100).await?;
+                    let downloads = releases.iter()
+                        .flat_map(|release| release.assets.iter())
+                        .filter_map(|asset| asset_to_download(&asset.name, asset.browser_download_url.as_str()))
+                        .collect();
+                    let downloads = keep_build(downloads, get_pinned_version(input).as_deref());
+                    Ok(with_mirror(input, "erlang", OTP_RELEASES, downloads))
+                }
+                _ => Ok(vec!()),
+            }
+        })
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(
+            NamedBin::new("erl").alias("erlang").windows(&["erl.exe"]),
+            NamedBin::new("erlc").windows(&["erlc.exe"]),
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        "erlang"
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["rc".to_string()].into_iter().collect()
+    }
+
+    /// The Hex builds have the Install script of a release, which points bin/erl at where it is
+    /// With -cross Install fixes up the current dir for the root it is moved to
+    fn post_prep(&self, cache_path: &str, install_dir: &str) -> Result<(), String> {
+        let install = Path::new(cache_path).join("Install");
+        if !install.is_file() {
+            return Ok(());
+        }
+        let install = std::path::absolute(&install).unwrap_or(install);
+        let root = std::path::absolute(install_dir).unwrap_or(Path::new(install_dir).to_path_buf());
+        match Command::new(&install).arg("-cross").arg("-minimal").arg(&root).current_dir(cache_path).output() {
+            Ok(output) if output.status.success() => {
+                debug!("Ran {}", install.display());
+                Ok(())
+            }
+            Ok(output) => Err(format!("{} failed: {}", install.display(), String::from_utf8_lossy(&output.stderr))),
+            Err(e) => Err(format!("Unable to run {}: {e}", install.display())),
+        }
+    }
+
+    /// The OTP_VERSION of the release, e.g. 26.2.5.4, as erl only prints the major
+    fn get_system_version(&self, bin_path: &Path) -> Option<GgVersion> {
+        let output = Command::new(bin_path).args(["-noshell", "-eval", OTP_VERSION_EVAL]).output().ok()?;
+        GgVersion::new(&to_semver(String::from_utf8_lossy(&output.stdout).trim()))
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("erlang:{tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_semver() {
+        assert_eq!("26.2.5+4", to_semver("26.2.5.4"));
+        assert_eq!("27.0.0-rc1", to_semver("27.0-rc1"));
+        assert_eq!("26.2.1", to_semver("26.2.1"));
+    }
+
+    #[test]
+    fn test_get_hex_downloads() {
+        let builds = "OTP-26.2.5.4 2f4e0b7 2024-10-03T10:22:41Z 0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef\nmaint-26 a1b2c3 2024-10-04T08:00:00Z\nOTP-27.0-rc1 d4e5f6 2024-02-14T09:00:00Z\n";
+        let downloads = get_hex_downloads("https://builds.hex.pm/builds/otp/amd64/ubuntu-22.04", builds, Arch::X86_64);
+        assert_eq!(2, downloads.len());
+        assert_eq!("https://builds.hex.pm/builds/otp/amd64/ubuntu-22.04/OTP-26.2.5.4.tar.gz", downloads[0].download_url);
+        assert_eq!("26.2.5+4", downloads[0].version.clone().unwrap().to_string());
+        assert_eq!(Some("2024-10-03".to_string()), downloads[0].date);
+        assert_eq!(Some("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string()), downloads[0].sha256);
+        assert!(downloads[1].tags.contains("rc") && downloads[1].sha256.is_none());
+        assert_eq!("26.2.1", asset_to_download("otp_win64_26.2.1.zip", "").unwrap().version.unwrap().to_string());
+        assert!(asset_to_download("otp_win64_26.2.1.exe", "").is_none());
+    }
+
+    #[test]
+    fn test_keep_build() {
+        let builds = "OTP-26.2.5.5 a 2024-11-01T00:00:00Z\nOTP-26.2.5.4 b 2024-10-03T00:00:00Z\nOTP-26.2.5 c 2024-05-02T00:00:00Z\nOTP-27.1 d 2024-09-18T00:00:00Z\n";
+        let downloads = get_hex_downloads("", builds, Arch::X86_64);
+        let versions = |pinned| keep_build(downloads.clone(), pinned).into_iter().map(|d| d.version.unwrap().to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!("26.2.5+4", "27.1.0"), versions(Some("26.2.5.4")));
+        assert_eq!(vec!("26.2.5+4", "27.1.0"), versions(Some("26.2.5+4")));
+        assert_eq!(4, versions(Some("26.2.5")).len());
+        assert_eq!(4, versions(None).len());
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use log::{info, warn};
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, intersect_version_reqs, VersionSource};
+use crate::executors::dart::{get_pubspec_version, PUBSPEC_YAML};
+use crate::mirror::{mirror_url, with_mirror};
+use crate::target::{Arch, Os, Variant};
+
+pub const FLUTTER_RELEASES: &str = "https://storage.googleapis.com/flutter_infra_release/releases";
+const FVMRC: &str = ".fvmrc";
+/// The config of fvm before 3.0
+const FVM_CONFIG_JSON: &str = ".fvm/fvm_config.json";
+const CHANNELS: [&str; 5] = ["stable", "beta", "dev", "master", "main"];
+
+pub struct Flutter {
+    pub executor_cmd: ExecutorCmd,
+}
+
+#[derive(Deserialize)]
+struct Releases {
+    releases: Vec<Release>,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    channel: String,
+    version: String,
+    #[serde(default)]
+    dart_sdk_arch: Option<String>,
+    #[serde(default)]
+    release_date: Option<String>,
+    archive: String,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FvmConfig {
+    #[serde(alias = "flutterSdkVersion")]
+    flutter: Option<String>,
+}
+
+fn get_os_name(os: Os) -> Option<&'static str> {
+    match os {
+        Os::Linux => Some("linux"),
+        Os::Mac => Some("macos"),
+        Os::Windows => Some("windows"),
+        Os::Any => None,
+    }
+}
+
+/// Archives of releases_<os>.json, e.g. stable/linux/flutter_linux_3.16.9-stable.tar.xz, tagged with the channel.
+/// On macOS dart_sdk_arch tells arm64 from x64, before arm64 builds there was none
+fn get_downloads(base: &str, releases: &Releases, os: Os) -> Vec<Download> {
+    releases.releases.iter().filter_map(|release| {
+        let arch = match release.dart_sdk_arch.as_deref() {
+            None | Some("x64") => Arch::X86_64,
+            Some("arm64") => Arch::Arm64,
+            _ => return None,
+        };
+        let mut download = Download::new(format!("{base}/{}", release.archive), &release.version, Some(Variant::Any));
+        download.version.as_ref()?;
+        download.os = Some(os);
+        download.arch = Some(arch);
+        download.sha256 = release.sha256.clone();
+        download.date = release.release_date.as_ref().map(|date| date.chars().take(10).collect());
+        download.tags.insert(release.channel.clone());
+        Some(download)
+    }).collect()
+}
+
+/// `3.19.0`, `3.19.0@beta` or a channel. fvm pins versions exactly
+fn parse_fvm_version(text: &str) -> Option<Result<VersionReq, String>> {
+    let config: FvmConfig = serde_json::from_str(text).ok()?;
+    let flutter = config.flutter?;
+    let version = flutter.split('@').next().unwrap_or_default();
+    if CHANNELS.contains(&version) {
+        return Some(Err(version.to_string()));
+    }
+    Some(VersionReq::parse(&format!("={}", version.trim_start_matches('v'))).map_err(|_| flutter.clone()))
+}
+
+/// The channel pinned with fvm, `beta` or `3.19.0@beta`
+fn get_fvm_channel() -> Option<String> {
+    [FVMRC, FVM_CONFIG_JSON].iter().find_map(|file| {
+        let config: FvmConfig = serde_json::from_str(&fs::read_to_string(file).ok()?).ok()?;
+        let flutter = config.flutter?;
+        let channel = flutter.rsplit('@').next()?;
+        CHANNELS.contains(&channel).then(|| channel.to_string())
+    })
+}
+
+fn get_fvm_version() -> Option<VersionSource> {
+    let (file, text) = [FVMRC, FVM_CONFIG_JSON].iter().find_map(|file| Some((file, fs::read_to_string(file).ok()?)))?;
+    match parse_fvm_version(&text)? {
+        Ok(version_req) => {
+            info!("Got version {version_req} from {file}");
+            Some(VersionSource::new(file, version_req))
+        }
+        Err(channel) if CHANNELS.contains(&channel.as_str()) => None,
+        Err(version) => {
+            warn!("Unable to use version {version} from {file}");
+            None
+        }
+    }
+}
+
+impl Executor for Flutter {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    /// `environment.sdk` of pubspec.yaml is the Dart version, so only `environment.flutter` is used
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_fvm_version().into_iter().chain(get_pubspec_version("flutter")).collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        [FVMRC, FVM_CONFIG_JSON, PUBSPEC_YAML].iter().map(PathBuf::from).collect()
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let Some(os_name) = get_os_name(input.target.os) else { return Ok(vec!()) };
+            let base = mirror_url(input, "flutter", FLUTTER_RELEASES);
+            let releases: Releases = diagnostics::get_json(&format!("{base}/releases_{os_name}.json")).await?;
+            Ok(with_mirror(input, "flutter", FLUTTER_RELEASES, get_downloads(FLUTTER_RELEASES, &releases, input.target.os)))
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "flutter.bat",
+            _ => "flutter"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "flutter"
+    }
+
+    /// Other channels than stable when fvm pins one, or the version is a pre-release
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        if self.get_version_req().map(|req| req.to_string().contains('-')).unwrap_or(false) {
+            return HashSet::new();
+        }
+        let channel = get_fvm_channel();
+        CHANNELS.iter().filter(|c| **c != "stable" && Some(c.to_string()) != channel).map(|c| c.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_downloads() {
+        let releases: Releases = serde_json::from_str(r#"{"base_url": "x", "releases": [
+            {"hash": "a", "channel": "stable", "version": "3.16.9", "dart_sdk_version": "3.2.6", "dart_sdk_arch": "arm64",
+             "release_date": "2024-01-25T18:54:28.941665Z", "archive": "stable/macos/flutter_macos_arm64_3.16.9-stable.zip", "sha256": "abc"},
+            {"hash": "b", "channel": "beta", "version": "3.19.0-0.4.pre", "release_date": "2024-01-31T09:39:08.920137Z",
+             "archive": "beta/macos/flutter_macos_3.19.0-0.4.pre-beta.zip", "sha256": "def"}
+        ]}"#).unwrap();
+        let downloads = get_downloads(FLUTTER_RELEASES, &releases, Os::Mac);
+        assert_eq!(2, downloads.len());
+        assert_eq!("https://storage.googleapis.com/flutter_infra_release/releases/stable/macos/flutter_macos_arm64_3.16.9-stable.zip", downloads[0].download_url);
+        assert_eq!(Some(Arch::Arm64), downloads[0].arch);
+        assert_eq!(Some("2024-01-25".to_string()), downloads[0].date);
+        assert_eq!(Some(Arch::X86_64), downloads[1].arch);
+        assert!(downloads[1].tags.contains("beta"));
+    }
+
+    #[test]
+    fn test_parse_fvm_version() {
+        assert_eq!("=3.19.0", parse_fvm_version(r#"{"flutter": "3.19.0"}"#).unwrap().unwrap().to_string());
+        assert_eq!("=3.16.9", parse_fvm_version(r#"{"flutterSdkVersion": "3.16.9", "flavors": {}}"#).unwrap().unwrap().to_string());
+        assert_eq!("=3.19.0-0.4.pre", parse_fvm_version(r#"{"flutter": "3.19.0-0.4.pre@beta"}"#).unwrap().unwrap().to_string());
+        assert_eq!(Some(Err("stable".to_string())), parse_fvm_version(r#"{"flutter": "stable"}"#));
+        assert!(parse_fvm_version(r#"{"runPubGetOnSdkChanges": true}"#).is_none());
+    }
+}
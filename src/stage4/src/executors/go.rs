@@ -1,17 +1,70 @@
 use std::collections::HashSet;
+use std::fs;
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
-use scraper::{Html, Selector};
 
-use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion};
+use log::info;
+use regex::Regex;
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::error::GgError;
+use crate::diagnostics;
+use crate::executor::{AppInput, AppPath, Download, ExecContext, Executor, ExecutorCmd, GgVersion, intersect_version_reqs, VersionSource};
+use crate::mirror::{mirror_url, with_mirror};
 use crate::target::Arch::{Arm64, X86_64};
 use crate::target::Os::{Linux, Windows, Mac};
 use crate::target::Variant::Any;
 
+pub const GO_DL: &str = "https://go.dev/dl";
+
 pub struct Go {
     pub executor_cmd: ExecutorCmd,
 }
 
+#[derive(Deserialize)]
+struct Release {
+    files: Vec<File>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    filename: String,
+    kind: String,
+    sha256: String,
+}
+
+/// Archives from https://go.dev/dl/?mode=json, installers and sources are left out
+fn get_downloads(releases: &[Release]) -> Vec<Download> {
+    releases.iter()
+        .flat_map(|release| release.files.iter().filter(|file| file.kind == "archive"))
+        .filter_map(|file| {
+            let mut download = link_href_to_download(&format!("/dl/{}", file.filename))?;
+            download.sha256 = Some(file.sha256.clone()).filter(|s| !s.is_empty());
+            Some(download)
+        })
+        .collect()
+}
+
+/// `go 1.21.3` is the minimum, as for the go command. `toolchain go1.22.1` is the one to use, when it is not older
+/// than that, as go ignores it then
+fn get_go_mod_sources(text: &str) -> Vec<VersionSource> {
+    let go = Regex::new(r"(?m)^go\s+(\d+\.\d+(?:\.\d+)?)\s*$").unwrap();
+    let toolchain = Regex::new(r"(?m)^toolchain\s+go(\d+\.\d+(?:\.\d+)?)\s*$").unwrap();
+    let minimum = go.captures(text).and_then(|c| VersionReq::parse(&format!(">={}", &c[1])).ok());
+    let toolchain = toolchain.captures(text)
+        .and_then(|c| GgVersion::new(&c[1]))
+        .filter(|version| minimum.as_ref().map(|req| req.matches(&version.to_version())).unwrap_or(true));
+    match (toolchain, minimum) {
+        (Some(toolchain), _) => VersionReq::parse(&format!("={}", toolchain.to_version())).ok()
+            .map(|version_req| VersionSource::new("go.mod toolchain", version_req))
+            .into_iter().collect(),
+        (None, Some(minimum)) => vec!(VersionSource::new("go.mod", minimum)),
+        (None, None) => vec!(),
+    }
+}
+
 fn link_href_to_download(href: &str) -> Option<Download> {
     let href_part = href.replace("/dl/go", "");
     let supported_oses = vec!(("linux", Linux), ("darwin", Mac), ("windows", Windows));
@@ -43,6 +96,9 @@ fn link_href_to_download(href: &str) -> Option<Download> {
                     arch: Some(arch),
                     os: Some(os),
                     variant: Some(Any),
+                    sha256: None,
+                    checksum: None,
+                    date: None,
                 });
             }
         }
@@ -55,25 +111,34 @@ impl Executor for Go {
         &self.executor_cmd
     }
 
-    fn get_download_urls<'a>(&self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        let sources = fs::read_to_string("go.mod").map(|text| get_go_mod_sources(&text)).unwrap_or_default();
+        for source in &sources {
+            info!("Got version {} from {}", source.version_req, source.source);
+        }
+        sources
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from("go.mod"))
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
         Box::pin(async move {
-            // let mut downloads: Vec<Download> = vec!();
-            let body = reqwest::get("https://go.dev/dl/").await
-                .expect("Unable to connect to go.dev").text().await
-                .expect("Unable to download gradle list of versions");
-
-            let document = Html::parse_document(body.as_str());
-            let downloads: Vec<Download> = document.select(&Selector::parse("a.download").unwrap()).map(|link| {
-                return if let Some(href) = link.value().attr("href") {
-                    link_href_to_download(href)
-                } else {
-                    None
-                };
-            }).filter_map(|document| document).collect();
-            downloads
+            let url = format!("{}/?mode=json&include=all", mirror_url(input, "go", GO_DL));
+            let releases: Vec<Release> = diagnostics::get_json(&url).await?;
+            Ok(with_mirror(input, "go", GO_DL, get_downloads(&releases)))
         })
     }
 
+    fn get_exec_context(&self, _input: &AppInput, app_path: &AppPath, _deps: &ExecContext) -> ExecContext {
+        ExecContext::new(app_path, &self.get_bin_dirs()).env("GOROOT", &app_path.install_dir)
+    }
+
     fn get_bins(&self, input: &AppInput) -> Vec<String> {
         vec!(match &input.target.os {
             Windows => "go.exe",
@@ -85,6 +150,10 @@ impl Executor for Go {
         "go"
     }
 
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("golang:{tag}"))
+    }
+
     fn get_default_exclude_tags(&self) -> HashSet<String> {
         vec!["beta".to_string()].into_iter().collect()
     }
@@ -94,7 +163,7 @@ impl Executor for Go {
 mod tests {
     use std::collections::HashSet;
     use crate::executor::{Download, GgVersion};
-    use crate::executors::go::link_href_to_download;
+    use crate::executors::go::{get_downloads, get_go_mod_sources, link_href_to_download, Release};
     use crate::target::Os::{Linux, Mac, Windows};
     use crate::target::{Arch, Variant};
 
@@ -108,6 +177,9 @@ mod tests {
             arch: Some(Arch::X86_64),
             variant: Some(Variant::Any),
             os: Some(Windows),
+            sha256: None,
+            checksum: None,
+            date: None,
         }));
     }
 
@@ -121,6 +193,9 @@ mod tests {
             arch: Some(Arch::X86_64),
             variant: Some(Variant::Any),
             os: Some(Linux),
+            sha256: None,
+            checksum: None,
+            date: None,
         }));
     }
 
@@ -134,6 +209,9 @@ mod tests {
             arch: Some(Arch::Arm64),
             variant: Some(Variant::Any),
             os: Some(Linux),
+            sha256: None,
+            checksum: None,
+            date: None,
         }));
     }
 
@@ -147,6 +225,9 @@ mod tests {
             arch: Some(Arch::X86_64),
             variant: Some(Variant::Any),
             os: Some(Mac),
+            sha256: None,
+            checksum: None,
+            date: None,
         }));
     }
 
@@ -161,9 +242,36 @@ mod tests {
             arch: Some(Arch::X86_64),
             variant: Some(Variant::Any),
             os: Some(Linux),
+            sha256: None,
+            checksum: None,
+            date: None,
         }));
     }
 
+    #[test]
+    fn test_get_downloads() {
+        let json = r#"[{"version": "go1.21.3", "stable": true, "files": [
+            {"filename": "go1.21.3.linux-amd64.tar.gz", "os": "linux", "arch": "amd64", "version": "go1.21.3", "sha256": "1241381b", "size": 66640773, "kind": "archive"},
+            {"filename": "go1.21.3.windows-amd64.msi", "os": "windows", "arch": "amd64", "version": "go1.21.3", "sha256": "5d1f3c1e", "size": 63070208, "kind": "installer"},
+            {"filename": "go1.21.3.src.tar.gz", "os": "", "arch": "", "version": "go1.21.3", "sha256": "186f2b6f", "size": 26855452, "kind": "source"}
+        ]}]"#;
+        let downloads = get_downloads(&serde_json::from_str::<Vec<Release>>(json).unwrap());
+        assert_eq!(1, downloads.len());
+        assert_eq!("https://go.dev/dl/go1.21.3.linux-amd64.tar.gz", downloads[0].download_url);
+        assert_eq!(Some("1241381b".to_string()), downloads[0].sha256);
+    }
+
+    #[test]
+    fn test_get_go_mod_sources() {
+        let sources = get_go_mod_sources("module example.com/app\n\ngo 1.21.3\n\ntoolchain go1.22.1\n");
+        assert_eq!(1, sources.len());
+        assert_eq!("=1.22.1", sources[0].version_req.to_string());
+        assert_eq!(">=1.21.3", get_go_mod_sources("go 1.21.3\n")[0].version_req.to_string());
+        assert_eq!(">=1.21", get_go_mod_sources("go 1.21\n")[0].version_req.to_string());
+        assert_eq!(">=1.22.0", get_go_mod_sources("go 1.22.0\ntoolchain go1.21.5\n")[0].version_req.to_string());
+        assert_eq!("=1.22.0", get_go_mod_sources("toolchain go1.22\n")[0].version_req.to_string());
+    }
+
     #[test]
     fn test_link_href_to_download_extensions() {
         let download = link_href_to_download("/dl/go1.20.6.linux-arm64.tar.gz");
@@ -1,17 +1,22 @@
+use std::env;
 use std::future::Future;
-use std::path::Path;
+use std::path::PathBuf;
 use std::pin::Pin;
 
 use log::{debug, info};
 use scraper::{Html, Selector};
 use semver::VersionReq;
-use sha256::try_digest;
 
+use crate::error::GgError;
+use crate::diagnostics;
 use crate::{Executor, target};
-use crate::executor::{AppInput, Download, ExecutorCmd};
+use crate::executor::{AppInput, AppPath, Download, ExecContext, ExecutorCmd};
 use crate::executors::gradle_properties::GradleAndWrapperProperties;
+use crate::mirror::with_mirror;
 use crate::target::Variant;
 
+pub const GRADLE_DISTRIBUTIONS: &str = "https://services.gradle.org/distributions";
+
 pub struct Gradle {
     pub executor_cmd: ExecutorCmd,
     props: GradleAndWrapperProperties,
@@ -33,29 +38,41 @@ impl Executor for Gradle {
         None
     }
 
-    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        GradleAndWrapperProperties::get_files()
+    }
+
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
         Box::pin(async move {
             if let Some(distribution_url) = self.props.get_distribution_url() {
                 if let Some(version) = self.props.get_version_from_distribution_url() {
-                    return vec![
-                        Download::new(distribution_url, version.as_str(), Some(Variant::Any))
-                    ];
+                    let mut download = Download::new(distribution_url, version.as_str(), Some(Variant::Any));
+                    download.sha256 = self.props.get_distribution_sha256sum();
+                    // A version on the command line, e.g. gradle@7, wins over the wrapper
+                    let wanted = self.executor_cmd.version.as_ref().map(|v| v.to_version_req());
+                    let matches = match (&wanted, &download.version) {
+                        (Some(wanted), Some(version)) => wanted.matches(&version.to_version()),
+                        _ => true,
+                    };
+                    if matches {
+                        return Ok(vec![download]);
+                    }
+                    debug!("Wrapper has gradle {version}, looking for another version");
                 }
             }
 
-            let body = reqwest::get("https://gradle.org/releases").await
-                .expect("Unable to connect to services.gradle.org").text().await
-                .expect("Unable to download gradle list of versions");
+            let body = diagnostics::get_text("https://gradle.org/releases").await?;
 
             let document = Html::parse_document(body.as_str());
-            document.select(&Selector::parse("a[name]").unwrap()).map(|link| {
+            let downloads = document.select(&Selector::parse("a[name]").unwrap()).map(|link| {
                 let version = link.value().attr("name").unwrap_or("").to_string();
                 Download::new(
-                    format!("https://services.gradle.org/distributions/gradle-{version}-bin.zip"),
+                    format!("{GRADLE_DISTRIBUTIONS}/gradle-{version}-bin.zip"),
                     version.as_str(),
                     Some(Variant::Any),
                 )
-            }).collect()
+            }).collect();
+            Ok(with_mirror(input, "gradle", GRADLE_DISTRIBUTIONS, downloads))
         })
     }
 
@@ -70,20 +87,38 @@ impl Executor for Gradle {
         "gradle"
     }
 
-    fn get_deps(&self) -> Vec<&str> {
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("gradle:{tag}"))
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
         vec!("java")
     }
 
-    fn post_download(&self, download_file_path: String) -> bool {
-        if let Some(checksum) = self.props.get_distribution_sha256sum() {
-            info!("Checksum found for {}: {}", &download_file_path, checksum);
-            debug!("Calculating checksum for {}", &download_file_path);
-            let input = Path::new(download_file_path.as_str());
-            let val = try_digest(input).unwrap();
-            info!("Calculated checksum: {}", val);
-            return checksum == val;
+    fn get_exec_context(&self, input: &AppInput, app_path: &AppPath, _deps: &ExecContext) -> ExecContext {
+        let mut exec_context = ExecContext::new(app_path, &self.get_bin_dirs());
+        if input.no_clap.isolate_gradle_home {
+            if let Some(gradle_user_home) = get_isolated_gradle_user_home(app_path) {
+                info!("Using isolated GRADLE_USER_HOME {gradle_user_home}");
+                exec_context.env.insert(String::from("GRADLE_USER_HOME"), gradle_user_home);
+            }
         }
-        debug!("No checksum found in gradle properties (skipping check)");
-        return true;
+        exec_context
     }
+
+    fn customize_args(&self, input: &AppInput, _app_path: &AppPath) -> Vec<String> {
+        let args = input.no_clap.app_args.clone();
+        if input.no_clap.ci && !args.iter().any(|arg| arg == "--daemon" || arg == "--no-daemon") {
+            info!("CI detected. Running gradle with --no-daemon");
+            return vec!("--no-daemon".to_string()).into_iter().chain(args).collect();
+        }
+        args
+    }
+}
+
+/// One GRADLE_USER_HOME per project and gradle installation, so caches and daemons of a system gradle are not shared
+fn get_isolated_gradle_user_home(app_path: &AppPath) -> Option<String> {
+    let install_name = app_path.install_dir.file_name()?.to_str()?.to_string();
+    let gradle_user_home = env::current_dir().ok()?.join(".cache/gg/gradle-home").join(install_name);
+    gradle_user_home.to_str().map(|s| s.to_string())
 }
@@ -1,4 +1,5 @@
 use std::fs;
+use std::path::PathBuf;
 
 use regex::Regex;
 use serde::Deserialize;
@@ -6,14 +7,17 @@ use serde::Deserialize;
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GradleWrapperProperties {
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
     pub distribution_url: Option<String>,
     pub jdk_version: Option<String>,
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
     pub distribution_sha256sum: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GradleProperties {
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
     pub distribution_url: Option<String>,
     pub jdk_version: Option<String>,
 }
@@ -23,6 +27,7 @@ pub struct GradleAndWrapperProperties {
     pub gradle_wrapper_properties: Option<GradleWrapperProperties>,
 }
 
+#[cfg_attr(not(feature = "gradle"), allow(dead_code))]
 fn get_version_from_gradle_url(gradle_url: &str) -> Option<String> {
     if let Ok(r) = Regex::new(r"gradle-(.*)-") {
         let captures: Vec<_> = r.captures_iter(gradle_url).collect();
@@ -35,16 +40,23 @@ fn get_version_from_gradle_url(gradle_url: &str) -> Option<String> {
     None
 }
 
+const GRADLE_PROPERTIES: &str = "gradle.properties";
+const GRADLE_WRAPPER_PROPERTIES: &str = "gradle/wrapper/gradle-wrapper.properties";
+
 impl GradleAndWrapperProperties {
     pub fn new() -> GradleAndWrapperProperties {
         GradleAndWrapperProperties {
-            gradle_properties: fs::read_to_string("gradle.properties").ok()
+            gradle_properties: fs::read_to_string(GRADLE_PROPERTIES).ok()
                 .and_then(|text| serde_java_properties::from_str(text.as_str()).ok()),
-            gradle_wrapper_properties: fs::read_to_string("gradle/wrapper/gradle-wrapper.properties").ok()
+            gradle_wrapper_properties: fs::read_to_string(GRADLE_WRAPPER_PROPERTIES).ok()
                 .and_then(|text| serde_java_properties::from_str(text.as_str()).ok()),
         }
     }
 
+    pub fn get_files() -> Vec<PathBuf> {
+        vec![PathBuf::from(GRADLE_PROPERTIES), PathBuf::from(GRADLE_WRAPPER_PROPERTIES)]
+    }
+
     fn map<F, G, U>(&self, gradle_wrapper_extractor: F, gradle_extractor: G) -> Option<U>
         where
             F: Fn(&GradleWrapperProperties) -> Option<U>,
@@ -54,10 +66,12 @@ impl GradleAndWrapperProperties {
             .or(self.gradle_properties.as_ref().and_then(&gradle_extractor))
     }
 
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
     pub fn get_version_from_distribution_url(&self) -> Option<String> {
         self.get_distribution_url().map(|url| get_version_from_gradle_url(url.as_str())).flatten()
     }
 
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
     pub fn get_distribution_url(&self) -> Option<String> {
         self.map(|p| p.distribution_url.clone(), |p| p.distribution_url.clone())
     }
@@ -66,6 +80,7 @@ impl GradleAndWrapperProperties {
         self.map(|p| p.jdk_version.clone(), |p| p.jdk_version.clone())
     }
 
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
     pub fn get_distribution_sha256sum(&self) -> Option<String> {
         self.gradle_wrapper_properties.as_ref().and_then(|p| p.distribution_sha256sum.clone())
     }
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256};
+use crate::http;
+use crate::mirror::mirror_url;
+use crate::target::{Arch, Os, Target, Variant};
+
+pub const HELM_DL: &str = "https://get.helm.sh";
+const RELEASE_PAGES: u32 = 2;
+
+pub struct Helm {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// helm-v3.14.2-linux-amd64.tar.gz, zip on Windows
+fn to_download(base: &str, tag: &str, target: &Target) -> Option<Download> {
+    let os = match target.os {
+        Os::Linux => "linux",
+        Os::Mac => "darwin",
+        Os::Windows => "windows",
+        Os::Any => return None,
+    };
+    let arch = match target.arch {
+        Arch::X86_64 => "amd64",
+        Arch::Arm64 => "arm64",
+        Arch::Armv7 => "arm",
+        Arch::Riscv64 => "riscv64",
+        Arch::Ppc64le => "ppc64le",
+        Arch::S390x => "s390x",
+        Arch::Any => return None,
+    };
+    let ext = if target.os == Os::Windows { "zip" } else { "tar.gz" };
+    let mut download = Download::new(format!("{base}/helm-{tag}-{os}-{arch}.{ext}"), tag, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(target.os);
+    download.arch = Some(target.arch);
+    if let Some((_, pre)) = tag.split_once('-') {
+        download.tags.insert(pre.chars().take_while(|c| c.is_ascii_alphabetic()).collect());
+    }
+    Some(download)
+}
+
+impl Executor for Helm {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    /// Tags of helm/helm, the archives are on get.helm.sh
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let base = mirror_url(input, "helm", HELM_DL);
+            Ok(http::get_releases("helm", "helm", RELEASE_PAGES, 100).await?.iter()
+                .filter_map(|release| to_download(&base, &release.tag_name, &input.target))
+                .collect())
+        })
+    }
+
+    /// <url>.sha256sum, `<hash>  <file>`
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256sum", download.download_url), download).await.map(Some)
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "helm.exe",
+            _ => "helm"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "helm"
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["alpha", "beta", "rc"].into_iter().map(|s| s.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_download() {
+        let target = Target { os: Os::Windows, arch: Arch::X86_64, variant: None };
+        let download = to_download(HELM_DL, "v3.14.2", &target).unwrap();
+        assert_eq!("https://get.helm.sh/helm-v3.14.2-windows-amd64.zip", download.download_url);
+        assert_eq!("3.14.2", download.version.unwrap().to_string());
+        assert!(to_download(HELM_DL, "v3.15.0-rc.1", &target).unwrap().tags.contains("rc"));
+    }
+}
@@ -1,15 +1,25 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::process::Command;
 
+use log::warn;
+use regex::Regex;
 use semver::VersionReq;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::error::GgError;
+use crate::diagnostics;
 use crate::Executor;
-use crate::executor::{AppInput, AppPath, Download, ExecutorCmd, GgVersion};
+use crate::executor::{AppInput, AppPath, Download, ExecContext, ExecutorCmd, fetch_sha256, get_file_version, GgVersion, intersect_version_reqs, parse_system_version, VersionSource};
 use crate::executors::gradle_properties::GradleAndWrapperProperties;
+use crate::executors::java_vendors;
+use crate::mirror::with_mirror;
+use crate::signatures::{ADOPTIUM_KEYS, Signature};
 use crate::target::{Arch, Os, Target, Variant};
+use crate::tool_versions;
 
 type Root = Vec<Root2>;
 
@@ -47,27 +57,120 @@ pub struct Java {
     pub executor_cmd: ExecutorCmd,
 }
 
+const JAVA_VERSION: &str = ".java-version";
+const SDKMANRC: &str = ".sdkmanrc";
+const POM_XML: &str = "pom.xml";
+const BUILD_GRADLE: [&str; 2] = ["build.gradle", "build.gradle.kts"];
+
 fn get_jdk_version() -> Option<String> {
     GradleAndWrapperProperties::new().get_jdk_version()
 }
 
+/// Only the major is used, 1.8 is 8. Updates are picked from what the vendor has
+fn parse_major(version: &str) -> Option<VersionReq> {
+    let version = version.trim().trim_start_matches("1.");
+    VersionReq::parse(version.split('.').next()?).ok()
+}
+
+/// jenv, e.g. 17, 17.0.2 or openjdk64-17.0.2
+fn parse_java_version_file(text: &str) -> Option<VersionReq> {
+    tool_versions::parse_version("java", text.lines().next()?.trim())
+}
+
+/// java=17.0.9-tem, the vendor is after the dash
+fn parse_sdkmanrc(text: &str) -> Option<VersionReq> {
+    text.lines()
+        .filter_map(|line| line.trim().strip_prefix("java="))
+        .find_map(|version| parse_major(version.split('-').next()?))
+}
+
+/// maven.compiler.release, or source / target for builds from before release existed, or java.version as in Spring Boot.
+/// The same properties as `gg init` looks for
+fn parse_pom(text: &str) -> Option<VersionReq> {
+    let re = Regex::new(r"<(?:maven\.compiler\.(?:release|source|target)|java\.version)>\s*([\d.]+)").unwrap();
+    parse_major(&re.captures(text)?[1])
+}
+
+/// `languageVersion = JavaLanguageVersion.of(17)` in a toolchain block, Groovy or Kotlin DSL
+fn parse_gradle_toolchain(text: &str) -> Option<VersionReq> {
+    let re = Regex::new(r#"JavaLanguageVersion\.of\(\s*"?(\d+)"?\s*\)"#).unwrap();
+    parse_major(&re.captures(text)?[1])
+}
+
+/// --vendor, then vendor in gg.toml. Azul is the company, zulu their build, as Adoptium is of temurin
+fn get_vendor(input: &AppInput) -> String {
+    let vendor = input.no_clap.vendor.clone().or(input.config.executor("java").vendor)
+        .map(|v| v.to_lowercase())
+        .unwrap_or("zulu".to_string());
+    let vendor = match vendor.as_str() {
+        "azul" => "zulu".to_string(),
+        "adoptium" => "temurin".to_string(),
+        _ => vendor,
+    };
+    if java_vendors::VENDORS.contains(&vendor.as_str()) {
+        vendor
+    } else {
+        warn!("JDK vendor {vendor} is not supported, using zulu. Supported: {}", java_vendors::VENDORS.join(", "));
+        "zulu".to_string()
+    }
+}
+
+/// The macOS archives of some vendors have the JDK in Contents/Home
+pub(crate) fn get_java_home(install_dir: &Path) -> PathBuf {
+    let home = install_dir.join("Contents").join("Home");
+    if home.is_dir() { home } else { install_dir.to_path_buf() }
+}
+
 impl Executor for Java {
     fn get_executor_cmd(&self) -> &ExecutorCmd {
         &self.executor_cmd
     }
 
     fn get_version_req(&self) -> Option<VersionReq> {
-        if let Some(jdk_version) = get_jdk_version() {
-            if let Ok(version) = VersionReq::parse(jdk_version.as_str()) {
-                return Some(version);
-            }
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    /// Files that name the JDK come first. The build files only say what is compiled for, so they are used when
+    /// nothing else is found
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        let mut sources: Vec<VersionSource> = get_jdk_version()
+            .and_then(|jdk_version| VersionReq::parse(jdk_version.as_str()).ok())
+            .map(|version_req| VersionSource::new("gradle.properties jdkVersion", version_req))
+            .into_iter()
+            .chain(get_file_version(JAVA_VERSION, parse_java_version_file))
+            .chain(get_file_version(SDKMANRC, parse_sdkmanrc))
+            .collect();
+        if sources.is_empty() {
+            sources.extend(get_file_version(POM_XML, parse_pom)
+                .or_else(|| BUILD_GRADLE.iter().find_map(|file| get_file_version(file, parse_gradle_toolchain))));
         }
+        sources
+    }
 
-        None
+    /// java -version prints to stderr
+    fn get_system_version(&self, bin_path: &Path) -> Option<GgVersion> {
+        let output = Command::new(bin_path).arg("-version").output().ok()?;
+        parse_system_version(&String::from_utf8_lossy(&output.stderr))
     }
 
-    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
-        Box::pin(async move { get_java_download_urls(&input.target).await })
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        let mut files = GradleAndWrapperProperties::get_files();
+        files.extend([JAVA_VERSION, SDKMANRC, POM_XML].iter().chain(BUILD_GRADLE.iter()).map(PathBuf::from));
+        files
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let config = input.config.executor("java");
+            let vendor = get_vendor(input);
+            let downloads = match java_vendors::get_downloads(&vendor, &input.target).await {
+                Some(downloads) => downloads?,
+                None => get_java_download_urls(&input.target).await?,
+            };
+            Ok(with_mirror(input, "java", java_vendors::get_download_base(&vendor), downloads).into_iter()
+                .filter(|d| config.javafx.map(|javafx| javafx == d.tags.contains("javafx")).unwrap_or(true))
+                .collect())
+        })
     }
 
     fn get_bins(&self, input: &AppInput) -> Vec<String> {
@@ -81,19 +184,47 @@ impl Executor for Java {
         "java"
     }
 
+    /// Zulu is left out, so installs from before vendors keep their folder
+    fn get_flavor(&self, input: &AppInput) -> Option<String> {
+        Some(get_vendor(input)).filter(|v| v != "zulu")
+    }
+
+    fn get_bin_dirs(&self) -> Vec<String> {
+        vec!["bin".to_string(), "Contents/Home/bin".to_string(), ".".to_string()]
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("eclipse-temurin:{tag}"))
+    }
+
+    /// GraalVM has a .sha256 next to each archive, the lists of the other vendors have the checksum
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            if !download.download_url.contains("/graalvm-community-jdk-") {
+                return Ok(None);
+            }
+            fetch_sha256(&format!("{}.sha256", download.download_url), download).await.map(Some)
+        })
+    }
+
+    /// Temurin publishes a .sig next to each binary. Other vendors don't
+    fn get_signature(&self, download: &Download) -> Option<Signature> {
+        download.download_url.contains("github.com/adoptium/")
+            .then(|| Signature::of_download(&download.download_url, &ADOPTIUM_KEYS))
+    }
+
     fn get_default_include_tags(&self) -> HashSet<String> {
         vec!["jdk", "ga"].into_iter().map(|s| s.to_string()).collect()
     }
 
-    fn get_env(&self, app_path: &AppPath) -> HashMap<String, String> {
-        [(String::from("JAVA_HOME"), app_path.install_dir.to_str().unwrap().to_string())].iter().cloned().collect()
+    fn get_exec_context(&self, _input: &AppInput, app_path: &AppPath, _deps: &ExecContext) -> ExecContext {
+        ExecContext::new(app_path, &self.get_bin_dirs()).env("JAVA_HOME", &get_java_home(&app_path.install_dir))
     }
 }
 
-async fn get_java_download_urls(target: &Target) -> Vec<Download> {
-    let json = reqwest::get("https://www.azul.com/wp-admin/admin-ajax.php?action=bundles&endpoint=community&use_stage=false&include_fields=java_version,release_status,abi,arch,bundle_type,cpu_gen,ext,features,hw_bitness,javafx,latest,os,support_term").await.unwrap().text().await.unwrap();
-    let root: Root = serde_json::from_str(json.as_str()).expect("JSON was not well-formatted");
-    root.iter().filter(|node| {
+async fn get_java_download_urls(target: &Target) -> Result<Vec<Download>, GgError> {
+    let root: Root = diagnostics::get_json("https://www.azul.com/wp-admin/admin-ajax.php?action=bundles&endpoint=community&use_stage=false&include_fields=java_version,release_status,abi,arch,bundle_type,cpu_gen,ext,features,hw_bitness,javafx,latest,os,support_term").await?;
+    Ok(root.iter().filter(|node| {
         match target.os {
             Os::Windows => node.ext == "zip",
             _ => node.ext == "tar.gz"
@@ -108,6 +239,9 @@ async fn get_java_download_urls(target: &Target) -> Vec<Download> {
         for feature in n.features {
             tags.insert(feature);
         }
+        if n.javafx {
+            tags.insert("javafx".to_string());
+        }
         let os = Some(match node.os.as_str() {
             "windows" => Os::Windows,
             x if x.contains("linux") => Os::Linux,
@@ -117,6 +251,7 @@ async fn get_java_download_urls(target: &Target) -> Vec<Download> {
             ("x86", "64") => Some(Arch::X86_64),
             ("arm", "32") => Some(Arch::Armv7),
             ("arm", "64") => Some(Arch::Arm64),
+            ("ppc", "64") => Some(Arch::Ppc64le),
             _ => None
         };
         let variant = if node.os.as_str().contains("musl") {
@@ -132,6 +267,49 @@ async fn get_java_download_urls(target: &Target) -> Vec<Download> {
             arch,
             variant,
             tags,
+            sha256: None,
+            checksum: None,
+            date: None,
         }
-    }).collect()
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_vendor() {
+        let mut input = AppInput::dummy();
+        assert_eq!("zulu", get_vendor(&input));
+        input.no_clap.vendor = Some("Adoptium".to_string());
+        assert_eq!("temurin", get_vendor(&input));
+        input.no_clap.vendor = Some("corretto".to_string());
+        assert_eq!("corretto", get_vendor(&input));
+    }
+
+    #[test]
+    fn test_parse_java_version_file() {
+        assert_eq!(VersionReq::parse("17").ok(), parse_java_version_file("17.0.2\n"));
+        assert_eq!(VersionReq::parse("11").ok(), parse_java_version_file("openjdk64-11.0.11"));
+    }
+
+    #[test]
+    fn test_parse_sdkmanrc() {
+        assert_eq!(VersionReq::parse("21").ok(), parse_sdkmanrc("# Enable auto-env\njava=21.0.2-tem\nmaven=3.9.6\n"));
+        assert_eq!(None, parse_sdkmanrc("maven=3.9.6"));
+    }
+
+    #[test]
+    fn test_parse_pom() {
+        assert_eq!(VersionReq::parse("17").ok(), parse_pom("<properties>\n  <maven.compiler.release>17</maven.compiler.release>\n</properties>"));
+        assert_eq!(VersionReq::parse("8").ok(), parse_pom("<maven.compiler.source>1.8</maven.compiler.source><maven.compiler.target>1.8</maven.compiler.target>"));
+        assert_eq!(None, parse_pom("<project></project>"));
+    }
+
+    #[test]
+    fn test_parse_gradle_toolchain() {
+        assert_eq!(VersionReq::parse("17").ok(), parse_gradle_toolchain("java {\n    toolchain {\n        languageVersion = JavaLanguageVersion.of(17)\n    }\n}"));
+        assert_eq!(VersionReq::parse("21").ok(), parse_gradle_toolchain("languageVersion.set(JavaLanguageVersion.of(\"21\"))"));
+    }
 }
@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+
+use futures_util::future::join_all;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::bloody_indiana_jones::{Checksum, HashAlgorithm};
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{Download, GgVersion};
+use crate::http;
+use crate::target::{Arch, Os, Target, Variant};
+
+pub const VENDORS: [&str; 5] = ["zulu", "temurin", "liberica", "graalvm", "corretto"];
+
+/// One repo per LTS, corretto-jdk has the newest feature release
+const CORRETTO_REPOS: [&str; 6] = ["corretto-8", "corretto-11", "corretto-17", "corretto-21", "corretto-25", "corretto-jdk"];
+const CORRETTO_DL: &str = "https://corretto.aws/downloads/resources";
+
+/// 8, 11, then every fourth release from 17
+fn is_lts(major: u64) -> bool {
+    major == 8 || major == 11 || (major >= 17 && (major - 17).is_multiple_of(4))
+}
+
+fn get_tags(image_type: &str, ga: bool, major: u64) -> HashSet<String> {
+    let mut tags = HashSet::new();
+    tags.insert(image_type.to_string());
+    tags.insert(if ga { "ga" } else { "ea" }.to_string());
+    if is_lts(major) {
+        tags.insert("lts".to_string());
+    }
+    tags
+}
+
+/// Where the archives of each vendor are, the part replaced by mirror in [java]
+pub fn get_download_base(vendor: &str) -> &'static str {
+    match vendor {
+        "temurin" => "https://github.com/adoptium",
+        "liberica" => "https://download.bell-sw.com",
+        "graalvm" => "https://github.com/graalvm/graalvm-ce-builds/releases/download",
+        "corretto" => CORRETTO_DL,
+        _ => "https://cdn.azul.com/zulu/bin",
+    }
+}
+
+/// The lists are already for the target, so the downloads are for any target
+fn to_download(download_url: String, version: &str, tags: HashSet<String>, sha256: Option<String>) -> Download {
+    Download {
+        download_url,
+        version: GgVersion::new(version),
+        tags,
+        arch: Some(Arch::Any),
+        os: Some(Os::Any),
+        variant: Some(Variant::Any),
+        sha256,
+        checksum: None,
+        date: None,
+    }
+}
+
+#[derive(Deserialize)]
+struct AdoptiumRelease {
+    binaries: Vec<AdoptiumBinary>,
+    version_data: AdoptiumVersion,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumBinary {
+    image_type: String,
+    package: AdoptiumPackage,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumPackage {
+    link: String,
+    checksum: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AdoptiumVersion {
+    major: u64,
+    minor: u64,
+    security: u64,
+}
+
+fn parse_adoptium(releases: &[AdoptiumRelease]) -> Vec<Download> {
+    releases.iter().flat_map(|release| {
+        let v = &release.version_data;
+        release.binaries.iter()
+            .filter(|b| b.image_type == "jdk" || b.image_type == "jre")
+            .map(move |b| to_download(
+                b.package.link.clone(),
+                &format!("{}.{}.{}", v.major, v.minor, v.security),
+                get_tags(&b.image_type, true, v.major),
+                b.package.checksum.clone(),
+            ))
+    }).collect()
+}
+
+/// The 100 newest GA releases from the Adoptium API, several years of every major
+async fn get_temurin(target: &Target) -> Result<Vec<Download>, GgError> {
+    let os = match (target.os, target.variant) {
+        (Os::Windows, _) => "windows",
+        (Os::Mac, _) => "mac",
+        (_, Some(Variant::Musl)) => "alpine-linux",
+        _ => "linux",
+    };
+    let arch = match target.arch {
+        Arch::X86_64 => "x64",
+        Arch::Arm64 => "aarch64",
+        Arch::Armv7 => "arm",
+        Arch::Riscv64 => "riscv64",
+        Arch::Ppc64le => "ppc64le",
+        Arch::S390x => "s390x",
+        Arch::Any => return Err(GgError::no_build("temurin", target)),
+    };
+    let url = format!("https://api.adoptium.net/v3/assets/version/%5B8%2C%29?os={os}&architecture={arch}&release_type=ga&vendor=eclipse&project=jdk&heap_size=normal&sort_order=DESC&page_size=100");
+    Ok(parse_adoptium(&diagnostics::get_json::<Vec<AdoptiumRelease>>(&url).await?))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibericaRelease {
+    feature_version: u64,
+    interim_version: u64,
+    update_version: u64,
+    download_url: String,
+    bundle_type: String,
+    #[serde(rename = "GA")]
+    ga: bool,
+    sha1: Option<String>,
+}
+
+/// BellSoft only publishes a SHA-1 of each archive
+fn parse_liberica(releases: Vec<LibericaRelease>) -> Vec<Download> {
+    releases.into_iter().map(|r| {
+        let version = format!("{}.{}.{}", r.feature_version, r.interim_version, r.update_version);
+        let mut download = to_download(r.download_url, &version, get_tags(&r.bundle_type, r.ga, r.feature_version), None);
+        download.checksum = r.sha1.map(|hash| Checksum { algorithm: HashAlgorithm::Sha1, hash });
+        download
+    }).collect()
+}
+
+async fn get_liberica(target: &Target) -> Result<Vec<Download>, GgError> {
+    let (os, package_type) = match (target.os, target.variant) {
+        (Os::Windows, _) => ("windows", "zip"),
+        (Os::Mac, _) => ("macos", "tar.gz"),
+        (_, Some(Variant::Musl)) => ("linux-musl", "tar.gz"),
+        _ => ("linux", "tar.gz"),
+    };
+    let (arch, bitness) = match target.arch {
+        Arch::X86_64 => ("x86", 64),
+        Arch::Arm64 => ("arm", 64),
+        Arch::Armv7 => ("arm", 32),
+        Arch::Riscv64 => ("riscv", 64),
+        Arch::Ppc64le => ("ppc", 64),
+        Arch::S390x | Arch::Any => return Err(GgError::no_build("liberica", target)),
+    };
+    let url = format!("https://api.bell-sw.com/v1/liberica/releases?os={os}&arch={arch}&bitness={bitness}&package-type={package_type}&installation-type=archive");
+    Ok(parse_liberica(diagnostics::get_json(&url).await?))
+}
+
+/// E.g. graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz, older CE releases are named differently and left out
+fn graalvm_asset_to_download(name: &str, url: &str, target: &Target) -> Option<Download> {
+    let re = Regex::new(r"^graalvm-community-jdk-(\d+(?:\.\d+)*)_(linux|macos|windows)-(x64|aarch64)_bin\.(?:tar\.gz|zip)$").unwrap();
+    let c = re.captures(name)?;
+    let os = match &c[2] {
+        "linux" => Os::Linux,
+        "macos" => Os::Mac,
+        _ => Os::Windows,
+    };
+    let arch = if &c[3] == "aarch64" { Arch::Arm64 } else { Arch::X86_64 };
+    if os != target.os || arch != target.arch || target.variant == Some(Variant::Musl) {
+        return None;
+    }
+    let major = c[1].split('.').next()?.parse().ok()?;
+    Some(to_download(url.to_string(), &c[1], get_tags("jdk", true, major), None))
+}
+
+async fn get_graalvm(target: &Target) -> Result<Vec<Download>, GgError> {
+    let releases = http::get_releases("graalvm", "graalvm-ce-builds", 2, 50).await?;
+    Ok(releases.iter()
+        .flat_map(|release| release.assets.iter())
+        .filter_map(|asset| graalvm_asset_to_download(&asset.name, asset.browser_download_url.as_str(), target))
+        .collect())
+}
+
+/// 21.0.2.13.1 is 21.0.2 build 13.1, and 8.402.08.1 of Java 8 is 8u402 build 08.1
+fn corretto_version(tag: &str) -> Option<String> {
+    let parts: Vec<u64> = tag.split('.').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let (version, build) = match parts.as_slice() {
+        [8, update, build @ ..] => (format!("8.0.{update}"), build),
+        [major, minor, patch, build @ ..] => (format!("{major}.{minor}.{patch}"), build),
+        _ => return None,
+    };
+    let build: Vec<String> = build.iter().map(|b| b.to_string()).collect();
+    Some(if build.is_empty() { version } else { format!("{version}+{}", build.join(".")) })
+}
+
+/// How the archive of the target is named after amazon-corretto-<tag>-
+fn corretto_platform(target: &Target) -> Option<&'static str> {
+    Some(match (target.os, target.arch, target.variant) {
+        (Os::Windows, Arch::X86_64, _) => "windows-x64-jdk.zip",
+        (Os::Mac, Arch::X86_64, _) => "macosx-x64.tar.gz",
+        (Os::Mac, Arch::Arm64, _) => "macosx-aarch64.tar.gz",
+        (Os::Linux, Arch::X86_64, Some(Variant::Musl)) => "alpine-linux-x64.tar.gz",
+        (Os::Linux, Arch::Arm64, Some(Variant::Musl)) => "alpine-linux-aarch64.tar.gz",
+        (Os::Linux, Arch::X86_64, _) => "linux-x64.tar.gz",
+        (Os::Linux, Arch::Arm64, _) => "linux-aarch64.tar.gz",
+        _ => return None,
+    })
+}
+
+/// The table in the notes of a release links each archive, with its MD5 and SHA-256 on the same row. Releases without
+/// the archive of the target are left out
+fn corretto_release_to_download(tag: &str, body: &str, prerelease: bool, platform: &str) -> Option<Download> {
+    let url = format!("{CORRETTO_DL}/{tag}/amazon-corretto-{tag}-{platform}");
+    let row = body.lines().find(|line| line.contains(&url))?;
+    let sha256 = Regex::new(r"\b[0-9a-f]{64}\b").unwrap().find(row).map(|m| m.as_str().to_string());
+    let version = corretto_version(tag)?;
+    let major = version.split('.').next()?.parse().ok()?;
+    Some(to_download(url, &version, get_tags("jdk", !prerelease, major), sha256))
+}
+
+async fn get_corretto(target: &Target) -> Result<Vec<Download>, GgError> {
+    let platform = corretto_platform(target).ok_or(GgError::no_build("corretto", target))?;
+    let repos = join_all(CORRETTO_REPOS.iter().map(|repo| http::get_releases("corretto", repo, 1, 50))).await;
+    let mut downloads = vec!();
+    for releases in repos {
+        downloads.extend(releases?.iter().filter_map(|release| corretto_release_to_download(
+            &release.tag_name, release.body.as_deref().unwrap_or_default(), release.prerelease, platform,
+        )));
+    }
+    Ok(downloads)
+}
+
+/// None for zulu, which is the default and handled by the java executor itself
+pub async fn get_downloads(vendor: &str, target: &Target) -> Option<Result<Vec<Download>, GgError>> {
+    match vendor {
+        "temurin" => Some(get_temurin(target).await),
+        "liberica" => Some(get_liberica(target).await),
+        "graalvm" => Some(get_graalvm(target).await),
+        "corretto" => Some(get_corretto(target).await),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_lts() {
+        assert_eq!(vec!(8, 11, 17, 21, 25), (7..27).filter(|m| is_lts(*m)).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_parse_adoptium() {
+        let json = r#"[{"binaries": [
+            {"image_type": "jdk", "os": "linux", "package": {"link": "https://github.com/adoptium/temurin17-binaries/releases/download/jdk-17.0.10%2B7/OpenJDK17U-jdk_x64_linux_hotspot_17.0.10_7.tar.gz", "checksum": "a8fd07e1"}},
+            {"image_type": "debugimage", "os": "linux", "package": {"link": "https://example.com/debug.tar.gz", "checksum": "00"}}
+        ], "version_data": {"major": 17, "minor": 0, "security": 10, "semver": "17.0.10+7"}}]"#;
+        let downloads = parse_adoptium(&serde_json::from_str::<Vec<AdoptiumRelease>>(json).unwrap());
+        assert_eq!(1, downloads.len());
+        assert_eq!("17.0.10", downloads[0].version.as_ref().unwrap().to_string());
+        assert_eq!(Some("a8fd07e1".to_string()), downloads[0].sha256);
+        assert!(downloads[0].tags.contains("jdk") && downloads[0].tags.contains("ga") && downloads[0].tags.contains("lts"));
+    }
+
+    #[test]
+    fn test_graalvm_asset_to_download() {
+        let target = Target { os: Os::Mac, arch: Arch::Arm64, variant: None };
+        let download = graalvm_asset_to_download("graalvm-community-jdk-21.0.2_macos-aarch64_bin.tar.gz", "https://example.com/a", &target).unwrap();
+        assert_eq!("21.0.2", download.version.unwrap().to_string());
+        assert!(graalvm_asset_to_download("graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz", "", &target).is_none());
+        assert!(graalvm_asset_to_download("graalvm-community-jdk-21.0.2_macos-aarch64_bin.tar.gz.sha256", "", &target).is_none());
+    }
+
+    #[test]
+    fn test_parse_liberica() {
+        let json = r#"[{"featureVersion": 21, "interimVersion": 0, "updateVersion": 2, "downloadUrl": "https://download.bell-sw.com/java/21.0.2+14/bellsoft-jdk21.0.2+14-linux-amd64.tar.gz", "bundleType": "jdk", "GA": true, "sha1": "0a1b2c"}]"#;
+        let downloads = parse_liberica(serde_json::from_str(json).unwrap());
+        assert_eq!(Some(Checksum { algorithm: HashAlgorithm::Sha1, hash: "0a1b2c".to_string() }), downloads[0].checksum);
+    }
+
+    #[test]
+    fn test_corretto_release_to_download() {
+        let sha256 = "d6ec5b0ec8d8fd1ab4ef1e2c5e0b3cd0b2ae4cd6f9d7ade8dd4e01b5e7a4e1d0";
+        let body = format!("|Platform | Type | Download Link | Checksum (MD5) / Checksum (SHA256) | Sig File |\n\
+            |Linux x64 | JDK | [amazon-corretto-21.0.2.13.1-linux-x64.tar.gz](https://corretto.aws/downloads/resources/21.0.2.13.1/amazon-corretto-21.0.2.13.1-linux-x64.tar.gz) | `5cc8aa7cb2ed6cc96e4c4bc2bf0e7a1c` / `{sha256}` | [Download](https://corretto.aws/downloads/resources/21.0.2.13.1/amazon-corretto-21.0.2.13.1-linux-x64.tar.gz.sig) |\n");
+        let download = corretto_release_to_download("21.0.2.13.1", &body, false, "linux-x64.tar.gz").unwrap();
+        assert_eq!("21.0.2+13.1", download.version.unwrap().to_string());
+        assert_eq!(Some(sha256.to_string()), download.sha256);
+        assert!(download.tags.contains("ga") && download.tags.contains("lts"));
+        assert!(corretto_release_to_download("21.0.2.13.1", &body, false, "macosx-aarch64.tar.gz").is_none());
+        assert_eq!(Some("8.0.402+8.1".to_string()), corretto_version("8.402.08.1"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use regex::Regex;
+use semver::VersionReq;
+
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256, get_file_version, intersect_version_reqs, NamedBin, VersionSource};
+use crate::http;
+use crate::mirror::with_mirror;
+use crate::target::{Arch, Os, Variant};
+
+const KOTLIN_RELEASES: &str = "https://github.com/JetBrains/kotlin/releases/download";
+const RELEASE_PAGES: u32 = 3;
+const LIBS_VERSIONS_TOML: &str = "gradle/libs.versions.toml";
+const BUILD_GRADLE: [&str; 2] = ["build.gradle.kts", "build.gradle"];
+
+pub struct Kotlin {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// kotlin-compiler-2.0.0-RC1.zip is tagged rc, -Beta4 beta
+fn asset_to_download(name: &str, url: &str) -> Option<Download> {
+    let version = name.strip_prefix("kotlin-compiler-")?.strip_suffix(".zip")?;
+    let mut download = Download::new(url.to_string(), version, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(Os::Any);
+    download.arch = Some(Arch::Any);
+    if let Some((_, pre)) = version.split_once('-') {
+        download.tags.insert(pre.trim_end_matches(|c: char| c.is_ascii_digit()).to_lowercase());
+    }
+    Some(download)
+}
+
+fn to_version_req(version: &str) -> Option<VersionReq> {
+    VersionReq::parse(&format!("={version}")).ok()
+}
+
+/// `kotlin = "1.9.22"` in [versions] of the Gradle version catalog
+fn parse_version_catalog(text: &str) -> Option<VersionReq> {
+    let catalog: toml::Value = toml::from_str(text).ok()?;
+    to_version_req(catalog.get("versions")?.get("kotlin")?.as_str()?)
+}
+
+/// `kotlin("jvm") version "1.9.22"` or `id("org.jetbrains.kotlin.jvm") version "1.9.22"`, Kotlin or Groovy DSL
+fn parse_gradle_plugin(text: &str) -> Option<VersionReq> {
+    let plugin = Regex::new(r#"(?:kotlin\(\s*"[\w.-]+"\s*\)|id\s*\(?\s*["']org\.jetbrains\.kotlin\.[\w.-]+["']\s*\)?)\s+version\s+["']([^"']+)["']"#).unwrap();
+    to_version_req(&plugin.captures(text)?[1])
+}
+
+impl Executor for Kotlin {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    /// The version catalog first, the plugin in the build file only without one
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_file_version(LIBS_VERSIONS_TOML, parse_version_catalog)
+            .or_else(|| BUILD_GRADLE.iter().find_map(|file| get_file_version(file, parse_gradle_plugin)))
+            .into_iter()
+            .collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        [LIBS_VERSIONS_TOML].iter().chain(BUILD_GRADLE.iter()).map(PathBuf::from).collect()
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let releases = http::get_releases("JetBrains", "kotlin", RELEASE_PAGES, 100).await?;
+            let downloads = releases.iter()
+                .flat_map(|release| release.assets.iter())
+                .filter_map(|asset| asset_to_download(&asset.name, asset.browser_download_url.as_str()))
+                .collect();
+            Ok(with_mirror(input, "kotlin", KOTLIN_RELEASES, downloads))
+        })
+    }
+
+    /// Each zip has a .sha256 next to it
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256", download.download_url), download).await.map(Some)
+        })
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(NamedBin::new("kotlin").alias("kotlinc").unix(&["kotlinc"]).windows(&["kotlinc.bat"]))
+    }
+
+    fn get_name(&self) -> &str {
+        "kotlin"
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
+        vec!("java")
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["beta", "rc", "dev"].into_iter().map(|s| s.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_to_download() {
+        let download = asset_to_download("kotlin-compiler-1.9.22.zip", "https://example.com/a").unwrap();
+        assert_eq!("1.9.22", download.version.unwrap().to_string());
+        assert!(download.tags.is_empty());
+        assert!(asset_to_download("kotlin-compiler-2.0.0-RC1.zip", "").unwrap().tags.contains("rc"));
+        assert!(asset_to_download("kotlin-compiler-2.0.0-Beta4.zip", "").unwrap().tags.contains("beta"));
+        assert!(asset_to_download("kotlin-compiler-1.9.22.zip.sha256", "").is_none());
+        assert!(asset_to_download("kotlin-native-linux-x86_64-1.9.22.tar.gz", "").is_none());
+    }
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!("=1.9.22", parse_version_catalog("[versions]\nkotlin = \"1.9.22\"\nktor = \"2.3.8\"\n").unwrap().to_string());
+        assert!(parse_version_catalog("[versions]\nktor = \"2.3.8\"\n").is_none());
+        assert_eq!("=1.9.22", parse_gradle_plugin("plugins {\n    kotlin(\"jvm\") version \"1.9.22\"\n}").unwrap().to_string());
+        assert_eq!("=2.0.0", parse_gradle_plugin("plugins {\n    id 'org.jetbrains.kotlin.jvm' version '2.0.0'\n}").unwrap().to_string());
+        assert!(parse_gradle_plugin("plugins {\n    id(\"java\")\n}").is_none());
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+#[cfg(unix)]
+use std::fs;
+use std::future::Future;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::path::Path;
+use std::pin::Pin;
+
+
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256};
+use crate::http;
+use crate::mirror::mirror_url;
+use crate::target::{Arch, Os, Target, Variant};
+
+pub const K8S_DL: &str = "https://dl.k8s.io";
+const RELEASE_PAGES: u32 = 3;
+
+pub struct Kubectl {
+    pub executor_cmd: ExecutorCmd,
+}
+
+fn get_os_arch(target: &Target) -> Option<(&'static str, &'static str)> {
+    let os = match target.os {
+        Os::Linux => "linux",
+        Os::Mac => "darwin",
+        Os::Windows => "windows",
+        Os::Any => return None,
+    };
+    let arch = match target.arch {
+        Arch::X86_64 => "amd64",
+        Arch::Arm64 => "arm64",
+        Arch::Armv7 => "arm",
+        Arch::Ppc64le => "ppc64le",
+        Arch::S390x => "s390x",
+        Arch::Riscv64 | Arch::Any => return None,
+    };
+    Some((os, arch))
+}
+
+fn get_bin(target: &Target) -> &'static str {
+    match target.os {
+        Os::Windows => "kubectl.exe",
+        _ => "kubectl",
+    }
+}
+
+/// The binary itself, not an archive. v1.30.0-rc.1 is tagged rc. The version in stable.txt is tagged stable
+fn to_download(base: &str, tag: &str, stable: &str, target: &Target) -> Option<Download> {
+    let (os, arch) = get_os_arch(target)?;
+    let mut download = Download::new(format!("{base}/release/{tag}/bin/{os}/{arch}/{}", get_bin(target)), tag, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(target.os);
+    download.arch = Some(target.arch);
+    if let Some((_, pre)) = tag.split_once('-') {
+        download.tags.insert(pre.chars().take_while(|c| c.is_ascii_alphabetic()).collect());
+    }
+    if tag == stable {
+        download.tags.insert("stable".to_string());
+    }
+    Some(download)
+}
+
+fn get_downloads(base: &str, tags: &[String], stable: &str, target: &Target) -> Vec<Download> {
+    let mut tags: Vec<&str> = tags.iter().map(|t| t.as_str()).collect();
+    if !stable.is_empty() && !tags.contains(&stable) {
+        tags.push(stable);
+    }
+    tags.into_iter().filter_map(|tag| to_download(base, tag, stable, target)).collect()
+}
+
+impl Executor for Kubectl {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    /// Tags of kubernetes/kubernetes, plus release/stable.txt in case the newest is not in the pages read
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let base = mirror_url(input, "kubectl", K8S_DL);
+            let stable = diagnostics::get_text(&format!("{base}/release/stable.txt")).await?.trim().to_string();
+            let tags: Vec<String> = http::get_releases("kubernetes", "kubernetes", RELEASE_PAGES, 100).await?
+                .into_iter()
+                .map(|release| release.tag_name)
+                .collect();
+            Ok(get_downloads(&base, &tags, &stable, &input.target))
+        })
+    }
+
+    /// <url>.sha256 has only the hash
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256", download.download_url), download).await.map(Some)
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(get_bin(&input.target).to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "kubectl"
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["alpha", "beta", "rc"].into_iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Downloaded as is, so not executable yet
+    #[cfg(unix)]
+    fn post_prep(&self, cache_path: &str, _install_dir: &str) -> Result<(), String> {
+        let path = Path::new(cache_path).join("kubectl");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Unable to make {} executable: {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_downloads() {
+        let target = Target { os: Os::Linux, arch: Arch::Arm64, variant: None };
+        let tags = vec!("v1.30.0-rc.1".to_string(), "v1.29.2".to_string());
+        let downloads = get_downloads(K8S_DL, &tags, "v1.29.3", &target);
+        assert_eq!(3, downloads.len());
+        assert!(downloads[0].tags.contains("rc"));
+        assert_eq!("https://dl.k8s.io/release/v1.29.2/bin/linux/arm64/kubectl", downloads[1].download_url);
+        assert_eq!("1.29.3", downloads[2].version.as_ref().unwrap().to_string());
+        assert!(downloads[2].tags.contains("stable"));
+    }
+}
@@ -1,17 +1,88 @@
 use std::collections::HashSet;
+use std::env;
+use std::fs;
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
 
+use log::info;
+use regex::Regex;
 use scraper::{Html, Selector};
+use semver::VersionReq;
 
+use crate::error::GgError;
+use crate::diagnostics;
 use crate::Executor;
-use crate::executor::{AppInput, Download, ExecutorCmd, GgVersion};
+use crate::executor::{AppInput, AppPath, Download, ExecutorCmd, GgMeta, GgVersion, NamedBin};
+use crate::executors::java::get_java_home;
+use crate::mirror::mirror_url;
 use crate::target::{Arch, Os, Variant};
 
+const MAVEN_WRAPPER_PROPERTIES: &str = ".mvn/wrapper/maven-wrapper.properties";
+pub const MAVEN_DIST: &str = "https://archive.apache.org/dist/maven";
+
 pub struct Maven {
     pub executor_cmd: ExecutorCmd,
 }
 
+struct Jdk {
+    version: String,
+    vendor: String,
+    home: String,
+}
+
+fn toolchains_xml(jdks: &[Jdk]) -> String {
+    let toolchains = jdks.iter().map(|jdk| format!(r"  <toolchain>
+    <type>jdk</type>
+    <provides>
+      <version>{}</version>
+      <vendor>{}</vendor>
+    </provides>
+    <configuration>
+      <jdkHome>{}</jdkHome>
+    </configuration>
+  </toolchain>
+", jdk.version, jdk.vendor, jdk.home)).collect::<String>();
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<toolchains>\n{toolchains}</toolchains>\n")
+}
+
+fn find_installed_jdks(input: &AppInput) -> Vec<Jdk> {
+    walkdir::WalkDir::new(input.config.get_install_dir().join("java")).max_depth(2).into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_name().to_string_lossy() == "gg-meta.json")
+        .filter_map(|entry| {
+            let meta = serde_json::from_str::<GgMeta>(&fs::read_to_string(entry.path()).ok()?).ok()?;
+            if meta.download.tags.contains("jre") {
+                return None;
+            }
+            let version = meta.download.version?.to_string();
+            // Zulu has no flavor, as installs from before vendors
+            let vendor = meta.flavor.unwrap_or("zulu".to_string());
+            let install_dir = env::current_dir().ok()?.join(entry.path().parent()?).canonicalize().ok()?;
+            let home = get_java_home(&install_dir);
+            Some(Jdk { version, vendor, home: home.to_str()?.to_string() })
+        }).collect()
+}
+
+/// Writes toolchains.xml for use with maven-toolchains-plugin, returns the path to the file
+fn write_toolchains_xml(input: &AppInput) -> Option<String> {
+    let jdks = find_installed_jdks(input);
+    info!("Found {} JDKs for toolchains.xml", jdks.len());
+    fs::create_dir_all(".cache/gg/maven").ok()?;
+    let path = env::current_dir().ok()?.join(".cache/gg/maven/toolchains.xml");
+    fs::write(&path, toolchains_xml(&jdks)).ok()?;
+    path.to_str().map(|s| s.to_string())
+}
+
+/// Version of distributionUrl, e.g. https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/3.9.6/apache-maven-3.9.6-bin.zip
+fn get_wrapper_version(properties: &str) -> Option<String> {
+    let url = properties.lines()
+        .find_map(|line| line.trim().strip_prefix("distributionUrl"))?
+        .trim_start_matches(|c: char| c == '=' || c == ':' || c.is_whitespace());
+    let version = Regex::new(r"apache-maven-([\d.]+)-bin").unwrap();
+    version.captures(url).map(|c| c[1].to_string())
+}
+
 fn get_version(link: &str) -> String {
     link.replace("apache-maven-", "").replace("maven-", "").replace("-bin.tar.gz", "").replace(".tar.gz", "").to_string()
 }
@@ -21,15 +92,25 @@ impl Executor for Maven {
         return &self.executor_cmd;
     }
 
-    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
+    /// Exactly the version of the maven wrapper, as the wrapper would download it
+    fn get_version_req(&self) -> Option<VersionReq> {
+        let version = get_wrapper_version(&fs::read_to_string(MAVEN_WRAPPER_PROPERTIES).ok()?)?;
+        info!("Found maven {version} in {MAVEN_WRAPPER_PROPERTIES}");
+        VersionReq::parse(&format!("={version}")).ok()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(MAVEN_WRAPPER_PROPERTIES))
+    }
+
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
         Box::pin(async move {
-            let url = "https://archive.apache.org/dist/maven/binaries/";
-            let body = reqwest::get(url).await
-                .expect("Unable to connect to archive.apache.org").text().await
-                .expect("Unable to download maven list of versions");
+            let base = mirror_url(input, "maven", MAVEN_DIST);
+            let url = format!("{base}/binaries/");
+            let body = diagnostics::get_text(&url).await?;
 
             let document = Html::parse_document(body.as_str());
-            document.select(&Selector::parse("a").unwrap())
+            let mut downloads: Vec<Download> = document.select(&Selector::parse("a").unwrap())
                 .map(|a| a.text().next().unwrap_or("").trim())
                 .filter(|link| link.contains("maven") && link.ends_with("tar.gz"))
                 .map(|link| {
@@ -47,22 +128,52 @@ impl Executor for Maven {
                         arch: Some(Arch::Any),
                         variant: Some(Variant::Any),
                         tags,
+                        sha256: None,
+                        checksum: None,
+                        date: None,
                     }
-                }).collect()
+                }).collect();
+            // Newer releases are only found per version, as maven-3/<version>/binaries
+            if let Some(version) = fs::read_to_string(MAVEN_WRAPPER_PROPERTIES).ok().and_then(|p| get_wrapper_version(&p)) {
+                if !downloads.iter().any(|d| d.version.as_ref().map(|v| v.to_string()) == Some(version.clone())) {
+                    downloads.push(Download::new(
+                        format!("{base}/maven-3/{version}/binaries/apache-maven-{version}-bin.tar.gz"),
+                        &version,
+                        Some(Variant::Any),
+                    ));
+                }
+            }
+            Ok(downloads)
         })
     }
 
-    fn get_bins(&self, _input: &AppInput) -> Vec<String> {
-        vec!["mvn".to_string(), "mvn.bat".to_string(), "maven.bat".to_string()]
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        let files = ["mvn", "mvn.bat", "maven.bat"];
+        vec!(NamedBin::new("maven").alias("mvn").unix(&files).windows(&files))
     }
 
     fn get_name(&self) -> &str {
         "maven"
     }
 
-    fn get_deps(&self) -> Vec<&str> {
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("maven:{tag}"))
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
         vec!("java")
     }
+
+    fn customize_args(&self, input: &AppInput, _app_path: &AppPath) -> Vec<String> {
+        let args = input.no_clap.app_args.clone();
+        if input.no_clap.maven_toolchains && !args.iter().any(|arg| arg == "-t" || arg == "--toolchains") {
+            if let Some(path) = write_toolchains_xml(input) {
+                info!("Using toolchains {path}");
+                return vec!("--toolchains".to_string(), path).into_iter().chain(args).collect();
+            }
+        }
+        args
+    }
 }
 
 #[cfg(test)]
@@ -74,7 +185,16 @@ mod tests {
         let mvn = Maven { executor_cmd: ExecutorCmd::dummy() };
         let app_input = AppInput::dummy();
         let urls = mvn.get_download_urls(&app_input).await;
-        assert_eq!(urls.is_empty(), false);
+        assert!(!urls.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_toolchains_xml() {
+        let xml = toolchains_xml(&[Jdk { version: "17.0.8".to_string(), vendor: "temurin".to_string(), home: "/tmp/java".to_string() }]);
+        assert!(xml.contains("<version>17.0.8</version>"));
+        assert!(xml.contains("<vendor>temurin</vendor>"));
+        assert!(xml.contains("<jdkHome>/tmp/java</jdkHome>"));
+        assert!(xml.starts_with("<?xml"));
     }
 
     #[test]
@@ -84,4 +204,11 @@ mod tests {
         assert_eq!(get_version("maven-1.0-beta-10.tar.gz"), "1.0-beta-10");
         assert_eq!(get_version("maven-2.0-alpha-2-bin.tar.gz"), "2.0-alpha-2");
     }
+
+    #[test]
+    fn test_get_wrapper_version() {
+        let properties = "wrapperVersion=3.3.2\ndistributionUrl=https\\://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/3.9.6/apache-maven-3.9.6-bin.zip\n";
+        assert_eq!(Some("3.9.6".to_string()), get_wrapper_version(properties));
+        assert_eq!(None, get_wrapper_version("wrapperVersion=3.3.2"));
+    }
 }
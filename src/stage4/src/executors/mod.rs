@@ -1,11 +1,54 @@
+#[cfg(feature = "java")]
 pub mod java;
+#[cfg(feature = "java")]
+pub mod java_vendors;
 pub mod custom_command;
+pub mod plugin;
+#[cfg(feature = "gradle")]
 pub mod gradle;
+#[cfg(feature = "maven")]
 pub mod maven;
+#[cfg(feature = "openapi")]
 pub mod openapigenerator;
+#[cfg(feature = "rat")]
 pub mod rat;
+#[cfg(feature = "node")]
 pub mod node;
+#[cfg(feature = "deno")]
 pub mod deno;
+#[cfg(any(feature = "java", feature = "gradle"))]
 pub mod gradle_properties;
+#[cfg(feature = "go")]
 pub mod go;
+#[cfg(feature = "caddy")]
 pub mod caddy;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "node")]
+pub mod npm_registry;
+#[cfg(feature = "kotlin")]
+pub mod kotlin;
+#[cfg(feature = "terraform")]
+pub mod terraform;
+#[cfg(feature = "kubectl")]
+pub mod kubectl;
+#[cfg(feature = "helm")]
+pub mod helm;
+#[cfg(feature = "rust")]
+pub mod rust;
+#[cfg(feature = "dotnet")]
+pub mod dotnet;
+#[cfg(feature = "dart")]
+pub mod dart;
+#[cfg(feature = "flutter")]
+pub mod flutter;
+#[cfg(feature = "ruby")]
+pub mod ruby;
+#[cfg(feature = "php")]
+pub mod php;
+#[cfg(feature = "composer")]
+pub mod composer;
+#[cfg(feature = "erlang")]
+pub mod erlang;
+#[cfg(feature = "elixir")]
+pub mod elixir;
@@ -1,16 +1,25 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fs;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::process::Command;
+use std::sync::Once;
 
-use log::info;
+use log::{info, warn};
 use package_json::PackageJsonManager;
 use regex::Regex;
 use semver::VersionReq;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion};
+use crate::bloody_indiana_jones::{Checksum, HashAlgorithm};
+use crate::error::GgError;
+use crate::diagnostics;
+use crate::mirror;
+use crate::signatures::{NODE_KEYS, Signature};
+use crate::executor::{AppInput, AppPath, Download, ExecContext, Executor, ExecutorCmd, fetch_sha256, GgVersion, intersect_version_reqs, NamedBin, parse_system_version, VersionSource};
 use crate::target::{Arch, Os, Target, Variant};
 
 type Root = Vec<Root2>;
@@ -42,24 +51,88 @@ pub struct Node {
     pub executor_cmd: ExecutorCmd,
 }
 
-fn get_package_version() -> Option<Box<VersionReq>> {
+/// npm ranges separate comparators with space, semver crate wants comma
+fn parse_npm_range(range: &str) -> Option<VersionReq> {
+    let range = range.trim();
+    if range.contains("||") {
+        return None;
+    }
+    let range = Regex::new(r"([0-9xX*])\s+([<>=~^])").unwrap().replace_all(range, "$1, $2");
+    VersionReq::parse(&range).ok()
+}
+
+fn get_engines_version() -> Option<VersionReq> {
     let mut manager = PackageJsonManager::new();
     if manager.locate_closest().is_ok() {
         if let Ok(json) = manager.read_ref() {
-            if json.engines.is_some() {
-                return Some(Box::new(VersionReq::parse(json.clone().engines.clone().unwrap().get("node").unwrap_or(&"".to_string())).unwrap_or(VersionReq::default())));
+            if let Some(node) = json.engines.as_ref().and_then(|engines| engines.get("node")) {
+                info!("Got version {node} from package.json engines");
+                return parse_npm_range(node);
             }
         }
     }
+    None
+}
+
+/// The packageManager field of package.json
+pub struct PackageManagerPin {
+    pub name: String,
+    pub version_req: VersionReq,
+    /// Of the tarball, after the `+`
+    pub checksum: Option<Checksum>,
+}
+
+/// "pnpm@8.15.4+sha256.1234", as Corepack reads it. The version is exact, the hash is of the tarball
+fn parse_package_manager(value: &str) -> Option<PackageManagerPin> {
+    let (name, version) = value.trim().split_once('@')?;
+    let (version, hash) = version.split_once('+').map(|(v, h)| (v, Some(h))).unwrap_or((version, None));
+    let checksum = hash.and_then(|hash| hash.split_once('.')).and_then(|(algorithm, hash)| {
+        let algorithm = match algorithm {
+            "sha1" => HashAlgorithm::Sha1,
+            "sha256" => HashAlgorithm::Sha256,
+            "sha512" => HashAlgorithm::Sha512,
+            _ => return None,
+        };
+        Some(Checksum { algorithm, hash: hash.to_string() })
+    });
+    let version_req = VersionReq::parse(&format!("={version}")).ok()?;
+    Some(PackageManagerPin { name: name.to_string(), version_req, checksum })
+}
+
+/// The packageManager field in the closest package.json
+pub fn get_package_manager() -> Option<PackageManagerPin> {
+    let mut manager = PackageJsonManager::new();
+    manager.locate_closest().ok()?;
+    let value = manager.read_ref().ok()?.unknowns.get("packageManager")?.as_str()?.to_string();
+    parse_package_manager(&value)
+}
+
+static OTHER_PACKAGE_MANAGER: Once = Once::new();
+
+/// The packageManager field, when it is for this package manager. Corepack refuses to run another one, gg warns once
+pub fn get_package_manager_source(name: &str) -> Option<VersionSource> {
+    let package_manager = get_package_manager()?;
+    if package_manager.name != name {
+        OTHER_PACKAGE_MANAGER.call_once(|| warn!("package.json has packageManager {}, not {name}", package_manager.name));
+        return None;
+    }
+    info!("Got version {} from package.json packageManager", package_manager.version_req);
+    Some(VersionSource::new("package.json packageManager", package_manager.version_req))
+}
 
+/// npm and npx are the ones bundled with node, unless packageManager pins npm
+pub fn is_npm_pinned() -> bool {
+    get_package_manager().map(|package_manager| package_manager.name == "npm").unwrap_or(false)
+}
 
+fn get_nvmrc_version() -> Option<VersionReq> {
     if let Ok(nvmrc) = fs::read_to_string(".nvmrc") {
         let nvmrc = Regex::new("^v").unwrap().replace(&nvmrc, "");
         let nvmrc = nvmrc.trim();
         info!("Got version {nvmrc} from .nvmrc");
-        if let Ok(ver) = VersionReq::parse(&nvmrc) {
+        if let Ok(ver) = VersionReq::parse(nvmrc) {
             info!("Got parsed version {ver} from .nvmrc");
-            return Some(Box::new(ver.clone()));
+            return Some(ver);
         }
     }
     None
@@ -71,46 +144,150 @@ impl Executor for Node {
     }
 
     fn get_version_req(&self) -> Option<VersionReq> {
-        if let Some(v) = get_package_version() {
-            Some(*v)
-        } else {
-            None
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        let mut sources = vec!();
+        if let Some(version_req) = get_engines_version() {
+            sources.push(VersionSource::new("package.json engines", version_req));
         }
+        if let Some(version_req) = get_nvmrc_version() {
+            sources.push(VersionSource::new(".nvmrc", version_req));
+        }
+        sources
     }
 
-    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
-        Box::pin(async move { get_node_urls(&input.target).await })
+    fn get_system_version(&self, bin_path: &Path) -> Option<GgVersion> {
+        let output = Command::new(bin_path).arg("--version").output().ok()?;
+        parse_system_version(&String::from_utf8_lossy(&output.stdout))
     }
 
-    fn get_bins(&self, input: &AppInput) -> Vec<String> {
-        vec![match &input.target.os {
-            Os::Windows => match self.executor_cmd.cmd.as_str() {
-                "node" => "node.exe",
-                "npm" => "npm.cmd",
-                _ => "npx.cmd",
-            },
-            _ => match self.executor_cmd.cmd.as_str() {
-                "node" => "node",
-                "npm" => "npm",
-                _ => "npx"
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        let package_json = PackageJsonManager::new().locate_closest().ok();
+        package_json.into_iter().chain([PathBuf::from(".nvmrc")]).collect()
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            match get_mirror(mirror::get_mirror(input, "node")) {
+                Some(mirror) => download_urls(mirror.trim_end_matches('/'), &input.target).await,
+                None => get_node_urls(&input.target).await,
             }
-        }.to_string()]
+        })
+    }
+
+    /// corepack is bundled from node 16.9
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(
+            NamedBin::new("node").windows(&["node.exe"]),
+            NamedBin::new("npm").windows(&["npm.cmd"]),
+            NamedBin::new("npx").windows(&["npx.cmd"]),
+            NamedBin::new("corepack").windows(&["corepack.cmd"]),
+        )
     }
 
     fn get_name(&self) -> &str {
         "node"
     }
+
+    /// From SHASUMS256.txt next to the download, on nodejs.org, unofficial-builds and mirrors alike
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            let dir = download.download_url.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+            fetch_sha256(&format!("{dir}/SHASUMS256.txt"), download).await.map(Some)
+        })
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("node:{tag}"))
+    }
+
+    /// SHASUMS256.txt.sig, signed by one of the releasers
+    fn get_signature(&self, download: &Download) -> Option<Signature> {
+        let (dir, _) = download.download_url.rsplit_once('/')?;
+        Some(Signature::of_checksums(format!("{dir}/SHASUMS256.txt"), format!("{dir}/SHASUMS256.txt.sig"), &NODE_KEYS))
+    }
+
+    /// node-gyp builds with python, the one of gg
+    fn get_deps(&self, input: &AppInput) -> Vec<&str> {
+        if input.no_clap.node_gyp { vec!("python") } else { vec!() }
+    }
+
+    fn get_exec_context(&self, input: &AppInput, app_path: &AppPath, deps: &ExecContext) -> ExecContext {
+        let mut exec_context = ExecContext::new(app_path, &self.get_bin_dirs());
+        if input.no_clap.node_gyp {
+            exec_context.env.extend(get_node_gyp_env(input, app_path, deps));
+        }
+        exec_context
+    }
+}
+
+/// Environment variables node-gyp reads, so native modules can be built on machines without any manual setup
+fn get_node_gyp_env(input: &AppInput, app_path: &AppPath, deps: &ExecContext) -> HashMap<String, String> {
+    let mut env_vars = HashMap::new();
+
+    // The python dep, else one in PATH when there is no build of it
+    let python = ["python3", "python"].iter().find_map(|bin| deps.find(bin))
+        .or_else(|| ["python3", "python"].iter().find_map(|bin| which::which(bin).ok()));
+    if let Some(python) = python.and_then(|p| p.to_str().map(|s| s.to_string())) {
+        info!("node-gyp: Using python {python}");
+        env_vars.insert(String::from("npm_config_python"), python.clone());
+        env_vars.insert(String::from("PYTHON"), python);
+    } else {
+        warn!("node-gyp: python not found. Native modules will most likely fail to build");
+    }
+
+    // Headers are part of the node distribution (not the Windows zip), so node-gyp doesn't have to download them
+    if input.target.os != Os::Windows && app_path.install_dir.join("include/node").exists() {
+        if let Some(node_dir) = app_path.install_dir.to_str() {
+            env_vars.insert(String::from("npm_config_nodedir"), node_dir.to_string());
+        }
+    }
+
+    if input.target.os == Os::Windows {
+        if let Some(msvs_version) = get_msvs_version() {
+            info!("node-gyp: Using Visual Studio {msvs_version}");
+            env_vars.insert(String::from("npm_config_msvs_version"), msvs_version);
+        } else {
+            warn!("node-gyp: Visual Studio Build Tools not found");
+        }
+    }
+    env_vars
 }
 
-async fn unofficial_downloads(target: &Target) -> Vec<Download> {
-    return download_urls("unofficial-builds.nodejs.org", target).await;
+fn get_msvs_version() -> Option<String> {
+    let program_files = env::var("ProgramFiles(x86)").ok()?;
+    let vswhere = PathBuf::from(program_files).join("Microsoft Visual Studio/Installer/vswhere.exe");
+    let output = Command::new(vswhere)
+        .args(["-latest", "-products", "*", "-requires", "Microsoft.VisualStudio.Component.VC.Tools.x86.x64", "-property", "catalog_productLineVersion"])
+        .output().ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
 }
 
-async fn official_downloads(target: &Target) -> Vec<Download> {
-    return download_urls("nodejs.org", target).await;
+async fn unofficial_downloads(target: &Target) -> Result<Vec<Download>, GgError> {
+    return download_urls("https://unofficial-builds.nodejs.org/download/release", target).await;
 }
 
-async fn download_urls(host: &str, target: &Target) -> Vec<Download> {
+async fn official_downloads(target: &Target) -> Result<Vec<Download>, GgError> {
+    return download_urls("https://nodejs.org/download/release", target).await;
+}
+
+/// GG_NODE_MIRROR or [node] mirror in gg.toml, then the mirror variables nvm and volta users might have set already
+fn get_mirror(gg_mirror: Option<String>) -> Option<String> {
+    gg_mirror
+        .or(env::var("NODE_MIRROR").ok())
+        .or(env::var("NVM_NODEJS_ORG_MIRROR").ok())
+        .filter(|mirror| !mirror.trim().is_empty())
+}
+
+/// base is the folder with index.json, e.g. https://nodejs.org/download/release
+async fn download_urls(base: &str, target: &Target) -> Result<Vec<Download>, GgError> {
     let file = match (target.os, target.arch, target.variant) {
         (Os::Windows, Arch::Arm64, _) => "win-arm64-zip",
         (Os::Windows, _, _) => "win-x64-zip",
@@ -119,50 +296,130 @@ async fn download_urls(host: &str, target: &Target) -> Vec<Download> {
         (Os::Linux, Arch::X86_64, Some(Variant::Musl)) => "linux-x64-musl",
         (Os::Linux, Arch::Armv7, _) => "linux-armv7l",
         (Os::Linux, Arch::Arm64, _) => "linux-arm64",
+        (Os::Linux, Arch::Riscv64, _) => "linux-riscv64",
+        (Os::Linux, Arch::Ppc64le, _) => "linux-ppc64le",
+        (Os::Linux, Arch::S390x, _) => "linux-s390x",
         (Os::Mac, Arch::Armv7, _) => "osx-armv7l-tar",
         (Os::Mac, Arch::X86_64, _) => "osx-x64-tar",
         (Os::Mac, Arch::Arm64, _) => "osx-arm64-tar",
-        _ => "linux-x64",
+        (Os::Linux, Arch::X86_64, _) => "linux-x64",
+        _ => return Err(GgError::no_build("node", target)),
     };
-    let json = reqwest::get(format!("https://{host}/download/release/index.json")).await.unwrap().text().await.unwrap();
-    let root: Root = serde_json::from_str(json.as_str()).expect("JSON was not well-formatted");
+    let root: Root = diagnostics::get_json(&format!("{base}/index.json")).await?;
+    Ok(get_downloads(base, file, &root))
+}
 
+/// LTS releases get both lts and the codename as tags, e.g. node@20+iron
+fn get_downloads(base: &str, file: &str, root: &Root) -> Vec<Download> {
     root.iter().filter(|r|
         r.files.contains(&file.to_string())
     ).map(|r| {
-        let lts = match r.lts {
-            LTS::String(_) => true,
-            _ => false
-        };
         let file_fix = if file.ends_with("-zip") {
             file.replace("-zip", ".zip")
         } else {
             file.to_string() + ".tar.gz"
         }.replace("osx", "darwin").replace("-tar", "");
 
-        let tags: HashSet<String> = if lts {
-            ["lts".to_string()].iter().cloned().collect()
-        } else {
-            HashSet::new()
-        };
+        let mut tags: HashSet<String> = HashSet::new();
+        if let LTS::String(codename) = &r.lts {
+            tags.insert("lts".to_string());
+            tags.insert(codename.to_lowercase());
+        }
+        if r.security {
+            tags.insert("security".to_string());
+        }
         let version_string = r.version.as_str();
         let version = GgVersion::new(version_string);
         return Download {
-            download_url: format!("https://{host}/download/release/{version_string}/node-{version_string}-{file_fix}"),
+            download_url: format!("{base}/{version_string}/node-{version_string}-{file_fix}"),
             version,
             tags,
             // Arch and Os are mapped by target Arch/Os
             arch: Some(Arch::Any),
             os: Some(Os::Any),
             variant: Some(Variant::Any),
+            sha256: None,
+            checksum: None,
+            date: Some(r.date.clone()),
         };
     }).collect()
 }
 
-async fn get_node_urls(target: &Target) -> Vec<Download> {
+async fn get_node_urls(target: &Target) -> Result<Vec<Download>, GgError> {
     match (target.os, target.arch, target.variant) {
         (Os::Linux, _, Some(Variant::Musl)) => unofficial_downloads(target).await,
         (Os::Windows, Arch::Arm64, _) => unofficial_downloads(target).await,
+        // Only unofficial builds for riscv64
+        (Os::Linux, Arch::Riscv64, _) => unofficial_downloads(target).await,
         _ => official_downloads(target).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_downloads() {
+        let root: Root = serde_json::from_str(r#"[
+            {"version": "v20.11.1", "date": "2024-02-13", "files": ["linux-x64", "win-x64-zip"], "npm": "10.2.4", "v8": "11.3", "lts": "Iron", "security": true},
+            {"version": "v21.6.2", "date": "2024-02-13", "files": ["win-x64-zip"], "v8": "11.8", "lts": false, "security": true}
+        ]"#).unwrap();
+        let downloads = get_downloads("https://nodejs.org/download/release", "linux-x64", &root);
+        assert_eq!(1, downloads.len());
+        assert_eq!("https://nodejs.org/download/release/v20.11.1/node-v20.11.1-linux-x64.tar.gz", downloads[0].download_url);
+        assert_eq!(Some("2024-02-13".to_string()), downloads[0].date);
+        assert!(downloads[0].tags.contains("lts") && downloads[0].tags.contains("iron") && downloads[0].tags.contains("security"));
+    }
+
+    #[test]
+    fn test_parse_package_manager() {
+        let package_manager = parse_package_manager("pnpm@8.15.4+sha256.d3d8497c0815b4c4bbdc3e5e3d9e8e1bec3be5be").unwrap();
+        assert_eq!("pnpm", package_manager.name);
+        assert_eq!("=8.15.4", package_manager.version_req.to_string());
+        assert_eq!(Some(Checksum::sha256("d3d8497c0815b4c4bbdc3e5e3d9e8e1bec3be5be")), package_manager.checksum);
+        let package_manager = parse_package_manager("yarn@4.1.0").unwrap();
+        assert_eq!("=4.1.0", package_manager.version_req.to_string());
+        assert!(package_manager.checksum.is_none());
+        assert!(parse_package_manager("pnpm").is_none());
+    }
+
+    #[test]
+    fn test_get_mirror() {
+        env::remove_var("NODE_MIRROR");
+        env::set_var("NVM_NODEJS_ORG_MIRROR", "https://npmmirror.com/mirrors/node/");
+        assert_eq!(Some("https://npmmirror.com/mirrors/node/".to_string()), get_mirror(None));
+        env::set_var("NODE_MIRROR", "https://mirror.example.com/node");
+        assert_eq!(Some("https://mirror.example.com/node".to_string()), get_mirror(None));
+        assert_eq!(Some("https://gg.example.com/node".to_string()), get_mirror(Some("https://gg.example.com/node".to_string())));
+        env::remove_var("NODE_MIRROR");
+        env::remove_var("NVM_NODEJS_ORG_MIRROR");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_node_gyp_python() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = env::temp_dir().join(format!("gg-node-gyp-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let python = dir.join("python3");
+        fs::write(&python, "").unwrap();
+        fs::set_permissions(&python, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut input = AppInput::dummy();
+        input.no_clap.node_gyp = true;
+        let node = Node { executor_cmd: ExecutorCmd::dummy() };
+        assert_eq!(vec!("python"), node.get_deps(&input));
+        let deps = ExecContext { env: HashMap::new(), path_prepends: vec!(dir.clone()) };
+        let env_vars = get_node_gyp_env(&input, &AppPath { install_dir: dir.join("node") }, &deps);
+        assert_eq!(Some(&python.to_string_lossy().to_string()), env_vars.get("npm_config_python"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_npm_range() {
+        assert_eq!(">=18, <21", parse_npm_range(">=18 <21").unwrap().to_string());
+        assert_eq!(">=18.0.0", parse_npm_range(">=18.0.0").unwrap().to_string());
+        assert_eq!("^18", parse_npm_range("18").unwrap().to_string());
+        assert_eq!(None, parse_npm_range("^16 || ^18"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use package_json::PackageJsonManager;
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::bloody_indiana_jones::{Checksum, HashAlgorithm};
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, AppPath, Download, Executor, ExecutorCmd, GgVersion, intersect_version_reqs, VersionSource};
+use crate::executors::node;
+use crate::mirror::{mirror_url, with_mirror};
+use crate::target::{Arch, Os, Variant};
+
+pub const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+const PRERELEASE: &str = "pre";
+/// Yarn 2 and newer (berry) is published as a separate package, 1.x stays in yarn
+const YARN_BERRY: &str = "@yarnpkg%2fcli-dist";
+
+/// npm, pnpm or yarn, from the npm registry and run with node from the node dependency. npm is only used when
+/// packageManager in package.json pins it, otherwise the one bundled with node is
+pub struct PackageManager {
+    pub executor_cmd: ExecutorCmd,
+    name: &'static str,
+}
+
+/// Package metadata, only what is needed of it
+#[derive(Deserialize)]
+struct Packument {
+    versions: HashMap<String, PackageVersion>,
+    #[serde(default)]
+    time: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct PackageVersion {
+    dist: Dist,
+}
+
+#[derive(Deserialize)]
+struct Dist {
+    tarball: String,
+    integrity: Option<String>,
+    shasum: Option<String>,
+}
+
+/// The SHA-512 of dist.integrity, `sha512-<base64>`. Old packages only have the SHA-1 of dist.shasum
+fn get_checksum(dist: &Dist) -> Option<Checksum> {
+    let sha512 = dist.integrity.as_deref()
+        .and_then(|integrity| integrity.split_whitespace().find_map(|i| i.strip_prefix("sha512-")))
+        .and_then(|hash| STANDARD.decode(hash).ok())
+        .map(|hash| Checksum { algorithm: HashAlgorithm::Sha512, hash: hash.iter().map(|b| format!("{b:02x}")).collect() });
+    sha512.or_else(|| dist.shasum.clone().map(|hash| Checksum { algorithm: HashAlgorithm::Sha1, hash }))
+}
+
+/// Prereleases are tagged pre and with their kind, 9.0.0-rc.1 gets pre and rc
+fn get_tags(version: &str) -> HashSet<String> {
+    let Some((_, pre)) = version.split_once('-') else { return HashSet::new() };
+    let kind: String = pre.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    vec!(PRERELEASE.to_string(), kind).into_iter().filter(|tag| !tag.is_empty()).collect()
+}
+
+/// Prereleases are left out, unless the version asked for is one, e.g. pnpm@9.0.0-rc.1
+fn get_default_exclude_tags(version_req: Option<&VersionReq>) -> HashSet<String> {
+    if version_req.map(|v| v.comparators.iter().any(|c| !c.pre.is_empty())).unwrap_or(false) {
+        HashSet::new()
+    } else {
+        [PRERELEASE.to_string()].into()
+    }
+}
+
+/// Tarballs are JavaScript, so any target
+fn get_downloads(packument: Packument) -> Vec<Download> {
+    let time = packument.time;
+    packument.versions.into_iter().map(|(version, package)| Download {
+        tags: get_tags(&version),
+        date: time.get(&version).map(|time| time.chars().take(10).collect()),
+        version: GgVersion::new(&version),
+        checksum: get_checksum(&package.dist),
+        download_url: package.dist.tarball,
+        os: Some(Os::Any),
+        arch: Some(Arch::Any),
+        variant: Some(Variant::Any),
+        sha256: None,
+    }).collect()
+}
+
+/// All versions of an npm package, from the registry or its mirror ([<name>] mirror in gg.toml)
+async fn get_package_downloads(input: &AppInput, name: &str, package: &str) -> Result<Vec<Download>, GgError> {
+    let url = format!("{}/{package}", mirror_url(input, name, NPM_REGISTRY));
+    let packument: Packument = diagnostics::get_json(&url).await?;
+    Ok(with_mirror(input, name, NPM_REGISTRY, get_downloads(packument)))
+}
+
+/// The hash after the version in packageManager is what Corepack checks, so it is checked instead of the registry's
+fn apply_pinned_checksum(downloads: &mut [Download], version_req: &VersionReq, checksum: &Checksum) {
+    for download in downloads.iter_mut().filter(|d| d.version.as_ref().map(|v| version_req.matches(&v.to_version())).unwrap_or(false)) {
+        match checksum.algorithm {
+            HashAlgorithm::Sha256 => download.sha256 = Some(checksum.hash.clone()),
+            _ => download.checksum = Some(checksum.clone()),
+        }
+    }
+}
+
+impl PackageManager {
+    pub fn new(name: &'static str, executor_cmd: ExecutorCmd) -> Self {
+        Self { executor_cmd, name }
+    }
+
+    fn get_packages(&self) -> Vec<&'static str> {
+        match self.name {
+            "yarn" => vec!("yarn", YARN_BERRY),
+            name => vec!(name),
+        }
+    }
+
+    /// In bin of the tarball
+    fn get_script(&self) -> &'static str {
+        match (self.name, self.executor_cmd.cmd.as_str()) {
+            ("npm", "npx") => "npx-cli.js",
+            ("npm", _) => "npm-cli.js",
+            ("pnpm", _) => "pnpm.cjs",
+            _ => "yarn.js",
+        }
+    }
+}
+
+impl Executor for PackageManager {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        node::get_package_manager_source(self.name).into_iter().collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        PackageJsonManager::new().locate_closest().ok().into_iter().collect()
+    }
+
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let mut downloads = vec!();
+            for package in self.get_packages() {
+                downloads.extend(get_package_downloads(input, self.name, package).await?);
+            }
+            let pin = node::get_package_manager().filter(|pin| pin.name == self.name);
+            if let Some((version_req, checksum)) = pin.and_then(|pin| Some((pin.version_req, pin.checksum?))) {
+                apply_pinned_checksum(&mut downloads, &version_req, &checksum);
+            }
+            Ok(downloads)
+        })
+    }
+
+    /// The tarball is JavaScript, run with node from the node dependency
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "node.exe",
+            _ => "node"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        self.name
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
+        vec!("node")
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        let version_req = self.executor_cmd.version.as_ref().map(|v| v.to_version_req()).or(self.get_version_req());
+        get_default_exclude_tags(version_req.as_ref())
+    }
+
+    fn customize_args(&self, input: &AppInput, app_path: &AppPath) -> Vec<String> {
+        let script = app_path.install_dir.join("bin").join(self.get_script());
+        vec!(script.to_string_lossy().to_string()).into_iter().chain(input.no_clap.app_args.iter().cloned()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_downloads() {
+        let packument: Packument = serde_json::from_str(r#"{
+            "versions": {
+                "8.15.4": {"dist": {"tarball": "https://registry.npmjs.org/pnpm/-/pnpm-8.15.4.tgz", "integrity": "sha512-3q2+7w==", "shasum": "abc"}},
+                "9.0.0-rc.1": {"dist": {"tarball": "https://registry.npmjs.org/pnpm/-/pnpm-9.0.0-rc.1.tgz", "shasum": "def"}}
+            },
+            "time": {"8.15.4": "2024-02-26T13:07:02.016Z"}
+        }"#).unwrap();
+        let mut downloads = get_downloads(packument);
+        downloads.sort_by_key(|d| d.download_url.clone());
+        assert_eq!("https://registry.npmjs.org/pnpm/-/pnpm-8.15.4.tgz", downloads[0].download_url);
+        assert_eq!(Some("2024-02-26".to_string()), downloads[0].date);
+        assert!(downloads[0].tags.is_empty());
+        assert_eq!(Some(Checksum { algorithm: HashAlgorithm::Sha512, hash: "deadbeef".to_string() }), downloads[0].checksum);
+        assert_eq!(Some(Checksum { algorithm: HashAlgorithm::Sha1, hash: "def".to_string() }), downloads[1].checksum);
+        assert_eq!("9.0.0-rc.1", downloads[1].version.as_ref().unwrap().to_string());
+        assert_eq!(HashSet::from(["pre".to_string(), "rc".to_string()]), downloads[1].tags);
+        assert!(get_default_exclude_tags(None).contains("pre"));
+        assert!(get_default_exclude_tags(VersionReq::parse("=9.0.0-rc.1").ok().as_ref()).is_empty());
+
+        apply_pinned_checksum(&mut downloads, &VersionReq::parse("=8.15.4").unwrap(), &Checksum::sha256("0123"));
+        assert_eq!(Some("0123".to_string()), downloads[0].sha256);
+        assert!(downloads[1].sha256.is_none());
+    }
+
+    #[test]
+    fn test_get_script() {
+        let cmd = |cmd: &str| ExecutorCmd { cmd: cmd.to_string(), ..ExecutorCmd::dummy() };
+        assert_eq!("npx-cli.js", PackageManager::new("npm", cmd("npx")).get_script());
+        assert_eq!("npm-cli.js", PackageManager::new("npm", cmd("npm")).get_script());
+        assert_eq!("pnpm.cjs", PackageManager::new("pnpm", cmd("pnpm")).get_script());
+        assert_eq!(vec!("yarn", YARN_BERRY), PackageManager::new("yarn", cmd("yarn")).get_packages());
+    }
+}
@@ -3,9 +3,11 @@ use std::fs::{read_dir, rename};
 use std::future::Future;
 use std::pin::Pin;
 
-use crate::bloody_maven::get_download_urls_from_maven;
+use crate::error::GgError;
+use crate::bloody_maven::{get_download_urls_from_maven, MAVEN_CENTRAL};
 use crate::Executor;
 use crate::executor::{AppInput, AppPath, Download, ExecutorCmd};
+use crate::mirror::mirror_url;
 
 pub struct OpenAPIGenerator {
     pub executor_cmd: ExecutorCmd,
@@ -16,8 +18,8 @@ impl Executor for OpenAPIGenerator {
         return &self.executor_cmd;
     }
 
-    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
-        get_download_urls_from_maven("openapitools", "openapi-generator-cli")
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        get_download_urls_from_maven(mirror_url(input, "openapi", MAVEN_CENTRAL), "openapitools", "openapi-generator-cli")
     }
 
     fn get_bins(&self, _input: &AppInput) -> Vec<String> {
@@ -28,7 +30,7 @@ impl Executor for OpenAPIGenerator {
         "openapi"
     }
 
-    fn get_deps(&self) -> Vec<&str> {
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
         vec!("java")
     }
 
@@ -48,7 +50,7 @@ impl Executor for OpenAPIGenerator {
         }
     }
 
-    fn post_prep(&self, cache_path: &str) {
+    fn post_prep(&self, cache_path: &str, _install_dir: &str) -> Result<(), String> {
         let entries = read_dir(&cache_path);
         if let Ok(entries) = entries {
             entries.for_each(|entry| {
@@ -61,5 +63,6 @@ impl Executor for OpenAPIGenerator {
                 }
             });
         }
+        Ok(())
     }
 }
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use log::{info, warn};
+use regex::Regex;
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, intersect_version_reqs, VersionSource};
+use crate::mirror::mirror_url;
+use crate::target::{Arch, Os, Variant};
+
+/// Sources of windows.php.net/downloads, the current releases and archives/ for the older ones
+pub const PHP_WINDOWS: &str = "https://windows.php.net/downloads/releases";
+/// Static CLI builds of static-php-cli with the common extensions, for Linux and macOS
+pub const STATIC_PHP: &str = "https://dl.static-php.dev/static-php-cli/common";
+pub const COMPOSER_JSON: &str = "composer.json";
+
+pub struct Php {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// releases.json, the newest patch of each minor version, e.g. "8.3"
+#[derive(Deserialize)]
+struct WindowsRelease {
+    #[serde(flatten)]
+    builds: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ComposerJson {
+    config: Option<ComposerConfig>,
+}
+
+#[derive(Deserialize)]
+struct ComposerConfig {
+    platform: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// php-8.3.12-cli-linux-x86_64.tar.gz in the listing, which is just the php binary
+fn get_static_downloads(base: &str, listing: &str) -> Vec<Download> {
+    let re = Regex::new(r"php-(\d+\.\d+\.\d+)-cli-(linux|macos)-(x86_64|aarch64)\.tar\.gz").unwrap();
+    let mut names: Vec<&str> = re.find_iter(listing).map(|m| m.as_str()).collect();
+    names.dedup();
+    names.into_iter().filter_map(|name| {
+        let c = re.captures(name)?;
+        let mut download = Download::new(format!("{base}/{name}"), &c[1], Some(Variant::Any));
+        download.os = Some(if &c[2] == "linux" { Os::Linux } else { Os::Mac });
+        download.arch = Some(if &c[3] == "x86_64" { Arch::X86_64 } else { Arch::Arm64 });
+        Some(download)
+    }).collect()
+}
+
+/// The non-thread-safe x64 builds, php-8.3.12-nts-Win32-vs16-x64.zip, as the CLI and php-cgi use. releases.json has the
+/// SHA-256, the archives are listed without
+fn get_windows_downloads(base: &str, releases: &HashMap<String, WindowsRelease>, archives: &str) -> Vec<Download> {
+    let zip = Regex::new(r"^php-(\d+\.\d+\.\d+)-nts-Win32-(?:vs|vc)\d+-x64\.zip$").unwrap();
+    let to_download = |url: String, name: &str, sha256: Option<String>| {
+        let c = zip.captures(name)?;
+        let mut download = Download::new(url, &c[1], Some(Variant::Any));
+        download.os = Some(Os::Windows);
+        download.arch = Some(Arch::X86_64);
+        download.sha256 = sha256;
+        Some(download)
+    };
+    let current = releases.values()
+        .flat_map(|release| release.builds.iter())
+        .filter(|(key, _)| key.starts_with("nts-") && key.ends_with("-x64"))
+        .filter_map(|(_, build)| {
+            let path = build.get("zip")?.get("path")?.as_str()?;
+            let sha256 = build.get("zip")?.get("sha256").and_then(|s| s.as_str()).map(|s| s.to_string());
+            to_download(format!("{base}/{path}"), path, sha256)
+        });
+    let href = Regex::new(r#"php-\d+\.\d+\.\d+-nts-Win32-(?:vs|vc)\d+-x64\.zip"#).unwrap();
+    let mut names: Vec<&str> = href.find_iter(archives).map(|m| m.as_str()).collect();
+    names.dedup();
+    let archived = names.into_iter().filter_map(|name| to_download(format!("{base}/archives/{name}"), name, None));
+    current.chain(archived).collect()
+}
+
+/// `config.platform.php` is the version Composer resolves dependencies for, so the newest patch of it
+fn parse_composer_json(text: &str) -> Option<VersionReq> {
+    let composer_json: ComposerJson = serde_json::from_str(text).ok()?;
+    let php = composer_json.config?.platform?.get("php")?.as_str()?.to_string();
+    VersionReq::parse(&format!("~{php}")).map_err(|e| warn!("Unable to use config.platform.php {php}: {e}")).ok()
+}
+
+fn get_composer_json_version() -> Option<VersionSource> {
+    let version_req = parse_composer_json(&fs::read_to_string(COMPOSER_JSON).ok()?)?;
+    info!("Got version {version_req} from {COMPOSER_JSON} config.platform.php");
+    Some(VersionSource::new(COMPOSER_JSON, version_req))
+}
+
+impl Executor for Php {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_composer_json_version().into_iter().collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(COMPOSER_JSON))
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            if input.target.os == Os::Windows {
+                let base = mirror_url(input, "php", PHP_WINDOWS);
+                let releases: HashMap<String, WindowsRelease> = diagnostics::get_json(&format!("{base}/releases.json")).await?;
+                let archives = diagnostics::get_text(&format!("{base}/archives/")).await?;
+                return Ok(get_windows_downloads(&base, &releases, &archives));
+            }
+            let base = mirror_url(input, "php", STATIC_PHP);
+            let listing = diagnostics::get_text(&format!("{base}/")).await?;
+            Ok(get_static_downloads(&base, &listing))
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        vec!(match &input.target.os {
+            Os::Windows => "php.exe",
+            _ => "php"
+        }.to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "php"
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("php:{tag}-cli"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_static_downloads() {
+        let listing = r#"<a href="php-8.3.12-cli-linux-aarch64.tar.gz">php-8.3.12-cli-linux-aarch64.tar.gz</a>
+            <a href="php-8.3.12-fpm-linux-aarch64.tar.gz">x</a><a href="php-8.2.24-cli-macos-x86_64.tar.gz">x</a>"#;
+        let downloads = get_static_downloads(STATIC_PHP, listing);
+        assert_eq!(2, downloads.len());
+        assert_eq!("https://dl.static-php.dev/static-php-cli/common/php-8.3.12-cli-linux-aarch64.tar.gz", downloads[0].download_url);
+        assert_eq!((Some(Os::Linux), Some(Arch::Arm64)), (downloads[0].os, downloads[0].arch));
+        assert_eq!((Some(Os::Mac), Some(Arch::X86_64)), (downloads[1].os, downloads[1].arch));
+    }
+
+    #[test]
+    fn test_get_windows_downloads() {
+        let releases: HashMap<String, WindowsRelease> = serde_json::from_str(r#"{"8.3": {"version": "8.3.12",
+            "nts-vs16-x64": {"mtime": "x", "zip": {"path": "php-8.3.12-nts-Win32-vs16-x64.zip", "size": "31MB", "sha256": "abc"}},
+            "ts-vs16-x64": {"zip": {"path": "php-8.3.12-Win32-vs16-x64.zip", "sha256": "def"}},
+            "nts-vs16-x86": {"zip": {"path": "php-8.3.12-nts-Win32-vs16-x86.zip", "sha256": "ghi"}}}}"#).unwrap();
+        let archives = r#"<A HREF="/downloads/releases/archives/php-7.4.33-nts-Win32-vc15-x64.zip">php-7.4.33-nts-Win32-vc15-x64.zip</A>"#;
+        let downloads = get_windows_downloads(PHP_WINDOWS, &releases, archives);
+        assert_eq!(2, downloads.len());
+        assert_eq!(Some("abc".to_string()), downloads[0].sha256);
+        assert_eq!("https://windows.php.net/downloads/releases/archives/php-7.4.33-nts-Win32-vc15-x64.zip", downloads[1].download_url);
+        assert_eq!(None, downloads[1].sha256);
+    }
+
+    #[test]
+    fn test_parse_composer_json() {
+        assert_eq!("~8.2.0", parse_composer_json(r#"{"require": {"php": "^8.1"}, "config": {"platform": {"php": "8.2.0"}}}"#).unwrap().to_string());
+        assert_eq!("~8.1", parse_composer_json(r#"{"config": {"platform": {"php": "8.1", "ext-redis": "5.3"}}}"#).unwrap().to_string());
+        assert!(parse_composer_json(r#"{"require": {"php": "^8.1"}}"#).is_none());
+    }
+}
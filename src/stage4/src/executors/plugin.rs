@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+use log::{info, warn};
+use serde::Deserialize;
+
+use crate::config::{self, GgConfig};
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256, GgVersion};
+use crate::target::{Arch, Os, Variant};
+
+const PRERELEASE: &str = "pre";
+const DEFAULT_EXT: &str = "tar.gz";
+const OSES: [(Os, &str); 3] = [(Os::Linux, "linux"), (Os::Mac, "mac"), (Os::Windows, "windows")];
+const ARCHES: [(Arch, &str); 6] = [
+    (Arch::X86_64, "x86_64"), (Arch::Arm64, "arm64"), (Arch::Armv7, "armv7"),
+    (Arch::Riscv64, "riscv64"), (Arch::Ppc64le, "ppc64le"), (Arch::S390x, "s390x"),
+];
+
+static TOOLS: OnceLock<BTreeMap<String, ToolManifest>> = OnceLock::new();
+
+/// A tool gg does not know about, described by a manifest, e.g. ~/.config/gg/tools/just.toml or [custom-tools.just]
+/// in gg.toml
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolManifest {
+    /// The command, defaults to the file name without extension, or the key in [custom-tools]
+    #[serde(default)]
+    pub name: String,
+    /// Download URL with {version}, {tag}, {os}, {arch} and {ext}
+    pub url: String,
+    /// Path of the binary in the archive, .exe is added on Windows
+    pub bin: String,
+    /// JSON list of versions, as strings or objects with tag_name (GitHub releases) or version
+    pub versions: String,
+    /// Checksum file with {url}, {dir} and {file}. A single hash, or SHA256SUMS lines
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// {os} by linux, mac and windows. Only these are offered
+    #[serde(default = "default_os")]
+    pub os: BTreeMap<String, String>,
+    /// {arch} by x86_64, arm64 and armv7. Only these are offered
+    #[serde(default = "default_arch")]
+    pub arch: BTreeMap<String, String>,
+    /// {ext} by os, tar.gz for the rest
+    #[serde(default = "default_ext")]
+    pub ext: BTreeMap<String, String>,
+    #[serde(default)]
+    pub deps: Vec<String>,
+}
+
+fn to_map(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn default_os() -> BTreeMap<String, String> {
+    to_map(&[("linux", "linux"), ("mac", "darwin"), ("windows", "windows")])
+}
+
+fn default_arch() -> BTreeMap<String, String> {
+    to_map(&[("x86_64", "amd64"), ("arm64", "arm64")])
+}
+
+fn default_ext() -> BTreeMap<String, String> {
+    to_map(&[("windows", "zip")])
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VersionEntry {
+    Name(String),
+    Object {
+        tag_name: Option<String>,
+        version: Option<String>,
+    },
+}
+
+impl VersionEntry {
+    fn get_tag(self) -> Option<String> {
+        match self {
+            VersionEntry::Name(name) => Some(name),
+            VersionEntry::Object { tag_name, version } => tag_name.or(version),
+        }
+    }
+}
+
+/// Manifests from gg/tools in the config dir and [custom-tools] in gg.toml, read once at startup.
+/// The project wins over the config dir
+pub fn init(config: &GgConfig) {
+    let mut tools = get_tools_dir().map(|dir| load_dir(&dir)).unwrap_or_default();
+    for (name, manifest) in &config.custom_tools {
+        info!("Found tool {name} in [custom-tools]");
+        tools.insert(name.clone(), ToolManifest { name: name.clone(), ..manifest.clone() });
+    }
+    let _ = TOOLS.set(tools);
+}
+
+/// gg/tools in XDG_CONFIG_HOME, ~/.config or APPDATA
+pub fn get_tools_dir() -> Option<PathBuf> {
+    config::get_config_home().map(|dir| dir.join("tools"))
+}
+
+/// Commands of all manifests
+pub fn get_names() -> Vec<String> {
+    TOOLS.get().map(|tools| tools.keys().cloned().collect()).unwrap_or_default()
+}
+
+pub fn get(name: &str) -> Option<ToolManifest> {
+    TOOLS.get()?.get(name).cloned()
+}
+
+fn load_dir(dir: &Path) -> BTreeMap<String, ToolManifest> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir).into_iter().flatten().flatten()
+        .map(|entry| entry.path())
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("toml") | Some("json")))
+        .collect();
+    paths.sort();
+    paths.iter().filter_map(|path| {
+        let manifest = fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|text| parse(path, &text))
+            .map_err(|e| warn!("Unable to load {}: {e}", path.display()))
+            .ok()?;
+        info!("Found tool {} in {}", manifest.name, path.display());
+        Some((manifest.name.clone(), manifest))
+    }).collect()
+}
+
+fn parse(path: &Path, text: &str) -> Result<ToolManifest, String> {
+    let mut manifest: ToolManifest = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(text).map_err(|e| e.to_string())?,
+        _ => toml::from_str(text).map_err(|e| e.to_string())?,
+    };
+    if manifest.name.is_empty() {
+        manifest.name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+    }
+    Ok(manifest)
+}
+
+impl ToolManifest {
+    /// Targets with their {os} and {arch} values. Any when the URL is the same for all
+    fn get_oses(&self) -> Vec<(Os, &str, &str)> {
+        if !self.url.contains("{os}") {
+            return vec!((Os::Any, "", DEFAULT_EXT));
+        }
+        OSES.iter()
+            .filter_map(|(os, key)| Some((*os, self.os.get(*key)?.as_str(), self.ext.get(*key).map(|e| e.as_str()).unwrap_or(DEFAULT_EXT))))
+            .collect()
+    }
+
+    fn get_arches(&self) -> Vec<(Arch, &str)> {
+        if !self.url.contains("{arch}") {
+            return vec!((Arch::Any, ""));
+        }
+        ARCHES.iter().filter_map(|(arch, key)| Some((*arch, self.arch.get(*key)?.as_str()))).collect()
+    }
+
+    fn get_downloads(&self, tags: &[String]) -> Vec<Download> {
+        let mut downloads = vec!();
+        for tag in tags {
+            let Some(version) = GgVersion::new(tag) else { continue };
+            for (os, os_name, ext) in self.get_oses() {
+                for (arch, arch_name) in self.get_arches() {
+                    let url = self.url
+                        .replace("{version}", tag.trim_start_matches('v'))
+                        .replace("{tag}", tag)
+                        .replace("{os}", os_name)
+                        .replace("{arch}", arch_name)
+                        .replace("{ext}", ext);
+                    let mut download = Download::new(url, &version.to_string(), Some(Variant::Any));
+                    download.os = Some(os);
+                    download.arch = Some(arch);
+                    if version.to_string().contains('-') {
+                        download.tags.insert(PRERELEASE.to_string());
+                    }
+                    downloads.push(download);
+                }
+            }
+        }
+        downloads
+    }
+
+    fn get_checksum_url(&self, download_url: &str) -> Option<String> {
+        let (dir, file) = download_url.rsplit_once('/')?;
+        Some(self.checksum.as_ref()?.replace("{url}", download_url).replace("{dir}", dir).replace("{file}", file))
+    }
+}
+
+fn parse_version_list(text: &str) -> Result<Vec<String>, serde_json::Error> {
+    let entries: Vec<VersionEntry> = serde_json::from_str(text)?;
+    Ok(entries.into_iter().filter_map(|entry| entry.get_tag()).collect())
+}
+
+/// Runs the tool of a manifest like any other executor
+pub struct Plugin {
+    pub executor_cmd: ExecutorCmd,
+    pub manifest: ToolManifest,
+}
+
+impl Executor for Plugin {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let url = &self.manifest.versions;
+            let text = diagnostics::get_text(url).await?;
+            let tags = parse_version_list(&text).map_err(|e| GgError::parse(url, "JSON", e))?;
+            Ok(self.manifest.get_downloads(&tags))
+        })
+    }
+
+    /// A hash for the file from SHA256SUMS lines, or a file with just the hash, at `checksum` of the manifest
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            match self.manifest.get_checksum_url(&download.download_url) {
+                Some(url) => fetch_sha256(&url, download).await.map(Some),
+                None => Ok(download.sha256.clone()),
+            }
+        })
+    }
+
+    fn get_bins(&self, input: &AppInput) -> Vec<String> {
+        let bin = Path::new(&self.manifest.bin).file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        vec!(match input.target.os {
+            Os::Windows if !bin.contains('.') => format!("{bin}.exe"),
+            _ => bin.to_string(),
+        })
+    }
+
+    fn get_name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn get_deps(&self, _input: &AppInput) -> Vec<&str> {
+        self.manifest.deps.iter().map(|dep| dep.as_str()).collect()
+    }
+
+    /// Prereleases are left out, unless the version asked for is one
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        match &self.executor_cmd.version {
+            Some(version) if version.to_string().contains('-') => HashSet::new(),
+            _ => [PRERELEASE.to_string()].into(),
+        }
+    }
+
+    /// Where the binary is in the archive
+    fn get_bin_dirs(&self) -> Vec<String> {
+        let dir = Path::new(&self.manifest.bin).parent().and_then(|p| p.to_str()).unwrap_or_default();
+        vec!(if dir.is_empty() { ".".to_string() } else { dir.to_string() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = parse(Path::new("tools/just.toml"), r#"
+            url = "https://github.com/casey/just/releases/download/{tag}/just-{version}-{arch}-{os}.{ext}"
+            bin = "just"
+            versions = "https://api.github.com/repos/casey/just/releases"
+            checksum = "{dir}/SHA256SUMS"
+            [os]
+            linux = "unknown-linux-musl"
+            windows = "pc-windows-msvc"
+            [arch]
+            x86_64 = "x86_64"
+        "#).unwrap();
+        assert_eq!("just", manifest.name);
+        assert_eq!("zip", manifest.ext["windows"]);
+        let json = parse(Path::new("tools/tool.json"), r#"{"name": "other", "url": "https://example.com/{version}/tool", "bin": "bin/tool", "versions": "https://example.com/versions.json"}"#).unwrap();
+        assert_eq!("other", json.name);
+        assert_eq!(default_os(), json.os);
+        assert!(parse(Path::new("tools/broken.toml"), "url = \"x\"").is_err());
+
+        let mut downloads = manifest.get_downloads(&["1.25.2".to_string(), "v1.26.0-rc.1".to_string(), "latest".to_string()]);
+        downloads.sort_by_key(|d| d.download_url.clone());
+        assert_eq!(4, downloads.len());
+        assert_eq!("https://github.com/casey/just/releases/download/1.25.2/just-1.25.2-x86_64-pc-windows-msvc.zip", downloads[0].download_url);
+        assert_eq!("https://github.com/casey/just/releases/download/v1.26.0-rc.1/just-1.26.0-rc.1-x86_64-unknown-linux-musl.tar.gz", downloads[3].download_url);
+        assert_eq!(Some(Os::Linux), downloads[3].os);
+        assert!(downloads[3].tags.contains("pre"));
+        assert_eq!(Some("https://github.com/casey/just/releases/download/1.25.2/SHA256SUMS".to_string()), manifest.get_checksum_url(&downloads[0].download_url));
+
+        let any = json.get_downloads(&["2.0".to_string()]);
+        assert_eq!(1, any.len());
+        assert_eq!(Some(Os::Any), any[0].os);
+        assert_eq!("https://example.com/2.0/tool", any[0].download_url);
+    }
+
+    #[test]
+    fn test_parse_version_list() {
+        assert_eq!(vec!("1.2.3", "1.2.2"), parse_version_list(r#"["1.2.3", "1.2.2"]"#).unwrap());
+        assert_eq!(vec!("v1.2.3", "1.0.0"), parse_version_list(r#"[{"tag_name": "v1.2.3", "name": "Release"}, {"version": "1.0.0"}]"#).unwrap());
+    }
+}
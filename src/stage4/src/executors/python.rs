@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Command;
+
+use log::{info, warn};
+use regex::Regex;
+use semver::VersionReq;
+
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256, GgVersion, intersect_version_reqs, NamedBin, parse_system_version, VersionSource};
+use crate::http;
+use crate::mirror::with_mirror;
+use crate::target::{Arch, Os, Variant};
+use crate::tool_versions;
+
+/// Releases have every maintained Python version, so the newest ones are enough
+const RELEASE_PAGES: u32 = 3;
+const PYTHON_RELEASES: &str = "https://github.com/astral-sh/python-build-standalone/releases/download";
+
+pub struct Python {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// Rust style target triples, only the ones with install_only archives gg can run
+fn get_target(triple: &str) -> Option<(Os, Arch, Option<Variant>)> {
+    Some(match triple.trim_end_matches("-shared") {
+        "x86_64-unknown-linux-gnu" => (Os::Linux, Arch::X86_64, None),
+        "x86_64-unknown-linux-musl" => (Os::Linux, Arch::X86_64, Some(Variant::Musl)),
+        "aarch64-unknown-linux-gnu" => (Os::Linux, Arch::Arm64, None),
+        "armv7-unknown-linux-gnueabihf" => (Os::Linux, Arch::Armv7, None),
+        "x86_64-apple-darwin" => (Os::Mac, Arch::X86_64, Some(Variant::Any)),
+        "aarch64-apple-darwin" => (Os::Mac, Arch::Arm64, Some(Variant::Any)),
+        "x86_64-pc-windows-msvc" => (Os::Windows, Arch::X86_64, Some(Variant::Any)),
+        "aarch64-pc-windows-msvc" => (Os::Windows, Arch::Arm64, Some(Variant::Any)),
+        _ => return None,
+    })
+}
+
+/// E.g. cpython-3.12.1+20240107-x86_64-unknown-linux-gnu-install_only.tar.gz. The full archives are for embedding and
+/// building, install_only is a plain installation. Pre-releases are skipped
+fn asset_to_download(name: &str, url: &str) -> Option<Download> {
+    let re = Regex::new(r"^cpython-(\d+\.\d+\.\d+)\+(\d{4})(\d{2})(\d{2})-(.+)-install_only(_stripped)?\.tar\.gz$").unwrap();
+    let c = re.captures(name)?;
+    let (os, arch, variant) = get_target(&c[5])?;
+    let mut tags = HashSet::new();
+    if c.get(6).is_some() {
+        tags.insert("stripped".to_string());
+    }
+    Some(Download {
+        download_url: url.to_string(),
+        version: GgVersion::new(&c[1]),
+        tags,
+        arch: Some(arch),
+        os: Some(os),
+        variant,
+        sha256: None,
+        checksum: None,
+        date: Some(format!("{}-{}-{}", &c[2], &c[3], &c[4])),
+    })
+}
+
+/// PEP 440 as in requires-python, e.g. `>=3.9,<4` or `~=3.10`. != is left out
+fn parse_requires_python(spec: &str) -> Option<VersionReq> {
+    let comparators: Vec<String> = spec.split(',')
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty() && !c.starts_with("!="))
+        .map(|c| {
+            if let Some(v) = c.strip_prefix("~=") {
+                let v = v.trim();
+                if v.split('.').count() >= 3 { format!("~{v}") } else { format!("^{v}") }
+            } else if let Some(v) = c.strip_prefix("==") {
+                let v = v.trim();
+                if v.ends_with(".*") { v.to_string() } else { format!("={v}") }
+            } else {
+                c.to_string()
+            }
+        })
+        .collect();
+    VersionReq::parse(&comparators.join(", ")).ok()
+}
+
+fn get_pyproject_version() -> Option<VersionReq> {
+    let pyproject: toml::Value = toml::from_str(&fs::read_to_string("pyproject.toml").ok()?).ok()?;
+    let requires_python = pyproject.get("project")?.get("requires-python")?.as_str()?;
+    info!("Got version {requires_python} from pyproject.toml requires-python");
+    parse_requires_python(requires_python)
+}
+
+/// pyenv style, the first version in the file. Other implementations like pypy-3.10 are not for gg
+fn get_python_version_file_version() -> Option<VersionReq> {
+    let text = fs::read_to_string(".python-version").ok()?;
+    let version = text.lines().map(|l| l.trim()).find(|l| !l.is_empty() && !l.starts_with('#'))?;
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        warn!("Ignoring {version} from .python-version");
+        return None;
+    }
+    info!("Got version {version} from .python-version");
+    tool_versions::parse_version("python", version)
+}
+
+impl Executor for Python {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        let mut sources = vec!();
+        if let Some(version_req) = get_python_version_file_version() {
+            sources.push(VersionSource::new(".python-version", version_req));
+        }
+        if let Some(version_req) = get_pyproject_version() {
+            sources.push(VersionSource::new("pyproject.toml requires-python", version_req));
+        }
+        sources
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(".python-version"), PathBuf::from("pyproject.toml"))
+    }
+
+    fn get_system_version(&self, bin_path: &Path) -> Option<GgVersion> {
+        let output = Command::new(bin_path).arg("--version").output().ok()?;
+        parse_system_version(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let releases = http::get_releases("astral-sh", "python-build-standalone", RELEASE_PAGES, 10).await?;
+            let downloads = releases.iter()
+                .flat_map(|release| release.assets.iter())
+                .filter_map(|asset| asset_to_download(&asset.name, asset.browser_download_url.as_str()))
+                .collect();
+            Ok(with_mirror(input, "python", PYTHON_RELEASES, downloads))
+        })
+    }
+
+    /// SHA256SUMS is part of each release
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            let dir = download.download_url.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+            fetch_sha256(&format!("{dir}/SHA256SUMS"), download).await.map(Some)
+        })
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(
+            NamedBin::new("python").alias("python3").unix(&["python3", "python"]).windows(&["python.exe"]),
+            NamedBin::new("pip").alias("pip3").unix(&["pip3", "pip"]).windows(&["pip.exe", "pip3.exe"]),
+        )
+    }
+
+    /// pip and scripts installed with it are in Scripts on Windows
+    fn get_bin_dirs(&self) -> Vec<String> {
+        vec!("bin".to_string(), "Scripts".to_string(), ".".to_string())
+    }
+
+    fn get_name(&self) -> &str {
+        "python"
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("python:{tag}"))
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        vec!["stripped".to_string()].into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_to_download() {
+        let download = asset_to_download("cpython-3.12.1+20240107-x86_64-unknown-linux-musl-install_only.tar.gz", "https://example.com/a").unwrap();
+        assert_eq!("3.12.1", download.version.unwrap().to_string());
+        assert_eq!(Some(Variant::Musl), download.variant);
+        assert_eq!(Some("2024-01-07".to_string()), download.date);
+        let download = asset_to_download("cpython-3.10.13+20240107-x86_64-pc-windows-msvc-shared-install_only.tar.gz", "").unwrap();
+        assert_eq!(Some(Os::Windows), download.os);
+        assert!(asset_to_download("cpython-3.12.1+20240107-aarch64-apple-darwin-install_only_stripped.tar.gz", "").unwrap().tags.contains("stripped"));
+        assert!(asset_to_download("cpython-3.12.1+20240107-x86_64-unknown-linux-gnu-pgo+lto-full.tar.zst", "").is_none());
+        assert!(asset_to_download("cpython-3.13.0rc1+20240801-x86_64-unknown-linux-gnu-install_only.tar.gz", "").is_none());
+        assert!(asset_to_download("cpython-3.12.1+20240107-x86_64_v3-unknown-linux-gnu-install_only.tar.gz", "").is_none());
+    }
+
+    #[test]
+    fn test_parse_requires_python() {
+        assert_eq!(VersionReq::parse(">=3.9, <4").ok(), parse_requires_python(">=3.9,<4"));
+        assert_eq!(VersionReq::parse("^3.10").ok(), parse_requires_python("~=3.10"));
+        assert_eq!(VersionReq::parse("~3.10.2").ok(), parse_requires_python("~= 3.10.2"));
+        assert_eq!(VersionReq::parse("3.11.*").ok(), parse_requires_python("==3.11.*"));
+        assert_eq!(VersionReq::parse(">=3.8").ok(), parse_requires_python(">=3.8, !=3.9.1"));
+    }
+}
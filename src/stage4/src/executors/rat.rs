@@ -5,9 +5,14 @@ use std::future::Future;
 use std::os::unix::fs::PermissionsExt;
 use std::pin::Pin;
 
-use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion};
+use crate::error::GgError;
+use crate::diagnostics;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion, NamedBin};
+use crate::mirror::mirror_url;
 use crate::target::{Arch, Os, Variant};
 
+const RAT_BASE: &str = "https://ratbinsa.z1.web.core.windows.net";
+
 pub struct Rat {
     pub executor_cmd: ExecutorCmd,
 }
@@ -17,11 +22,12 @@ impl Executor for Rat {
         &self.executor_cmd
     }
 
-    fn get_download_urls<'a>(&'a self, _input: &'a AppInput) -> Pin<Box<dyn Future<Output=Vec<Download>> + 'a>> {
+    fn get_download_urls<'a>(&'a self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
         Box::pin(async move {
-            let versions: Vec<String> = reqwest::get("https://ratbinsa.z1.web.core.windows.net/list.json").await.unwrap().json().await.unwrap();
-            versions.into_iter().map(|name| {
-                let url = format!("https://ratbinsa.z1.web.core.windows.net/{}", name);
+            let base = mirror_url(input, "rat", RAT_BASE);
+            let versions: Vec<String> = diagnostics::get_json(&format!("{base}/list.json")).await?;
+            Ok(versions.into_iter().map(|name| {
+                let url = format!("{base}/{}", name);
                 let name = name.clone();
                 let parts = name.split("-");
                 let version = parts.clone().nth(1).unwrap_or("NA");
@@ -38,23 +44,23 @@ impl Executor for Rat {
                     arch: Some(Arch::X86_64),
                     os,
                     variant: Some(Variant::Any),
+                    sha256: None,
+                    checksum: None,
+                    date: None,
                 }
-            }).collect()
+            }).collect())
         })
     }
 
-    fn get_bins(&self, input: &AppInput) -> Vec<String> {
-        vec![match &input.target.os {
-            Os::Windows => "rat.exe",
-            _ => "rat.bin"
-        }.to_string()]
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(NamedBin::new("rat").alias("ra").unix(&["rat.bin"]).windows(&["rat.exe"]))
     }
 
     fn get_name(&self) -> &str {
         "rat"
     }
 
-    fn post_prep(&self, cache_path: &str) {
+    fn post_prep(&self, cache_path: &str, _install_dir: &str) -> Result<(), String> {
         let entries = read_dir(&cache_path);
         if let Ok(entries) = entries {
             entries.for_each(|entry| {
@@ -80,5 +86,6 @@ impl Executor for Rat {
                 }
             });
         }
+        Ok(())
     }
 }
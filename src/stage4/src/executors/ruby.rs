@@ -0,0 +1,189 @@
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use log::warn;
+use regex::Regex;
+use semver::VersionReq;
+
+use crate::diagnostics::Diagnostic;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, get_file_version, intersect_version_reqs, NamedBin, VersionSource};
+use crate::http;
+use crate::mirror::with_mirror;
+use crate::target::{Arch, Os, Variant};
+
+pub const PORTABLE_RUBY_RELEASES: &str = "https://github.com/Homebrew/homebrew-portable-ruby/releases/download";
+const RELEASE_PAGES: u32 = 2;
+const RUBY_VERSION: &str = ".ruby-version";
+const GEMFILE: &str = "Gemfile";
+
+pub struct Ruby {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// The relocatable Ruby Homebrew runs on, e.g. portable-ruby-3.3.5.x86_64_linux.bottle.tar.gz or
+/// portable-ruby-3.3.5.arm64_big_sur.bottle.tar.gz. macOS bottles without an arch are x86_64, _1 is a rebuild
+fn asset_to_download(name: &str, url: &str) -> Option<Download> {
+    let re = Regex::new(r"^portable-ruby-(\d+\.\d+\.\d+)(?:_\d+)?\.(\w+)\.bottle\.tar\.gz$").unwrap();
+    let c = re.captures(name)?;
+    let platform = &c[2];
+    let arch = if platform.starts_with("arm64_") { Arch::Arm64 } else { Arch::X86_64 };
+    let (os, variant) = match platform.strip_suffix("_linux") {
+        Some("x86_64" | "arm64") => (Os::Linux, None),
+        Some(_) => return None,
+        None => (Os::Mac, Some(Variant::Any)),
+    };
+    let mut download = Download::new(url.to_string(), &c[1], variant);
+    download.os = Some(os);
+    download.arch = Some(arch);
+    Some(download)
+}
+
+/// The SHA-256 of the bottle in its .bottle.json, in `tags` under its platform, e.g. x86_64_linux
+fn parse_bottle_json(text: &str, platform: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(text).ok()?;
+    json.as_object()?.values()
+        .find_map(|formula| formula["bottle"]["tags"][platform]["sha256"].as_str().map(|s| s.to_string()))
+}
+
+/// `3.3.5` or `ruby-3.3.5` is exactly that version, `3.3` the newest 3.3
+fn parse_ruby_version(text: &str) -> Option<VersionReq> {
+    let version = text.lines().next()?.trim();
+    let version = version.strip_prefix("ruby-").unwrap_or(version);
+    match version.split('.').count() {
+        3 => VersionReq::parse(&format!("={version}")).ok(),
+        _ => VersionReq::parse(&format!("~{version}")).ok(),
+    }
+}
+
+/// A RubyGems requirement, `~> 3.3.0` is >= 3.3.0 and < 3.4, `~> 3.3` >= 3.3 and < 4, a bare version is exact
+fn to_comparator(requirement: &str) -> String {
+    let requirement = requirement.trim();
+    if let Some(v) = requirement.strip_prefix("~>") {
+        let v = v.trim();
+        if v.split('.').count() >= 3 { format!("~{v}") } else { format!("^{v}") }
+    } else if let Some(v) = requirement.strip_prefix('=') {
+        format!("={}", v.trim())
+    } else if requirement.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("={requirement}")
+    } else {
+        requirement.to_string()
+    }
+}
+
+/// `ruby "3.3.5"` or `ruby ">= 3.1", "< 3.4"` in the Gemfile. `ruby file: ".ruby-version"` is .ruby-version itself
+fn parse_gemfile(text: &str) -> Option<VersionReq> {
+    let line = Regex::new(r#"(?m)^\s*ruby\s*\(?\s*((?:["'][^"']+["']\s*,?\s*)+)"#).unwrap();
+    let requirements = Regex::new(r#"["']([^"']+)["']"#).unwrap();
+    let c = line.captures(text)?;
+    let comparators: Vec<String> = requirements.captures_iter(&c[1]).map(|r| to_comparator(&r[1])).collect();
+    VersionReq::parse(&comparators.join(", ")).map_err(|e| warn!("Unable to use ruby {} from {GEMFILE}: {e}", &c[1])).ok()
+}
+
+impl Executor for Ruby {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_file_version(RUBY_VERSION, parse_ruby_version).into_iter()
+            .chain(get_file_version(GEMFILE, parse_gemfile))
+            .collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        vec!(PathBuf::from(RUBY_VERSION), PathBuf::from(GEMFILE))
+    }
+
+    /// Only the Ruby versions Homebrew has needed are built, there are none for Windows
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let releases = http::get_releases("Homebrew", "homebrew-portable-ruby", RELEASE_PAGES, 100).await?;
+            let downloads = releases.iter()
+                .flat_map(|release| release.assets.iter())
+                .filter_map(|asset| asset_to_download(&asset.name, asset.browser_download_url.as_str()))
+                .collect();
+            Ok(with_mirror(input, "ruby", PORTABLE_RUBY_RELEASES, downloads))
+        })
+    }
+
+    /// Each bottle has a .bottle.json next to it
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            let file = download.download_url.rsplit('/').next().unwrap_or_default();
+            let Some(name) = download.download_url.strip_suffix(".tar.gz") else { return Ok(None) };
+            let url = format!("{name}.json");
+            let platform = name.rsplit('.').nth(1).unwrap_or_default();
+            let error = |e: &reqwest::Error| Diagnostic::new(format!("Unable to get the checksum of {file}")).url(&url).request_error(e).to_string();
+            let res = http::get(&url).await.and_then(|res| res.error_for_status()).map_err(|e| error(&e))?;
+            let text = res.text().await.map_err(|e| error(&e))?;
+            parse_bottle_json(&text, platform).map(Some).ok_or(Diagnostic::new(format!("No SHA-256 of {file}")).url(&url).to_string())
+        })
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(NamedBin::new("ruby"), NamedBin::new("gem"), NamedBin::new("bundle"))
+    }
+
+    fn get_name(&self) -> &str {
+        "ruby"
+    }
+
+    /// Bottles are portable-ruby/<version>/bin/ruby, the version dir is moved up so bin is where gg looks
+    fn post_prep(&self, cache_path: &str, _install_dir: &str) -> Result<(), String> {
+        let Some(version_dir) = fs::read_dir(cache_path).into_iter().flatten().filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .find(|p| p.join("bin").is_dir()) else { return Ok(()) };
+        let res = fs::read_dir(&version_dir).and_then(|entries| {
+            for entry in entries {
+                let entry = entry?;
+                fs::rename(entry.path(), Path::new(cache_path).join(entry.file_name()))?;
+            }
+            fs::remove_dir(&version_dir)
+        });
+        res.map_err(|e| format!("Unable to move {} into {cache_path}: {e}", version_dir.display()))
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("ruby:{tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_to_download() {
+        let download = asset_to_download("portable-ruby-3.3.5.arm64_big_sur.bottle.tar.gz", "").unwrap();
+        assert_eq!((Some(Os::Mac), Some(Arch::Arm64)), (download.os, download.arch));
+        let download = asset_to_download("portable-ruby-2.6.10_1.x86_64_linux.bottle.tar.gz", "").unwrap();
+        assert_eq!("2.6.10", download.version.unwrap().to_string());
+        assert_eq!((Some(Os::Linux), Some(Arch::X86_64), None), (download.os, download.arch, download.variant));
+        assert_eq!(Some(Arch::X86_64), asset_to_download("portable-ruby-3.1.4.el_capitan.bottle.tar.gz", "").unwrap().arch);
+        assert!(asset_to_download("portable-ruby-3.3.5.x86_64_linux.bottle.json", "").is_none());
+    }
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!("=3.3.5", parse_ruby_version("ruby-3.3.5\n").unwrap().to_string());
+        assert_eq!("~3.2", parse_ruby_version("3.2").unwrap().to_string());
+        assert_eq!("=3.3.5", parse_gemfile("source \"https://rubygems.org\"\n\nruby \"3.3.5\"\ngem \"rails\"\n").unwrap().to_string());
+        assert_eq!("~3.3.0", parse_gemfile("ruby '~> 3.3.0'").unwrap().to_string());
+        assert_eq!(">=3.1, <3.4", parse_gemfile("ruby \">= 3.1\", \"< 3.4\"").unwrap().to_string());
+        assert!(parse_gemfile("ruby file: \".ruby-version\"\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_bottle_json() {
+        let text = r#"{"portable-ruby":{"formula":{"name":"portable-ruby"},"bottle":{"rebuild":0,"tags":{"x86_64_linux":{"filename":"portable-ruby-3.3.5.x86_64_linux.bottle.tar.gz","sha256":"abc123"}}}}}"#;
+        assert_eq!(Some("abc123".to_string()), parse_bottle_json(text, "x86_64_linux"));
+        assert_eq!(None, parse_bottle_json(text, "arm64_big_sur"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use log::{info, warn};
+use semver::VersionReq;
+
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256, GgVersion, intersect_version_reqs, NamedBin, VersionSource};
+use crate::http;
+use crate::mirror::with_mirror;
+use crate::target::{Arch, Os, Target, Variant};
+
+pub const RUST_DIST: &str = "https://static.rust-lang.org/dist";
+const RELEASE_PAGES: u32 = 2;
+/// rustup reads the first that exists
+const TOOLCHAIN_FILES: [&str; 2] = ["rust-toolchain", "rust-toolchain.toml"];
+
+pub struct Rust {
+    pub executor_cmd: ExecutorCmd,
+}
+
+fn get_triple(target: &Target) -> Option<String> {
+    let musl = target.variant == Some(Variant::Musl);
+    let triple = match (target.os, target.arch) {
+        (Os::Linux, Arch::X86_64) => "x86_64-unknown-linux",
+        (Os::Linux, Arch::Arm64) => "aarch64-unknown-linux",
+        (Os::Linux, Arch::Armv7) => return Some(format!("armv7-unknown-linux-{}", if musl { "musleabihf" } else { "gnueabihf" })),
+        (Os::Linux, Arch::Riscv64) => "riscv64gc-unknown-linux",
+        (Os::Linux, Arch::Ppc64le) => "powerpc64le-unknown-linux",
+        (Os::Linux, Arch::S390x) => "s390x-unknown-linux",
+        (Os::Mac, Arch::X86_64) => return Some("x86_64-apple-darwin".to_string()),
+        (Os::Mac, Arch::Arm64) => return Some("aarch64-apple-darwin".to_string()),
+        (Os::Windows, Arch::X86_64) => return Some("x86_64-pc-windows-msvc".to_string()),
+        (Os::Windows, Arch::Arm64) => return Some("aarch64-pc-windows-msvc".to_string()),
+        _ => return None,
+    };
+    Some(format!("{triple}-{}", if musl { "musl" } else { "gnu" }))
+}
+
+/// The combined rust-<version>-<triple>.tar.xz, with rustc, cargo, the standard library, clippy and rustfmt
+fn get_downloads(base: &str, tags: &[String], target: &Target) -> Vec<Download> {
+    let Some(triple) = get_triple(target) else { return vec!() };
+    tags.iter()
+        .filter_map(|tag| GgVersion::new(tag).map(|_| tag))
+        .map(|version| {
+            let mut download = Download::new(format!("{base}/rust-{version}-{triple}.tar.xz"), version, target.variant);
+            download.os = Some(target.os);
+            download.arch = Some(target.arch);
+            download
+        })
+        .collect()
+}
+
+/// `channel` in [toolchain] of rust-toolchain.toml, or the legacy one line rust-toolchain. `1.75` is the newest 1.75.x.
+/// stable is the newest version, beta and nightly are not distributed as versions
+fn parse_channel(text: &str) -> Option<Result<Option<VersionReq>, String>> {
+    let channel = match toml::from_str::<toml::Value>(text) {
+        Ok(value) => value.get("toolchain")?.get("channel")?.as_str()?.to_string(),
+        Err(_) => text.lines().next()?.trim().to_string(),
+    };
+    if channel == "stable" {
+        return Some(Ok(None));
+    }
+    let version_req = match channel.split('.').count() {
+        3 => VersionReq::parse(&format!("={channel}")),
+        _ => VersionReq::parse(&format!("~{channel}")),
+    };
+    Some(version_req.map(Some).map_err(|_| channel))
+}
+
+/// Where rustup looks, the dir and each of its parents
+fn get_toolchain_files(dir: &Path) -> Vec<PathBuf> {
+    dir.ancestors().flat_map(|dir| TOOLCHAIN_FILES.iter().map(move |file| dir.join(file))).collect()
+}
+
+fn get_toolchain_version() -> Option<VersionSource> {
+    let path = get_toolchain_files(&std::env::current_dir().ok()?).into_iter().find(|file| file.is_file())?;
+    let file = &path.to_string_lossy();
+    match parse_channel(&fs::read_to_string(&path).ok()?)? {
+        Ok(version_req) => {
+            let version_req = version_req?;
+            info!("Got version {version_req} from {file}");
+            Some(VersionSource::new(file, version_req))
+        }
+        Err(channel) => {
+            warn!("Channel {channel} in {file} is not supported, only stable and versions like 1.75.0");
+            None
+        }
+    }
+}
+
+/// Moves what is not in `to` already, going into dirs that are in both
+fn merge_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if dest.is_dir() && entry.path().is_dir() {
+            merge_dir(&entry.path(), &dest)?;
+        } else if !dest.exists() {
+            fs::rename(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+impl Executor for Rust {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_toolchain_version().into_iter().collect()
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        std::env::current_dir().map(|dir| get_toolchain_files(&dir)).unwrap_or_default()
+    }
+
+    /// Versions from the GitHub releases of rust-lang/rust, downloads from static.rust-lang.org
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let tags: Vec<String> = http::get_releases("rust-lang", "rust", RELEASE_PAGES, 100).await?
+                .into_iter().map(|release| release.tag_name).collect();
+            let downloads = get_downloads(RUST_DIST, &tags, &input.target);
+            if downloads.is_empty() && !tags.is_empty() {
+                return Err(GgError::no_build("rust", &input.target));
+            }
+            Ok(with_mirror(input, "rust", RUST_DIST, downloads))
+        })
+    }
+
+    /// Each tarball has a .sha256 next to it
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(async move {
+            fetch_sha256(&format!("{}.sha256", download.download_url), download).await.map(Some)
+        })
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(
+            NamedBin::new("rustc").alias("rust").windows(&["rustc.exe"]),
+            NamedBin::new("cargo").windows(&["cargo.exe"]),
+        )
+    }
+
+    /// As the components are in the tarball, what install.sh would put together
+    fn get_bin_dirs(&self) -> Vec<String> {
+        ["rustc", "cargo", "clippy-preview", "rustfmt-preview"].iter().map(|c| format!("{c}/bin")).collect()
+    }
+
+    fn get_name(&self) -> &str {
+        "rust"
+    }
+
+    /// rustc looks for the standard library in its own lib/rustlib, which is a component of its own in the tarball
+    fn post_prep(&self, cache_path: &str, _install_dir: &str) -> Result<(), String> {
+        let rustlib = Path::new(cache_path).join("rustc").join("lib").join("rustlib");
+        let std_dirs = fs::read_dir(cache_path).into_iter().flatten().filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("rust-std-"));
+        for std_dir in std_dirs {
+            merge_dir(&std_dir.path().join("lib").join("rustlib"), &rustlib)
+                .map_err(|e| format!("Unable to move {} into {}: {e}", std_dir.path().display(), rustlib.display()))?;
+        }
+        Ok(())
+    }
+
+    fn get_container_image(&self, tag: &str) -> Option<String> {
+        Some(format!("rust:{tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_downloads() {
+        let target = Target { os: Os::Linux, arch: Arch::X86_64, variant: Some(Variant::Musl) };
+        let tags = vec!("1.75.0".to_string(), "release-notes".to_string());
+        let downloads = get_downloads(RUST_DIST, &tags, &target);
+        assert_eq!(1, downloads.len());
+        assert_eq!("https://static.rust-lang.org/dist/rust-1.75.0-x86_64-unknown-linux-musl.tar.xz", downloads[0].download_url);
+        let target = Target { os: Os::Windows, arch: Arch::X86_64, variant: None };
+        assert!(get_downloads(RUST_DIST, &tags, &target)[0].download_url.ends_with("-x86_64-pc-windows-msvc.tar.xz"));
+    }
+
+    #[test]
+    fn test_parse_channel() {
+        assert_eq!("=1.75.0", parse_channel("[toolchain]\nchannel = \"1.75.0\"\ncomponents = [\"clippy\"]\n").unwrap().unwrap().unwrap().to_string());
+        assert_eq!("~1.75", parse_channel("1.75\n").unwrap().unwrap().unwrap().to_string());
+        assert_eq!(Some(Ok(None)), parse_channel("[toolchain]\nchannel = \"stable\"\n"));
+        assert_eq!(Some(Err("nightly-2024-01-01".to_string())), parse_channel("nightly-2024-01-01"));
+        assert!(parse_channel("[toolchain]\nprofile = \"minimal\"\n").is_none());
+    }
+
+    #[test]
+    fn test_get_toolchain_files() {
+        let files = get_toolchain_files(Path::new("/a/b"));
+        assert_eq!(6, files.len());
+        assert_eq!(Path::new("/a/b/rust-toolchain"), files[0]);
+        assert_eq!(Path::new("/a/rust-toolchain.toml"), files[3]);
+        assert_eq!(Path::new("/rust-toolchain.toml"), files[5]);
+    }
+
+    #[test]
+    fn test_merge_dir() {
+        let dir = std::env::temp_dir().join(format!("gg-rust-merge-{}", std::process::id()));
+        let triple = "x86_64-unknown-linux-gnu";
+        fs::create_dir_all(dir.join("std").join(triple).join("lib")).unwrap();
+        fs::write(dir.join("std").join(triple).join("lib").join("libstd.rlib"), "").unwrap();
+        fs::create_dir_all(dir.join("rustlib").join(triple).join("bin")).unwrap();
+        merge_dir(&dir.join("std"), &dir.join("rustlib")).unwrap();
+        assert!(dir.join("rustlib").join(triple).join("lib").join("libstd.rlib").exists());
+        assert!(dir.join("rustlib").join(triple).join("bin").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use log::{info, warn};
+use regex::Regex;
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::diagnostics;
+use crate::error::GgError;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, fetch_sha256, intersect_version_reqs, NamedBin, VersionSource};
+use crate::http;
+use crate::mirror::{mirror_url, with_mirror};
+use crate::signatures::{HASHICORP_KEYS, OPENTOFU_KEYS, Signature};
+use crate::target::{Arch, Os, Variant};
+use crate::tool_versions;
+
+pub const HASHICORP_RELEASES: &str = "https://releases.hashicorp.com";
+const OPENTOFU_RELEASES: &str = "https://github.com/opentofu/opentofu/releases/download";
+const RELEASE_PAGES: u32 = 2;
+
+pub struct Terraform {
+    pub executor_cmd: ExecutorCmd,
+}
+
+/// OpenTofu, the open source fork. Same project files, different version file
+pub struct OpenTofu {
+    pub executor_cmd: ExecutorCmd,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    versions: HashMap<String, IndexVersion>,
+}
+
+#[derive(Deserialize)]
+struct IndexVersion {
+    builds: Vec<Build>,
+}
+
+#[derive(Deserialize)]
+struct Build {
+    version: String,
+    os: String,
+    arch: String,
+    url: String,
+}
+
+fn get_os(os: &str) -> Option<Os> {
+    match os {
+        "linux" => Some(Os::Linux),
+        "darwin" => Some(Os::Mac),
+        "windows" => Some(Os::Windows),
+        _ => None,
+    }
+}
+
+fn get_arch(arch: &str) -> Option<Arch> {
+    match arch {
+        "amd64" => Some(Arch::X86_64),
+        "arm64" => Some(Arch::Arm64),
+        "arm" => Some(Arch::Armv7),
+        _ => None,
+    }
+}
+
+/// 1.8.0-alpha20240214 is tagged alpha, -rc1 rc
+fn to_download(url: &str, version: &str, os: &str, arch: &str) -> Option<Download> {
+    let mut download = Download::new(url.to_string(), version, Some(Variant::Any));
+    download.version.as_ref()?;
+    download.os = Some(get_os(os)?);
+    download.arch = Some(get_arch(arch)?);
+    if let Some((_, pre)) = version.split_once('-') {
+        download.tags.insert(pre.chars().take_while(|c| c.is_ascii_alphabetic()).collect());
+    }
+    Some(download)
+}
+
+fn get_terraform_downloads(index: Index) -> Vec<Download> {
+    index.versions.into_values()
+        .flat_map(|version| version.builds)
+        .filter_map(|build| to_download(&build.url, &build.version, &build.os, &build.arch))
+        .collect()
+}
+
+/// tofu_1.6.2_linux_amd64.zip
+fn opentofu_asset_to_download(name: &str, url: &str) -> Option<Download> {
+    let parts: Vec<&str> = name.strip_prefix("tofu_")?.strip_suffix(".zip")?.split('_').collect();
+    match parts.as_slice() {
+        [version, os, arch] => to_download(url, version, os, arch),
+        _ => None,
+    }
+}
+
+/// Terraform constraints: `>= 1.2.0, < 2.0.0`, and `~> 1.5` for any 1.x from 1.5, `~> 1.5.0` for any 1.5.x. A bare
+/// version is exact, `1.7` is 1.7.0, where semver would take 1.7.4 as ^1.7.4
+fn parse_constraint(constraint: &str) -> Option<VersionReq> {
+    let parts: Option<Vec<String>> = constraint.split(',').map(|part| {
+        let part = part.trim();
+        match part.strip_prefix("~>") {
+            Some(version) => {
+                let version = version.trim();
+                let numbers: Vec<u64> = version.split('.').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+                match numbers.as_slice() {
+                    [major] => Some(format!(">={major}")),
+                    [major, minor] => Some(format!(">={major}.{minor}, <{}", major + 1)),
+                    [major, minor, _] => Some(format!(">={version}, <{major}.{}", minor + 1)),
+                    _ => None,
+                }
+            }
+            None if part.starts_with("!=") => None,
+            None => match part.strip_prefix('=').unwrap_or(part).trim() {
+                version if version.starts_with(|c: char| c.is_ascii_digit()) => {
+                    let mut numbers: Vec<&str> = version.split('.').collect();
+                    numbers.resize(numbers.len().max(3), "0");
+                    Some(format!("={}", numbers.join(".")))
+                }
+                _ => Some(part.to_string()),
+            },
+        }
+    }).collect();
+    VersionReq::parse(&parts?.join(", ")).ok()
+}
+
+/// `required_version = "..."` in the terraform block of .tf files in the project folder
+fn get_required_version_sources() -> Vec<VersionSource> {
+    let required_version = Regex::new(r#"required_version\s*=\s*"([^"]+)""#).unwrap();
+    get_tf_files().iter()
+        .filter_map(|file| {
+            let text = fs::read_to_string(file).ok()?;
+            let constraint = required_version.captures(&text)?[1].to_string();
+            let version_req = parse_constraint(&constraint);
+            if version_req.is_none() {
+                warn!("Unsupported required_version {constraint} in {}", file.display());
+            }
+            Some(VersionSource::new(&format!("{} required_version", file.display()), version_req?))
+        })
+        .collect()
+}
+
+fn get_tf_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(".").into_iter().flatten().flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|e| e == "tf").unwrap_or(false))
+        .collect();
+    files.sort();
+    files
+}
+
+/// .terraform-version (tfenv) or .opentofu-version (tofuenv), then required_version
+fn get_version_sources(version_file: &str, name: &str) -> Vec<VersionSource> {
+    let mut sources = vec!();
+    if let Some(version_req) = fs::read_to_string(version_file).ok().and_then(|v| tool_versions::parse_version(name, v.trim())) {
+        info!("Got version {version_req} from {version_file}");
+        sources.push(VersionSource::new(version_file, version_req));
+    }
+    sources.extend(get_required_version_sources());
+    sources
+}
+
+fn get_version_inputs(version_file: &str) -> Vec<PathBuf> {
+    vec!(PathBuf::from(version_file)).into_iter().chain(get_tf_files()).collect()
+}
+
+/// From the SHA256SUMS file of the release, which HashiCorp and OpenTofu both publish
+async fn get_checksum_from(download: &Download, prefix: &str) -> Result<Option<String>, String> {
+    let dir = download.download_url.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+    let version = download.version.as_ref().ok_or(format!("No version in {}", download.download_url))?.to_string();
+    fetch_sha256(&format!("{dir}/{prefix}_{version}_SHA256SUMS"), download).await.map(Some)
+}
+
+fn get_default_exclude_tags() -> HashSet<String> {
+    vec!["alpha", "beta", "rc"].into_iter().map(|s| s.to_string()).collect()
+}
+
+impl Executor for Terraform {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_version_sources(".terraform-version", "terraform")
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        get_version_inputs(".terraform-version")
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let url = format!("{}/terraform/index.json", mirror_url(input, "terraform", HASHICORP_RELEASES));
+            let index: Index = diagnostics::get_json(&url).await?;
+            Ok(with_mirror(input, "terraform", HASHICORP_RELEASES, get_terraform_downloads(index)))
+        })
+    }
+
+    /// terraform_<version>_SHA256SUMS next to the zip
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(get_checksum_from(download, "terraform"))
+    }
+
+    fn get_signature(&self, download: &Download) -> Option<Signature> {
+        let (dir, _) = download.download_url.rsplit_once('/')?;
+        let sums = format!("{dir}/terraform_{}_SHA256SUMS", download.version.as_ref()?.to_string());
+        Some(Signature::of_checksums(sums.clone(), format!("{sums}.sig"), &HASHICORP_KEYS))
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(NamedBin::new("terraform").windows(&["terraform.exe"]))
+    }
+
+    fn get_name(&self) -> &str {
+        "terraform"
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        get_default_exclude_tags()
+    }
+}
+
+impl Executor for OpenTofu {
+    fn get_executor_cmd(&self) -> &ExecutorCmd {
+        &self.executor_cmd
+    }
+
+    fn get_version_req(&self) -> Option<VersionReq> {
+        intersect_version_reqs(&self.get_version_sources())
+    }
+
+    fn get_version_sources(&self) -> Vec<VersionSource> {
+        get_version_sources(".opentofu-version", "opentofu")
+    }
+
+    fn get_version_inputs(&self) -> Vec<PathBuf> {
+        get_version_inputs(".opentofu-version")
+    }
+
+    fn get_download_urls<'a>(&self, input: &'a AppInput) -> Pin<Box<dyn Future<Output=Result<Vec<Download>, GgError>> + 'a>> {
+        Box::pin(async move {
+            let releases = http::get_releases("opentofu", "opentofu", RELEASE_PAGES, 100).await?;
+            let downloads = releases.iter()
+                .flat_map(|release| release.assets.iter())
+                .filter_map(|asset| opentofu_asset_to_download(&asset.name, asset.browser_download_url.as_str()))
+                .collect();
+            Ok(with_mirror(input, "opentofu", OPENTOFU_RELEASES, downloads))
+        })
+    }
+
+    /// tofu_<version>_SHA256SUMS is part of each release
+    fn get_checksum<'a>(&'a self, download: &'a Download) -> Pin<Box<dyn Future<Output=Result<Option<String>, String>> + 'a>> {
+        Box::pin(get_checksum_from(download, "tofu"))
+    }
+
+    fn get_signature(&self, download: &Download) -> Option<Signature> {
+        let (dir, _) = download.download_url.rsplit_once('/')?;
+        let sums = format!("{dir}/tofu_{}_SHA256SUMS", download.version.as_ref()?.to_string());
+        Some(Signature::of_checksums(sums.clone(), format!("{sums}.gpgsig"), &OPENTOFU_KEYS))
+    }
+
+    fn get_named_bins(&self) -> Vec<NamedBin> {
+        vec!(NamedBin::new("opentofu").alias("tofu").unix(&["tofu"]).windows(&["tofu.exe"]))
+    }
+
+    fn get_name(&self) -> &str {
+        "opentofu"
+    }
+
+    fn get_default_exclude_tags(&self) -> HashSet<String> {
+        get_default_exclude_tags()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_terraform_downloads() {
+        let index: Index = serde_json::from_str(r#"{"versions": {"1.7.4": {"builds": [
+            {"version": "1.7.4", "os": "linux", "arch": "amd64", "url": "https://releases.hashicorp.com/terraform/1.7.4/terraform_1.7.4_linux_amd64.zip"},
+            {"version": "1.7.4", "os": "freebsd", "arch": "amd64", "url": "https://releases.hashicorp.com/terraform/1.7.4/terraform_1.7.4_freebsd_amd64.zip"}
+        ]}}}"#).unwrap();
+        let downloads = get_terraform_downloads(index);
+        assert_eq!(1, downloads.len());
+        assert_eq!(Some(Os::Linux), downloads[0].os);
+        assert_eq!(Some(Arch::X86_64), downloads[0].arch);
+
+        let download = opentofu_asset_to_download("tofu_1.7.0-rc1_darwin_arm64.zip", "https://example.com/a").unwrap();
+        assert_eq!(Some(Arch::Arm64), download.arch);
+        assert!(download.tags.contains("rc"));
+        assert!(opentofu_asset_to_download("tofu_1.6.2_SHA256SUMS", "").is_none());
+        assert!(opentofu_asset_to_download("tofu_1.6.2_amd64.deb", "").is_none());
+    }
+
+    #[test]
+    fn test_parse_constraint() {
+        assert_eq!(">=1.2.0, <2.0.0", parse_constraint(">= 1.2.0, < 2.0.0").unwrap().to_string());
+        assert_eq!(">=1.5, <2", parse_constraint("~> 1.5").unwrap().to_string());
+        assert_eq!(">=1.5.0, <1.6", parse_constraint("~> 1.5.0").unwrap().to_string());
+        assert_eq!("=1.7.4", parse_constraint("= 1.7.4").unwrap().to_string());
+        assert_eq!("=1.7.4", parse_constraint("1.7.4").unwrap().to_string());
+        assert_eq!("=1.7.0", parse_constraint("1.7").unwrap().to_string());
+        assert_eq!(">=1.5.0, =1.7.4", parse_constraint(">= 1.5.0, 1.7.4").unwrap().to_string());
+        assert!(parse_constraint("!= 1.3.0").is_none());
+    }
+}
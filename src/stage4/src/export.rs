@@ -0,0 +1,188 @@
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::executor::{AppInput, Executor, ExecutorCmd, get_install_suffix, get_locked_version_req, read_meta, resolve_version_req, with_deps};
+use crate::installed;
+
+const CACHE_DIR: &str = ".cache/gg";
+const BUNDLE_META: &str = "gg-bundle.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BundleTool {
+    name: String,
+    version: Option<String>,
+    path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BundleMeta {
+    target: String,
+    tools: Vec<BundleTool>,
+}
+
+/// The installs the tools in gg.toml / gg.lock and what they depend on run with, the version each resolves to as
+/// when running. Every installed tool when there are none
+fn find_tools(input: &AppInput) -> Vec<BundleTool> {
+    let mut names: Vec<&String> = input.config.tools.keys().chain(input.lock.tools.keys()).collect();
+    names.sort();
+    names.dedup();
+    if names.is_empty() {
+        return find_all_tools(input);
+    }
+    let executors = with_deps(names.into_iter().filter_map(|name| <dyn Executor>::new(ExecutorCmd {
+        cmd: name.clone(),
+        version: None,
+        include_tags: Default::default(),
+        exclude_tags: Default::default(),
+    })).collect(), input);
+    executors.iter().filter_map(|executor| {
+        let executor = executor.as_ref();
+        let name = executor.get_name();
+        let version_req = resolve_version_req(executor, input);
+        let version_req = get_locked_version_req(executor, input, &version_req).or(version_req);
+        let Some(dir) = installed::find_version(&input.config, name, &get_install_suffix(executor, input), &version_req) else {
+            warn!("{name} is not installed, run it once to include it in the bundle");
+            return None;
+        };
+        let path = format!("{name}/{dir}");
+        let version = read_meta(&input.config.get_install_dir().join(&path)).and_then(|meta| meta.download.version);
+        Some(BundleTool { name: name.to_string(), version: version.map(|v| v.to_string()), path })
+    }).collect()
+}
+
+fn find_all_tools(input: &AppInput) -> Vec<BundleTool> {
+    let install_dir = input.config.get_install_dir();
+    walkdir::WalkDir::new(&install_dir).min_depth(2).max_depth(2).into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().is_dir())
+        .filter_map(|x| {
+            let meta = read_meta(x.path())?;
+            let name = x.path().parent()?.file_name()?.to_str()?.to_string();
+            let path = x.path().strip_prefix(&install_dir).ok()?.to_str()?.replace('\\', "/");
+            Some(BundleTool { name, version: meta.download.version.map(|v| v.to_string()), path })
+        }).collect()
+}
+
+/// gg itself (stage 2-4), so the offline machine does not have to download it either
+fn find_gg_dirs() -> Vec<PathBuf> {
+    walkdir::WalkDir::new(CACHE_DIR).min_depth(1).max_depth(1).into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().is_dir() && x.file_name().to_string_lossy().starts_with("gg-"))
+        .map(|x| x.into_path())
+        .collect()
+}
+
+fn export_to(input: &AppInput, file: &str) -> Result<usize, String> {
+    let tools = find_tools(input);
+    let meta = BundleMeta { target: input.target.key(), tools };
+    let meta_json = serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())?;
+
+    let mut builder = tar::Builder::new(File::create(file).map_err(|e| e.to_string())?);
+    builder.follow_symlinks(false);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(meta_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, BUNDLE_META, meta_json.as_bytes()).map_err(|e| e.to_string())?;
+
+    for tool in &meta.tools {
+        println!("Adding {} {}", tool.name, tool.version.clone().unwrap_or_default());
+        builder.append_dir_all(&tool.path, input.config.get_install_dir().join(&tool.path)).map_err(|e| e.to_string())?;
+    }
+    for dir in find_gg_dirs() {
+        let name = dir.strip_prefix(CACHE_DIR).map_err(|e| e.to_string())?.to_path_buf();
+        info!("Adding {}", name.display());
+        builder.append_dir_all(name, &dir).map_err(|e| e.to_string())?;
+    }
+    builder.finish().map_err(|e| e.to_string())?;
+    Ok(meta.tools.len())
+}
+
+fn import_from(input: &AppInput, file: &str) -> Result<usize, String> {
+    let install_dir = input.config.get_install_dir();
+    fs::create_dir_all(CACHE_DIR).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&install_dir).map_err(|e| e.to_string())?;
+    let mut archive = tar::Archive::new(File::open(file).map_err(|e| e.to_string())?);
+    let mut meta: Option<BundleMeta> = None;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if entry.path().map(|p| p == Path::new(BUNDLE_META)).unwrap_or(false) {
+            meta = Some(serde_json::from_reader(&mut entry).map_err(|e| e.to_string())?);
+            continue;
+        }
+        // gg itself always goes to .cache/gg, tools to the install dir
+        let is_gg = entry.path().ok()
+            .and_then(|p| p.components().next().map(|c| c.as_os_str().to_string_lossy().starts_with("gg-")))
+            .unwrap_or(false);
+        let dir = if is_gg { PathBuf::from(CACHE_DIR) } else { install_dir.clone() };
+        entry.unpack_in(dir).map_err(|e| e.to_string())?;
+    }
+    let meta = meta.ok_or(format!("{file} is not a gg bundle, {BUNDLE_META} is missing"))?;
+    if meta.target != input.target.key() {
+        warn!("Bundle is for {}, this system is {}", meta.target, input.target.key());
+    }
+    for tool in &meta.tools {
+        println!("Imported {} {}", tool.name, tool.version.clone().unwrap_or_default());
+    }
+    Ok(meta.tools.len())
+}
+
+pub fn export(input: &AppInput, file: Option<&String>) -> ExitCode {
+    let file = file.map(|f| f.as_str()).unwrap_or("gg-bundle.tar");
+    match export_to(input, file) {
+        Ok(count) => {
+            println!("Exported {count} tools to {file}");
+            ExitCode::from(0)
+        }
+        Err(e) => {
+            println!("Unable to export: {e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+pub fn import(input: &AppInput, file: Option<&String>) -> ExitCode {
+    let file = match file {
+        Some(file) => file,
+        None => {
+            println!("Missing bundle file. Usage: import <bundle.tar>");
+            return ExitCode::from(1);
+        }
+    };
+    match import_from(input, file) {
+        Ok(count) => {
+            println!("Imported {count} tools from {file}");
+            ExitCode::from(0)
+        }
+        Err(e) => {
+            println!("Unable to import: {e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "gradle", feature = "java"))]
+    #[test]
+    fn test_find_tools() {
+        let dir = std::env::temp_dir().join(format!("gg-export-find-tools-{}", std::process::id()));
+        let mut input = AppInput::dummy();
+        input.config.cache.install_dir = Some(dir.to_string_lossy().to_string());
+        input.config.tools.insert("gradle".to_string(), "8".to_string());
+        for install in ["gradle/7.6", "gradle/8.5", "java/17.0.10", "java/21.0.2", "node/20.0.0"] {
+            fs::create_dir_all(dir.join(install)).unwrap();
+            fs::write(dir.join(install).join(crate::bloody_indiana_jones::COMPLETE_MARKER), "").unwrap();
+        }
+        let paths: Vec<String> = find_tools(&input).into_iter().map(|tool| tool.path).collect();
+        assert_eq!(vec!("gradle/8.5", "java/21.0.2"), paths);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+use log::info;
+
+fn shell(script: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", script]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+}
+
+/// Script from [hooks] in gg.toml, run from the project dir. A non-zero exit code is an error
+pub fn run(hook: &str, name: &str, script: &str, path: Option<&str>, env_vars: &HashMap<String, String>) -> Result<(), String> {
+    info!("Running {hook} hook for {name}: {script}");
+    let mut command = shell(script);
+    if let Some(path) = path {
+        command.env("PATH", path);
+    }
+    let status = command.envs(env_vars).status()
+        .map_err(|e| format!("Unable to run {hook} hook for {name}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{hook} hook for {name} failed ({status}): {script}"))
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let env_vars = HashMap::from([("GG_HOOK_TEST".to_string(), "node".to_string())]);
+        assert!(run("post_install", "node", "test \"$GG_HOOK_TEST\" = node", None, &env_vars).is_ok());
+        assert!(run("post_install", "node", "exit 3", None, &env_vars).is_err());
+    }
+}
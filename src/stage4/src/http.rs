@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::{NoProxy, Proxy, Request, RequestBuilder, Response, StatusCode};
+
+use crate::config::HttpConfig;
+use crate::diagnostics;
+use crate::error::GgError;
+
+const DEFAULT_USER_AGENT: &str = concat!("gg.cmd/", env!("CARGO_PKG_VERSION"));
+
+/// GitHub API, proxied to avoid rate limits
+pub const GITHUB_API: &str = "https://ghapi.ggcmd.io";
+
+const DEFAULT_RETRIES: u32 = 2;
+const BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+static HEADERS: OnceLock<HeaderMap> = OnceLock::new();
+static HOST_HEADERS: OnceLock<BTreeMap<String, HeaderMap>> = OnceLock::new();
+static PROXY: OnceLock<Option<Proxy>> = OnceLock::new();
+static ATTEMPTS: OnceLock<u32> = OnceLock::new();
+
+/// `${NAME}` in header values is replaced with the environment variable, so tokens can stay out of gg.toml
+fn expand_env(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else { break };
+        result.push_str(&rest[..start]);
+        result.push_str(&env::var(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn get_headers(config: &HttpConfig) -> Result<HeaderMap, String> {
+    let user_agent = env::var("GG_USER_AGENT").ok()
+        .or(config.user_agent.clone())
+        .unwrap_or(DEFAULT_USER_AGENT.to_string());
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_str(&user_agent).map_err(|e| format!("Invalid user-agent: {e}"))?);
+    Ok(headers)
+}
+
+/// Headers of [http.headers."<host>"], so tokens only go to the host they are for
+fn get_host_headers(config: &HttpConfig) -> Result<BTreeMap<String, HeaderMap>, String> {
+    let mut hosts = BTreeMap::new();
+    for (host, values) in &config.headers {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("Invalid header {name}: {e}"))?;
+            let value = HeaderValue::from_str(&expand_env(value)).map_err(|e| format!("Invalid value for header {name}: {e}"))?;
+            headers.insert(name, value);
+        }
+        hosts.insert(host.to_lowercase(), headers);
+    }
+    Ok(hosts)
+}
+
+fn add_host_headers(hosts: &BTreeMap<String, HeaderMap>, request: &mut Request) {
+    let Some(headers) = request.url().host_str().and_then(|host| hosts.get(&host.to_lowercase())) else { return };
+    for (name, value) in headers {
+        request.headers_mut().insert(name, value.clone());
+    }
+}
+
+/// Without proxy in gg.toml reqwest reads HTTPS_PROXY, HTTP_PROXY, ALL_PROXY and NO_PROXY itself
+fn get_proxy(config: &HttpConfig) -> Result<Option<Proxy>, String> {
+    let Some(url) = &config.proxy else { return Ok(None) };
+    let no_proxy = config.no_proxy.as_deref().map(NoProxy::from_string).unwrap_or_else(NoProxy::from_env);
+    Proxy::all(expand_env(url)).map(|proxy| Some(proxy.no_proxy(no_proxy))).map_err(|e| format!("Invalid proxy {url}: {e}"))
+}
+
+/// Headers and proxy from [http] in gg.toml, used for every request from here on
+pub fn init(config: &HttpConfig) {
+    let headers = get_headers(config).unwrap_or_else(|e| {
+        warn!("Ignoring [http] in gg.toml: {e}");
+        get_headers(&HttpConfig::default()).unwrap_or_default()
+    });
+    let _ = HEADERS.set(headers);
+    let host_headers = get_host_headers(config).unwrap_or_else(|e| {
+        warn!("Ignoring [http.headers] in gg.toml: {e}");
+        BTreeMap::new()
+    });
+    let _ = HOST_HEADERS.set(host_headers);
+    let proxy = get_proxy(config).unwrap_or_else(|e| {
+        warn!("Ignoring proxy in gg.toml: {e}");
+        None
+    });
+    let _ = PROXY.set(proxy);
+    let _ = ATTEMPTS.set(get_attempts(config.retries));
+}
+
+fn headers() -> HeaderMap {
+    HEADERS.get().cloned().unwrap_or_else(|| get_headers(&HttpConfig::default()).unwrap_or_default())
+}
+
+/// Every request goes through this, so the user agent and proxy apply to all executors
+pub fn client() -> reqwest::Client {
+    let builder = reqwest::Client::builder().default_headers(headers()).connect_timeout(CONNECT_TIMEOUT);
+    let builder = match PROXY.get() {
+        Some(Some(proxy)) => builder.proxy(proxy.clone()),
+        _ => builder,
+    };
+    builder.build().unwrap_or_default()
+}
+
+/// The first try and then the retries of [http]
+fn get_attempts(retries: Option<u32>) -> u32 {
+    retries.unwrap_or(DEFAULT_RETRIES).saturating_add(1)
+}
+
+/// An I/O error somewhere in the chain, e.g. a connection reset while sending. Not one about the request itself, like
+/// an invalid URL or header
+fn is_io(e: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(e) = source {
+        if e.is::<std::io::Error>() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// 5xx, 408 Request Timeout and 429 Too Many Requests are worth another try. Other 4xx are not
+fn is_transient(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Doubles from 500 ms up to 10 s, plus up to half of that again so parallel runs don't retry in step
+fn get_backoff(attempt: u32, jitter: f64) -> Duration {
+    let backoff = BACKOFF.saturating_mul(1 << attempt.saturating_sub(1).min(10)).min(MAX_BACKOFF);
+    backoff + backoff.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+fn get_jitter() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos() as f64 / 1e9).unwrap_or_default()
+}
+
+/// With the headers of the host, and retries and backoff on connection errors, timeouts and transient statuses, as
+/// many more tries as retries in [http]. Requests with a body that can't be cloned, e.g. a file stream, are sent once
+pub async fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    if let Some(hosts) = HOST_HEADERS.get() {
+        add_host_headers(hosts, &mut request);
+    }
+    let attempts = ATTEMPTS.get().copied().unwrap_or(get_attempts(None));
+    let mut attempt = 1;
+    loop {
+        let Some(retry) = request.try_clone().filter(|_| attempt < attempts) else {
+            return client.execute(request).await;
+        };
+        let reason = match client.execute(retry).await {
+            Ok(res) if is_transient(res.status()) => format!("{} answered {}", res.url(), res.status()),
+            Err(e) if e.is_connect() || e.is_timeout() || is_io(&e) => e.to_string(),
+            res => return res,
+        };
+        let backoff = get_backoff(attempt, get_jitter());
+        warn!("{reason}, trying again in {:.1}s ({attempt}/{attempts})", backoff.as_secs_f64());
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    }
+}
+
+pub async fn get(url: &str) -> reqwest::Result<Response> {
+    send(client().get(url)).await
+}
+
+/// Releases of a GitHub repository, newest first, at most `pages` pages. With the same client as everything else, so
+/// through the proxy as well
+pub async fn get_releases(owner: &str, repo: &str, pages: u32, per_page: u8) -> Result<Vec<octocrab::models::repos::Release>, GgError> {
+    let mut releases = vec!();
+    for page in 1..=pages {
+        let url = format!("{GITHUB_API}/repos/{owner}/{repo}/releases?page={page}&per_page={per_page}");
+        let list: Vec<octocrab::models::repos::Release> = diagnostics::get_json(&url).await?;
+        let last = list.len() < per_page as usize;
+        releases.extend(list);
+        if last {
+            break;
+        }
+    }
+    Ok(releases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_headers() {
+        env::set_var("GG_TEST_MIRROR_TOKEN", "secret");
+        let token = [("X-Mirror-Token".to_string(), "Bearer ${GG_TEST_MIRROR_TOKEN}".to_string())].into();
+        let config = HttpConfig {
+            user_agent: Some("corp-agent/1.0".to_string()),
+            headers: [("Mirror.Corp".to_string(), token)].into(),
+            ..Default::default()
+        };
+        let headers = get_headers(&config).unwrap();
+        assert_eq!("corp-agent/1.0", headers[USER_AGENT]);
+        assert!(!headers.contains_key("x-mirror-token"));
+
+        let hosts = get_host_headers(&config).unwrap();
+        let mut request = Request::new(reqwest::Method::GET, "https://mirror.corp/node/index.json".parse().unwrap());
+        add_host_headers(&hosts, &mut request);
+        assert_eq!("Bearer secret", request.headers()["x-mirror-token"]);
+        let mut request = Request::new(reqwest::Method::GET, "https://nodejs.org/dist/index.json".parse().unwrap());
+        add_host_headers(&hosts, &mut request);
+        assert!(!request.headers().contains_key("x-mirror-token"));
+
+        let headers = get_headers(&HttpConfig::default()).unwrap();
+        assert_eq!(DEFAULT_USER_AGENT, headers[USER_AGENT]);
+
+        let bad = [("Bad Header".to_string(), "x".to_string())].into();
+        let config = HttpConfig { headers: [("mirror.corp".to_string(), bad)].into(), ..Default::default() };
+        assert!(get_host_headers(&config).is_err());
+    }
+
+    #[test]
+    fn test_retries() {
+        assert!(is_transient(StatusCode::BAD_GATEWAY) && is_transient(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient(StatusCode::NOT_FOUND) && !is_transient(StatusCode::OK));
+        assert_eq!(Duration::from_millis(500), get_backoff(1, 0.0));
+        assert_eq!(Duration::from_millis(3000), get_backoff(3, 1.0));
+        assert_eq!(Duration::from_secs(15), get_backoff(20, 1.0));
+        assert_eq!(3, get_attempts(None));
+        assert_eq!(1, get_attempts(Some(0)));
+        let invalid = reqwest::Client::new().get("not a url").build().unwrap_err();
+        assert!(!is_io(&invalid));
+    }
+
+    #[test]
+    fn test_get_proxy() {
+        assert!(get_proxy(&HttpConfig::default()).unwrap().is_none());
+        let config = HttpConfig { proxy: Some("http://proxy.corp:3128".to_string()), no_proxy: Some("localhost, .corp".to_string()), ..Default::default() };
+        assert!(get_proxy(&config).unwrap().is_some());
+        let config = HttpConfig { proxy: Some("not a url".to_string()), ..Default::default() };
+        assert!(get_proxy(&config).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::config::GgConfig;
+
+const DEFAULT_TTL_SECS: u64 = 3600;
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+struct Settings {
+    dir: Option<PathBuf>,
+    ttl: Duration,
+}
+
+/// A version list as it came from upstream, with what is needed to ask whether it changed
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Entry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+/// [cache] index-ttl in gg.toml, used for every version list from here on
+pub fn init(config: &GgConfig) {
+    let _ = SETTINGS.set(Settings {
+        dir: config.get_index_dir(),
+        ttl: Duration::from_secs(config.cache.index_ttl.unwrap_or(DEFAULT_TTL_SECS)),
+    });
+}
+
+fn get_dir() -> Option<&'static PathBuf> {
+    SETTINGS.get()?.dir.as_ref()
+}
+
+fn get_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(format!("{}.json", sha256::digest(url)))
+}
+
+fn read(dir: &Path, url: &str) -> Option<(Entry, Duration)> {
+    let path = get_path(dir, url);
+    let age = fs::metadata(&path).ok()?.modified().ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default();
+    let entry: Entry = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+    Some((entry, age)).filter(|(entry, _)| entry.url == url)
+}
+
+fn write(dir: &Path, entry: &Entry) {
+    let res = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(get_path(dir, &entry.url), serde_json::to_string(entry).unwrap_or_default()));
+    if let Err(e) = res {
+        debug!("Unable to cache {}: {e}", entry.url);
+    }
+}
+
+/// Younger than the TTL, so upstream is not asked at all
+pub fn get_fresh(url: &str) -> Option<String> {
+    let (entry, age) = read(get_dir()?, url)?;
+    if age < SETTINGS.get()?.ttl {
+        debug!("Using cached {url}, {}s old", age.as_secs());
+        Some(entry.body)
+    } else {
+        None
+    }
+}
+
+/// Any age, for revalidation with ETag / Last-Modified, or when upstream is down
+pub fn get(url: &str) -> Option<Entry> {
+    read(get_dir()?, url).map(|(entry, _)| entry)
+}
+
+pub fn set(entry: &Entry) {
+    if let Some(dir) = get_dir() {
+        write(dir, entry);
+    }
+}
+
+/// 304 Not Modified, the cached list is fresh again
+pub fn touch(url: &str) {
+    let Some(dir) = get_dir() else { return };
+    let res = fs::File::options().write(true).open(get_path(dir, url))
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(e) = res {
+        debug!("Unable to touch cached {url}: {e}");
+    }
+}
+
+/// When the cached body turned out to be unusable
+pub fn remove(url: &str) {
+    if let Some(dir) = get_dir() {
+        let _ = fs::remove_file(get_path(dir, url));
+    }
+}
+
+/// Forget every cached version list, returns how many were removed
+pub fn clear() -> usize {
+    let Some(dir) = get_dir() else { return 0 };
+    fs::read_dir(dir).into_iter().flatten().flatten()
+        .filter(|entry| fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_write() {
+        let dir = std::env::temp_dir().join(format!("gg-index-test-{}", std::process::id()));
+        let entry = Entry {
+            url: "https://nodejs.org/download/release/index.json".to_string(),
+            etag: Some("\"65c8a3f1-2f1a4\"".to_string()),
+            last_modified: None,
+            body: "[]".to_string(),
+        };
+        assert!(read(&dir, &entry.url).is_none());
+        write(&dir, &entry);
+        let (cached, age) = read(&dir, &entry.url).unwrap();
+        assert_eq!(entry, cached);
+        assert!(age < Duration::from_secs(60));
+        assert!(read(&dir, "https://go.dev/dl/?mode=json").is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
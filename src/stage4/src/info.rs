@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use crate::barus::create_barus;
+use crate::bloody_indiana_jones::Checksum;
+use crate::diagnostics;
+use crate::executor::{AppInput, Executor, ExecutorCmd, find_bin, get_download_urls, get_install_path, get_install_suffix, get_locked_version_req, get_url_matches, GgVersionReq, intersect_version_reqs, prep, read_meta, resolve_version_sources};
+use crate::installed;
+use crate::no_clap::NoClap;
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Source {
+    source: String,
+    version_req: String,
+}
+
+/// What `<tool>` would run and why, without installing it
+#[derive(Serialize, Debug, PartialEq)]
+struct Info {
+    name: String,
+    version: Option<String>,
+    /// All sources together, or the locked version
+    version_req: Option<String>,
+    sources: Vec<Source>,
+    locked: Option<String>,
+    url: Option<String>,
+    sha256: Option<String>,
+    /// Of another algorithm, for tools without a SHA-256
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<Checksum>,
+    install_dir: PathBuf,
+    installed: bool,
+}
+
+#[derive(Serialize)]
+struct Which {
+    name: String,
+    version: Option<String>,
+    path: PathBuf,
+}
+
+/// `node@18`, with [aliases] and [versions] from gg.toml as when running
+fn get_executor(input: &AppInput, tool: Option<&String>) -> Option<Box<dyn Executor>> {
+    let no_clap = NoClap::parse(vec!(tool?.clone()));
+    let no_clap = no_clap.with_aliases(&input.config.aliases, &input.config.versions).unwrap_or(no_clap);
+    let cmd = no_clap.cmds.into_iter().next()?;
+    <dyn Executor>::new(ExecutorCmd {
+        cmd: cmd.cmd,
+        version: GgVersionReq::new(cmd.version.unwrap_or_default().as_str()),
+        include_tags: cmd.include_tags,
+        exclude_tags: cmd.exclude_tags,
+    })
+}
+
+async fn get_info(executor: &dyn Executor, input: &AppInput) -> Result<Info, String> {
+    let name = executor.get_name();
+    let sources = resolve_version_sources(executor, input);
+    let version_req = intersect_version_reqs(&sources);
+    let locked_version_req = get_locked_version_req(executor, input, &version_req);
+    let locked = locked_version_req.as_ref().and(input.lock.tools.get(name)).map(|l| l.version.clone());
+    let version_req = locked_version_req.or(version_req);
+    let installed_dir = installed::find_version(&input.config, name, &get_install_suffix(executor, input), &version_req)
+        .map(|dir| input.config.get_install_dir().join(name).join(dir));
+    let meta = installed_dir.and_then(|dir| read_meta(&dir));
+    let installed = meta.is_some();
+    let download = match meta {
+        Some(meta) => Some(meta.download),
+        None => {
+            let urls = get_download_urls(executor, input).await.map_err(|e| e.to_diagnostic().to_string())?;
+            get_url_matches(&urls, input, executor, &version_req).into_iter().next()
+        }
+    };
+    let install_dir = input.config.get_install_dir().join(get_install_path(executor, input, download.as_ref().and_then(|d| d.version.as_ref()), &version_req));
+    Ok(Info {
+        name: name.to_string(),
+        version: download.as_ref().and_then(|d| d.version.as_ref()).map(|v| v.to_string()),
+        version_req: version_req.map(|v| v.to_string()),
+        sources: sources.into_iter().map(|s| Source { source: s.source, version_req: s.version_req.to_string() }).collect(),
+        locked,
+        url: download.as_ref().map(|d| d.download_url.clone()),
+        sha256: download.as_ref().and_then(|d| d.sha256.clone()),
+        checksum: download.and_then(|d| d.checksum),
+        install_dir,
+        installed,
+    })
+}
+
+fn get_lines(info: &Info) -> Vec<String> {
+    let sources = if info.sources.is_empty() {
+        "none, the newest version".to_string()
+    } else {
+        info.sources.iter().map(|s| format!("{} from {}", s.version_req, s.source)).collect::<Vec<_>>().join(", ")
+    };
+    let mut lines = vec!(
+        format!("{} {}", info.name, info.version.clone().unwrap_or("(no matching version)".to_string())),
+        format!("  Requirement: {sources}"),
+    );
+    if let Some(locked) = &info.locked {
+        lines.push(format!("  Locked:      {locked} in gg.lock"));
+    }
+    if let Some(url) = &info.url {
+        lines.push(format!("  Download:    {url}"));
+    }
+    if let Some(sha256) = &info.sha256 {
+        lines.push(format!("  SHA-256:     {sha256}"));
+    } else if let Some(checksum) = &info.checksum {
+        lines.push(format!("  {:<13}{}", format!("{}:", checksum.algorithm.name()), checksum.hash));
+    }
+    let installed = if info.installed { "installed" } else { "not installed" };
+    lines.push(format!("  Install dir: {} ({installed})", info.install_dir.display()));
+    lines
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+/// `info node`: the version gg would pick, where the requirement comes from, the download and the install dir
+pub async fn info(input: &AppInput, args: &[String]) -> ExitCode {
+    let Some(executor) = get_executor(input, args.first()) else {
+        println!("Usage: info <tool>[@<version>]");
+        return ExitCode::from(1);
+    };
+    let input = &input.for_executor(executor.get_name());
+    match get_info(&*executor, input).await {
+        Ok(info) if input.no_clap.json => print_json(&info),
+        Ok(info) => get_lines(&info).iter().for_each(|line| println!("{line}")),
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(1);
+        }
+    }
+    ExitCode::from(0)
+}
+
+/// `which node`: the binary that would run, installed first when needed
+pub async fn which(input: &AppInput, args: &[String]) -> ExitCode {
+    let Some(executor) = get_executor(input, args.first()) else {
+        println!("Usage: which <tool>[@<version>]");
+        return ExitCode::from(1);
+    };
+    let pb = create_barus();
+    let res = prep(&*executor, input, &pb).await;
+    pb.finish_and_clear();
+    let app_path = match res {
+        Ok(app_path) => app_path,
+        Err(e) => {
+            eprintln!("{}", diagnostics::render_error(&e));
+            return ExitCode::from(1);
+        }
+    };
+    let Some(path) = find_bin(&*executor, &input.for_executor(executor.get_name()), &app_path) else {
+        eprintln!("Binary not found in {}", app_path.install_dir.display());
+        return ExitCode::from(1);
+    };
+    let path = std::path::absolute(&path).unwrap_or(path);
+    if input.no_clap.json {
+        let version = read_meta(&app_path.install_dir).and_then(|meta| meta.download.version).map(|v| v.to_string());
+        print_json(&Which { name: executor.get_name().to_string(), version, path });
+    } else {
+        println!("{}", path.display());
+    }
+    ExitCode::from(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_lines() {
+        let mut info = Info {
+            name: "node".to_string(),
+            version: Some("18.19.1".to_string()),
+            version_req: Some("=18.19.1".to_string()),
+            sources: vec!(Source { source: ".nvmrc".to_string(), version_req: "^18".to_string() }),
+            locked: Some("18.19.1".to_string()),
+            url: Some("https://nodejs.org/dist/v18.19.1/node-v18.19.1-linux-x64.tar.xz".to_string()),
+            sha256: None,
+            checksum: None,
+            install_dir: PathBuf::from(".cache/gg/node/node=18.19.1"),
+            installed: true,
+        };
+        assert_eq!(vec!(
+            "node 18.19.1",
+            "  Requirement: ^18 from .nvmrc",
+            "  Locked:      18.19.1 in gg.lock",
+            "  Download:    https://nodejs.org/dist/v18.19.1/node-v18.19.1-linux-x64.tar.xz",
+            "  Install dir: .cache/gg/node/node=18.19.1 (installed)",
+        ), get_lines(&info));
+        info.sources.clear();
+        assert_eq!("  Requirement: none, the newest version", get_lines(&info)[1]);
+        info.checksum = Some(Checksum { algorithm: crate::bloody_indiana_jones::HashAlgorithm::Sha512, hash: "abc".to_string() });
+        assert_eq!("  SHA-512:     abc", get_lines(&info)[4]);
+    }
+}
@@ -0,0 +1,174 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use regex::Regex;
+
+use crate::config;
+use crate::config::GgConfig;
+use crate::executor::AppInput;
+use crate::upgrade;
+
+pub struct Detected {
+    pub tool: String,
+    pub version: String,
+    pub source: String,
+}
+
+fn detected(tool: &str, version: &str, source: &str) -> Detected {
+    Detected { tool: tool.to_string(), version: version.trim().to_string(), source: source.to_string() }
+}
+
+/// Exact versions become ~ ranges, so upgrade does not move them to another minor version
+fn to_range(version: &str) -> String {
+    if version.split('.').count() == 3 && version.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        format!("~{version}")
+    } else {
+        version.to_string()
+    }
+}
+
+fn detect_package_json(text: &str) -> Vec<Detected> {
+    let json: serde_json::Value = serde_json::from_str(text).unwrap_or_default();
+    json["engines"]["node"].as_str()
+        .map(|node| detected("node", node, "package.json engines"))
+        .into_iter().collect()
+}
+
+fn detect_pom(text: &str) -> Vec<Detected> {
+    let java = Regex::new(r"<(?:maven\.compiler\.release|maven\.compiler\.source|java\.version)>\s*(?:1\.)?(\d+)").unwrap();
+    let mut result = vec!(detected("maven", "^3", "pom.xml"));
+    if let Some(c) = java.captures(text) {
+        result.push(detected("java", &c[1], "pom.xml"));
+    }
+    result
+}
+
+fn detect_go_mod(text: &str) -> Vec<Detected> {
+    let go = Regex::new(r"(?m)^go\s+(\d+\.\d+)").unwrap();
+    go.captures(text).map(|c| detected("go", &c[1], "go.mod")).into_iter().collect()
+}
+
+fn detect_gradle_wrapper(text: &str) -> Vec<Detected> {
+    let gradle = Regex::new(r"gradle-([\d.]+)-(?:bin|all)\.zip").unwrap();
+    gradle.captures(text).map(|c| detected("gradle", &c[1], "gradle-wrapper.properties")).into_iter().collect()
+}
+
+/// Images like node:18-alpine or eclipse-temurin:17-jdk, the tag up to the first dash is the version
+fn detect_dockerfile(text: &str) -> Vec<Detected> {
+    let from = Regex::new(r"(?im)^FROM\s+(?:--platform=\S+\s+)?(\S+?):(\d+(?:\.\d+)*)").unwrap();
+    from.captures_iter(text).filter_map(|c| {
+        let image = c[1].rsplit('/').next().unwrap_or_default().to_lowercase();
+        let tool = match image.as_str() {
+            "node" => "node",
+            "eclipse-temurin" | "openjdk" | "amazoncorretto" | "zulu-openjdk" => "java",
+            "maven" => "maven",
+            "gradle" => "gradle",
+            "golang" => "go",
+            _ => return None,
+        };
+        Some(detected(tool, &c[2], "Dockerfile"))
+    }).collect()
+}
+
+type Detector = fn(&str) -> Vec<Detected>;
+
+/// First found wins, so project files go before the Dockerfile
+fn detect(read: impl Fn(&str) -> Option<String>) -> Vec<Detected> {
+    let detectors: [(&str, Detector); 5] = [
+        ("package.json", detect_package_json),
+        ("pom.xml", detect_pom),
+        ("go.mod", detect_go_mod),
+        ("gradle/wrapper/gradle-wrapper.properties", detect_gradle_wrapper),
+        ("Dockerfile", detect_dockerfile),
+    ];
+    let mut result: Vec<Detected> = vec!();
+    for (file, detector) in detectors {
+        for d in read(file).map(|text| detector(&text)).unwrap_or_default() {
+            if !result.iter().any(|r| r.tool == d.tool) {
+                result.push(d);
+            }
+        }
+    }
+    if !result.iter().any(|r| r.tool == "node") {
+        if let Some(nvmrc) = read(".nvmrc") {
+            result.push(detected("node", nvmrc.trim().trim_start_matches('v'), ".nvmrc"));
+        }
+    }
+    result
+}
+
+fn confirm(question: &str) -> bool {
+    print!("{question} [y/N] ");
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    let _ = io::stdin().lock().read_line(&mut answer);
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Writes [tools] in gg.toml from what is found in the project, then gg.lock with upgrade
+pub async fn init(input: &AppInput) -> ExitCode {
+    if Path::new("gg.toml").exists() {
+        println!("gg.toml already exists");
+        return ExitCode::from(1);
+    }
+    let detected = detect(|file| fs::read_to_string(file).ok());
+    if detected.is_empty() {
+        println!("No tools detected. Looked for package.json, .nvmrc, pom.xml, go.mod, gradle wrapper and Dockerfile");
+        return ExitCode::from(1);
+    }
+    println!("[tools]");
+    for d in &detected {
+        println!("{} = \"{}\" # {}", d.tool, to_range(&d.version), d.source);
+    }
+    let yes = input.no_clap.app_args.iter().any(|a| a == "-y" || a == "--yes");
+    if !yes && !confirm("Write gg.toml and gg.lock?") {
+        return ExitCode::from(1);
+    }
+    for d in &detected {
+        if let Err(e) = config::set_tool_version(&d.tool, &to_range(&d.version)) {
+            println!("Unable to write gg.toml: {e}");
+            return ExitCode::from(1);
+        }
+    }
+    let config = match GgConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    let input = &AppInput { config, ..input.clone() };
+    upgrade::upgrade(input, None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let files = |file: &str| match file {
+            "package.json" => Some(r#"{"name": "app", "engines": {"node": ">=18"}}"#.to_string()),
+            "pom.xml" => Some("<properties><maven.compiler.source>1.8</maven.compiler.source></properties>".to_string()),
+            "Dockerfile" => Some("FROM node:20-alpine AS build\nFROM --platform=linux/amd64 golang:1.21.3\n".to_string()),
+            _ => None,
+        };
+        let detected: Vec<(String, String)> = detect(files).into_iter().map(|d| (d.tool, d.version)).collect();
+        assert_eq!(vec!(
+            ("node".to_string(), ">=18".to_string()),
+            ("maven".to_string(), "^3".to_string()),
+            ("java".to_string(), "8".to_string()),
+            ("go".to_string(), "1.21.3".to_string()),
+        ), detected);
+    }
+
+    #[test]
+    fn test_detect_go_mod_and_gradle() {
+        assert_eq!("1.21", detect_go_mod("module example.com/app\n\ngo 1.21\n")[0].version);
+        assert_eq!("8.4", detect_gradle_wrapper("distributionUrl=https\\://services.gradle.org/distributions/gradle-8.4-bin.zip")[0].version);
+        assert_eq!("~1.21.3", to_range("1.21.3"));
+        assert_eq!(">=18", to_range(">=18"));
+    }
+}
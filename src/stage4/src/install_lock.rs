@@ -0,0 +1,67 @@
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use indicatif::ProgressBar;
+use log::{debug, info};
+
+const POLL: Duration = Duration::from_millis(200);
+
+fn get_path(install_dir: &Path) -> PathBuf {
+    let mut path = install_dir.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Advisory lock next to the install, e.g. .cache/gg/node/20.11.1.lock, held while it is downloaded and unpacked.
+/// Released when the file is dropped, or when the process dies. None when the file system can't lock, then installs
+/// are as before
+pub async fn acquire(install_dir: &Path, pb: &ProgressBar) -> Option<File> {
+    let path = get_path(install_dir);
+    let file = path.parent().map(fs::create_dir_all).transpose()
+        .and_then(|_| File::options().create(true).truncate(false).write(true).open(&path));
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => {
+            debug!("Unable to create {}, installing without lock: {e}", path.display());
+            return None;
+        }
+    };
+    let mut waiting = false;
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Some(file),
+            Err(TryLockError::WouldBlock) => {
+                if !waiting {
+                    info!("{} is locked, waiting for the other install", path.display());
+                    pb.set_message("Waiting for another gg installing it");
+                    waiting = true;
+                }
+                tokio::time::sleep(POLL).await;
+            }
+            Err(TryLockError::Error(e)) => {
+                debug!("Unable to lock {}, installing without lock: {e}", path.display());
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire() {
+        let dir = std::env::temp_dir().join(format!("gg-install-lock-{}", std::process::id()));
+        let install_dir = dir.join("node").join("node^18.19");
+        let pb = ProgressBar::hidden();
+        let lock = acquire(&install_dir, &pb).await.unwrap();
+        assert!(dir.join("node").join("node^18.19.lock").exists());
+        let other = File::options().write(true).open(get_path(&install_dir)).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,402 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
+
+use indicatif::HumanBytes;
+use log::debug;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+
+use crate::config::GgConfig;
+use crate::executor::{GgVersion, is_complete, read_meta};
+use crate::no_clap::NoClap;
+
+const META: &str = "gg-meta.json";
+const DAY: u64 = 24 * 60 * 60;
+
+/// Tool name and optionally a version range
+type ToolFilter = Option<(String, Option<VersionReq>)>;
+
+/// With --json
+#[derive(Serialize)]
+struct InstalledJson {
+    name: String,
+    version: String,
+    path: PathBuf,
+    size: u64,
+    /// Seconds since the Unix epoch
+    last_used: u64,
+}
+
+struct Installed {
+    name: String,
+    version: String,
+    dir: PathBuf,
+    last_used: SystemTime,
+}
+
+/// The modification time of gg-meta.json is when the install was last used. It is left out of gg.lock digests, so
+/// touching it changes nothing else
+pub fn mark_used(install_dir: &Path) {
+    let res = fs::File::options().write(true).open(install_dir.join(META))
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(e) = res {
+        debug!("Unable to mark {} as used: {e}", install_dir.display());
+    }
+}
+
+/// `<install dir>/<tool>/<install>/gg-meta.json`
+fn find(config: &GgConfig) -> Vec<Installed> {
+    let mut installed: Vec<Installed> = walkdir::WalkDir::new(config.get_install_dir()).min_depth(3).max_depth(3).into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_name() == META)
+        .filter_map(|entry| {
+            let dir = entry.path().parent()?.to_path_buf();
+            let meta = read_meta(&dir)?;
+            Some(Installed {
+                name: dir.parent()?.file_name()?.to_string_lossy().to_string(),
+                version: meta.download.version.map(|v| v.to_string()).unwrap_or_default(),
+                last_used: entry.metadata().ok()?.modified().ok()?,
+                dir,
+            })
+        })
+        .collect();
+    installed.sort_by_key(|i| (i.name.clone(), Reverse(Version::parse(&i.version).ok())));
+    installed
+}
+
+/// The newest complete install in `<install dir>/<tool>/<version><suffix>` the requirement matches, e.g. 20.11.1 for
+/// node@20. Installs with other tags or flavor have another suffix
+pub fn find_version(config: &GgConfig, name: &str, suffix: &str, version_req: &Option<VersionReq>) -> Option<String> {
+    let mut versions: Vec<(Version, String)> = fs::read_dir(config.get_install_dir().join(name)).ok()?.flatten()
+        .filter_map(|entry| {
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let version = dir_name.strip_suffix(suffix).filter(|v| !v.contains('_'))?;
+            let version = GgVersion::new(version)?.to_version();
+            let matches = version_req.as_ref().map(|v| v.matches(&version)).unwrap_or(true);
+            (matches && is_complete(&entry.path())).then_some((version, dir_name))
+        })
+        .collect();
+    versions.sort();
+    versions.pop().map(|(_, dir_name)| dir_name)
+}
+
+/// Installed versions of a tool, newest first
+pub fn get_versions(config: &GgConfig, name: &str) -> Vec<String> {
+    let mut versions: Vec<String> = find(config).into_iter().filter(|i| i.name == name).map(|i| i.version).collect();
+    versions.sort_by_key(|v| (Reverse(Version::parse(v).ok()), v.clone()));
+    versions.dedup();
+    versions
+}
+
+pub fn get_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir).into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().is_file())
+        .filter_map(|x| x.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn get_age(age: Duration) -> String {
+    match age.as_secs() / DAY {
+        0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{days} days ago"),
+    }
+}
+
+fn get_age_since(time: SystemTime) -> Duration {
+    SystemTime::now().duration_since(time).unwrap_or_default()
+}
+
+/// Installed versions with size and when they were last used
+pub fn list(config: &GgConfig, json: bool) -> ExitCode {
+    let installed = find(config);
+    if json {
+        let installed: Vec<InstalledJson> = installed.iter().map(|install| InstalledJson {
+            name: install.name.clone(),
+            version: install.version.clone(),
+            path: install.dir.clone(),
+            size: get_size(&install.dir),
+            last_used: install.last_used.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+        }).collect();
+        println!("{}", serde_json::to_string_pretty(&installed).unwrap_or_default());
+        return ExitCode::from(0);
+    }
+    if installed.is_empty() {
+        println!("Nothing installed in {}", config.get_install_dir().display());
+        return ExitCode::from(0);
+    }
+    let mut total = 0;
+    for install in &installed {
+        let size = get_size(&install.dir);
+        total += size;
+        println!("{:10} {:12} {:>10}  {:14} {}", install.name, install.version, HumanBytes(size).to_string(),
+                 get_age(get_age_since(install.last_used)), install.dir.display());
+    }
+    println!("{} installs, {}", installed.len(), HumanBytes(total));
+    ExitCode::from(0)
+}
+
+/// `<tool>[@<range>]`, and / or `--older-than <days>` for installs not used in that many days
+fn parse_args(args: &[String]) -> Result<(ToolFilter, Option<u64>), String> {
+    let mut tool = None;
+    let mut older_than = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--older-than" {
+            let days = args.next().ok_or("Missing number of days after --older-than")?;
+            older_than = Some(days.trim_end_matches('d').parse::<u64>().map_err(|_| format!("Invalid number of days {days}"))?);
+        } else {
+            let cmd = NoClap::parse(vec!(arg.clone())).cmds.into_iter().next().ok_or(format!("Invalid tool {arg}"))?;
+            let version_req = match &cmd.version {
+                Some(version) => Some(VersionReq::parse(version).map_err(|e| format!("Invalid version {version}: {e}"))?),
+                None => None,
+            };
+            tool = Some((cmd.cmd, version_req));
+        }
+    }
+    if tool.is_none() && older_than.is_none() {
+        return Err("Usage: uninstall <tool>[@<version>] [--older-than <days>]".to_string());
+    }
+    Ok((tool, older_than))
+}
+
+fn matches(install: &Installed, tool: &ToolFilter, older_than: Option<u64>) -> bool {
+    let tool_matches = match tool {
+        Some((name, version_req)) => &install.name == name && match version_req {
+            Some(version_req) => Version::parse(&install.version).map(|v| version_req.matches(&v)).unwrap_or(false),
+            None => true,
+        },
+        None => true,
+    };
+    let age_matches = match older_than {
+        Some(days) => get_age_since(install.last_used) > Duration::from_secs(days * DAY),
+        None => true,
+    };
+    tool_matches && age_matches
+}
+
+/// Removes installed versions of a tool, or everything not used for a while
+pub fn uninstall(config: &GgConfig, args: &[String]) -> ExitCode {
+    let (tool, older_than) = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    let installs: Vec<Installed> = find(config).into_iter().filter(|i| matches(i, &tool, older_than)).collect();
+    if installs.is_empty() {
+        println!("No installs in {} match {}", config.get_install_dir().display(), args.join(" "));
+        return ExitCode::from(1);
+    }
+    remove(installs.iter().map(|i| (i, get_size(&i.dir))).collect())
+}
+
+/// Fails when any of them could not be removed
+fn remove(installs: Vec<(&Installed, u64)>) -> ExitCode {
+    let mut removed = 0;
+    let mut freed = 0;
+    let mut failed = 0;
+    for (install, size) in installs {
+        match fs::remove_dir_all(&install.dir) {
+            Ok(_) => {
+                println!("Removed {} {} ({})", install.name, install.version, HumanBytes(size));
+                removed += 1;
+                freed += size;
+            }
+            Err(e) => {
+                println!("Unable to remove {}: {e}", install.dir.display());
+                failed += 1;
+            }
+        }
+    }
+    println!("Removed {removed} installs, freed {}", HumanBytes(freed));
+    if failed > 0 {
+        println!("{failed} installs could not be removed");
+        return ExitCode::from(1);
+    }
+    ExitCode::from(0)
+}
+
+/// `clean` policies, any combination of them
+#[derive(Debug, Default, PartialEq)]
+struct CleanPolicy {
+    /// Days since last use
+    older_than: Option<u64>,
+    /// Newest versions to keep per tool
+    keep: Option<usize>,
+    /// Bytes, the least recently used are removed until the rest fits
+    max_size: Option<u64>,
+    dry_run: bool,
+}
+
+/// 500M, 10G, 1.5GB or bytes
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.to_uppercase();
+    let number = size.trim_end_matches('B');
+    let (number, unit) = match number.chars().last()? {
+        'K' => (&number[..number.len() - 1], 1 << 10),
+        'M' => (&number[..number.len() - 1], 1 << 20),
+        'G' => (&number[..number.len() - 1], 1 << 30),
+        'T' => (&number[..number.len() - 1], 1u64 << 40),
+        _ => (number, 1),
+    };
+    number.parse::<f64>().ok().filter(|n| *n >= 0.0).map(|n| (n * unit as f64) as u64)
+}
+
+fn parse_clean_args(args: &[String]) -> Result<CleanPolicy, String> {
+    let mut policy = CleanPolicy::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().cloned().ok_or(format!("Missing value after {name}"));
+        match arg.as_str() {
+            "--older-than" => {
+                let days = value(arg)?;
+                policy.older_than = Some(days.trim_end_matches('d').parse().map_err(|_| format!("Invalid number of days {days}"))?);
+            }
+            "--keep" => {
+                let keep = value(arg)?;
+                policy.keep = Some(keep.parse().map_err(|_| format!("Invalid number of versions {keep}"))?);
+            }
+            "--max-size" => {
+                let size = value(arg)?;
+                policy.max_size = Some(parse_size(&size).ok_or(format!("Invalid size {size}, e.g. 500M or 10G"))?);
+            }
+            "--dry-run" => policy.dry_run = true,
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+    if policy.older_than.is_none() && policy.keep.is_none() && policy.max_size.is_none() {
+        return Err("Usage: clean [--older-than <days>] [--keep <versions>] [--max-size <size>] [--dry-run]".to_string());
+    }
+    Ok(policy)
+}
+
+/// Indexes of the installs to remove. Installs are sorted by name and newest version first, as from find
+fn select(installs: &[(&Installed, u64)], policy: &CleanPolicy, now: SystemTime) -> Vec<usize> {
+    let mut remove: Vec<bool> = installs.iter().enumerate().map(|(i, (install, _))| {
+        let old = policy.older_than
+            .map(|days| now.duration_since(install.last_used).unwrap_or_default() > Duration::from_secs(days * DAY))
+            .unwrap_or(false);
+        let newer = installs[..i].iter().filter(|(other, _)| other.name == install.name).count();
+        old || policy.keep.map(|keep| newer >= keep).unwrap_or(false)
+    }).collect();
+    if let Some(max_size) = policy.max_size {
+        let mut size: u64 = installs.iter().zip(&remove).filter(|(_, r)| !**r).map(|((_, size), _)| size).sum();
+        let mut by_last_used: Vec<usize> = (0..installs.len()).filter(|i| !remove[*i]).collect();
+        by_last_used.sort_by_key(|i| installs[*i].0.last_used);
+        for i in by_last_used {
+            if size <= max_size {
+                break;
+            }
+            remove[i] = true;
+            size -= installs[i].1;
+        }
+    }
+    (0..installs.len()).filter(|i| remove[*i]).collect()
+}
+
+/// Removes installs by the policies, or with --dry-run only lists what would be removed
+pub fn clean(config: &GgConfig, args: &[String]) -> ExitCode {
+    let policy = match parse_clean_args(args) {
+        Ok(policy) => policy,
+        Err(e) => {
+            println!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    let installed = find(config);
+    let installs: Vec<(&Installed, u64)> = installed.iter().map(|i| (i, get_size(&i.dir))).collect();
+    let selected: Vec<(&Installed, u64)> = select(&installs, &policy, SystemTime::now()).into_iter().map(|i| installs[i]).collect();
+    if !policy.dry_run {
+        return remove(selected);
+    }
+    for (install, size) in &selected {
+        println!("Would remove {} {} ({}, used {})", install.name, install.version, HumanBytes(*size), get_age(get_age_since(install.last_used)));
+    }
+    println!("Would remove {} installs, freeing {}", selected.len(), HumanBytes(selected.iter().map(|(_, size)| size).sum()));
+    ExitCode::from(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let install = Installed {
+            name: "node".to_string(),
+            version: "18.19.0".to_string(),
+            dir: PathBuf::new(),
+            last_used: SystemTime::now() - Duration::from_secs(10 * DAY),
+        };
+        let (tool, older_than) = parse_args(&["node@18".to_string()]).unwrap();
+        assert!(matches(&install, &tool, older_than));
+        let (tool, older_than) = parse_args(&["node@20".to_string()]).unwrap();
+        assert!(!matches(&install, &tool, older_than));
+        let (tool, older_than) = parse_args(&["--older-than".to_string(), "30".to_string()]).unwrap();
+        assert!(!matches(&install, &tool, older_than));
+        let (tool, older_than) = parse_args(&["--older-than".to_string(), "7d".to_string()]).unwrap();
+        assert!(matches(&install, &tool, older_than));
+        assert!(parse_args(&[]).is_err());
+        assert_eq!("today", get_age(Duration::from_secs(60)));
+        assert_eq!("3 days ago", get_age(Duration::from_secs(3 * DAY + 5)));
+    }
+
+    #[test]
+    fn test_remove_fails() {
+        let install = Installed {
+            name: "node".to_string(),
+            version: "18.19.0".to_string(),
+            dir: std::env::temp_dir().join(format!("gg-remove-missing-{}", std::process::id())),
+            last_used: SystemTime::now(),
+        };
+        assert_eq!(ExitCode::from(1), remove(vec!((&install, 0))));
+        assert_eq!(ExitCode::from(0), remove(vec!()));
+    }
+
+    #[test]
+    fn test_find_version() {
+        let dir = std::env::temp_dir().join(format!("gg-find-version-{}", std::process::id()));
+        let mut config = GgConfig::default();
+        config.cache.install_dir = Some(dir.to_string_lossy().to_string());
+        for install in ["18.19.0", "20.10.0", "20.11.1_temurin", "20.12.0.tmp", "21.0.0", "node_hat_20"] {
+            fs::create_dir_all(dir.join("node").join(install)).unwrap();
+            fs::write(dir.join("node").join(install).join(crate::bloody_indiana_jones::COMPLETE_MARKER), "").unwrap();
+        }
+        fs::create_dir_all(dir.join("node").join("20.11.0")).unwrap();
+        let req = |r: &str| Some(VersionReq::parse(r).unwrap());
+        assert_eq!(Some("20.10.0".to_string()), find_version(&config, "node", "", &req("^20")));
+        assert_eq!(Some("20.11.1_temurin".to_string()), find_version(&config, "node", "_temurin", &req("^20")));
+        assert_eq!(Some("21.0.0".to_string()), find_version(&config, "node", "", &None));
+        assert_eq!(None, find_version(&config, "node", "", &req("^19")));
+        assert_eq!(None, find_version(&config, "java", "", &None));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_select() {
+        let now = SystemTime::now();
+        let install = |name: &str, version: &str, days: u64| Installed {
+            name: name.to_string(),
+            version: version.to_string(),
+            dir: PathBuf::new(),
+            last_used: now - Duration::from_secs(days * DAY),
+        };
+        let installed = [install("java", "21.0.2", 1), install("node", "20.11.1", 0), install("node", "18.19.0", 40), install("node", "16.20.2", 5)];
+        let installs: Vec<(&Installed, u64)> = installed.iter().zip([300, 40, 40, 40]).collect();
+        let policy = |args: &[&str]| parse_clean_args(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>()).unwrap();
+        assert_eq!(vec!(2), select(&installs, &policy(&["--older-than", "30"]), now));
+        assert_eq!(vec!(2, 3), select(&installs, &policy(&["--keep", "1", "--dry-run"]), now));
+        assert_eq!(vec!(0, 2, 3), select(&installs, &policy(&["--max-size", "100"]), now));
+        assert_eq!(vec!(2, 3), select(&installs, &policy(&["--keep", "2", "--max-size", "350"]), now));
+        assert_eq!(Some(1536 << 20), parse_size("1.5G"));
+        assert_eq!(Some(500 << 20), parse_size("500MB"));
+        assert!(parse_clean_args(&["--dry-run".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::process::ExitCode;
+
+use semver::VersionReq;
+use serde::Serialize;
+
+use crate::bloody_indiana_jones::Checksum;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, get_url_matches};
+use crate::no_clap::NoClap;
+
+/// With --json
+#[derive(Serialize, Debug, PartialEq)]
+struct RemoteVersion {
+    version: String,
+    url: String,
+    lts: bool,
+    date: Option<String>,
+    tags: Vec<String>,
+    sha256: Option<String>,
+    /// Of another algorithm, for tools without a SHA-256
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<Checksum>,
+    /// What gg would pick
+    picked: bool,
+}
+
+/// One download per version, newest first
+fn get_versions(downloads: &[Download]) -> Vec<&Download> {
+    let mut seen = HashSet::new();
+    downloads.iter()
+        .filter(|d| d.version.as_ref().map(|v| seen.insert(v.to_string())).unwrap_or(false))
+        .collect()
+}
+
+fn get_remote_versions(downloads: &[Download]) -> Vec<RemoteVersion> {
+    get_versions(downloads).iter().enumerate().map(|(i, d)| {
+        let mut tags: Vec<String> = d.tags.iter().cloned().collect();
+        tags.sort();
+        RemoteVersion {
+            version: d.version.as_ref().map(|v| v.to_string()).unwrap_or_default(),
+            url: d.download_url.clone(),
+            lts: d.tags.contains("lts"),
+            date: d.date.clone(),
+            tags,
+            sha256: d.sha256.clone(),
+            checksum: d.checksum.clone(),
+            picked: i == 0,
+        }
+    }).collect()
+}
+
+/// One line per version, newest first. The first is what gg would pick
+fn get_lines(downloads: &[Download]) -> Vec<String> {
+    let versions = get_versions(downloads);
+    let width = versions.iter().filter_map(|d| d.version.as_ref()).map(|v| v.to_string().len()).max().unwrap_or(0);
+    versions.iter().enumerate().map(|(i, d)| {
+        let version = d.version.as_ref().map(|v| v.to_string()).unwrap_or_default();
+        let lts = if d.tags.contains("lts") { "lts" } else { "" };
+        let date = d.date.clone().unwrap_or_default();
+        let picked = if i == 0 { "<- picked" } else { "" };
+        format!("{version:width$}  {lts:3}  {date:10}  {picked}").trim_end().to_string()
+    }).collect()
+}
+
+/// `list-remote node@^18`, or `list-remote node ">=18, <21"`. Tags work as when running, e.g. java@21-jdk+jre
+pub async fn list_remote(input: &AppInput, args: &[String]) -> ExitCode {
+    let Some(cmd) = args.first().and_then(|tool| NoClap::parse(vec!(tool.clone())).cmds.into_iter().next()) else {
+        println!("Usage: list-remote <tool>[@<range>] [range]");
+        return ExitCode::from(1);
+    };
+    let range = args.get(1).cloned().or(cmd.version.clone());
+    let version_req = match &range {
+        Some(range) => match VersionReq::parse(range) {
+            Ok(version_req) => Some(version_req),
+            Err(e) => {
+                println!("Invalid version range {range}: {e}");
+                return ExitCode::from(1);
+            }
+        },
+        None => None,
+    };
+    let Some(executor) = <dyn Executor>::new(ExecutorCmd {
+        cmd: cmd.cmd.clone(),
+        version: None,
+        include_tags: cmd.include_tags.clone(),
+        exclude_tags: cmd.exclude_tags.clone(),
+    }) else {
+        println!("{}: Unknown tool", cmd.cmd);
+        return ExitCode::from(1);
+    };
+    let name = executor.get_name();
+    let input = &input.for_executor(name);
+    let urls = match executor.get_download_urls(input).await {
+        Ok(urls) => urls,
+        Err(e) => {
+            eprintln!("{}", e.to_diagnostic());
+            return ExitCode::from(1);
+        }
+    };
+    let matches = get_url_matches(&urls, input, &*executor, &version_req);
+    if input.no_clap.json {
+        println!("{}", serde_json::to_string_pretty(&get_remote_versions(&matches)).unwrap_or_default());
+        return ExitCode::from(if matches.is_empty() { 1 } else { 0 });
+    }
+    let range = version_req.map(|v| format!(" matching {v}")).unwrap_or_default();
+    if matches.is_empty() {
+        println!("No {name} versions for {}{range}", input.target.key());
+        return ExitCode::from(1);
+    }
+    println!("{name} versions for {}{range}:", input.target.key());
+    for line in get_lines(&matches) {
+        println!("  {line}");
+    }
+    ExitCode::from(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_lines() {
+        let mut lts = Download::new("https://example.com/a".to_string(), "20.11.1", None);
+        lts.tags.insert("lts".to_string());
+        lts.date = Some("2024-02-14".to_string());
+        let downloads = vec!(lts.clone(), lts, Download::new("https://example.com/b".to_string(), "9.0.0", None));
+        assert_eq!(vec!(
+            "20.11.1  lts  2024-02-14  <- picked",
+            "9.0.0",
+        ), get_lines(&downloads));
+        let remote = get_remote_versions(&downloads);
+        assert_eq!(2, remote.len());
+        assert_eq!(RemoteVersion {
+            version: "20.11.1".to_string(),
+            url: "https://example.com/a".to_string(),
+            lts: true,
+            date: Some("2024-02-14".to_string()),
+            tags: vec!("lts".to_string()),
+            sha256: None,
+            checksum: None,
+            picked: true,
+        }, remote[0]);
+        assert!(!remote[1].picked);
+    }
+}
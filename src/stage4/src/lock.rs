@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::bloody_indiana_jones::COMPLETE_MARKER;
+use crate::bundle::BUNDLE_DIR;
+
+const LOCK_FILE: &str = "gg.lock";
+
+/// The download resolved for one target
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockedDownload {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockedTool {
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Digest of the installed files per target, see `digest_dir`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub digests: BTreeMap<String, String>,
+    /// Download per target, e.g. linux-x86_64. Used instead of fetching the versions
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, LockedDownload>,
+}
+
+/// Exact versions resolved from ranges in gg.toml, one entry per tool
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct GgLock {
+    #[serde(flatten)]
+    pub tools: BTreeMap<String, LockedTool>,
+}
+
+impl GgLock {
+    pub fn load() -> Self {
+        let bundled = Path::new(BUNDLE_DIR).join(LOCK_FILE);
+        match fs::read_to_string(LOCK_FILE).or_else(|_| fs::read_to_string(bundled)) {
+            Ok(text) => {
+                info!("Found {LOCK_FILE}");
+                toml::from_str(&text).unwrap_or_else(|e| {
+                    warn!("Unable to parse {LOCK_FILE}: {e}");
+                    GgLock::default()
+                })
+            }
+            Err(_) => GgLock::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(LOCK_FILE, format!("# Generated by gg. Do not edit\n{text}")).map_err(|e| e.to_string())
+    }
+}
+
+/// Records digest of a fresh install, if the tool is locked to this version and has no digest for the target yet
+pub fn record_digest(name: &str, version: &str, target_key: &str, install_dir: &Path) {
+    let mut lock = GgLock::load();
+    let locked = match lock.tools.get_mut(name) {
+        Some(locked) if locked.version == version && !locked.digests.contains_key(target_key) => locked,
+        _ => return,
+    };
+    match digest_dir(install_dir) {
+        Ok(digest) => {
+            info!("Recording digest {digest} for {name} {version} ({target_key}) in {LOCK_FILE}");
+            locked.digests.insert(target_key.to_string(), digest);
+            if let Err(e) = lock.save() {
+                warn!("Unable to write {LOCK_FILE}: {e}");
+            }
+        }
+        Err(e) => warn!("Unable to calculate digest of {}: {e}", install_dir.display()),
+    }
+}
+
+/// Records the download of a fresh install, if the tool is locked to this version and has none for the target yet
+pub fn record_download(name: &str, version: &str, target_key: &str, download: LockedDownload) {
+    let mut lock = GgLock::load();
+    let locked = match lock.tools.get_mut(name) {
+        Some(locked) if locked.version == version && !locked.targets.contains_key(target_key) => locked,
+        _ => return,
+    };
+    info!("Recording {} for {name} {version} ({target_key}) in {LOCK_FILE}", download.url);
+    locked.targets.insert(target_key.to_string(), download);
+    if let Err(e) = lock.save() {
+        warn!("Unable to write {LOCK_FILE}: {e}");
+    }
+}
+
+/// SHA-256 over relative path and SHA-256 of every file, sorted by path. gg-meta.json and the marker are not part of
+/// the install
+pub fn digest_dir(dir: &Path) -> Result<String, String> {
+    let mut lines = vec!();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let relative = entry.path().strip_prefix(dir).map_err(|e| e.to_string())?.to_string_lossy().replace('\\', "/");
+        if relative == "gg-meta.json" || relative == COMPLETE_MARKER || entry.file_type().is_dir() {
+            continue;
+        }
+        let hash = if entry.path_is_symlink() {
+            sha256::digest(fs::read_link(entry.path()).map_err(|e| e.to_string())?.to_string_lossy().to_string())
+        } else {
+            sha256::try_digest(entry.path()).map_err(|e| e.to_string())?
+        };
+        debug!("{relative} {hash}");
+        lines.push(format!("{relative}\0{hash}\n"));
+    }
+    Ok(sha256::digest(lines.concat()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut lock = GgLock::default();
+        let mut digests = BTreeMap::new();
+        digests.insert("linux-x86_64".to_string(), "abc".to_string());
+        let mut targets = BTreeMap::new();
+        targets.insert("linux-x86_64".to_string(), LockedDownload { url: "https://nodejs.org/node.tar.gz".to_string(), sha256: Some("def".to_string()) });
+        lock.tools.insert("node".to_string(), LockedTool { version: "18.19.0".to_string(), url: Some("https://nodejs.org/node.tar.gz".to_string()), digests, targets });
+        lock.tools.insert("java".to_string(), LockedTool { version: "17.0.8".to_string(), url: None, digests: BTreeMap::new(), targets: BTreeMap::new() });
+        let text = toml::to_string(&lock).unwrap();
+        assert_eq!(lock, toml::from_str(&text).unwrap());
+        assert!(text.contains("[node]\nversion = \"18.19.0\""));
+        assert!(text.contains("[node.targets.linux-x86_64]"));
+    }
+
+    #[test]
+    fn digest_dir_changes_with_content() {
+        let dir = std::env::temp_dir().join(format!("gg-digest-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(dir.join("bin/tool"), "hello").unwrap();
+        let first = digest_dir(&dir).unwrap();
+        fs::write(dir.join("gg-meta.json"), "{}").unwrap();
+        assert_eq!(first, digest_dir(&dir).unwrap());
+        fs::write(dir.join("bin/tool"), "tampered").unwrap();
+        assert_ne!(first, digest_dir(&dir).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::process::ExitCode;
 
-use futures_util::future::join_all;
+use futures_util::stream::{self, StreamExt};
 use indicatif::MultiProgress;
-use log::{debug, info, LevelFilter};
+use log::{debug, info, LevelFilter, warn};
 
 use crate::barus::create_barus;
-use crate::bloody_indiana_jones::BloodyIndianaJones;
-use crate::executor::{AppInput, Executor, ExecutorCmd, GgVersionReq, prep, try_run};
+use crate::config::GgConfig;
+use crate::lock::GgLock;
+use crate::executor::{AppInput, AppPath, ExecContext, Executor, ExecutorCmd, get_deps_exec_context, get_path, GgVersionReq, only_for_other_targets, prep, read_meta, try_run, with_deps};
 use crate::no_clap::NoClap;
 use crate::target::Target;
 
@@ -16,10 +17,50 @@ mod target;
 mod bloody_indiana_jones;
 mod executor;
 mod no_clap;
+#[cfg(feature = "openapi")]
 mod bloody_maven;
 mod executors;
 mod checker;
 mod barus;
+mod config;
+mod watch;
+mod lock;
+mod upgrade;
+mod eol;
+mod vulns;
+mod verify;
+mod export;
+mod bundle;
+mod self_update;
+mod diagnostics;
+mod error;
+mod remote_cache;
+mod serve;
+mod http;
+mod timings;
+mod otlp;
+mod hooks;
+mod container;
+mod suggest;
+mod download_cache;
+mod tool_versions;
+mod refresh;
+mod list_remote;
+mod installed;
+mod install_lock;
+mod compare;
+mod matrix;
+mod init;
+mod daemon;
+mod tee;
+mod resolve_cache;
+mod mirror;
+mod index_cache;
+mod shell_env;
+mod completions;
+mod info;
+mod signatures;
+mod doctor;
 
 fn print_help(ver: &str) {
     println!(r"
@@ -30,18 +71,10 @@ Version: {ver}
 Usage: ./gg.cmd [options] <executable name>@<version>:<dependent executable name>@<version> [program arguments]
 
 Options:
-    -v              Info output
-    -vv             Debug output
-    -vvv            Trace output
-    -w              Even more output
-    -V              Print version
+{}
 
 Built in commands:
-    update          Update gg.cmd
-    help            Print help
-    check           Check for updates
-    check-update    Check for updates and update if available
-    clean-cache     Clean cache
+{}
 
 Examples:
     ./gg.cmd node
@@ -51,26 +84,47 @@ Examples:
     ./gg.cmd java@-jdk+jre -version
     ./gg.cmd run:java@17 soapui
     ./gg.cmd run:java@14 env
+    ./gg.cmd run build
+    ./gg.cmd run web
     ./gg.cmd update
 
 Supported systems:
-    node (npm, npx will also work, version refers to node version)
+    node (npm, npx and corepack will also work, version refers to node version, unless packageManager in package.json pins npm)
     gradle
     java
     maven
     openapi
     rat (ra)
     deno
-    run (any arbitrary command)
+    run (any arbitrary command, or a script from [scripts] or a task from [tasks] in gg.toml)
     go
     caddy
-");
+    python (pip will also work)
+    pnpm
+    yarn
+    kotlin (kotlinc)
+    terraform
+    opentofu (tofu)
+    kubectl
+    helm
+    rust (cargo, rustc)
+    dotnet
+    dart
+    flutter
+    ruby (gem, bundle)
+    php
+    composer
+    erlang (erl, erlc)
+    elixir (mix, iex)
+    Any tool with a manifest in ~/.config/gg/tools or [custom-tools] in gg.toml, see README
+", no_clap::format_table(&no_clap::OPTIONS, 24), no_clap::format_table(&no_clap::COMMANDS, 16));
 }
 
 #[tokio::main]
 async fn main() -> ExitCode {
     let ver = option_env!("VERSION").unwrap_or("dev");
 
+    timings::start();
     let no_clap = NoClap::new();
     let log_level = match no_clap.log_level.as_str() {
         "debug" => LevelFilter::Debug,
@@ -89,9 +143,32 @@ async fn main() -> ExitCode {
     }
 
     let system = fs::read_to_string(format!("./.cache/gg/gg-{ver}/system")).unwrap_or(String::from("x86_64-linux")).trim().to_string();
-    let target = Target::parse(&system);
+    let host = Target::detect(&system);
+    let target = match host.with_overrides(no_clap.target.as_deref(), no_clap.os.as_deref(), no_clap.arch.as_deref(), no_clap.variant.as_deref()) {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    if target.key() != host.key() {
+        warn!("Using target {} instead of {}. Tools for it might not run here, prep only downloads them", target.key(), host.key());
+    }
+    diagnostics::install_panic_hook(&target);
 
-    let input = &AppInput { target, no_clap: no_clap.clone() };
+    let mut config = match GgConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(1);
+        }
+    };
+    config.set_shared_install_dir(&target, no_clap.local);
+    http::init(&config.http);
+    index_cache::init(&config);
+    executors::plugin::init(&config);
+    let lock = GgLock::load();
+    let input = &AppInput { target, no_clap: no_clap.clone(), config, lock };
 
     if no_clap.version {
         println!("{}", ver);
@@ -103,12 +180,7 @@ async fn main() -> ExitCode {
     if let Some(cmd) = no_clap.cmds.first() {
         match cmd.cmd.as_str() {
             "update" => {
-                println!("Updating gg.cmd...");
-                let url = "https://github.com/eirikb/gg/releases/latest/download/gg.cmd";
-                let pb = create_barus();
-                let bloody_indiana_jones = BloodyIndianaJones::new_with_file_name(url.to_string(), "gg.cmd".to_string(), pb.clone());
-                bloody_indiana_jones.download().await;
-                return ExitCode::from(0);
+                return self_update::update(&no_clap.app_args, &host, input).await;
             }
             "help" => {
                 print_help(ver);
@@ -122,8 +194,99 @@ async fn main() -> ExitCode {
                 checker::check(input, true).await;
                 return ExitCode::from(0);
             }
+            "init" => {
+                return init::init(input).await;
+            }
+            "upgrade" => {
+                return upgrade::upgrade(input, no_clap.app_args.first().map(|s| s.as_str())).await;
+            }
+            "doctor" => {
+                return doctor::doctor(input, ver).await;
+            }
+            "update-lock" => {
+                return upgrade::update_lock(input, no_clap.app_args.first().map(|s| s.as_str())).await;
+            }
+            "refresh" => {
+                return refresh::refresh(input, no_clap.app_args.first().map(|s| s.as_str())).await;
+            }
+            "list" => {
+                return installed::list(&input.config, no_clap.json);
+            }
+            "uninstall" => {
+                return installed::uninstall(&input.config, &no_clap.app_args);
+            }
+            "clean" => {
+                return installed::clean(&input.config, &no_clap.app_args);
+            }
+            "which" => {
+                return info::which(input, &no_clap.app_args).await;
+            }
+            "info" => {
+                return info::info(input, &no_clap.app_args).await;
+            }
+            "list-remote" => {
+                return list_remote::list_remote(input, &no_clap.app_args).await;
+            }
+            "compare" => {
+                return compare::compare(input);
+            }
+            "matrix" => {
+                return matrix::matrix(input);
+            }
+            "export" => {
+                return export::export(input, no_clap.app_args.first());
+            }
+            "pack" => {
+                return bundle::bundle(input, no_clap.app_args.first());
+            }
+            "import" => {
+                return export::import(input, no_clap.app_args.first());
+            }
+            "prep" => {
+                let args = no_clap.gg_args.iter().cloned().chain([no_clap.app_args.join(":")]).collect();
+                let prep_no_clap = NoClap::parse(args);
+                let prep_no_clap = prep_no_clap.with_aliases(&input.config.aliases, &input.config.versions).unwrap_or(prep_no_clap);
+                let input = &AppInput { target, no_clap: prep_no_clap, config: input.config.clone(), lock: input.lock.clone() };
+                return run(input, ver, RunMode::Prep).await;
+            }
+            "env" => {
+                let (shell, tools) = match shell_env::get_shell(&no_clap.app_args) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        eprintln!("{e}");
+                        return ExitCode::from(1);
+                    }
+                };
+                // The tools in gg.toml when none are given
+                let tools = if tools.is_empty() { input.config.tools.keys().cloned().collect() } else { tools };
+                let args = no_clap.gg_args.iter().cloned().chain([tools.join(":")]).collect();
+                let env_no_clap = NoClap::parse(args);
+                let env_no_clap = env_no_clap.with_aliases(&input.config.aliases, &input.config.versions).unwrap_or(env_no_clap);
+                let input = &AppInput { target, no_clap: env_no_clap, config: input.config.clone(), lock: input.lock.clone() };
+                return run(input, ver, RunMode::Env(shell)).await;
+            }
+            "completions" => {
+                return completions::completions(input, &no_clap.app_args);
+            }
+            "daemon" => {
+                return daemon::daemon(&no_clap.app_args);
+            }
+            "serve" => {
+                return serve::serve(no_clap.app_args.first(), input).await;
+            }
+            "verify" => {
+                return verify::verify(input);
+            }
+            "watch" => {
+                return watch::watch(&no_clap).await;
+            }
             "clean-cache" => {
                 println!("Cleaning cache");
+                if input.config.is_shared_install_dir() {
+                    println!("Tools in {} are shared with other projects, remove them with uninstall or clean", input.config.get_install_dir().display());
+                } else {
+                    let _ = fs::remove_dir_all(input.config.get_install_dir());
+                }
                 let _ = fs::remove_dir_all(".cache/gg");
                 return ExitCode::from(0);
             }
@@ -133,41 +296,72 @@ async fn main() -> ExitCode {
 
     info!("System is {system}. {:?}", &target);
 
-    return if no_clap.cmds.first().is_some() {
-        let mut executors = no_clap.cmds.iter().filter_map(|cmd| <dyn Executor>::new(ExecutorCmd {
+    let mut no_clap = no_clap;
+    for _ in 0..10 {
+        match get_script_no_clap(&no_clap, &input.config).or(get_task_no_clap(&no_clap, &input.config)).or(no_clap.with_aliases(&input.config.aliases, &input.config.versions)) {
+            Some(script_no_clap) => no_clap = script_no_clap,
+            None => break,
+        }
+    }
+    let input = &AppInput { target, no_clap, config: input.config.clone(), lock: input.lock.clone() };
+
+    run(input, ver, RunMode::Exec).await
+}
+
+/// What to do with the tools once they are installed
+#[derive(Clone, Copy, PartialEq)]
+enum RunMode {
+    Exec,
+    /// Print a summary, without running anything
+    Prep,
+    /// Print PATH and the tool variables for the shell, without running anything
+    Env(shell_env::Shell),
+}
+
+/// `run <script>` where script is defined in gg.toml is the same as running gg with the script as arguments
+fn get_script_no_clap(no_clap: &NoClap, config: &GgConfig) -> Option<NoClap> {
+    if no_clap.cmds.len() != 1 || no_clap.cmds[0].cmd != "run" || no_clap.cmds[0].version.is_some() {
+        return None;
+    }
+    let name = no_clap.app_args.first()?;
+    let script_args = config.get_script_args(name)?;
+    info!("Running script {name}: {}", script_args.join(" "));
+    let args = no_clap.gg_args.iter().cloned()
+        .chain(script_args)
+        .chain(no_clap.app_args.iter().skip(1).cloned())
+        .collect();
+    let mut script_no_clap = NoClap::parse(args);
+    script_no_clap.ci = no_clap.ci;
+    Some(script_no_clap)
+}
+
+/// `run <task>` where task is in [tasks] in gg.toml runs it in the shell, with all tools in [tools] prepared and in PATH
+fn get_task_no_clap(no_clap: &NoClap, config: &GgConfig) -> Option<NoClap> {
+    if no_clap.cmds.len() != 1 || no_clap.cmds[0].cmd != "run" || no_clap.cmds[0].version.is_some() {
+        return None;
+    }
+    let name = no_clap.app_args.first()?;
+    let task_args = config.get_task_args(name, &no_clap.app_args[1..])?;
+    info!("Running task {name}: {}", config.tasks[name]);
+    let args = no_clap.gg_args.iter().cloned().chain(task_args).collect();
+    let mut task_no_clap = NoClap::parse(args);
+    task_no_clap.ci = no_clap.ci;
+    Some(task_no_clap)
+}
+
+async fn run(input: &AppInput, ver: &str, mode: RunMode) -> ExitCode {
+    let no_clap = &input.no_clap;
+
+    if !no_clap.cmds.is_empty() {
+        let executors = with_deps(no_clap.cmds.iter().filter_map(|cmd| <dyn Executor>::new(ExecutorCmd {
             cmd: cmd.cmd.to_string(),
             version: GgVersionReq::new(cmd.version.clone().unwrap_or("".to_string()).as_str()),
             include_tags: cmd.include_tags.clone(),
             exclude_tags: cmd.exclude_tags.clone(),
-        })).collect::<Vec<Box<dyn Executor>>>();
-
-        let mut look_for_deps = true;
-        while look_for_deps {
-            look_for_deps = false;
-            let mut to_add = Vec::new();
-            for x in &executors {
-                for dep_name in x.get_deps() {
-                    if !executors.iter().any(|e| &e.get_name().to_string() == dep_name) {
-                        if let Some(e) = <dyn Executor>::new(ExecutorCmd {
-                            cmd: dep_name.to_string(),
-                            version: None,
-                            include_tags: Default::default(),
-                            exclude_tags: Default::default(),
-                        }) {
-                            look_for_deps = true;
-                            to_add.push(e);
-                        }
-                    }
-                }
-            }
-            for x in to_add {
-                executors.push(x);
-            }
-        }
+        })).collect(), input);
 
         return if executors.first().is_some() {
-            let mut env_vars: HashMap<String, String> = HashMap::new();
-            let mut path_vars: Vec<String> = vec!();
+            let mut exec_context = ExecContext::default();
 
             let m = MultiProgress::new();
 
@@ -177,40 +371,92 @@ async fn main() -> ExitCode {
                 (x, pb)
             }).map(|(x, pb)| async move {
                 let app_path = prep(&**x, &input, &pb).await?;
-                let env = x.get_env(&app_path);
-                let bin_dirs = x.get_bin_dirs();
-                Ok::<_, String>((app_path, env, bin_dirs))
+                // The shell may leave the project folder, as may what the tool runs, e.g. node from npm scripts of a package
+                let app_path = match mode {
+                    RunMode::Prep => app_path,
+                    _ => AppPath { install_dir: std::path::absolute(&app_path.install_dir).unwrap_or(app_path.install_dir) },
+                };
+                let deps = get_deps_exec_context(&**x, input).await?;
+                let exec_context = x.get_exec_context(input, &app_path, &deps);
+                Ok::<_, String>((app_path, exec_context))
             });
-            let res = join_all(alles).await;
+            // In the order given, at most [prep] jobs at a time
+            let res: Vec<_> = stream::iter(alles).buffered(input.config.get_prep_jobs()).collect().await;
+
+            if mode == RunMode::Exec && container::is_enabled(input) {
+                let mut without_native = vec!();
+                for (executor, _) in executors.iter().zip(&res).filter(|(_, r)| r.is_err()) {
+                    match only_for_other_targets(&**executor, input).await {
+                        Ok(true) => without_native.push(executor.get_name().to_string()),
+                        Ok(false) => {}
+                        Err(e) => warn!("Unable to tell if {} has builds for other targets: {e}", executor.get_name()),
+                    }
+                }
+                if !without_native.is_empty() {
+                    return match container::run(&*executors[0], input, &without_native) {
+                        Ok(true) => ExitCode::from(0),
+                        Ok(false) => ExitCode::from(1),
+                        Err(e) => {
+                            println!("{}", diagnostics::render_error(&e));
+                            ExitCode::from(1)
+                        }
+                    };
+                }
+            }
 
             res.iter().filter(|x| x.is_err()).for_each(|x| {
-                println!("Prep failed: {}", x.clone().err().unwrap());
+                let error = diagnostics::render_error(&x.clone().err().unwrap());
+                // Not for the shell to evaluate
+                if let RunMode::Env(_) = mode {
+                    eprintln!("{error}");
+                } else {
+                    println!("{error}");
+                }
             });
             if res.iter().any(|x| x.is_err()) {
+                timings::report(input).await;
                 return ExitCode::from(1);
             }
 
             let res = res.into_iter().filter_map(|x| x.ok()).collect::<Vec<_>>();
 
-            for (app_path, env, bin_dirs) in res.clone() {
-                for bin_dir in &bin_dirs {
-                    path_vars.push(app_path.install_dir.clone().join(bin_dir).to_str().unwrap_or("").to_string());
-                }
-                for (key, value) in env {
-                    env_vars.insert(key.to_string(), value.to_string());
+            if mode == RunMode::Prep {
+                for (executor, (app_path, _)) in executors.iter().zip(&res) {
+                    let version = read_meta(&app_path.install_dir)
+                        .and_then(|meta| meta.download.version)
+                        .map(|v| v.to_string())
+                        .unwrap_or("unknown".to_string());
+                    println!("{} {} {}", executor.get_name(), version, app_path.install_dir.display());
                 }
+                timings::report(input).await;
+                return ExitCode::from(0);
             }
 
-            let (app_path, _, _) = &res[0];
+            for (_, context) in res.clone() {
+                exec_context.merge(context);
+            }
+
+            if let RunMode::Env(shell) = mode {
+                let path = get_path(input, exec_context.path_vars(), &env::var("PATH").unwrap_or_default());
+                println!("{}", shell_env::render(shell, &path, &exec_context.env));
+                return ExitCode::from(0);
+            }
+
+            let (app_path, _) = &res[0];
             let executor = &executors[0];
 
-            info!("Path vars: {}", &path_vars.join(", "));
+            info!("Path vars: {}", &exec_context.path_vars().join(", "));
 
-            if try_run(input, &**executor, app_path.clone(), path_vars, env_vars).await.unwrap() {
-                ExitCode::from(0)
-            } else {
-                println!("Unable to execute");
-                ExitCode::from(1)
+            match try_run(input, &**executor, app_path.clone(), exec_context).await {
+                Ok(true) => ExitCode::from(0),
+                Ok(false) => {
+                    println!("Unable to execute");
+                    ExitCode::from(1)
+                }
+                Err(e) => {
+                    println!("{e}");
+                    ExitCode::from(1)
+                }
             }
         } else {
             println!("No executor found!");
@@ -220,5 +466,5 @@ async fn main() -> ExitCode {
         println!("Missing command. Try help");
         print_help(ver);
         ExitCode::from(1)
-    };
+    }
 }
@@ -0,0 +1,82 @@
+use std::process::ExitCode;
+
+use crate::compare::{print_summary, run_variants, split_args};
+use crate::executor::AppInput;
+
+/// `node=18,20 java=17,21` gives every combination, `node@18:java@17`, `node@18:java@21` and so on
+fn get_variants(dimensions: &[String]) -> Result<Vec<String>, String> {
+    let mut variants = vec!(String::new());
+    for dimension in dimensions {
+        let (tool, versions) = dimension.split_once('=')
+            .ok_or(format!("Expected <tool>=<versions>, e.g. node=18,20, got {dimension}"))?;
+        let versions: Vec<&str> = versions.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()).collect();
+        if versions.is_empty() {
+            return Err(format!("No versions for {tool}"));
+        }
+        variants = variants.iter()
+            .flat_map(|variant| versions.iter().map(move |version| {
+                let separator = if variant.is_empty() { "" } else { ":" };
+                format!("{variant}{separator}{tool}@{version}")
+            }))
+            .collect();
+    }
+    Ok(variants)
+}
+
+/// -j <n>, --jobs <n> or --jobs=<n> among the dimensions
+fn get_jobs(args: &[String]) -> Result<(usize, Vec<String>), String> {
+    let mut jobs = 1;
+    let mut rest = vec!();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-j" | "--jobs" => args.next().cloned(),
+            a if a.starts_with("--jobs=") => Some(a["--jobs=".len()..].to_string()),
+            _ => {
+                rest.push(arg.clone());
+                continue;
+            }
+        };
+        jobs = value.and_then(|v| v.parse().ok()).filter(|j| *j > 0).ok_or("Invalid number of jobs")?;
+    }
+    Ok((jobs, rest))
+}
+
+/// `matrix node=18,20,22 -- npm test` runs the command with each version, sequentially unless --jobs is given
+pub fn matrix(input: &AppInput) -> ExitCode {
+    let (dimensions, command) = split_args(&input.no_clap.app_args);
+    let variants = get_jobs(&dimensions).and_then(|(jobs, dimensions)| Ok((jobs, get_variants(&dimensions)?)));
+    match variants {
+        Ok((_, variants)) if variants.len() < 2 || command.is_empty() => {
+            println!("Usage: matrix [--jobs <n>] <tool>=<versions> [...] -- <command>, e.g. matrix node=18,20,22 -- npm test");
+            ExitCode::from(1)
+        }
+        Ok((jobs, variants)) => print_summary(&run_variants(input, &variants, &command, jobs)),
+        Err(e) => {
+            println!("{e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_variants() {
+        let dimensions = ["node=18,20".to_string(), "java=17".to_string()];
+        assert_eq!(vec!("node@18:java@17", "node@20:java@17"), get_variants(&dimensions).unwrap());
+        assert_eq!(vec!("node@18", "node@20", "node@22"), get_variants(&["node=18, 20,22".to_string()]).unwrap());
+        assert!(get_variants(&["node".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_get_jobs() {
+        let args = ["--jobs", "3", "node=18,20"].map(String::from).to_vec();
+        assert_eq!((3, vec!("node=18,20".to_string())), get_jobs(&args).unwrap());
+        assert_eq!(2, get_jobs(&["--jobs=2".to_string()]).unwrap().0);
+        assert_eq!(1, get_jobs(&[]).unwrap().0);
+        assert!(get_jobs(&["-j".to_string(), "0".to_string()]).is_err());
+    }
+}
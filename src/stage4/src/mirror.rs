@@ -0,0 +1,61 @@
+use std::env;
+
+use log::debug;
+
+use crate::executor::{AppInput, Download};
+
+/// GG_NODE_MIRROR for node, GG_OPENAPI_MIRROR for openapi
+fn get_env_name(name: &str) -> String {
+    format!("GG_{}_MIRROR", name.to_uppercase().replace('-', "_"))
+}
+
+/// GG_<TOOL>_MIRROR, or mirror in [<tool>] in gg.toml
+pub fn get_mirror(input: &AppInput, name: &str) -> Option<String> {
+    env::var(get_env_name(name)).ok()
+        .or(input.config.executor(name).mirror)
+        .map(|mirror| mirror.trim().trim_end_matches('/').to_string())
+        .filter(|mirror| !mirror.is_empty())
+}
+
+/// The mirror when there is one, otherwise the upstream `base`, e.g. https://go.dev/dl
+pub fn mirror_url(input: &AppInput, name: &str, base: &str) -> String {
+    get_mirror(input, name).unwrap_or(base.to_string())
+}
+
+#[cfg_attr(not(any(feature = "java", feature = "deno", feature = "go", feature = "caddy", feature = "python", feature = "terraform", feature = "rust", feature = "dotnet", feature = "dart", feature = "ruby", feature = "erlang")), allow(dead_code))]
+fn rewrite(url: &str, base: &str, mirror: &str) -> String {
+    match url.strip_prefix(base) {
+        Some(rest) => format!("{mirror}{rest}"),
+        None => url.to_string(),
+    }
+}
+
+/// Download URLs under `base` moved to the mirror. Others, e.g. from a different host, are left alone
+#[cfg_attr(not(any(feature = "java", feature = "deno", feature = "go", feature = "caddy", feature = "python", feature = "terraform", feature = "rust", feature = "dotnet", feature = "dart", feature = "ruby", feature = "erlang")), allow(dead_code))]
+pub fn with_mirror(input: &AppInput, name: &str, base: &str, downloads: Vec<Download>) -> Vec<Download> {
+    let mirror = mirror_url(input, name, base);
+    if mirror == base {
+        return downloads;
+    }
+    debug!("Downloading {name} from {mirror} instead of {base}");
+    downloads.into_iter().map(|mut download| {
+        download.download_url = rewrite(&download.download_url, base, &mirror);
+        download
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        assert_eq!("GG_NODE_MIRROR", get_env_name("node"));
+        assert_eq!("GG_OPENAPI_GENERATOR_MIRROR", get_env_name("openapi-generator"));
+        let base = "https://cdn.azul.com/zulu/bin";
+        let mirror = "https://artifactory.corp/zulu";
+        assert_eq!("https://artifactory.corp/zulu/zulu21.32.17-ca-jdk21.0.2-linux_x64.tar.gz",
+                   rewrite("https://cdn.azul.com/zulu/bin/zulu21.32.17-ca-jdk21.0.2-linux_x64.tar.gz", base, mirror));
+        assert_eq!("https://example.com/other.tar.gz", rewrite("https://example.com/other.tar.gz", base, mirror));
+    }
+}
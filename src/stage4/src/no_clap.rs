@@ -1,14 +1,116 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 
 use regex::{Match, Regex};
 
+use crate::tee::DEFAULT_LOG_DIR;
+
 #[derive(Debug, Clone)]
 pub struct NoClapCmd {
     pub cmd: String,
     pub version: Option<String>,
     pub include_tags: HashSet<String>,
     pub exclude_tags: HashSet<String>,
+    /// Everything after @ as given, e.g. `17+jdk-jre`
+    pub spec: Option<String>,
+}
+
+impl NoClapCmd {
+    /// Back to the command line form, e.g. `java@17+jdk-jre`
+    pub fn to_arg(&self) -> String {
+        match &self.spec {
+            Some(spec) => format!("{}@{spec}", self.cmd),
+            None => self.cmd.clone(),
+        }
+    }
+}
+
+/// Options before the command as in help, the usage and what it does. The ones with `=` take a value
+pub const OPTIONS: [(&str, &str); 26] = [
+    ("-v", "Info output"),
+    ("-vv", "Debug output"),
+    ("-vvv", "Trace output"),
+    ("-w", "Even more output"),
+    ("-V", "Print version"),
+    ("--ci", "CI mode (default when CI environment variable is set)"),
+    ("--isolate-gradle-home", "Use a GRADLE_USER_HOME per project and gradle version"),
+    ("--maven-toolchains", "Generate toolchains.xml for maven from JDKs installed by gg"),
+    ("--node-gyp", "Set up environment for node-gyp (python, nodedir, build tools on Windows)"),
+    ("--strict", "Fail instead of warn, e.g. for end-of-life versions"),
+    ("--hermetic", "Run with only gg tools in PATH and a minimal set of environment variables"),
+    ("--system", "Use system installed node / java when the version matches, instead of downloading"),
+    ("--fail-on-shadow", "Fail when another binary in PATH shadows the one provided by gg"),
+    ("--log-output[=<dir>]", "Also write output of the tool to a timestamped log file (default .cache/gg/logs)"),
+    ("--vulns=<policy>", "Check for known vulnerabilities: off (default), warn or block"),
+    ("--container", "Run in a docker / podman container when there is no native build for this system"),
+    ("--timings", "Print where time went per tool: detection, index fetch, download, extraction and exec startup"),
+    ("--lts", "Use the newest LTS release of tools that have them (node, java). Or per tool, e.g. node@lts"),
+    ("--yes", "Use the closest available version when the requested one does not exist for this system"),
+    ("--vendor=<vendor>", "JDK vendor for java: zulu (default), temurin, liberica, graalvm or corretto"),
+    ("--json", "JSON output from list-remote, list, which and info, for scripts"),
+    ("--os=<os>", "Resolve and download for another OS: linux, mac or windows. Or GG_TARGET, e.g. linux-arm64"),
+    ("--arch=<arch>", "Resolve and download for another arch: x86_64, arm64, armv7, riscv64, ppc64le or s390x"),
+    ("--variant=<variant>", "Resolve and download musl or gnu builds on Linux"),
+    ("--local", "Install tools in .cache/gg of the project instead of the cache shared by all projects"),
+    ("--verify-signatures", "Verify GPG signatures of downloads (node, terraform, opentofu and temurin java)"),
+];
+
+/// Built in commands as in help, the usage and what it does. Lines after the first are indented in help
+pub const COMMANDS: [(&str, &str); 28] = [
+    ("update [beta] [--skip-checksum]", "Update gg.cmd (beta includes pre-releases). Verified, and rolled back if broken"),
+    ("help", "Print help"),
+    ("check", "Check for updates"),
+    ("check-update", "Check for updates and update if available"),
+    ("clean-cache", "Clean .cache/gg of the project, tools shared with other projects are left for uninstall or clean"),
+    ("watch -- <cmd>", "Run command, restart when .nvmrc, gg.toml, package.json etc. change"),
+    ("compare <tools> <tools> -- <cmd>", "Run command with each of the tools, e.g. compare node@18 node@20 -- npm test"),
+    ("matrix [--jobs <n>] <tool>=<versions> -- <cmd>", "Run command with each version, e.g. matrix node=18,20,22 -- npm test"),
+    ("prep <tools>", "Install tools without running them, e.g. prep node@18 java gradle"),
+    ("env [--shell <shell>] [tools]", "Install tools and print PATH, JAVA_HOME etc. to eval in bash, zsh, fish or powershell,\ne.g. env node java. Tools in gg.toml when none are given"),
+    ("init [-y]", "Create gg.toml and gg.lock from tools found in package.json, pom.xml, go.mod etc."),
+    ("upgrade [tool]", "Upgrade tools in gg.toml / gg.lock to newest matching version"),
+    ("update-lock [tool]", "Resolve gg.lock again and record download and checksum for this target (or --os/--arch)"),
+    ("refresh [tool]", "Forget resolved versions and cached lists, and fetch the list of versions again"),
+    ("list", "List installed tools with size and when they were last used"),
+    ("uninstall <tool>[@<version>] [--older-than <days>]", "Remove installed versions, e.g. uninstall node@16 or uninstall --older-than 30"),
+    ("clean [--older-than <days>] [--keep <versions>] [--max-size <size>] [--dry-run]", "Remove installs not used in days, all but the newest versions per tool, or the least recently\nused until the rest fits in size, e.g. clean --keep 2 --max-size 10G"),
+    ("list-remote <tool> [range]", "List available versions for this system, with LTS and release dates, e.g. list-remote node@^18"),
+    ("which <tool>", "Print the path of the binary that would run, e.g. which node@18"),
+    ("info <tool>", "Show the version gg picks for a tool and why, the download and the install dir"),
+    ("verify", "Verify installed tools against digests in gg.lock"),
+    ("doctor", "Check target, proxy, connection to download sites, broken installs and disk usage"),
+    ("export [file]", "Export installed tools to a tar file (default gg-bundle.tar)"),
+    ("import <file>", "Import tools from a tar file created by export"),
+    ("pack [file]", "Create a gg.cmd including gg itself, gg.toml and gg.lock (default gg-bundle.cmd)"),
+    ("completions <shell>", "Print completions for bash, zsh, fish or powershell, e.g. completions bash >> ~/.bashrc"),
+    ("daemon [stop]", "Keep version indexes and resolved versions in memory for fast repeated runs"),
+    ("serve [[<address>:]<port>]", "Serve a shared download cache (default 127.0.0.1:8080), e.g. serve 0.0.0.0:8080 for other machines"),
+];
+
+/// The option as typed, `--vendor=` of `--vendor=<vendor>` and `--log-output` of `--log-output[=<dir>]`
+pub fn option_name(usage: &str) -> &str {
+    match usage.find(['[', '<']) {
+        Some(i) => &usage[..i],
+        None => usage,
+    }
+}
+
+/// The command as typed, `update` of `update [beta]`
+pub fn command_name(usage: &str) -> &str {
+    usage.split(' ').next().unwrap_or(usage)
+}
+
+/// Usage and description in columns, the description on the next line when the usage does not fit
+pub fn format_table(rows: &[(&str, &str)], width: usize) -> String {
+    let indent = " ".repeat(4 + width);
+    rows.iter().map(|(usage, description)| {
+        let description = description.replace('\n', &format!("\n{indent}"));
+        if usage.len() < width {
+            format!("    {usage:width$}{description}")
+        } else {
+            format!("    {usage}\n{indent}{description}")
+        }
+    }).collect::<Vec<_>>().join("\n")
 }
 
 /// Why not clap? Yes
@@ -20,15 +122,49 @@ pub struct NoClap {
     pub log_external: bool,
     pub cmds: Vec<NoClapCmd>,
     pub version: bool,
+    pub ci: bool,
+    #[cfg_attr(not(feature = "gradle"), allow(dead_code))]
+    pub isolate_gradle_home: bool,
+    #[cfg_attr(not(feature = "maven"), allow(dead_code))]
+    pub maven_toolchains: bool,
+    #[cfg_attr(not(feature = "node"), allow(dead_code))]
+    pub node_gyp: bool,
+    pub strict: bool,
+    pub hermetic: bool,
+    pub system: bool,
+    pub fail_on_shadow: bool,
+    pub log_output: Option<String>,
+    pub vuln_policy: String,
+    pub timings: bool,
+    pub container: bool,
+    pub yes: bool,
+    pub lts: bool,
+    #[cfg_attr(not(feature = "java"), allow(dead_code))]
+    pub vendor: Option<String>,
+    /// JSON instead of text from list-remote, list etc.
+    pub json: bool,
+    /// --os, --arch and --variant, and GG_TARGET, instead of the detected target
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub variant: Option<String>,
+    pub target: Option<String>,
+    /// Tools in .cache/gg of the project instead of the shared cache
+    pub local: bool,
+    /// GPG signatures of downloads checked, failing for tools that publish none
+    pub verify_signatures: bool,
 }
 
 impl NoClap {
     pub fn new() -> Self {
         let args: Vec<String> = env::args().skip(1).collect();
-        return NoClap::parse(args);
+        let mut no_clap = NoClap::parse(args);
+        no_clap.ci = no_clap.ci || is_ci_env();
+        no_clap.target = env::var("GG_TARGET").ok().filter(|t| !t.is_empty());
+        no_clap
     }
 
     pub fn parse(args: Vec<String>) -> Self {
+        let args = join_option_values(args);
         let start_at = args.iter().position(|item| !item.starts_with("-")).unwrap_or(args.len());
         let cmds = args.get(start_at);
         let gg_args: Vec<String> = args.clone().into_iter().take(start_at).collect();
@@ -37,6 +173,28 @@ impl NoClap {
 
         let version = gg_args.contains(&"-V".to_string());
         let log_external = gg_args.contains(&"-w".to_string());
+        let ci = gg_args.contains(&"--ci".to_string());
+        let isolate_gradle_home = gg_args.contains(&"--isolate-gradle-home".to_string());
+        let maven_toolchains = gg_args.contains(&"--maven-toolchains".to_string());
+        let node_gyp = gg_args.contains(&"--node-gyp".to_string());
+        let strict = gg_args.contains(&"--strict".to_string());
+        let hermetic = gg_args.contains(&"--hermetic".to_string());
+        let system = gg_args.contains(&"--system".to_string());
+        let fail_on_shadow = gg_args.contains(&"--fail-on-shadow".to_string());
+        let log_output = get_value(&gg_args, "--log-output")
+            .or(if gg_args.contains(&"--log-output".to_string()) { Some(DEFAULT_LOG_DIR.to_string()) } else { None });
+        let container = gg_args.contains(&"--container".to_string());
+        let yes = gg_args.contains(&"--yes".to_string());
+        let lts = gg_args.contains(&"--lts".to_string());
+        let vendor = get_value(&gg_args, "--vendor");
+        let timings = gg_args.contains(&"--timings".to_string());
+        let json = gg_args.contains(&"--json".to_string());
+        let os = get_value(&gg_args, "--os");
+        let arch = get_value(&gg_args, "--arch");
+        let variant = get_value(&gg_args, "--variant");
+        let local = gg_args.contains(&"--local".to_string());
+        let verify_signatures = gg_args.contains(&"--verify-signatures".to_string());
+        let vuln_policy = get_value(&gg_args, "--vulns").unwrap_or("off".to_string());
 
         let log_level = if let Some((_, log_level)) = log_level {
             log_level
@@ -53,6 +211,7 @@ impl NoClap {
             let mut include_tags = HashSet::new();
             let mut exclude_tags = HashSet::new();
             let mut version = None;
+            let spec = if parts.len() == 2 { Some(parts[1].clone()) } else { None };
 
             if parts.len() == 2 {
                 cmd = parts[0].to_string();
@@ -78,15 +237,85 @@ impl NoClap {
                 });
             }
 
+            // node@lts is the newest LTS, same as node@+lts
+            if version.as_deref() == Some("lts") {
+                version = None;
+                include_tags.insert("lts".to_string());
+            }
+
             NoClapCmd {
                 cmd,
                 version,
                 include_tags,
                 exclude_tags,
+                spec,
             }
         }).collect();
 
-        Self { gg_args, app_args, log_level, log_external, cmds, version }
+        Self { gg_args, app_args, log_level, log_external, cmds, version, ci, isolate_gradle_home, maven_toolchains, node_gyp, strict, hermetic, system, fail_on_shadow, log_output, vuln_policy, timings, container, yes, lts, vendor, json, os, arch, variant, target: None, local, verify_signatures }
+    }
+}
+
+impl NoClap {
+    /// Named handles from [aliases] in gg.toml, e.g. `java8 = "java@8"` makes `java8 -version` the same as
+    /// `java@8 -version`. Only plain names are replaced, `java8@11` is left as is.
+    /// Version aliases from [versions], e.g. `prod-node = "20.11.1"`, replace everything after @
+    pub fn with_aliases(&self, aliases: &BTreeMap<String, String>, versions: &BTreeMap<String, String>) -> Option<NoClap> {
+        let rewrite = |c: &NoClapCmd| match &c.spec {
+            None => aliases.get(&c.cmd).cloned(),
+            Some(spec) => versions.get(spec).map(|version| format!("{}@{version}", c.cmd)),
+        };
+        if !self.cmds.iter().any(|c| rewrite(c).is_some()) {
+            return None;
+        }
+        let cmd = self.cmds.iter()
+            .map(|c| rewrite(c).unwrap_or(c.to_arg()))
+            .collect::<Vec<_>>()
+            .join(":");
+        let args = self.gg_args.iter().cloned()
+            .chain([cmd])
+            .chain(self.app_args.iter().cloned())
+            .collect();
+        let mut no_clap = NoClap::parse(args);
+        no_clap.ci = self.ci;
+        Some(no_clap)
+    }
+}
+
+/// Value of a `--name=value` option
+/// `--vendor temurin` as `--vendor=temurin`, for the options before the command that take a value
+fn join_option_values(args: Vec<String>) -> Vec<String> {
+    let takes_value: Vec<&str> = OPTIONS.iter().map(|(usage, _)| option_name(usage))
+        .filter_map(|name| name.strip_suffix('=')).collect();
+    let mut joined = vec!();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            joined.push(arg);
+            break;
+        }
+        match args.as_slice().first() {
+            Some(value) if takes_value.contains(&arg.as_str()) && !value.starts_with('-') => {
+                joined.push(format!("{arg}={value}"));
+                args.next();
+            }
+            _ => joined.push(arg),
+        }
+    }
+    joined.extend(args);
+    joined
+}
+
+fn get_value(gg_args: &[String], name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    gg_args.iter().find_map(|arg| arg.strip_prefix(prefix.as_str()).map(|v| v.to_string()))
+}
+
+/// Most CI providers (GitHub Actions, GitLab, Azure Pipelines, Travis etc.) set CI
+fn is_ci_env() -> bool {
+    match env::var("CI") {
+        Ok(ci) => !ci.is_empty() && ci.to_lowercase() != "false" && ci != "0",
+        Err(_) => false,
     }
 }
 
@@ -139,6 +368,50 @@ mod tests {
         assert_eq!("1.2.3", no_clap.cmds[1].version.as_ref().unwrap());
     }
 
+    #[test]
+    fn lts() {
+        let no_clap = NoClap::parse(["--lts", "node@lts:java@17"].map(String::from).to_vec());
+        assert!(no_clap.lts);
+        assert_eq!(None, no_clap.cmds[0].version);
+        assert!(no_clap.cmds[0].include_tags.contains("lts"));
+        assert_eq!("node@lts", no_clap.cmds[0].to_arg());
+        assert!(no_clap.cmds[1].include_tags.is_empty());
+    }
+
+    #[test]
+    fn target_overrides() {
+        let no_clap = NoClap::parse(["--os=windows", "--arch=arm64", "prep", "node"].map(String::from).to_vec());
+        assert_eq!((Some("windows"), Some("arm64"), None), (no_clap.os.as_deref(), no_clap.arch.as_deref(), no_clap.variant.as_deref()));
+    }
+
+    #[test]
+    fn vendor() {
+        let no_clap = NoClap::parse(["--vendor=temurin", "java@21"].map(String::from).to_vec());
+        assert_eq!(Some("temurin".to_string()), no_clap.vendor);
+        assert_eq!(None, NoClap::parse(["java@21"].map(String::from).to_vec()).vendor);
+
+        let no_clap = NoClap::parse(["--vendor", "temurin", "java@21", "-version"].map(String::from).to_vec());
+        assert_eq!(Some("temurin".to_string()), no_clap.vendor);
+        assert_eq!(vec!("--vendor=temurin"), no_clap.gg_args);
+        assert_eq!("java", no_clap.cmds[0].cmd);
+        assert_eq!(vec!("-version"), no_clap.app_args);
+    }
+
+    #[test]
+    fn aliases() {
+        let aliases = BTreeMap::from([("java8".to_string(), "java@8".to_string()), ("java17".to_string(), "java@17".to_string())]);
+        let versions = BTreeMap::from([("prod-node".to_string(), "20.11.1".to_string())]);
+        let no_clap = NoClap::parse(["-v", "java8:gradle@7", "-version"].map(String::from).to_vec()).with_aliases(&aliases, &versions).unwrap();
+        assert_eq!("java@8:gradle@7", no_clap.cmds.iter().map(|c| c.to_arg()).collect::<Vec<_>>().join(":"));
+        assert_eq!(vec!("-v".to_string()), no_clap.gg_args);
+        assert_eq!(vec!("-version".to_string()), no_clap.app_args);
+        assert!(NoClap::parse(["java8@11"].map(String::from).to_vec()).with_aliases(&aliases, &versions).is_none());
+        assert!(NoClap::parse(["java", "-version"].map(String::from).to_vec()).with_aliases(&aliases, &versions).is_none());
+        let no_clap = NoClap::parse(["node@prod-node:java@17-jre"].map(String::from).to_vec()).with_aliases(&aliases, &versions).unwrap();
+        assert_eq!(Some("20.11.1".to_string()), no_clap.cmds[0].version);
+        assert_eq!("java@17-jre", no_clap.cmds[1].to_arg());
+    }
+
     #[test]
     fn print_help_no_cmd() {
         let no_clap = NoClap::parse(["-h"].map(String::from).to_vec());
@@ -151,6 +424,32 @@ mod tests {
         assert_eq!(true, no_clap.version);
     }
 
+    #[test]
+    fn ci_and_isolate_gradle_home() {
+        let no_clap = NoClap::parse(["--ci", "--isolate-gradle-home", "gradle", "build"].map(String::from).to_vec());
+        assert!(no_clap.ci);
+        assert!(no_clap.isolate_gradle_home);
+        assert_eq!(["build"].map(String::from).to_vec(), no_clap.app_args);
+    }
+
+    #[test]
+    fn vuln_policy() {
+        let no_clap = NoClap::parse(["--vulns=block", "node"].map(String::from).to_vec());
+        assert_eq!("block", no_clap.vuln_policy);
+        let no_clap = NoClap::parse(["node"].map(String::from).to_vec());
+        assert_eq!("off", no_clap.vuln_policy);
+    }
+
+    #[test]
+    fn log_output() {
+        let no_clap = NoClap::parse(["--log-output", "gradle"].map(String::from).to_vec());
+        assert_eq!(Some(DEFAULT_LOG_DIR.to_string()), no_clap.log_output);
+        let no_clap = NoClap::parse(["--log-output=logs", "gradle"].map(String::from).to_vec());
+        assert_eq!(Some("logs".to_string()), no_clap.log_output);
+        let no_clap = NoClap::parse(["gradle"].map(String::from).to_vec());
+        assert_eq!(None, no_clap.log_output);
+    }
+
     #[test]
     fn java() {
         let no_clap = NoClap::parse(["-v", "java@11", "-version"].map(String::from).to_vec());
@@ -172,4 +471,14 @@ mod tests {
         assert_eq!("java", no_clap.cmds[2].cmd);
         assert_eq!(None, no_clap.cmds[2].version);
     }
+
+    #[test]
+    fn options_and_commands() {
+        assert_eq!("--log-output", option_name("--log-output[=<dir>]"));
+        assert_eq!("--vendor=", option_name("--vendor=<vendor>"));
+        assert_eq!("--ci", option_name("--ci"));
+        assert_eq!("update", command_name("update [beta]"));
+        let table = format_table(&[("help", "Print help"), ("update-lock [tool]", "Resolve\nagain")], 16);
+        assert_eq!("    help            Print help\n    update-lock [tool]\n                    Resolve\n                    again", table);
+    }
 }
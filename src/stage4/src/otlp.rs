@@ -0,0 +1,112 @@
+use std::env;
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+use serde_json::{json, Value};
+
+use crate::executor::AppInput;
+use crate::http;
+use crate::timings::Timing;
+
+/// OTEL_EXPORTER_OTLP_TRACES_ENDPOINT, or OTEL_EXPORTER_OTLP_ENDPOINT with /v1/traces, as other OTLP exporters
+fn get_endpoint() -> Option<String> {
+    env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").ok()
+        .or(env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().map(|e| format!("{}/v1/traces", e.trim_end_matches('/'))))
+        .filter(|e| !e.is_empty())
+}
+
+/// `key1=value1,key2=value2`
+fn get_headers(headers: &str) -> Vec<(String, String)> {
+    headers.split(',')
+        .filter_map(|h| h.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+fn get_id(seed: &str, len: usize) -> String {
+    sha256::digest(format!("{seed} {} {:?}", process::id(), SystemTime::now()))[..len].to_string()
+}
+
+fn nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// One root span for the gg run, with a child span per tool and phase
+fn get_body(input: &AppInput, start: SystemTime, end: SystemTime, timings: &[Timing]) -> Value {
+    let cmd = input.no_clap.cmds.iter().map(|c| c.to_arg()).collect::<Vec<_>>().join(":");
+    let trace_id = get_id("trace", 32);
+    let root_id = get_id("root", 16);
+    let mut spans = vec!(json!({
+        "traceId": trace_id,
+        "spanId": root_id,
+        "name": format!("gg {cmd}"),
+        "kind": 1,
+        "startTimeUnixNano": nanos(start),
+        "endTimeUnixNano": nanos(end),
+        "attributes": [attribute("gg.command", &cmd), attribute("gg.target", &input.target.key()), attribute("gg.ci", &input.no_clap.ci.to_string())],
+    }));
+    for (i, timing) in timings.iter().enumerate() {
+        spans.push(json!({
+            "traceId": trace_id,
+            "spanId": get_id(&format!("span {i}"), 16),
+            "parentSpanId": root_id,
+            "name": format!("{} {}", timing.phase, timing.name),
+            "kind": 1,
+            "startTimeUnixNano": nanos(timing.end - timing.duration),
+            "endTimeUnixNano": nanos(timing.end),
+            "attributes": [attribute("gg.tool", &timing.name), attribute("gg.phase", timing.phase)],
+        }));
+    }
+    let service_name = env::var("OTEL_SERVICE_NAME").unwrap_or("gg".to_string());
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [attribute("service.name", &service_name)] },
+            "scopeSpans": [{ "scope": { "name": "gg" }, "spans": spans }],
+        }]
+    })
+}
+
+/// OTLP/HTTP with JSON encoding. Failures are only warnings, observability should never break a build
+pub async fn export(input: &AppInput, start: SystemTime, timings: &[Timing]) {
+    let Some(endpoint) = get_endpoint() else { return };
+    let body = get_body(input, start, SystemTime::now(), timings);
+    debug!("Sending {} spans to {endpoint}", timings.len() + 1);
+    let mut request = http::client().post(&endpoint).json(&body).timeout(Duration::from_secs(3));
+    for (key, value) in get_headers(&env::var("OTEL_EXPORTER_OTLP_HEADERS").unwrap_or_default()) {
+        request = request.header(key, value);
+    }
+    match request.send().await {
+        Ok(res) if res.status().is_success() => {}
+        Ok(res) => warn!("Unable to send spans to {endpoint}: {}", res.status()),
+        Err(e) => warn!("Unable to send spans to {endpoint}: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_body() {
+        let start = UNIX_EPOCH + Duration::from_secs(1000);
+        let timings = vec!(Timing { name: "node".to_string(), phase: "download", duration: Duration::from_secs(2), end: start + Duration::from_secs(3) });
+        let body = get_body(&AppInput::dummy(), start, start + Duration::from_secs(4), &timings);
+        let spans = &body["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(2, spans.as_array().unwrap().len());
+        assert_eq!("download node", spans[1]["name"]);
+        assert_eq!("1001000000000", spans[1]["startTimeUnixNano"]);
+        assert_eq!("1003000000000", spans[1]["endTimeUnixNano"]);
+        assert_eq!(spans[0]["spanId"], spans[1]["parentSpanId"]);
+        assert_eq!(32, spans[0]["traceId"].as_str().unwrap().len());
+    }
+
+    #[test]
+    fn test_get_headers() {
+        assert_eq!(vec!(("x-api-key".to_string(), "abc".to_string()), ("team".to_string(), "a=b".to_string())), get_headers("x-api-key=abc, team=a=b"));
+    }
+}
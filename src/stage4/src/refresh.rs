@@ -0,0 +1,54 @@
+use std::process::ExitCode;
+
+use crate::executor::{AppInput, Executor, ExecutorCmd, get_url_matches, resolve_version_req};
+use crate::index_cache;
+use crate::resolve_cache;
+
+/// Forgets resolved versions and cached version lists and fetches them again, for when a new release does not show up
+pub async fn refresh(input: &AppInput, tool: Option<&str>) -> ExitCode {
+    println!("Cleared {} cached version list(s)", index_cache::clear());
+    let tools: Vec<String> = match tool {
+        Some(tool) => vec!(tool.to_string()),
+        None => input.config.tools.keys().cloned().collect(),
+    };
+
+    let tool_given = tool.is_some();
+    if !tool_given {
+        println!("Cleared {} resolved version(s)", resolve_cache::clear(None));
+    }
+
+    for tool in tools {
+        let executor = match <dyn Executor>::new(ExecutorCmd {
+            cmd: tool.clone(),
+            version: None,
+            include_tags: Default::default(),
+            exclude_tags: Default::default(),
+        }) {
+            Some(executor) => executor,
+            None => {
+                println!("{tool}: Unknown tool");
+                return ExitCode::from(1);
+            }
+        };
+        let name = executor.get_name();
+        if tool_given {
+            println!("Cleared {} resolved version(s) of {name}", resolve_cache::clear(Some(name)));
+        }
+        let input = &input.for_executor(name);
+        let version_req = resolve_version_req(&*executor, input);
+        let urls = match executor.get_download_urls(input).await {
+            Ok(urls) => urls,
+            Err(e) => {
+                eprintln!("{}", e.to_diagnostic());
+                return ExitCode::from(1);
+            }
+        };
+        let matches = get_url_matches(&urls, input, &*executor, &version_req);
+        let version_req = version_req.map(|v| v.to_string()).unwrap_or("*".to_string());
+        match matches.first().and_then(|d| d.version.clone()) {
+            Some(newest) => println!("{name}: {} versions, newest matching {version_req} is {}", urls.len(), newest.to_string()),
+            None => println!("{name}: {} versions, none matching {version_req}", urls.len()),
+        }
+    }
+    ExitCode::from(0)
+}
@@ -0,0 +1,110 @@
+use std::env;
+
+use log::{debug, info, warn};
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH};
+use reqwest::RequestBuilder;
+
+use crate::config::GgConfig;
+use crate::http;
+
+/// Lets `gg serve` fetch from upstream when it does not have the file yet
+pub const UPSTREAM_HEADER: &str = "X-Gg-Upstream";
+/// SHA-256 of an upload, `gg serve` stores it only when the body matches
+pub const SHA256_HEADER: &str = "X-Gg-Sha256";
+
+/// Only http(s)://, with GG_REMOTE_CACHE_TOKEN as bearer token. Buckets need an HTTP gateway, gg does not sign requests
+fn to_http_base(remote: &str) -> Result<String, String> {
+    let remote = remote.trim_end_matches('/');
+    if remote.starts_with("http://") || remote.starts_with("https://") {
+        Ok(remote.to_string())
+    } else {
+        Err(format!("Remote cache {remote} is not supported, use an http(s):// URL"))
+    }
+}
+
+pub struct RemoteCache {
+    base: String,
+    upload: bool,
+    token: Option<String>,
+}
+
+impl RemoteCache {
+    /// From GG_REMOTE_CACHE, or [cache] remote in gg.toml
+    pub fn from_config(config: &GgConfig) -> Option<Self> {
+        let remote = env::var("GG_REMOTE_CACHE").ok().or(config.cache.remote.clone())?;
+        let upload = env::var("GG_REMOTE_CACHE_UPLOAD").map(|v| v == "true" || v == "1").unwrap_or(config.cache.upload);
+        let base = to_http_base(&remote).map_err(|e| warn!("{e}")).ok()?;
+        Some(Self { base, upload, token: env::var("GG_REMOTE_CACHE_TOKEN").ok() })
+    }
+
+    /// Keyed by hash of the upstream URL, keeping the file name so the extension is the same
+    pub fn get_url(&self, url: &str) -> String {
+        let file_name = url.rsplit('/').next().unwrap_or("download");
+        let hash = sha256::digest(url);
+        format!("{}/{}/{file_name}", self.base, &hash[0..16])
+    }
+
+    fn auth(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    pub async fn exists(&self, url: &str) -> bool {
+        let remote_url = self.get_url(url);
+        debug!("Checking remote cache {remote_url}");
+        let request = http::client().head(&remote_url).header(UPSTREAM_HEADER, url);
+        match http::send(self.auth(request)).await {
+            Ok(res) => res.status().is_success(),
+            Err(e) => {
+                warn!("Unable to reach remote cache: {e}");
+                false
+            }
+        }
+    }
+
+    pub async fn upload(&self, url: &str, file_path: &str) {
+        if !self.upload {
+            return;
+        }
+        let remote_url = self.get_url(url);
+        info!("Uploading {file_path} to remote cache {remote_url}");
+        let file = match tokio::fs::File::open(file_path).await {
+            Ok(file) => file,
+            Err(e) => return warn!("Unable to open {file_path}: {e}"),
+        };
+        let sha256 = match sha256::try_digest(std::path::Path::new(file_path)) {
+            Ok(sha256) => sha256,
+            Err(e) => return warn!("Unable to read {file_path}: {e}"),
+        };
+        let len = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+        let request = http::client().put(&remote_url).header(CONTENT_LENGTH, len).header(SHA256_HEADER, sha256).body(file);
+        match http::send(self.auth(request)).await {
+            Ok(res) if res.status().is_success() => info!("Uploaded to remote cache"),
+            Ok(res) => warn!("Unable to upload to remote cache: {}", res.status()),
+            Err(e) => warn!("Unable to upload to remote cache: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_http_base() {
+        assert_eq!(Ok("https://cache.example.com/gg".to_string()), to_http_base("https://cache.example.com/gg/"));
+        assert_eq!(Ok("http://10.0.0.2:8080".to_string()), to_http_base("http://10.0.0.2:8080"));
+        assert!(to_http_base("s3://my-bucket/gg").is_err());
+        assert!(to_http_base("gs://my-bucket/gg").is_err());
+    }
+
+    #[test]
+    fn test_get_url() {
+        let cache = RemoteCache { base: "https://cache.example.com".to_string(), upload: false, token: None };
+        let url = cache.get_url("https://nodejs.org/dist/v18.19.0/node-v18.19.0-linux-x64.tar.gz");
+        assert!(url.starts_with("https://cache.example.com/"));
+        assert!(url.ends_with("/node-v18.19.0-linux-x64.tar.gz"));
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::debug;
+
+use crate::daemon;
+use crate::executor::{AppInput, AppPath, Executor};
+use crate::tool_versions;
+
+const RESOLVE_DIR: &str = ".cache/gg/resolved";
+
+/// Size and modification time, so the files don't have to be read or parsed
+fn get_file_stamp(path: &Path) -> String {
+    match fs::metadata(path) {
+        Ok(meta) => {
+            let modified = meta.modified().ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            format!("{} {} {modified}", path.display(), meta.len())
+        }
+        Err(_) => format!("{} missing", path.display()),
+    }
+}
+
+/// Hash of everything the resolved version depends on. gg.toml and gg.lock are already loaded, so their
+/// entries are used directly, project files like .nvmrc only by size and modification time
+pub fn get_key(executor: &dyn Executor, input: &AppInput) -> String {
+    let name = executor.get_name();
+    let cmd = executor.get_executor_cmd();
+    let mut include_tags: Vec<&String> = cmd.include_tags.iter().collect();
+    include_tags.sort();
+    let mut exclude_tags: Vec<&String> = cmd.exclude_tags.iter().collect();
+    exclude_tags.sort();
+    let mut parts = vec!(
+        name.to_string(),
+        input.target.key(),
+        format!("{:?} {include_tags:?} {exclude_tags:?} {}", cmd.version, input.no_clap.lts),
+        format!("{:?}", input.config.tools.get(name).map(|v| input.config.resolve_version(v))),
+        format!("{:?}", input.lock.tools.get(name)),
+        format!("{:?}", input.config.executor(name)),
+        format!("{:?}", executor.get_flavor(input)),
+    );
+    parts.extend(executor.get_version_inputs().iter().chain(&tool_versions::get_files()).map(|p| get_file_stamp(p)));
+    format!("{name}-{}", sha256::digest(parts.join("\n")))
+}
+
+pub fn get(key: &str) -> Option<AppPath> {
+    let install_dir = daemon::get(&format!("resolved/{key}"))
+        .or_else(|| fs::read_to_string(Path::new(RESOLVE_DIR).join(key)).ok())
+        .map(PathBuf::from)?;
+    if install_dir.exists() {
+        debug!("Resolved {} from cache", install_dir.display());
+        Some(AppPath { install_dir })
+    } else {
+        None
+    }
+}
+
+pub fn set(key: &str, app_path: &AppPath) {
+    daemon::set(&format!("resolved/{key}"), &app_path.install_dir.to_string_lossy());
+    if fs::create_dir_all(RESOLVE_DIR).is_ok() {
+        let _ = fs::write(Path::new(RESOLVE_DIR).join(key), app_path.install_dir.to_string_lossy().as_bytes());
+    }
+}
+
+/// Forget resolved versions of one or all tools, returns how many were removed
+pub fn clear(name: Option<&str>) -> usize {
+    daemon::clear();
+    let prefix = name.map(|name| format!("{name}-"));
+    fs::read_dir(RESOLVE_DIR).into_iter().flatten().flatten()
+        .filter(|entry| prefix.as_ref().map(|p| entry.file_name().to_string_lossy().starts_with(p)).unwrap_or(true))
+        .filter(|entry| fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+#[cfg(all(test, feature = "node"))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use indicatif::ProgressBar;
+
+    use super::*;
+    use crate::bloody_indiana_jones::COMPLETE_MARKER;
+    use crate::executor::{ExecutorCmd, prep};
+
+    fn node() -> Box<dyn Executor> {
+        <dyn Executor>::new(ExecutorCmd { cmd: "node".to_string(), ..ExecutorCmd::dummy() }).unwrap()
+    }
+
+    #[test]
+    fn key_changes_with_inputs() {
+        let input = AppInput::dummy();
+        let key = get_key(&*node(), &input);
+        assert_eq!(key, get_key(&*node(), &input));
+        assert!(key.starts_with("node-"));
+        let mut input = AppInput::dummy();
+        input.config.tools.insert("node".to_string(), "^18".to_string());
+        assert_ne!(key, get_key(&*node(), &input));
+    }
+
+    /// Running an installed tool again with nothing changed is the common case, it must not wait for gg
+    #[tokio::test]
+    async fn hot_path_is_fast() {
+        let dir = std::env::temp_dir().join(format!("gg-resolve-cache-hot-{}", std::process::id()));
+        let install_dir = dir.join("node").join("20.11.1");
+        fs::create_dir_all(&install_dir).unwrap();
+        fs::write(install_dir.join(COMPLETE_MARKER), "").unwrap();
+        let mut input = AppInput::dummy();
+        input.config.cache.install_dir = Some(dir.to_string_lossy().to_string());
+        input.config.tools.insert("node".to_string(), "20.11.1".to_string());
+        let pb = ProgressBar::hidden();
+        assert_eq!(install_dir, prep(&*node(), &input, &pb).await.unwrap().install_dir);
+
+        let runs = 100;
+        let start = Instant::now();
+        for _ in 0..runs {
+            assert_eq!(install_dir, prep(&*node(), &input, &pb).await.unwrap().install_dir);
+        }
+        let per_run = start.elapsed() / runs;
+        let _ = fs::remove_file(Path::new(RESOLVE_DIR).join(get_key(&*node(), &input)));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(per_run < Duration::from_millis(10), "{:?} per run", per_run);
+    }
+}
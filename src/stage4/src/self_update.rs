@@ -0,0 +1,246 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+use flate2::read::GzDecoder;
+use indicatif::ProgressBar;
+use log::{info, warn};
+use sha2::{Digest, Sha512};
+
+use crate::barus::create_barus;
+use crate::bloody_indiana_jones::BloodyIndianaJones;
+use crate::bundle::{get_gg_dir, get_stage1_len};
+use crate::executor::AppInput;
+use crate::http;
+use crate::mirror::mirror_url;
+use crate::target::{Arch, Os, Target, Variant};
+
+const GG_CMD: &str = "gg.cmd";
+const GG_CMD_NEW: &str = "gg.cmd.new";
+/// The staging dir of an update is in here, on the same file system as the gg dir it is moved to
+const GG_CACHE: &str = ".cache/gg";
+/// Where stage 3 downloads stage 4 from, by its SHA-512
+const STAGE4_HOST: &str = "https://ggcmd.z13.web.core.windows.net";
+
+struct Release {
+    tag: String,
+    gg_cmd_url: String,
+    checksum_url: Option<String>,
+}
+
+/// stable is the newest release, beta includes pre-releases
+async fn get_release(channel: &str) -> Result<Release, String> {
+    let releases = http::get_releases("eirikb", "gg", 1, 20).await?;
+    let release = releases.into_iter()
+        .filter(|r| !r.draft && (channel == "beta" || !r.prerelease))
+        .find(|r| r.assets.iter().any(|a| a.name == GG_CMD))
+        .ok_or(format!("No {channel} release found"))?;
+    let asset_url = |name: &str| release.assets.iter().find(|a| a.name == name).map(|a| a.browser_download_url.to_string());
+    Ok(Release {
+        tag: release.tag_name.clone(),
+        gg_cmd_url: asset_url(GG_CMD).unwrap_or_default(),
+        checksum_url: asset_url(&format!("{GG_CMD}.sha256")),
+    })
+}
+
+/// First word of a sha256sum line, e.g. `<hash>  gg.cmd`
+fn parse_checksum(text: &str) -> Option<String> {
+    text.split_whitespace().next().map(|s| s.to_lowercase()).filter(|s| s.len() == 64)
+}
+
+/// A release without gg.cmd.sha256 is refused, unless `--skip-checksum` is given
+async fn verify_checksum(release: &Release, skip_checksum: bool) -> Result<(), String> {
+    let Some(checksum_url) = &release.checksum_url else {
+        if skip_checksum {
+            warn!("Release {} has no checksum, updating without verifying it as --skip-checksum is given", release.tag);
+            return Ok(());
+        }
+        return Err(format!("Release {} has no {GG_CMD}.sha256 to verify it against. Use --skip-checksum to update anyway", release.tag));
+    };
+    let text = http::get(checksum_url).await.map_err(|e| e.to_string())?.text().await.map_err(|e| e.to_string())?;
+    let expected = parse_checksum(&text).ok_or("Invalid checksum file")?;
+    let actual = sha256::try_digest(std::path::Path::new(GG_CMD_NEW)).map_err(|e| e.to_string())?;
+    if expected != actual {
+        return Err(format!("Checksum mismatch, expected {expected} but got {actual}"));
+    }
+    info!("Checksum OK {actual}");
+    Ok(())
+}
+
+/// Line of the hashes file in gg.cmd for the target, e.g. stage4_x86_64-unknown-linux-musl=<sha512>
+fn find_hash(hashes: &str, target: &Target) -> Option<String> {
+    let os = match target.os {
+        Os::Linux => "linux",
+        Os::Mac => "darwin",
+        Os::Windows => "windows",
+        Os::Any => return None,
+    };
+    let musl = target.variant == Some(Variant::Musl);
+    let find = |arch: Arch| {
+        let arch = match arch {
+            Arch::X86_64 => "x86_64",
+            Arch::Arm64 => "aarch64",
+            Arch::Armv7 => "armv7",
+            _ => return None,
+        };
+        hashes.lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(name, _)| name.contains(arch) && name.contains(os) && name.contains("musl") == musl)
+            .map(|(_, hash)| hash.trim().to_lowercase())
+    };
+    find(target.arch).or_else(|| find(target.get_emulated_arch()?))
+}
+
+fn sha512_of(path: &Path) -> Result<String, String> {
+    let data = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Sha512::digest(data).iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Unpacks .cache/gg/gg-<version> from the new gg.cmd into the staging dir, as stage 1 would in the project. Relative
+/// to the staging dir
+fn unpack_gg_dir(staging: &Path) -> Result<PathBuf, String> {
+    let gg_cmd = fs::read(GG_CMD_NEW).map_err(|e| e.to_string())?;
+    let stage1_len = get_stage1_len(&gg_cmd).ok_or("Unable to find end of stage 1 in the new gg.cmd")?;
+    let mut archive = vec!();
+    GzDecoder::new(&gg_cmd[stage1_len..]).read_to_end(&mut archive).map_err(|e| e.to_string())?;
+    let gg_dir = get_gg_dir(&archive).ok_or("Unable to find gg version in the new gg.cmd")?;
+    tar::Archive::new(archive.as_slice()).unpack(staging).map_err(|e| e.to_string())?;
+    Ok(gg_dir)
+}
+
+/// Stage 4 of the new version for this system, checked against its SHA-512 in the hashes file, so the new gg.cmd
+/// doesn't have to download it on first run. From GG_STAGE4_MIRROR or mirror in [stage4] when set
+async fn prefetch_stage4(gg_dir: &Path, target: &Target, input: &AppInput, pb: ProgressBar) -> Result<(), String> {
+    let hashes = fs::read_to_string(gg_dir.join("hashes")).unwrap_or_default();
+    let Some(hash) = find_hash(&hashes, target) else {
+        warn!("No stage 4 for {} in the new gg.cmd, it is fetched on first run", target.key());
+        return Ok(());
+    };
+    let stage4 = gg_dir.join(if target.os == Os::Windows { "stage4.exe" } else { "stage4" });
+    let part = gg_dir.join("stage4.new");
+    let url = format!("{}/{hash}", mirror_url(input, "stage4", STAGE4_HOST));
+    BloodyIndianaJones::new_with_file_name(url, part.to_string_lossy().to_string(), pb).download().await?;
+    let actual = sha512_of(&part)?;
+    if actual != hash {
+        let _ = fs::remove_file(&part);
+        return Err(format!("Stage 4 checksum mismatch, expected {hash} but got {actual}"));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&part, fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&part, &stage4).map_err(|e| e.to_string())?;
+    info!("Stage 4 OK {}", stage4.display());
+    Ok(())
+}
+
+/// The new gg.cmd must be able to bootstrap itself from the staging dir and print its version
+fn self_check(staging: &Path) -> bool {
+    if fs::copy(GG_CMD_NEW, staging.join(GG_CMD)).is_err() {
+        return false;
+    }
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", GG_CMD, "-V"]).current_dir(staging).output()
+    } else {
+        Command::new("sh").args([GG_CMD, "-V"]).current_dir(staging).output()
+    };
+    match output {
+        Ok(output) => output.status.success() && !String::from_utf8_lossy(&output.stdout).trim().is_empty(),
+        Err(_) => false,
+    }
+}
+
+/// Moves the checked gg dir from the staging dir into the project, over one of the same version left from before
+fn move_gg_dir(staging: &Path, gg_dir: &Path) -> Result<(), String> {
+    if let Some(parent) = gg_dir.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let old = PathBuf::from(format!("{}.old", gg_dir.display()));
+    let has_old = fs::rename(gg_dir, &old).is_ok();
+    if let Err(e) = fs::rename(staging.join(gg_dir), gg_dir) {
+        if has_old {
+            let _ = fs::rename(&old, gg_dir);
+        }
+        return Err(format!("Unable to move {} in place: {e}", gg_dir.display()));
+    }
+    if has_old {
+        let _ = fs::remove_dir_all(&old);
+    }
+    Ok(())
+}
+
+/// Everything is unpacked, fetched and checked in a staging dir next to the gg dir first, so a failed update leaves
+/// the project as it was
+async fn stage_and_check(release: &Release, skip_checksum: bool, staging: &Path, target: &Target, input: &AppInput, pb: ProgressBar) -> Result<PathBuf, String> {
+    BloodyIndianaJones::new_with_file_name(release.gg_cmd_url.clone(), GG_CMD_NEW.to_string(), pb.clone()).download().await?;
+    verify_checksum(release, skip_checksum).await?;
+    let gg_dir = unpack_gg_dir(staging)?;
+    prefetch_stage4(&staging.join(&gg_dir), target, input, pb.clone()).await?;
+    pb.finish_and_clear();
+    if !self_check(staging) {
+        return Err(format!("{} failed self-check, gg.cmd is left as it was", release.tag));
+    }
+    Ok(gg_dir)
+}
+
+async fn update_to(channel: &str, skip_checksum: bool, target: &Target, input: &AppInput) -> Result<String, String> {
+    let release = get_release(channel).await?;
+    println!("Updating gg.cmd to {} ({channel})...", release.tag);
+    fs::create_dir_all(GG_CACHE).map_err(|e| e.to_string())?;
+    let staging = tempfile::Builder::new().prefix("update-").tempdir_in(GG_CACHE).map_err(|e| e.to_string())?;
+    let gg_dir = match stage_and_check(&release, skip_checksum, staging.path(), target, input, create_barus()).await {
+        Ok(gg_dir) => gg_dir,
+        Err(e) => {
+            let _ = fs::remove_file(GG_CMD_NEW);
+            return Err(e);
+        }
+    };
+    move_gg_dir(staging.path(), &gg_dir)?;
+    // rename replaces the file in one step, also on Windows, so gg.cmd is never missing
+    fs::rename(GG_CMD_NEW, GG_CMD).map_err(|e| e.to_string())?;
+    Ok(release.tag)
+}
+
+pub async fn update(args: &[String], target: &Target, input: &AppInput) -> ExitCode {
+    let skip_checksum = args.iter().any(|a| a == "--skip-checksum");
+    let channel = args.iter().find(|a| *a != "--skip-checksum").map(|c| c.as_str()).unwrap_or("stable");
+    if channel != "stable" && channel != "beta" {
+        println!("Unknown channel {channel}. Use stable or beta");
+        return ExitCode::from(1);
+    }
+    match update_to(channel, skip_checksum, target, input).await {
+        Ok(tag) => {
+            println!("Updated gg.cmd to {tag}");
+            ExitCode::from(0)
+        }
+        Err(e) => {
+            println!("Unable to update: {e}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_checksum() {
+        let hash = "a".repeat(64);
+        assert_eq!(Some(hash.clone()), parse_checksum(&format!("{hash}  gg.cmd\n")));
+        assert_eq!(Some(hash.clone()), parse_checksum(&hash.to_uppercase()));
+        assert_eq!(None, parse_checksum("nope"));
+    }
+
+    #[test]
+    fn test_find_hash() {
+        let hashes = "stage4_x86_64-unknown-linux-gnu=aa\nstage4_x86_64-unknown-linux-musl=bb\nstage4_aarch64-apple-darwin=cc\nstage4_x86_64-pc-windows-msvc=dd\n";
+        assert_eq!(Some("aa".to_string()), find_hash(hashes, &Target { arch: Arch::X86_64, os: Os::Linux, variant: None }));
+        assert_eq!(Some("bb".to_string()), find_hash(hashes, &Target { arch: Arch::X86_64, os: Os::Linux, variant: Some(Variant::Musl) }));
+        assert_eq!(Some("cc".to_string()), find_hash(hashes, &Target { arch: Arch::Arm64, os: Os::Mac, variant: None }));
+        assert_eq!(Some("dd".to_string()), find_hash(hashes, &Target { arch: Arch::Arm64, os: Os::Windows, variant: None }));
+        assert_eq!(None, find_hash(hashes, &Target { arch: Arch::Arm64, os: Os::Linux, variant: None }));
+    }
+}
@@ -0,0 +1,348 @@
+use std::env;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use futures_util::StreamExt;
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use reqwest::Url;
+use tokio::net::{lookup_host, TcpListener, TcpStream};
+
+use crate::executor::AppInput;
+use crate::http;
+use crate::remote_cache::{SHA256_HEADER, UPSTREAM_HEADER};
+
+const SERVE_DIR: &str = ".cache/gg/serve";
+/// Uploads are only accepted with this as bearer token, without it the cache is read only
+const TOKEN_ENV: &str = "GG_SERVE_TOKEN";
+/// Hosts the executors download archives from. Others only as a mirror of gg serve itself
+const UPSTREAM_HOSTS: [&str; 28] = [
+    "nodejs.org", "unofficial-builds.nodejs.org", "registry.npmjs.org", "github.com", "cdn.azul.com",
+    "download.bell-sw.com", "corretto.aws", "services.gradle.org", "archive.apache.org", "repo.maven.apache.org",
+    "repo1.maven.org", "go.dev", "dl.google.com", "releases.hashicorp.com", "dl.k8s.io", "get.helm.sh",
+    "static.rust-lang.org", "dotnetcli.blob.core.windows.net", "builds.dotnet.microsoft.com",
+    "download.visualstudio.microsoft.com", "storage.googleapis.com", "windows.php.net", "dl.static-php.dev",
+    "getcomposer.org", "builds.hex.pm", "repo.hex.pm", "ratbinsa.z1.web.core.windows.net",
+    "ggcmd.z13.web.core.windows.net",
+];
+
+struct Request {
+    method: String,
+    path: String,
+    upstream: Option<String>,
+    authorization: Option<String>,
+    sha256: Option<String>,
+    content_length: u64,
+}
+
+/// Only <hash>/<file name> as created by RemoteCache::get_url
+fn get_store_path(path: &str) -> Option<PathBuf> {
+    let (hash, file_name) = path.trim_start_matches('/').split_once('/')?;
+    let valid_hash = hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit());
+    let valid_file_name = !file_name.is_empty() && !file_name.contains(['/', '\\']) && !file_name.starts_with('.');
+    if !valid_hash || !valid_file_name {
+        return None;
+    }
+    Some(PathBuf::from(SERVE_DIR).join(hash).join(file_name))
+}
+
+/// The upstream URL must be the one the key was created from, so this is not an open proxy
+fn upstream_matches(path: &str, upstream: &str) -> bool {
+    let file_name = upstream.rsplit('/').next().unwrap_or("download");
+    let hash = sha256::digest(upstream);
+    path.trim_start_matches('/') == format!("{}/{file_name}", &hash[0..16])
+}
+
+/// Hosts of GG_<TOOL>_MIRROR and mirror in gg.toml of gg serve
+fn get_mirror_hosts(input: &AppInput) -> Vec<String> {
+    env::vars().filter(|(name, _)| name.starts_with("GG_") && name.ends_with("_MIRROR")).map(|(_, mirror)| mirror)
+        .chain(input.config.executors.values().filter_map(|executor| executor.mirror.clone()))
+        .filter_map(|mirror| Url::parse(mirror.trim()).ok()?.host_str().map(|host| host.to_lowercase()))
+        .collect()
+}
+
+/// Only https from the known download hosts and the mirrors, so clients can't make gg serve fetch from anywhere
+fn upstream_allowed(upstream: &str, mirror_hosts: &[String]) -> bool {
+    let Ok(url) = Url::parse(upstream) else { return false };
+    let Some(host) = url.host_str().map(|host| host.to_lowercase()) else { return false };
+    url.scheme() == "https" && (UPSTREAM_HOSTS.contains(&host.as_str()) || mirror_hosts.contains(&host))
+}
+
+/// Not loopback, private, link-local etc., so an upstream can't reach the network gg serve runs in
+fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64;
+            !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+                || ip.is_documentation() || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(&IpAddr::V4(ip)),
+            None => {
+                let unique_local = (ip.segments()[0] & 0xfe00) == 0xfc00;
+                let link_local = (ip.segments()[0] & 0xffc0) == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// Every address the host of the upstream resolves to must be public
+async fn resolves_to_public(upstream: &str) -> bool {
+    let Ok(url) = Url::parse(upstream) else { return false };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else { return false };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let ips: Vec<IpAddr> = match lookup_host((host, port)).await {
+        Ok(addrs) => addrs.map(|addr| addr.ip()).collect(),
+        Err(_) => vec!(),
+    };
+    !ips.is_empty() && ips.iter().all(is_public)
+}
+
+/// `8080` on 127.0.0.1, or the address as given, e.g. `0.0.0.0:8080` for other machines
+fn get_bind_addr(arg: Option<&String>) -> String {
+    match arg {
+        Some(addr) if addr.contains(':') => addr.to_string(),
+        Some(port) => format!("127.0.0.1:{port}"),
+        None => "127.0.0.1:8080".to_string(),
+    }
+}
+
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+    let mut upstream = None;
+    let mut authorization = None;
+    let mut sha256 = None;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await.ok()? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim().to_string();
+            match name.trim().to_lowercase().as_str() {
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                "authorization" => authorization = Some(value),
+                n if n == UPSTREAM_HEADER.to_lowercase() => upstream = Some(value),
+                n if n == SHA256_HEADER.to_lowercase() => sha256 = Some(value),
+                _ => {}
+            }
+        }
+    }
+    Some(Request { method, path, upstream, authorization, sha256, content_length })
+}
+
+/// An upload needs the token of gg serve and the SHA-256 of the body, returns the expected SHA-256 or the status
+fn check_upload(token: Option<&str>, authorization: Option<&str>, sha256: Option<&str>) -> Result<String, &'static str> {
+    let Some(token) = token else {
+        return Err("403 Forbidden");
+    };
+    if authorization != Some(format!("Bearer {token}").as_str()) {
+        return Err("401 Unauthorized");
+    }
+    match sha256 {
+        Some(sha256) if sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit()) => Ok(sha256.to_lowercase()),
+        _ => Err("400 Bad Request"),
+    }
+}
+
+/// Writes the body to the part file, returns its SHA-256
+async fn store_body(stream: &mut BufReader<TcpStream>, content_length: u64, tmp_path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::create(tmp_path).await?;
+    let mut body = stream.take(content_length);
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = body.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        file.write_all(&buf[..n]).await?;
+    }
+    file.flush().await?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn fetch_upstream(upstream: &str, store_path: &PathBuf) -> Result<(), String> {
+    info!("Fetching {upstream}");
+    let res = http::get(upstream).await.map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("{upstream} returned {}", res.status()));
+    }
+    let tmp_path = store_path.with_extension("part");
+    let mut file = fs::File::create(&tmp_path).await.map_err(|e| e.to_string())?;
+    let mut stream = res.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+    }
+    fs::rename(&tmp_path, store_path).await.map_err(|e| e.to_string())
+}
+
+async fn respond(stream: &mut BufReader<TcpStream>, status: &str, content_length: u64) -> std::io::Result<()> {
+    let head = format!("HTTP/1.1 {status}\r\nContent-Length: {content_length}\r\nConnection: close\r\n\r\n");
+    stream.get_mut().write_all(head.as_bytes()).await
+}
+
+async fn handle(stream: TcpStream, token: Option<String>, mirror_hosts: Vec<String>) -> std::io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let Some(request) = read_request(&mut stream).await else {
+        return respond(&mut stream, "400 Bad Request", 0).await;
+    };
+    debug!("{} {}", request.method, request.path);
+    let Some(store_path) = get_store_path(&request.path) else {
+        return respond(&mut stream, "404 Not Found", 0).await;
+    };
+    if let Some(parent) = store_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    match request.method.as_str() {
+        "HEAD" | "GET" => {
+            if !store_path.exists() {
+                match &request.upstream {
+                    Some(upstream) if upstream_matches(&request.path, upstream) => {
+                        if !upstream_allowed(upstream, &mirror_hosts) || !resolves_to_public(upstream).await {
+                            warn!("Refused to fetch {upstream}, only https from download hosts and mirrors with public addresses");
+                            return respond(&mut stream, "403 Forbidden", 0).await;
+                        }
+                        if let Err(e) = fetch_upstream(upstream, &store_path).await {
+                            warn!("Unable to fetch upstream: {e}");
+                            return respond(&mut stream, "502 Bad Gateway", 0).await;
+                        }
+                    }
+                    _ => return respond(&mut stream, "404 Not Found", 0).await,
+                }
+            }
+            let mut file = fs::File::open(&store_path).await?;
+            respond(&mut stream, "200 OK", file.metadata().await?.len()).await?;
+            if request.method == "GET" {
+                tokio::io::copy(&mut file, stream.get_mut()).await?;
+            }
+            Ok(())
+        }
+        "PUT" => {
+            let expected = match check_upload(token.as_deref(), request.authorization.as_deref(), request.sha256.as_deref()) {
+                Ok(expected) => expected,
+                Err(status) => {
+                    warn!("Rejected upload of {}: {status}", request.path);
+                    return respond(&mut stream, status, 0).await;
+                }
+            };
+            let tmp_path = store_path.with_extension("part");
+            let actual = store_body(&mut stream, request.content_length, &tmp_path).await?;
+            if actual != expected {
+                warn!("Rejected upload of {}: SHA-256 is {actual}, expected {expected}", request.path);
+                fs::remove_file(&tmp_path).await?;
+                return respond(&mut stream, "422 Unprocessable Entity", 0).await;
+            }
+            fs::rename(&tmp_path, &store_path).await?;
+            info!("Stored {}", store_path.display());
+            respond(&mut stream, "201 Created", 0).await
+        }
+        _ => respond(&mut stream, "405 Method Not Allowed", 0).await,
+    }
+}
+
+pub async fn serve(addr: Option<&String>, input: &AppInput) -> ExitCode {
+    let addr = get_bind_addr(addr);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("Unable to listen on {addr}: {e}");
+            return ExitCode::from(1);
+        }
+    };
+    println!("Serving {SERVE_DIR} on {addr}. Use GG_REMOTE_CACHE=http://<this host>:<port> on clients");
+    if addr.starts_with("127.0.0.1:") {
+        println!("Only this machine can connect, give an address for others, e.g. serve 0.0.0.0:8080");
+    }
+    let mirror_hosts = get_mirror_hosts(input);
+    let token = env::var(TOKEN_ENV).ok().filter(|t| !t.is_empty());
+    if token.is_none() {
+        println!("Uploads are disabled, set {TOKEN_ENV} to allow them");
+    }
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("Connection from {peer}");
+                let token = token.clone();
+                let mirror_hosts = mirror_hosts.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle(stream, token, mirror_hosts).await {
+                        warn!("Request failed: {e}");
+                    }
+                });
+            }
+            Err(e) => warn!("Unable to accept connection: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_store_path() {
+        assert_eq!(Some(PathBuf::from(SERVE_DIR).join("0123456789abcdef").join("node.tar.gz")), get_store_path("/0123456789abcdef/node.tar.gz"));
+        assert_eq!(None, get_store_path("/0123456789abcdef/../node.tar.gz"));
+        assert_eq!(None, get_store_path("/../../etc/passwd"));
+        assert_eq!(None, get_store_path("/0123456789abcdef/.hidden"));
+    }
+
+    #[test]
+    fn test_check_upload() {
+        let sha256 = "a".repeat(64);
+        assert_eq!(Err("403 Forbidden"), check_upload(None, Some("Bearer x"), Some(&sha256)));
+        assert_eq!(Err("401 Unauthorized"), check_upload(Some("x"), None, Some(&sha256)));
+        assert_eq!(Err("401 Unauthorized"), check_upload(Some("x"), Some("Bearer y"), Some(&sha256)));
+        assert_eq!(Err("400 Bad Request"), check_upload(Some("x"), Some("Bearer x"), None));
+        assert_eq!(Err("400 Bad Request"), check_upload(Some("x"), Some("Bearer x"), Some("abc")));
+        assert_eq!(Ok(sha256.clone()), check_upload(Some("x"), Some("Bearer x"), Some(&sha256.to_uppercase())));
+    }
+
+    #[test]
+    fn test_upstream_matches() {
+        let upstream = "https://nodejs.org/dist/v18.19.0/node-v18.19.0-linux-x64.tar.gz";
+        let hash = sha256::digest(upstream);
+        assert!(upstream_matches(&format!("/{}/node-v18.19.0-linux-x64.tar.gz", &hash[0..16]), upstream));
+        assert!(!upstream_matches("/0123456789abcdef/node-v18.19.0-linux-x64.tar.gz", upstream));
+    }
+
+    #[test]
+    fn test_upstream_allowed() {
+        let mirror_hosts = vec!("nexus.corp".to_string());
+        assert!(upstream_allowed("https://nodejs.org/dist/v18.19.0/node-v18.19.0-linux-x64.tar.gz", &mirror_hosts));
+        assert!(upstream_allowed("https://nexus.corp/node/v18.19.0/node-v18.19.0-linux-x64.tar.gz", &mirror_hosts));
+        assert!(!upstream_allowed("http://nodejs.org/dist/v18.19.0/node-v18.19.0-linux-x64.tar.gz", &mirror_hosts));
+        assert!(!upstream_allowed("https://169.254.169.254/latest/meta-data", &mirror_hosts));
+        assert!(!upstream_allowed("file:///etc/passwd", &mirror_hosts));
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["127.0.0.1", "10.0.0.1", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert!(!is_public(&ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["104.20.22.46", "2606:4700::6810:162e"] {
+            assert!(is_public(&ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_get_bind_addr() {
+        assert_eq!("127.0.0.1:8080", get_bind_addr(None));
+        assert_eq!("127.0.0.1:9000", get_bind_addr(Some(&"9000".to_string())));
+        assert_eq!("0.0.0.0:8080", get_bind_addr(Some(&"0.0.0.0:8080".to_string())));
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    /// Also zsh and other POSIX shells
+    Bash,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "bash" | "zsh" | "sh" | "posix" => Some(Shell::Bash),
+            "fish" => Some(Shell::Fish),
+            "powershell" | "pwsh" | "ps" => Some(Shell::Powershell),
+            _ => None,
+        }
+    }
+
+    /// From SHELL, PowerShell on Windows without it
+    fn detect() -> Self {
+        let shell = env::var("SHELL").ok()
+            .and_then(|shell| Path::new(&shell).file_name().and_then(|s| s.to_str()).and_then(Shell::parse));
+        match shell {
+            Some(shell) => shell,
+            None if cfg!(windows) => Shell::Powershell,
+            None => Shell::Bash,
+        }
+    }
+}
+
+/// --shell <shell> or --shell=<shell> among the tools, detected when not given
+pub fn get_shell(args: &[String]) -> Result<(Shell, Vec<String>), String> {
+    let mut shell = None;
+    let mut rest = vec!();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = match arg.as_str() {
+            "--shell" => args.next().cloned(),
+            a if a.starts_with("--shell=") => Some(a["--shell=".len()..].to_string()),
+            _ => {
+                rest.push(arg.clone());
+                continue;
+            }
+        };
+        let name = name.unwrap_or_default();
+        shell = Some(Shell::parse(&name).ok_or(format!("Unknown shell {name}, use bash, zsh, fish or powershell"))?);
+    }
+    Ok((shell.unwrap_or_else(Shell::detect), rest))
+}
+
+fn quote(shell: Shell, value: &str) -> String {
+    match shell {
+        Shell::Bash => format!("'{}'", value.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+        Shell::Powershell => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+/// Statements setting PATH and the tool variables, sorted so the output is stable
+pub fn render(shell: Shell, path: &str, env_vars: &HashMap<String, String>) -> String {
+    let vars: BTreeMap<&String, &String> = env_vars.iter().filter(|(key, _)| key.as_str() != "PATH").collect();
+    let mut lines = vec!(match shell {
+        Shell::Bash => format!("export PATH={}", quote(shell, path)),
+        // fish has PATH as a list
+        Shell::Fish => format!("set -gx PATH {}", path.split(':').map(|p| quote(shell, p)).collect::<Vec<_>>().join(" ")),
+        Shell::Powershell => format!("$env:PATH = {}", quote(shell, path)),
+    });
+    for (key, value) in vars {
+        lines.push(match shell {
+            Shell::Bash => format!("export {key}={}", quote(shell, value)),
+            Shell::Fish => format!("set -gx {key} {}", quote(shell, value)),
+            Shell::Powershell => format!("$env:{key} = {}", quote(shell, value)),
+        });
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_shell() {
+        let args = vec!("node".to_string(), "--shell".to_string(), "fish".to_string(), "java".to_string());
+        assert_eq!(Ok((Shell::Fish, vec!("node".to_string(), "java".to_string()))), get_shell(&args));
+        assert_eq!(Shell::Powershell, get_shell(&["--shell=pwsh".to_string()]).unwrap().0);
+        assert!(get_shell(&["--shell=cmd".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let env_vars = HashMap::from([
+            ("JAVA_HOME".to_string(), "/p/.cache/gg/java/abc".to_string()),
+            ("GG_NOTE".to_string(), "it's".to_string()),
+        ]);
+        let path = "/p/.cache/gg/java/abc/bin:/usr/bin";
+        assert_eq!("export PATH='/p/.cache/gg/java/abc/bin:/usr/bin'\nexport GG_NOTE='it'\\''s'\nexport JAVA_HOME='/p/.cache/gg/java/abc'", render(Shell::Bash, path, &env_vars));
+        assert_eq!("set -gx PATH '/p/.cache/gg/java/abc/bin' '/usr/bin'\nset -gx GG_NOTE 'it\\'s'\nset -gx JAVA_HOME '/p/.cache/gg/java/abc'", render(Shell::Fish, path, &env_vars));
+        assert_eq!("$env:PATH = '/p/.cache/gg/java/abc/bin:/usr/bin'\n$env:GG_NOTE = 'it''s'\n$env:JAVA_HOME = '/p/.cache/gg/java/abc'", render(Shell::Powershell, path, &env_vars));
+    }
+}
@@ -0,0 +1,236 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::{debug, info};
+
+use crate::diagnostics::Diagnostic;
+use crate::executor::get_sha256_from_shasums;
+use crate::http;
+
+/// Where the keys of a tool are, and the fingerprints of the ones it signs with. A signature only counts when it is
+/// by one of these, whatever else the downloaded keys contain
+#[derive(Debug, PartialEq)]
+pub struct Keys {
+    /// Armored keys, or a keybox like pubring.kbx
+    pub url: &'static str,
+    pub fingerprints: &'static [&'static str],
+}
+
+/// Keys of the Node.js releasers, as listed in the README of nodejs/node
+#[cfg_attr(not(feature = "node"), allow(dead_code))]
+pub const NODE_KEYS: Keys = Keys {
+    url: "https://github.com/nodejs/release-keys/raw/HEAD/gpg/pubring.kbx",
+    fingerprints: &[
+        "C0D6248439F1D5604AAFFB4021D900FFDB233756", // Antoine du Hamel
+        "DD792F5973C6DE52C432CBDAC77ABFA00DDBF2B7", // Juan José Arboleda
+        "CC68F5A3106FF448322E48ED27F5E38D5B0A215F", // Marco Ippolito
+        "8FCCA13FEF1D0C2E91008E09770F7A9A5AE15600", // Michaël Zasso
+        "890C08DB8579162FEE0DF9DB8BEAB4DFCF555EF4", // Rafael Gonzaga
+        "C82FA3AE1CBEDC6BE46B9360C43CEC45C17AB93C", // Richard Lau
+        "108F52B48DB57BB0CC439B2997B01419BD92F80A", // Ruy Adorno
+        "A363A499291CBBC940DD62E41F10027AF002F8B0", // Ulises Gascón
+        "4ED778F539E3634C779C87C6D7062848A1AB005C", // Beth Griggs
+        "141F07595B7B3FFE74309A937405533BE57C7D57", // Bryan English
+        "74F12602B6F1C4E913FAA37AD3A89613643B6201", // Danielle Adams
+        "1C050899334244A8AF75E53792EF661D867B9DFA", // Danielle Adams
+        "61FC681DFB92A079F1685E77973F295594EC4689", // Nicolò Ribaudo
+        "94AE36675C464D64BAFA68DD7434390BDBE9B9C5", // Colin Ihrig
+        "71DCFD284A79C3B38668286BC97EC7A07EDE3FC1", // James M Snell
+        "C4F0DFFF4E8C1A8236409D08E73BC641CC11F4C8", // Myles Borins
+        "DD8F2338BAE7501E3DD5AC78C273792F7D83545D", // Rod Vagg
+        "A48C2BEE680E841632CD4E44F07496B3EB3C1762", // Ruben Bridgewater
+        "B9E2F5981AA6E0CD28160D9FF13993A75599653C", // Shelley Vohr
+        "B9AE9905FFD7803F25714661B63B535A4C206CA9", // Evan Lucas
+        "77984A986EBC2AA786BC0F66B01FBB92821C587A", // Gibson Fahnestock
+        "93C7E9E91B49E432C2F75674B0A78B0A6C481CF6", // Isaac Z. Schlueter
+        "56730D5401028683275BD23C23EFEFE93C4CFFFE", // Italo A. Casas
+        "FD3A5288F042B6850C66B31F09FE44734EB7990E", // Jeremiah Senkpiel
+        "114F43EE0176B71C7BC219DD50A3051F888C628D", // Julien Gilli
+        "9554F04D7259F04124DE6B476D5A82AC7E37093B", // Chris Dickinson
+    ],
+};
+#[cfg_attr(not(feature = "terraform"), allow(dead_code))]
+pub const HASHICORP_KEYS: Keys = Keys {
+    url: "https://www.hashicorp.com/.well-known/pgp-key.txt",
+    fingerprints: &["C874011F0AB405110D02105534365D9472D7468F"],
+};
+#[cfg_attr(not(feature = "terraform"), allow(dead_code))]
+pub const OPENTOFU_KEYS: Keys = Keys {
+    url: "https://get.opentofu.org/opentofu.asc",
+    fingerprints: &["E3E6E43D84CB852EADB0051D0C0AF313E5FD9F80"],
+};
+#[cfg_attr(not(feature = "java"), allow(dead_code))]
+pub const ADOPTIUM_KEYS: Keys = Keys {
+    url: "https://keyserver.ubuntu.com/pks/lookup?op=get&search=0x3B04D753C9050D9A5D343F39843C48A565F8F04B",
+    fingerprints: &["3B04D753C9050D9A5D343F39843C48A565F8F04B"],
+};
+
+/// A detached signature of the download, or of a checksums file listing it, e.g. SHASUMS256.txt.sig
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    /// None when the download itself is signed
+    pub checksums_url: Option<String>,
+    pub signature_url: String,
+    pub keys: &'static Keys,
+}
+
+impl Signature {
+    #[cfg_attr(not(feature = "java"), allow(dead_code))]
+    pub fn of_download(download_url: &str, keys: &'static Keys) -> Self {
+        Self { checksums_url: None, signature_url: format!("{download_url}.sig"), keys }
+    }
+
+    #[cfg_attr(not(any(feature = "node", feature = "terraform")), allow(dead_code))]
+    pub fn of_checksums(checksums_url: String, signature_url: String, keys: &'static Keys) -> Self {
+        Self { checksums_url: Some(checksums_url), signature_url, keys }
+    }
+}
+
+/// For tools without get_signature
+pub fn unavailable(name: &str) -> String {
+    Diagnostic::new(format!("{name} publishes no signatures gg can verify"))
+        .help("Signatures are verified for node, terraform, opentofu and the temurin java vendor")
+        .help("Run without --verify-signatures to install it with the checksum check only")
+        .to_string()
+}
+
+async fn fetch(url: &str) -> Result<Vec<u8>, String> {
+    let res = http::get(url).await.and_then(|res| res.error_for_status())
+        .map_err(|e| Diagnostic::new("Unable to get signature").url(url).request_error(&e).to_string())?;
+    let bytes = res.bytes().await
+        .map_err(|e| Diagnostic::new("Unable to get signature").url(url).request_error(&e).to_string())?;
+    Ok(bytes.to_vec())
+}
+
+/// Keybox files have the magic KBXf after the first header fields
+fn is_keybox(keys: &[u8]) -> bool {
+    keys.get(8..12) == Some(b"KBXf".as_slice())
+}
+
+fn get_gpg() -> Result<PathBuf, String> {
+    which::which("gpg").map_err(|_| Diagnostic::new("gpg is needed for --verify-signatures")
+        .help("Install GnuPG, e.g. `apt install gnupg`, `brew install gnupg` or Gpg4win")
+        .to_string())
+}
+
+/// The status lines of gpg, machine readable unlike the rest of its output
+fn run_gpg(gpg: &Path, home: &Path, args: &[&OsStr]) -> Result<String, String> {
+    let output = Command::new(gpg).arg("--homedir").arg(home).args(["--batch", "--status-fd", "1"]).args(args).output()
+        .map_err(|e| format!("Unable to run gpg: {e}"))?;
+    debug!("gpg {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// `[GNUPG:] VALIDSIG <fingerprint> ... <primary key fingerprint>` of a good signature, by a subkey or the key itself.
+/// The fingerprint of one of the keys makes it count
+fn is_signed_by(status: &str, keys: &Keys) -> bool {
+    status.lines()
+        .filter_map(|line| line.strip_prefix("[GNUPG:] VALIDSIG "))
+        .flat_map(|fields| {
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            [fields.first().copied(), fields.last().copied()]
+        })
+        .flatten()
+        .any(|fingerprint| keys.fingerprints.iter().any(|f| f.eq_ignore_ascii_case(fingerprint)))
+}
+
+fn check_checksums(text: &str, file_name: &str, actual: &str) -> Result<(), String> {
+    match get_sha256_from_shasums(text, file_name) {
+        Some(expected) if expected.eq_ignore_ascii_case(actual) => Ok(()),
+        Some(expected) => Err(Diagnostic::new(format!("{file_name} does not match the signed checksums"))
+            .note("expected", expected)
+            .note("actual", actual)
+            .to_string()),
+        None => Err(Diagnostic::new(format!("{file_name} is not in the signed checksums")).to_string()),
+    }
+}
+
+async fn verify_in(home: &Path, signature: &Signature, file: &Path, file_name: &str) -> Result<(), String> {
+    let gpg = get_gpg()?;
+    fs::create_dir_all(home).map_err(|e| format!("Unable to create {}: {e}", home.display()))?;
+    let write = |name: &str, bytes: &[u8]| -> Result<PathBuf, String> {
+        let path = home.join(name);
+        fs::write(&path, bytes).map_err(|e| format!("Unable to write {}: {e}", path.display()))?;
+        Ok(path)
+    };
+    let keys = fetch(signature.keys.url).await?;
+    if is_keybox(&keys) {
+        write("pubring.kbx", &keys)?;
+    } else {
+        let keys_path = write("keys.asc", &keys)?;
+        run_gpg(&gpg, home, &["--import".as_ref(), keys_path.as_os_str()])
+            .map_err(|e| Diagnostic::new("Unable to import signing keys").url(signature.keys.url).note("gpg", e).to_string())?;
+    }
+    let signature_path = write("signature", &fetch(&signature.signature_url).await?)?;
+    let checksums = match &signature.checksums_url {
+        Some(url) => Some(fetch(url).await?),
+        None => None,
+    };
+    let signed_path = match &checksums {
+        Some(checksums) => write("checksums", checksums)?,
+        None => file.to_path_buf(),
+    };
+    let bad_signature = |e: String| Diagnostic::new(format!("Bad signature for {file_name}"))
+        .url(&signature.signature_url)
+        .note("keys", signature.keys.url)
+        .note("gpg", e)
+        .help("The download or checksums were changed, or signed with a key gg doesn't know")
+        .to_string();
+    let status = run_gpg(&gpg, home, &["--verify".as_ref(), signature_path.as_os_str(), signed_path.as_os_str()]).map_err(bad_signature)?;
+    if !is_signed_by(&status, signature.keys) {
+        return Err(bad_signature(format!("not signed by any of {}", signature.keys.fingerprints.join(", "))));
+    }
+    if let Some(checksums) = checksums {
+        let actual = sha256::try_digest(file)
+            .map_err(|e| Diagnostic::new(format!("Unable to read '{}'", file.display())).note("cause", e).to_string())?;
+        check_checksums(&String::from_utf8_lossy(&checksums), file_name, &actual)?;
+    }
+    info!("Signature of {file_name} ok");
+    Ok(())
+}
+
+/// With gpg in an empty home of its own, also when several tools are verified at once
+pub async fn verify(signature: &Signature, file: &Path, file_name: &str) -> Result<(), String> {
+    let home = tempfile::Builder::new().prefix("gg-gpg-").tempdir().map_err(|e| format!("Unable to create a gpg home: {e}"))?;
+    let res = verify_in(home.path(), signature, file, file_name).await;
+    if res.is_err() {
+        let _ = fs::remove_file(file);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_keybox() {
+        assert!(is_keybox(b"\0\0\0\x20\x01\x01\0\0KBXf\0\0"));
+        assert!(!is_keybox(b"-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+    }
+
+    #[test]
+    fn test_is_signed_by() {
+        let status = "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 34365D9472D7468F HashiCorp Security\n\
+            [GNUPG:] VALIDSIG 9A34F1A9E0E2E6C1F68925D6A2D447B6E4D3A1E2 2024-01-10 1704880000 0 4 0 1 10 00 C874011F0AB405110D02105534365D9472D7468F\n";
+        assert!(is_signed_by(status, &HASHICORP_KEYS));
+        assert!(!is_signed_by(status, &OPENTOFU_KEYS));
+        assert!(!is_signed_by("[GNUPG:] GOODSIG 34365D9472D7468F HashiCorp Security\n", &HASHICORP_KEYS));
+        let status = "[GNUPG:] VALIDSIG 3B04D753C9050D9A5D343F39843C48A565F8F04B 2024-01-10 1704880000 0 4 0 1 10 00 3B04D753C9050D9A5D343F39843C48A565F8F04B\n";
+        assert!(is_signed_by(status, &ADOPTIUM_KEYS));
+    }
+
+    #[test]
+    fn test_check_checksums() {
+        let text = "abc123  node-v20.11.0-linux-x64.tar.xz\ndef456  node-v20.11.0.tar.gz\n";
+        assert!(check_checksums(text, "node-v20.11.0-linux-x64.tar.xz", "ABC123").is_ok());
+        assert!(check_checksums(text, "node-v20.11.0-linux-x64.tar.xz", "def456").is_err());
+        assert!(check_checksums(text, "node-v20.11.0-darwin-arm64.tar.gz", "abc123").is_err());
+    }
+}
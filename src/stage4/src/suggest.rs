@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+use semver::{Version, VersionReq};
+
+use crate::executor::Download;
+
+/// The version asked for, e.g. 20.11.99 for `=20.11.99` and 18.0.0 for `^18`
+fn get_wanted(version_req: &VersionReq) -> Option<Version> {
+    version_req.comparators.first().map(|c| Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0)))
+}
+
+fn get_distance(a: &Version, b: &Version) -> (u64, u64, u64) {
+    (a.major.abs_diff(b.major), a.minor.abs_diff(b.minor), a.patch.abs_diff(b.patch))
+}
+
+/// Downloads for this target closest to the version asked for, one per version.
+/// `available` is newest first, so the newer of two equally close versions comes first
+pub fn get_nearest(version_req: &Option<VersionReq>, available: &[Download], count: usize) -> Vec<Download> {
+    let mut seen = HashSet::new();
+    let mut nearest: Vec<Download> = available.iter()
+        .filter(|d| d.version.as_ref().map(|v| seen.insert(v.to_string())).unwrap_or(false))
+        .cloned()
+        .collect();
+    if let Some(wanted) = version_req.as_ref().and_then(get_wanted) {
+        nearest.sort_by_key(|d| d.version.as_ref().map(|v| get_distance(&wanted, &v.to_version())));
+    }
+    nearest.truncate(count);
+    nearest
+}
+
+fn parse_choice(answer: &str, count: usize) -> Option<usize> {
+    answer.trim().parse::<usize>().ok().filter(|n| *n >= 1 && *n <= count).map(|n| n - 1)
+}
+
+/// The closest with --yes, otherwise asks when there is someone to ask
+pub fn pick(name: &str, nearest: &[Download], yes: bool, interactive: bool) -> Option<Download> {
+    if nearest.is_empty() || (!yes && !interactive) {
+        return None;
+    }
+    if yes {
+        return nearest.first().cloned();
+    }
+    // On stderr, as stdout is the output of the tool
+    eprintln!("Closest available {name} versions:");
+    for (i, download) in nearest.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, download.version.as_ref().map(|v| v.to_string()).unwrap_or_default());
+    }
+    eprint!("Pick one [1-{}], or nothing to cancel: ", nearest.len());
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    let _ = io::stdin().lock().read_line(&mut answer);
+    parse_choice(&answer, nearest.len()).map(|i| nearest[i].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downloads(versions: &[&str]) -> Vec<Download> {
+        versions.iter().map(|v| Download::new(format!("https://example.com/{v}"), v, None)).collect()
+    }
+
+    #[test]
+    fn test_get_nearest() {
+        let available = downloads(&["21.1.0", "20.12.0", "20.11.1", "20.11.1", "20.11.0", "18.19.0"]);
+        let nearest = get_nearest(&VersionReq::parse("=20.11.99").ok(), &available, 3);
+        let versions: Vec<String> = nearest.iter().map(|d| d.version.as_ref().unwrap().to_string()).collect();
+        assert_eq!(vec!("20.11.1", "20.11.0", "20.12.0"), versions);
+        assert_eq!(5, get_nearest(&None, &available, 10).len());
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(Some(1), parse_choice("2\n", 3));
+        assert_eq!(None, parse_choice("\n", 3));
+        assert_eq!(None, parse_choice("4", 3));
+        assert_eq!(None, parse_choice("0", 3));
+    }
+}
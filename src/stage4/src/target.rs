@@ -1,10 +1,15 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq)]
 #[derive(Debug)]
 #[derive(Copy, Clone)]
 #[derive(Serialize, Deserialize)]
-pub enum Arch { X86_64, Armv7, Arm64, Any }
+pub enum Arch { X86_64, Armv7, Arm64, Riscv64, Ppc64le, S390x, Any }
 
 #[derive(PartialEq)]
 #[derive(Debug)]
@@ -18,6 +23,40 @@ pub enum Os { Windows, Linux, Mac, Any }
 #[derive(Serialize, Deserialize)]
 pub enum Variant { Musl, Any }
 
+impl Arch {
+    fn from_name(name: &str) -> Option<Arch> {
+        match name.to_lowercase().as_str() {
+            "x86_64" | "amd64" | "x64" => Some(Arch::X86_64),
+            "arm64" | "aarch64" => Some(Arch::Arm64),
+            "armv7" | "arm" => Some(Arch::Armv7),
+            "riscv64" => Some(Arch::Riscv64),
+            "ppc64le" => Some(Arch::Ppc64le),
+            "s390x" => Some(Arch::S390x),
+            _ => None,
+        }
+    }
+}
+
+impl Os {
+    fn from_name(name: &str) -> Option<Os> {
+        match name.to_lowercase().as_str() {
+            "linux" => Some(Os::Linux),
+            "mac" | "macos" | "darwin" => Some(Os::Mac),
+            "windows" | "win" => Some(Os::Windows),
+            _ => None,
+        }
+    }
+}
+
+/// musl, or gnu for none
+fn variant_from_name(name: &str) -> Option<Option<Variant>> {
+    match name.to_lowercase().as_str() {
+        "musl" => Some(Some(Variant::Musl)),
+        "gnu" | "glibc" => Some(None),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 #[derive(Copy, Clone)]
 pub struct Target {
@@ -30,10 +69,13 @@ impl Target {
     pub fn parse(input: &str) -> Target {
         let parts = input.split("-").collect::<Vec<_>>();
         return Target {
-            arch: match parts[0] {
+            arch: match parts[0].to_lowercase() {
                 x if x.contains("x86_64") => Arch::X86_64,
                 x if x.contains("arm64") => Arch::Arm64,
                 x if x.contains("aarch64") => Arch::Arm64,
+                x if x.contains("riscv64") => Arch::Riscv64,
+                x if x.contains("ppc64le") || x.contains("powerpc64le") => Arch::Ppc64le,
+                x if x.contains("s390x") => Arch::S390x,
                 _ => Arch::Armv7
             },
             os: match input.to_lowercase() {
@@ -47,13 +89,248 @@ impl Target {
             },
         };
     }
+
+    /// The system from stage2, with the real arch for x86_64 processes emulated on ARM
+    pub fn detect(system: &str) -> Target {
+        let mut target = Target::parse(system);
+        if let Some(arch) = get_native_arch() {
+            target.arch = arch;
+        }
+        let emulated = match target.os {
+            Os::Mac => is_rosetta(),
+            Os::Windows => is_windows_arm64(env::var("PROCESSOR_ARCHITEW6432").ok().as_deref(), env::var("PROCESSOR_IDENTIFIER").ok().as_deref()),
+            _ => false,
+        };
+        if target.arch == Arch::X86_64 && emulated {
+            target.arch = Arch::Arm64;
+        }
+        if target.os == Os::Linux && target.variant.is_none() && is_musl() {
+            target.variant = Some(Variant::Musl);
+        }
+        target
+    }
+
+    /// Arch that runs emulated when there is no native build: x64 on Windows on ARM, and Rosetta 2 on macOS
+    pub fn get_emulated_arch(&self) -> Option<Arch> {
+        match (self.os, self.arch) {
+            (Os::Windows | Os::Mac, Arch::Arm64) => Some(Arch::X86_64),
+            _ => None,
+        }
+    }
+
+    /// GG_TARGET as in key(), e.g. linux-arm64-musl, replaces the detected target. Then --os, --arch and --variant
+    pub fn with_overrides(self, target: Option<&str>, os: Option<&str>, arch: Option<&str>, variant: Option<&str>) -> Result<Target, String> {
+        let mut res = self;
+        if let Some(target) = target {
+            res.variant = None;
+            for part in target.split('-') {
+                if let Some(os) = Os::from_name(part) {
+                    res.os = os;
+                } else if let Some(arch) = Arch::from_name(part) {
+                    res.arch = arch;
+                } else if let Some(variant) = variant_from_name(part) {
+                    res.variant = variant;
+                } else {
+                    return Err(format!("Unknown GG_TARGET {target}, expected e.g. linux-arm64 or linux-x86_64-musl"));
+                }
+            }
+        }
+        if let Some(os) = os {
+            res.os = Os::from_name(os).ok_or(format!("Unknown --os={os}, use linux, mac or windows"))?;
+            if res.os != Os::Linux {
+                res.variant = None;
+            }
+        }
+        if let Some(arch) = arch {
+            res.arch = Arch::from_name(arch).ok_or(format!("Unknown --arch={arch}, use x86_64, arm64, armv7, riscv64, ppc64le or s390x"))?;
+        }
+        if let Some(variant) = variant {
+            res.variant = variant_from_name(variant).ok_or(format!("Unknown --variant={variant}, use musl or gnu"))?;
+        }
+        Ok(res)
+    }
+
+    /// Native, emulated or any
+    pub fn can_run(&self, arch: Arch) -> bool {
+        arch == Arch::Any || arch == self.arch || Some(arch) == self.get_emulated_arch()
+    }
+
+    /// Stable name for the target, e.g. linux-x86_64 or linux-arm64-musl
+    pub fn key(&self) -> String {
+        let variant = match self.variant {
+            Some(Variant::Musl) => "-musl",
+            _ => "",
+        };
+        format!("{:?}-{:?}{variant}", self.os, self.arch).to_lowercase()
+    }
+}
+
+/// Arches without a stage3 build, where stage4 is built for the machine itself
+fn get_native_arch() -> Option<Arch> {
+    if cfg!(target_arch = "riscv64") {
+        Some(Arch::Riscv64)
+    } else if cfg!(all(target_arch = "powerpc64", target_endian = "little")) {
+        Some(Arch::Ppc64le)
+    } else if cfg!(target_arch = "s390x") {
+        Some(Arch::S390x)
+    } else {
+        None
+    }
+}
+
+/// The loader /bin/sh runs with, e.g. /lib/ld-musl-x86_64.so.1 on Alpine. A musl loader in /lib alone is not enough,
+/// glibc systems can have musl installed next to it. ldd is only asked when /bin/sh says nothing, e.g. is static
+fn is_musl() -> bool {
+    if cfg!(not(target_os = "linux")) {
+        return false;
+    }
+    let mut head = vec!(0; 4096);
+    let interpreter = File::open("/bin/sh").and_then(|mut f| f.read(&mut head)).ok().and_then(|n| get_interpreter(&head[..n]));
+    if let Some(interpreter) = interpreter {
+        return interpreter.contains("ld-musl-");
+    }
+    Command::new("ldd").arg("--version").output()
+        .map(|o| is_musl_ldd(&format!("{}{}", String::from_utf8_lossy(&o.stdout), String::from_utf8_lossy(&o.stderr))))
+        .unwrap_or(false)
+}
+
+/// PT_INTERP of an ELF file, as far as it is in the bytes given
+fn get_interpreter(elf: &[u8]) -> Option<String> {
+    if elf.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = *elf.get(4)? == 2;
+    let is_le = *elf.get(5)? == 1;
+    let read = |at: usize, len: usize| -> Option<usize> {
+        let bytes = elf.get(at..at + len)?;
+        let value = if is_le { bytes.iter().rev().fold(0u64, |v, b| v << 8 | *b as u64) } else { bytes.iter().fold(0u64, |v, b| v << 8 | *b as u64) };
+        Some(value as usize)
+    };
+    let (phoff, phentsize, phnum) = if is_64 { (read(0x20, 8)?, read(0x36, 2)?, read(0x38, 2)?) } else { (read(0x1c, 4)?, read(0x2a, 2)?, read(0x2c, 2)?) };
+    (0..phnum).map(|i| phoff + i * phentsize).find(|&ph| read(ph, 4) == Some(3)).and_then(|ph| {
+        let (offset, size) = if is_64 { (read(ph + 0x08, 8)?, read(ph + 0x20, 8)?) } else { (read(ph + 0x04, 4)?, read(ph + 0x10, 4)?) };
+        let path = elf.get(offset..offset.checked_add(size)?)?;
+        Some(String::from_utf8_lossy(path).trim_end_matches('\0').to_string())
+    })
+}
+
+/// musl ldd prints its version to stderr, glibc ldd to stdout
+fn is_musl_ldd(output: &str) -> bool {
+    output.to_lowercase().contains("musl")
+}
+
+/// sysctl.proc_translated is 1 for processes translated by Rosetta 2, and missing on Intel Macs
+fn is_rosetta() -> bool {
+    if cfg!(not(target_os = "macos")) {
+        return false;
+    }
+    Command::new("sysctl").args(["-n", "sysctl.proc_translated"]).output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "1")
+        .unwrap_or(false)
 }
 
+/// Emulated processes see their own PROCESSOR_ARCHITECTURE, while PROCESSOR_IDENTIFIER is the real processor,
+/// e.g. `ARMv8 (64-bit) Family 8 Model D4B Revision 0, Qualcomm Technologies Inc`
+fn is_windows_arm64(architew6432: Option<&str>, identifier: Option<&str>) -> bool {
+    architew6432.map(|a| a.eq_ignore_ascii_case("ARM64")).unwrap_or(false)
+        || identifier.map(|i| i.to_uppercase().starts_with("ARMV8")).unwrap_or(false)
+}
+
+/// WSL sets WSL_DISTRO_NAME, older versions only show up in /proc/version
+pub fn is_wsl() -> bool {
+    if cfg!(not(target_os = "linux")) {
+        return false;
+    }
+    std::env::var("WSL_DISTRO_NAME").is_ok()
+        || std::fs::read_to_string("/proc/version").map(|v| v.to_lowercase().contains("microsoft")).unwrap_or(false)
+}
+
+/// Windows drives mounted in WSL, e.g. /mnt/c/Program Files/nodejs
+pub fn is_windows_mount(path: &str) -> bool {
+    let mut parts = path.split('/');
+    parts.next() == Some("") && parts.next() == Some("mnt")
+        && parts.next().map(|d| d.len() == 1 && d.chars().all(|c| c.is_ascii_alphabetic())).unwrap_or(false)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn windows_mount() {
+        assert!(is_windows_mount("/mnt/c/Program Files/nodejs"));
+        assert!(is_windows_mount("/mnt/d"));
+        assert!(!is_windows_mount("/mnt/data/bin"));
+        assert!(!is_windows_mount("/usr/bin"));
+    }
+
+    #[test]
+    fn arm64_windows() {
+        let target = Target::parse("ARM64-windows");
+        assert_eq!(Arch::Arm64, target.arch);
+        assert_eq!(Os::Windows, target.os);
+        assert_eq!(Some(Arch::X86_64), target.get_emulated_arch());
+        assert!(is_windows_arm64(None, Some("ARMv8 (64-bit) Family 8 Model D4B Revision 0, Qualcomm Technologies Inc")));
+        assert!(is_windows_arm64(Some("ARM64"), None));
+        assert!(!is_windows_arm64(None, Some("Intel64 Family 6 Model 158 Stepping 10, GenuineIntel")));
+    }
+
+    #[test]
+    fn arm64_apple_darwin() {
+        let target = Target::parse("aarch64-apple-darwin");
+        assert_eq!(Arch::Arm64, target.arch);
+        assert_eq!(Some(Arch::X86_64), target.get_emulated_arch());
+        assert_eq!(None, Target::parse("aarch64-unknown-linux-gnu").get_emulated_arch());
+    }
+
+    #[test]
+    fn other_linux_arches() {
+        assert_eq!(Arch::Riscv64, Target::parse("riscv64gc-unknown-linux-gnu").arch);
+        assert_eq!(Arch::Ppc64le, Target::parse("powerpc64le-unknown-linux-gnu").arch);
+        assert_eq!(Arch::S390x, Target::parse("s390x-linux").arch);
+        assert_eq!("linux-riscv64", Target::parse("riscv64-linux").key());
+    }
+
+    /// A 64-bit little-endian ELF header with one PT_INTERP program header
+    fn elf_with_interpreter(interpreter: &str) -> Vec<u8> {
+        let mut elf = vec!(0u8; 0x78);
+        elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        elf[0x20] = 0x40;
+        elf[0x36] = 0x38;
+        elf[0x38] = 1;
+        elf[0x40] = 3;
+        elf[0x48] = 0x78;
+        elf[0x60] = interpreter.len() as u8 + 1;
+        elf.extend(interpreter.as_bytes());
+        elf.push(0);
+        elf
+    }
+
+    #[test]
+    fn interpreter() {
+        assert_eq!(Some("/lib/ld-musl-x86_64.so.1".to_string()), get_interpreter(&elf_with_interpreter("/lib/ld-musl-x86_64.so.1")));
+        assert_eq!(Some("/lib64/ld-linux-x86-64.so.2".to_string()), get_interpreter(&elf_with_interpreter("/lib64/ld-linux-x86-64.so.2")));
+        assert_eq!(None, get_interpreter(&elf_with_interpreter("/lib/ld-musl-x86_64.so.1")[..0x70]));
+        assert_eq!(None, get_interpreter(b"#!/bin/sh\n"));
+    }
+
+    #[test]
+    fn musl_ldd() {
+        assert!(is_musl_ldd("musl libc (x86_64)\nVersion 1.2.4\nDynamic Program Loader"));
+        assert!(!is_musl_ldd("ldd (Ubuntu GLIBC 2.35-0ubuntu3.6) 2.35"));
+    }
+
+    #[test]
+    fn overrides() {
+        let host = Target::parse("x86_64-unknown-linux-musl");
+        assert_eq!("linux-arm64", host.with_overrides(Some("linux-arm64"), None, None, None).unwrap().key());
+        assert_eq!("mac-arm64", host.with_overrides(None, Some("macos"), Some("aarch64"), None).unwrap().key());
+        assert_eq!("linux-x86_64", host.with_overrides(None, None, None, Some("gnu")).unwrap().key());
+        assert_eq!("windows-x86_64", host.with_overrides(Some("linux-arm64-musl"), Some("windows"), Some("amd64"), None).unwrap().key());
+        assert!(host.with_overrides(Some("linux-sparc"), None, None, None).is_err());
+        assert!(host.with_overrides(None, Some("freebsd"), None, None).is_err());
+    }
+
     #[test]
     fn x86_64_linux_gnu() {
         let target = Target::parse("x86_64-unknown-linux-gnu");
@@ -84,6 +361,13 @@ mod tests {
         assert_eq!(Some(Variant::Musl), target.variant);
     }
 
+    #[test]
+    fn key() {
+        assert_eq!("linux-x86_64-musl", Target::parse("x86_64-unknown-linux-musl").key());
+        assert_eq!("windows-x86_64", Target::parse("x86_64-pc-windows-msvc").key());
+        assert_eq!("mac-arm64", Target::parse("aarch64-apple-darwin").key());
+    }
+
     #[test]
     fn armv7_unknown_linux_gnu() {
         let target = Target::parse("armv7-unknown-linux-gnu");
@@ -0,0 +1,66 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::info;
+
+use crate::eol::civil_from_days;
+
+pub const DEFAULT_LOG_DIR: &str = ".cache/gg/logs";
+
+/// E.g. 2026-10-14T101500
+fn get_timestamp(secs: u64) -> String {
+    let day_secs = secs % 86400;
+    format!("{}T{:02}{:02}{:02}", civil_from_days((secs / 86400) as i64), day_secs / 3600, day_secs % 3600 / 60, day_secs % 60)
+}
+
+fn copy_to<R: Read + Send + 'static, W: Write + Send + 'static>(mut from: R, mut to: W, log: Arc<Mutex<File>>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0; 8192];
+        while let Ok(n) = from.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let _ = to.write_all(&buf[..n]);
+            let _ = to.flush();
+            if let Ok(mut log) = log.lock() {
+                let _ = log.write_all(&buf[..n]);
+            }
+        }
+    })
+}
+
+/// Run command with stdout and stderr streamed to the terminal and to <log_dir>/<name>-<timestamp>.log
+pub fn run_tee(command: &mut Command, log_dir: &str, name: &str) -> io::Result<bool> {
+    fs::create_dir_all(log_dir)?;
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let log_path = PathBuf::from(log_dir).join(format!("{name}-{}.log", get_timestamp(secs)));
+    info!("Logging output to {}", log_path.display());
+    let log = Arc::new(Mutex::new(File::create(&log_path)?));
+
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().map(|out| copy_to(out, io::stdout(), log.clone()));
+    let stderr = child.stderr.take().map(|err| copy_to(err, io::stderr(), log.clone()));
+    let status = child.wait()?;
+    for handle in stdout.into_iter().chain(stderr) {
+        let _ = handle.join();
+    }
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_timestamp() {
+        assert_eq!("1970-01-01T000000", get_timestamp(0));
+        assert_eq!("2023-11-14T221320", get_timestamp(1700000000));
+    }
+}
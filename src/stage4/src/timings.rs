@@ -0,0 +1,105 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::executor::AppInput;
+use crate::otlp;
+
+pub const PREP: &str = "prep";
+pub const INDEX: &str = "index";
+pub const DOWNLOAD: &str = "download";
+pub const EXTRACT: &str = "extract";
+pub const EXEC: &str = "exec";
+
+#[derive(Clone)]
+pub struct Timing {
+    pub name: String,
+    pub phase: &'static str,
+    pub duration: Duration,
+    pub end: SystemTime,
+}
+
+static TIMINGS: Mutex<Vec<Timing>> = Mutex::new(vec!());
+static START: Mutex<Option<(Instant, SystemTime)>> = Mutex::new(None);
+
+pub fn start() {
+    *START.lock().unwrap() = Some((Instant::now(), SystemTime::now()));
+}
+
+pub fn record(name: &str, phase: &'static str, start: Instant) {
+    TIMINGS.lock().unwrap().push(Timing { name: name.to_string(), phase, duration: start.elapsed(), end: SystemTime::now() });
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.as_secs() == 0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+/// One line per tool. Detection is what is left of prep after fetching the index, downloading and extracting
+fn get_report(timings: &[Timing], total: Option<Duration>) -> String {
+    let mut names: Vec<&str> = vec!();
+    for timing in timings {
+        if !names.contains(&timing.name.as_str()) {
+            names.push(&timing.name);
+        }
+    }
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0).max(5) + 2;
+    let sum = |name: &str, phase: &str| timings.iter()
+        .filter(|t| t.name == name && t.phase == phase)
+        .map(|t| t.duration)
+        .sum::<Duration>();
+    let mut lines = vec!(format!("{:<width$}{:>10}{:>10}{:>10}{:>10}{:>10}", "", "detect", INDEX, DOWNLOAD, EXTRACT, EXEC));
+    for name in names {
+        let phases = [INDEX, DOWNLOAD, EXTRACT].map(|phase| sum(name, phase));
+        let detect = sum(name, PREP).saturating_sub(phases.iter().sum());
+        let cells = [detect, phases[0], phases[1], phases[2], sum(name, EXEC)]
+            .map(|d| if d.is_zero() { "-".to_string() } else { format_duration(d) });
+        lines.push(format!("{name:<width$}{:>10}{:>10}{:>10}{:>10}{:>10}", cells[0], cells[1], cells[2], cells[3], cells[4]));
+    }
+    if let Some(total) = total {
+        lines.push(format!("{:<width$}{:>10}", "total", format_duration(total)));
+    }
+    lines.join("\n")
+}
+
+/// Printed to stderr with --timings, so output of the tool is not affected. Also sent as spans when OTLP is configured
+pub async fn report(input: &AppInput) {
+    let start = *START.lock().unwrap();
+    let timings = TIMINGS.lock().unwrap().clone();
+    if input.no_clap.timings {
+        eprintln!("{}", get_report(&timings, start.map(|(start, _)| start.elapsed())));
+    }
+    if let Some((_, start)) = start {
+        otlp::export(input, start, &timings).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let timing = |name: &str, phase: &'static str, ms: u64| {
+            Timing { name: name.to_string(), phase, duration: Duration::from_millis(ms), end: SystemTime::now() }
+        };
+        let timings = vec!(
+            timing("node", PREP, 2500),
+            timing("node", INDEX, 300),
+            timing("node", INDEX, 100),
+            timing("node", DOWNLOAD, 1500),
+            timing("node", EXTRACT, 590),
+            timing("node", EXEC, 3),
+            timing("java", PREP, 12),
+        );
+        assert_eq!(
+            "           detect     index  download   extract      exec
+node         10ms     400ms     1.50s     590ms       3ms
+java         12ms         -         -         -         -
+total       3.00s",
+            get_report(&timings, Some(Duration::from_millis(3000)))
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use log::info;
+use semver::VersionReq;
+
+const TOOL_VERSIONS: &str = ".tool-versions";
+
+/// asdf plugin names that differ from the names in gg
+fn get_name(plugin: &str) -> &str {
+    match plugin {
+        "nodejs" => "node",
+        "golang" => "go",
+        _ => plugin,
+    }
+}
+
+/// asdf pins exact versions, mise also takes prefixes like 20 or 1.21. Java is like temurin-17.0.9+9 or openjdk-17,
+/// only the major is used as gg has its own distribution. Elixir is like 1.16.0-otp-26, elixir picks the build for
+/// that OTP. Erlang can have four parts, 26.2.5.4, a requirement only three, erlang picks the build of the fourth
+pub fn parse_version(name: &str, version: &str) -> Option<VersionReq> {
+    if version == "system" || version.starts_with("ref:") || version.starts_with("path:") {
+        return None;
+    }
+    let version = version.split("-otp-").next().unwrap_or(version);
+    let version = match name {
+        "erlang" => version.split('.').take(3).collect::<Vec<_>>().join("."),
+        _ => version.to_string(),
+    };
+    let version = version.as_str();
+    let version = version.rsplit('-').next().unwrap_or(version);
+    let version = version.split('+').next().unwrap_or(version);
+    if name == "java" {
+        return VersionReq::parse(version.split('.').next()?).ok();
+    }
+    match version.split('.').count() {
+        3 => VersionReq::parse(&format!("={version}")).ok(),
+        2 => VersionReq::parse(&format!("~{version}")).ok(),
+        _ => VersionReq::parse(version).ok(),
+    }
+}
+
+/// Lines of `<plugin> <version> [fallback versions]`, the first version is used
+fn parse_line<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let plugin = parts.next()?;
+            if get_name(plugin) != name {
+                return None;
+            }
+            parts.next()
+        })
+}
+
+fn parse(text: &str, name: &str) -> Option<VersionReq> {
+    parse_version(name, parse_line(text, name)?)
+}
+
+/// The version as written in the closest .tool-versions that has the tool, e.g. 1.16.0-otp-26 or 26.2.5.4
+#[cfg_attr(not(any(feature = "erlang", feature = "elixir")), allow(dead_code))]
+pub fn get_version(name: &str) -> Option<String> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors().find_map(|dir| parse_line(&fs::read_to_string(dir.join(TOOL_VERSIONS)).ok()?, name).map(|v| v.to_string()))
+}
+
+/// From the closest .tool-versions that has the tool, in the current dir or above, as asdf does
+pub fn get_version_req(name: &str) -> Option<VersionReq> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors().find_map(|dir| {
+        let path = dir.join(TOOL_VERSIONS);
+        let version_req = parse(&fs::read_to_string(&path).ok()?, name)?;
+        info!("Got version {version_req} for {name} from {}", path.display());
+        Some(version_req)
+    })
+}
+
+/// Every .tool-versions that can give a version, for knowing when to resolve again
+pub fn get_files() -> Vec<PathBuf> {
+    let Ok(cwd) = env::current_dir() else { return vec!() };
+    cwd.ancestors().map(|dir| dir.join(TOOL_VERSIONS)).filter(|path| path.is_file()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "nodejs 20.11.1 18.19.0\njava temurin-17.0.9+9 # comment\ngolang 1.21\ngradle system\n";
+        assert_eq!(VersionReq::parse("=20.11.1").ok(), parse(text, "node"));
+        assert_eq!(VersionReq::parse("17").ok(), parse(text, "java"));
+        assert_eq!(VersionReq::parse("~1.21").ok(), parse(text, "go"));
+        assert_eq!(None, parse(text, "gradle"));
+        assert_eq!(None, parse(text, "maven"));
+        assert_eq!(Some("temurin-17.0.9+9"), parse_line(text, "java"));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(VersionReq::parse("11").ok(), parse_version("java", "adoptopenjdk-11.0.11+9"));
+        assert_eq!(VersionReq::parse("20").ok(), parse_version("node", "20"));
+        assert_eq!(None, parse_version("node", "ref:v1.0.2-a"));
+        assert_eq!(VersionReq::parse("=1.16.0").ok(), parse_version("elixir", "1.16.0-otp-26"));
+        assert_eq!(VersionReq::parse("=26.2.5").ok(), parse_version("erlang", "26.2.5.4"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::ExitCode;
+
+use log::{debug, info};
+use semver::VersionReq;
+
+use crate::config;
+use crate::executor::{AppInput, Download, Executor, ExecutorCmd, GgVersion, get_url_matches, resolve_version_req};
+use crate::lock::{LockedDownload, LockedTool};
+
+/// An exact pin in gg.toml, e.g. `node = "18.1.0"`, is moved within the same major version.
+/// Anything else is a range, and only the locked version changes
+fn get_pinned_version(version: &str) -> Option<GgVersion> {
+    let version = version.trim().trim_start_matches('=');
+    if version.split('.').count() == 3 {
+        GgVersion::new(version)
+    } else {
+        None
+    }
+}
+
+fn pick_newest(downloads: &[Download], version_req: &Option<VersionReq>) -> Option<Download> {
+    if version_req.is_none() {
+        if let Some(lts) = downloads.iter().find(|d| d.tags.contains("lts")) {
+            return Some(lts.clone());
+        }
+    }
+    downloads.first().cloned()
+}
+
+/// The URL with the published checksum, for gg.lock
+async fn get_locked_download(executor: &dyn Executor, download: &Download) -> Result<LockedDownload, String> {
+    let sha256 = match &download.sha256 {
+        Some(sha256) => Some(sha256.clone()),
+        None => executor.get_checksum(download).await?,
+    };
+    Ok(LockedDownload { url: download.download_url.clone(), sha256 })
+}
+
+fn get_executor(tool: &str) -> Option<Box<dyn Executor>> {
+    <dyn Executor>::new(ExecutorCmd {
+        cmd: tool.to_string(),
+        version: None,
+        include_tags: Default::default(),
+        exclude_tags: Default::default(),
+    })
+}
+
+pub async fn upgrade(input: &AppInput, tool: Option<&str>) -> ExitCode {
+    let tools: Vec<String> = match tool {
+        Some(tool) => vec!(tool.to_string()),
+        None => input.config.tools.keys().cloned().collect(),
+    };
+    if tools.is_empty() {
+        println!("Nothing to upgrade. Add tools to [tools] in gg.toml or run upgrade <tool>");
+        return ExitCode::from(1);
+    }
+
+    let mut lock = input.lock.clone();
+    let mut changed = false;
+    for tool in tools {
+        let executor = match get_executor(&tool) {
+            Some(executor) => executor,
+            None => {
+                println!("{tool}: Unknown tool");
+                return ExitCode::from(1);
+            }
+        };
+        let name = executor.get_name().to_string();
+        let configured = input.config.tools.get(&name).cloned();
+        let pinned = configured.as_ref().and_then(|v| get_pinned_version(v));
+        let version_req = match &pinned {
+            Some(pinned) => VersionReq::parse(format!("^{}", pinned.to_string()).as_str()).ok(),
+            None => resolve_version_req(&*executor, input),
+        };
+        info!("{name}: Looking for newest version matching {:?}", version_req.as_ref().map(|v| v.to_string()));
+
+        let urls = match executor.get_download_urls(input).await {
+            Ok(urls) => urls,
+            Err(e) => {
+                eprintln!("{}", e.to_diagnostic());
+                return ExitCode::from(1);
+            }
+        };
+        let matches = get_url_matches(&urls, input, &*executor, &version_req);
+        debug!("{name}: {} matches", matches.len());
+        let newest = match pick_newest(&matches, &version_req) {
+            Some(newest) => newest,
+            None => {
+                println!("{name}: No version found matching {}", version_req.map(|v| v.to_string()).unwrap_or("*".to_string()));
+                continue;
+            }
+        };
+        let newest_version = newest.version.clone().map(|v| v.to_string()).unwrap_or_default();
+
+        if let Some(pinned) = &pinned {
+            if pinned.to_version() < GgVersion::new(&newest_version).map(|v| v.to_version()).unwrap_or(pinned.to_version()) {
+                println!("gg.toml");
+                println!("-{name} = \"{}\"", configured.clone().unwrap_or_default());
+                println!("+{name} = \"{newest_version}\"");
+                if let Err(e) = config::set_tool_version(&name, &newest_version) {
+                    println!("Unable to update gg.toml: {e}");
+                    return ExitCode::from(1);
+                }
+            }
+        }
+
+        let locked_download = match get_locked_download(&*executor, &newest).await {
+            Ok(locked_download) => locked_download,
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::from(1);
+            }
+        };
+        let targets = BTreeMap::from([(input.target.key(), locked_download)]);
+        let locked = LockedTool { version: newest_version.clone(), url: Some(newest.download_url.clone()), digests: Default::default(), targets };
+        let previous = lock.tools.get(&name).cloned();
+        if previous.as_ref().map(|p| (&p.version, &p.url)) != Some((&locked.version, &locked.url)) {
+            println!("gg.lock");
+            if let Some(previous) = previous {
+                println!("-{name} {}", previous.version);
+            }
+            println!("+{name} {newest_version}");
+            lock.tools.insert(name.clone(), locked);
+            changed = true;
+        } else {
+            println!("{name}: {newest_version} is the newest version matching {}", configured.unwrap_or("*".to_string()));
+        }
+    }
+
+    if changed {
+        if let Err(e) = lock.save() {
+            println!("Unable to write gg.lock: {e}");
+            return ExitCode::from(1);
+        }
+    }
+    ExitCode::from(0)
+}
+
+/// `update-lock [tool]` resolves the tools in gg.toml and gg.lock again, from .nvmrc, engines, gg.toml and so on, and
+/// records the download and checksum for the target. Other targets are kept while the version stays
+pub async fn update_lock(input: &AppInput, tool: Option<&str>) -> ExitCode {
+    let tools: Vec<String> = match tool {
+        Some(tool) => vec!(tool.to_string()),
+        None => input.config.tools.keys().chain(input.lock.tools.keys()).cloned().collect::<BTreeSet<_>>().into_iter().collect(),
+    };
+    if tools.is_empty() {
+        println!("Nothing to lock. Add tools to [tools] in gg.toml or run update-lock <tool>");
+        return ExitCode::from(1);
+    }
+
+    let target = input.target.key();
+    let mut lock = input.lock.clone();
+    let mut changed = false;
+    for tool in tools {
+        let Some(executor) = get_executor(&tool) else {
+            println!("{tool}: Unknown tool");
+            return ExitCode::from(1);
+        };
+        let name = executor.get_name().to_string();
+        let version_req = resolve_version_req(&*executor, input);
+        let urls = match executor.get_download_urls(input).await {
+            Ok(urls) => urls,
+            Err(e) => {
+                eprintln!("{}", e.to_diagnostic());
+                return ExitCode::from(1);
+            }
+        };
+        let matches = get_url_matches(&urls, input, &*executor, &version_req);
+        let Some(newest) = pick_newest(&matches, &version_req) else {
+            println!("{name}: No version found matching {}", version_req.map(|v| v.to_string()).unwrap_or("*".to_string()));
+            continue;
+        };
+        let version = newest.version.clone().map(|v| v.to_string()).unwrap_or_default();
+        let previous = lock.tools.get(&name).cloned();
+        let mut locked = match &previous {
+            Some(previous) if previous.version == version => previous.clone(),
+            _ => LockedTool { version: version.clone(), url: Some(newest.download_url.clone()), digests: Default::default(), targets: Default::default() },
+        };
+        match get_locked_download(&*executor, &newest).await {
+            Ok(locked_download) => locked.targets.insert(target.clone(), locked_download),
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::from(1);
+            }
+        };
+        if previous.as_ref() == Some(&locked) {
+            println!("{name}: {version} ({target}) is up to date");
+            continue;
+        }
+        println!("gg.lock");
+        if let Some(previous) = previous.filter(|p| p.version != version) {
+            println!("-{name} {}", previous.version);
+        }
+        println!("+{name} {version} ({target})");
+        lock.tools.insert(name, locked);
+        changed = true;
+    }
+
+    if changed {
+        if let Err(e) = lock.save() {
+            println!("Unable to write gg.lock: {e}");
+            return ExitCode::from(1);
+        }
+    }
+    ExitCode::from(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::executor::GgVersionReq;
+
+    use super::*;
+
+    #[test]
+    fn test_get_pinned_version() {
+        assert_eq!(Some("18.1.0".to_string()), get_pinned_version("18.1.0").map(|v| v.to_string()));
+        assert_eq!(Some("18.1.0".to_string()), get_pinned_version("=18.1.0").map(|v| v.to_string()));
+        assert_eq!(None, get_pinned_version("^18.1.0"));
+        assert_eq!(None, get_pinned_version("18"));
+    }
+
+    #[test]
+    fn test_pick_newest_prefers_lts_without_range() {
+        let mut lts = Download::new("lts".to_string(), "18.19.0", None);
+        lts.tags.insert("lts".to_string());
+        let downloads = vec!(Download::new("current".to_string(), "21.0.0", None), lts);
+        assert_eq!("lts", pick_newest(&downloads, &None).unwrap().download_url);
+        let version_req = GgVersionReq::new("*").map(|v| v.to_version_req());
+        assert_eq!("current", pick_newest(&downloads, &version_req).unwrap().download_url);
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use log::info;
+
+use crate::executor::{AppInput, read_meta};
+use crate::lock::digest_dir;
+
+/// Install dirs of a tool with the given version
+fn find_installs(input: &AppInput, name: &str, version: &str) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(input.config.get_install_dir().join(name)).min_depth(1).max_depth(1).into_iter()
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().is_dir())
+        .map(|x| x.into_path())
+        .filter(|path| read_meta(path).and_then(|meta| meta.download.version).map(|v| v.to_string()) == Some(version.to_string()))
+        .collect()
+}
+
+/// Compares installed tools with digests in gg.lock. Fails if anything differs
+pub fn verify(input: &AppInput) -> ExitCode {
+    if input.lock.tools.is_empty() {
+        println!("Nothing to verify. No tools in gg.lock");
+        return ExitCode::from(1);
+    }
+    let target_key = input.target.key();
+    let mut drift = false;
+    for (name, locked) in &input.lock.tools {
+        let digest = match locked.digests.get(&target_key) {
+            Some(digest) => digest,
+            None => {
+                println!("{name} {}: No digest for {target_key} in gg.lock", locked.version);
+                continue;
+            }
+        };
+        let installs = find_installs(input, name, &locked.version);
+        if installs.is_empty() {
+            println!("{name} {}: Not installed", locked.version);
+            continue;
+        }
+        for install in installs {
+            info!("Verifying {}", install.display());
+            match digest_dir(&install) {
+                Ok(actual) if &actual == digest => println!("{name} {}: OK", locked.version),
+                Ok(actual) => {
+                    println!("{name} {}: MISMATCH in {}. Expected {digest}, got {actual}", locked.version, install.display());
+                    drift = true;
+                }
+                Err(e) => {
+                    println!("{name} {}: Unable to verify {}: {e}", locked.version, install.display());
+                    drift = true;
+                }
+            }
+        }
+    }
+    if drift {
+        ExitCode::from(1)
+    } else {
+        ExitCode::from(0)
+    }
+}
@@ -0,0 +1,125 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::executor::{Download, GgVersion};
+use crate::http;
+
+#[derive(Serialize)]
+struct OsvPackage<'a> {
+    name: &'a str,
+    ecosystem: &'a str,
+}
+
+#[derive(Serialize)]
+struct OsvQuery<'a> {
+    version: &'a str,
+    package: OsvPackage<'a>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvVuln {
+    id: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OsvResponse {
+    #[serde(default)]
+    vulns: Vec<OsvVuln>,
+}
+
+/// Tools with a package in https://osv.dev
+fn get_osv_package(name: &str) -> Option<OsvPackage<'static>> {
+    match name {
+        "go" => Some(OsvPackage { name: "stdlib", ecosystem: "Go" }),
+        _ => None,
+    }
+}
+
+async fn query_osv(name: &str, version: &GgVersion) -> Vec<String> {
+    let package = match get_osv_package(name) {
+        Some(package) => package,
+        None => return vec!(),
+    };
+    let version = version.to_string();
+    let query = OsvQuery { version: &version, package };
+    info!("Querying OSV for {name} {version}");
+    let res = http::send(http::client().post("https://api.osv.dev/v1/query").json(&query)).await;
+    match res {
+        Ok(res) => res.json::<OsvResponse>().await.map(|r| r.vulns.into_iter().map(|v| v.id).collect()).unwrap_or_default(),
+        Err(e) => {
+            warn!("Unable to query OSV: {e}");
+            vec!()
+        }
+    }
+}
+
+/// Newer releases on the same major version tagged as security releases, e.g. from nodejs.org index.json
+fn get_newer_security_releases(version: &GgVersion, downloads: &[Download]) -> Vec<GgVersion> {
+    let version = version.to_version();
+    let mut versions = downloads.iter()
+        .filter(|d| d.tags.contains("security"))
+        .filter_map(|d| d.version.clone())
+        .filter(|v| v.to_version().major == version.major && v.to_version() > version)
+        .collect::<Vec<_>>();
+    versions.sort_by_key(|v| v.to_version());
+    versions.dedup();
+    versions
+}
+
+/// Policy is `off`, `warn` or `block`
+pub async fn check(name: &str, version: &GgVersion, downloads: &[Download], policy: &str) -> Result<(), String> {
+    if policy == "off" {
+        return Ok(());
+    }
+    let mut findings = vec!();
+    let security_releases = get_newer_security_releases(version, downloads);
+    if let Some(latest) = security_releases.last() {
+        findings.push(format!("security releases after it ({}), upgrade to at least {}",
+                              security_releases.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
+                              latest.to_string()));
+    }
+    let osv = query_osv(name, version).await;
+    if !osv.is_empty() {
+        findings.push(format!("known vulnerabilities {}", osv.join(", ")));
+    }
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let message = format!("{name} {} has {}", version.to_string(), findings.join(" and "));
+    if policy == "block" {
+        return Err(message);
+    }
+    warn!("{message}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download(version: &str, security: bool) -> Download {
+        let mut download = Download::new(String::new(), version, None);
+        if security {
+            download.tags.insert("security".to_string());
+        }
+        download
+    }
+
+    #[test]
+    fn test_get_newer_security_releases() {
+        let downloads = vec!(download("18.19.1", true), download("18.19.0", false), download("18.18.2", true), download("20.11.1", true));
+        let version = GgVersion::new("18.18.0").unwrap();
+        let versions = get_newer_security_releases(&version, &downloads).into_iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!("18.18.2", "18.19.1"), versions);
+        assert!(get_newer_security_releases(&GgVersion::new("18.19.1").unwrap(), &downloads).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_policy() {
+        let downloads = vec!(download("18.19.1", true));
+        let version = GgVersion::new("18.18.0").unwrap();
+        assert!(check("node", &version, &downloads, "off").await.is_ok());
+        assert!(check("node", &version, &downloads, "warn").await.is_ok());
+        assert!(check("node", &version, &downloads, "block").await.is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use std::env;
+use std::fs;
+use std::process::{Child, Command, ExitCode};
+use std::time::{Duration, SystemTime};
+
+use log::{debug, info};
+
+use crate::no_clap::NoClap;
+
+const WATCHED_FILES: [&str; 5] = [".nvmrc", ".tool-versions", "gg.toml", "package.json", "gradle/wrapper/gradle-wrapper.properties"];
+
+fn get_modified_times() -> Vec<Option<SystemTime>> {
+    WATCHED_FILES.iter().map(|file| fs::metadata(file).and_then(|m| m.modified()).ok()).collect()
+}
+
+/// In the process group of gg, so it stays in the foreground of the terminal. Ctrl-C reaches it and it can read input
+fn spawn(args: &[String]) -> Option<Child> {
+    let exe = env::current_exe().ok()?;
+    debug!("Spawning {:?} {:?}", exe, args);
+    Command::new(exe).args(args).spawn().ok()
+}
+
+/// The gg arguments with the command after `watch --`. None without a command
+fn get_args(no_clap: &NoClap) -> Option<Vec<String>> {
+    let app_args: Vec<String> = no_clap.app_args.iter().skip_while(|arg| *arg == "--").cloned().collect();
+    if app_args.is_empty() {
+        return None;
+    }
+    Some(no_clap.gg_args.iter().cloned().chain(app_args).collect())
+}
+
+/// Output of pgrep, one pid per line
+#[cfg(any(unix, test))]
+fn parse_pids(output: &str) -> Vec<u32> {
+    output.lines().filter_map(|line| line.trim().parse().ok()).collect()
+}
+
+/// Children, their children and so on, as the group of the child is the one of gg
+#[cfg(unix)]
+fn get_descendants(pid: u32) -> Vec<u32> {
+    let mut descendants = vec!();
+    let mut parents = vec!(pid);
+    while let Some(parent) = parents.pop() {
+        let Ok(output) = Command::new("pgrep").args(["-P", &parent.to_string()]).output() else { continue };
+        for child in parse_pids(&String::from_utf8_lossy(&output.stdout)) {
+            descendants.push(child);
+            parents.push(child);
+        }
+    }
+    descendants
+}
+
+/// Kill gg and the executable it started, not only gg
+fn kill_tree(child: &mut Child) {
+    let pid = child.id();
+    info!("Killing {pid}");
+    #[cfg(unix)]
+    {
+        let pids: Vec<String> = get_descendants(pid).into_iter().chain([pid]).map(|p| p.to_string()).collect();
+        let _ = Command::new("kill").arg("-TERM").args(&pids).status();
+    }
+    #[cfg(windows)]
+    let _ = Command::new("taskkill").args(["/T", "/F", "/PID", pid.to_string().as_str()]).status();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Runs gg with the arguments after `watch --` and restarts it whenever one of the version files change.
+/// The restarted process resolves versions again, so a change in .nvmrc etc. gives the new version
+pub async fn watch(no_clap: &NoClap) -> ExitCode {
+    let Some(args) = get_args(no_clap) else {
+        println!("Missing command. Usage: watch -- <executable name> [program arguments]");
+        return ExitCode::from(1);
+    };
+
+    println!("Watching {}", WATCHED_FILES.join(", "));
+    let mut modified_times = get_modified_times();
+    let mut child = spawn(&args);
+    loop {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let new_modified_times = get_modified_times();
+        if new_modified_times != modified_times {
+            modified_times = new_modified_times;
+            println!("Version files changed. Restarting");
+            if let Some(mut c) = child {
+                kill_tree(&mut c);
+            }
+            child = spawn(&args);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_args() {
+        let mut no_clap = NoClap::parse(vec!());
+        no_clap.gg_args = vec!("-l".to_string());
+        assert_eq!(None, get_args(&no_clap));
+        no_clap.app_args = vec!("--".to_string(), "npm".to_string(), "start".to_string());
+        assert_eq!(Some(vec!("-l".to_string(), "npm".to_string(), "start".to_string())), get_args(&no_clap));
+    }
+
+    #[test]
+    fn test_parse_pids() {
+        assert_eq!(vec!(12, 345), parse_pids("12\n 345\n\n"));
+        assert!(parse_pids("").is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_kill_tree() {
+        let mut child = Command::new("sh").args(["-c", "sleep 30 & wait"]).spawn().unwrap();
+        let mut descendants = vec!();
+        for _ in 0..50 {
+            descendants = get_descendants(child.id());
+            if !descendants.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(1, descendants.len());
+        kill_tree(&mut child);
+        std::thread::sleep(Duration::from_millis(100));
+        // Reparented when sh is killed, a zombie until reaped
+        let output = Command::new("ps").args(["-o", "stat=", "-p", &descendants[0].to_string()]).output().unwrap();
+        let stat = String::from_utf8_lossy(&output.stdout);
+        assert!(stat.trim().is_empty() || stat.trim().starts_with('Z'));
+    }
+}